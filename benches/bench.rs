@@ -5,16 +5,17 @@ use futures_util::future::join_all;
 use helpers::{SUB_METHOD_NAME, UNSUB_METHOD_NAME};
 use jsonrpsee::core::client::{ClientT, SubscriptionClientT};
 use jsonrpsee::http_client::HttpClientBuilder;
-use jsonrpsee::types::{Id, ParamsSer, RequestSer};
+use jsonrpsee::types::{Id, Params, ParamsSer, RequestSer};
 use jsonrpsee::ws_client::WsClientBuilder;
 use tokio::runtime::Runtime as TokioRuntime;
 
 mod helpers;
 
-criterion_group!(types_benches, jsonrpsee_types_v2);
+criterion_group!(types_benches, jsonrpsee_types_v2, params_parsing);
 criterion_group!(
 	sync_benches,
 	SyncBencher::http_requests,
+	SyncBencher::http_requests_without_connection_reuse,
 	SyncBencher::batched_http_requests,
 	SyncBencher::websocket_requests,
 	SyncBencher::batched_ws_requests,
@@ -22,6 +23,7 @@ criterion_group!(
 criterion_group!(
 	async_benches,
 	AsyncBencher::http_requests,
+	AsyncBencher::http_requests_without_connection_reuse,
 	AsyncBencher::batched_http_requests,
 	AsyncBencher::websocket_requests,
 	AsyncBencher::batched_ws_requests
@@ -75,6 +77,25 @@ pub fn jsonrpsee_types_v2(crit: &mut Criterion) {
 	});
 }
 
+pub fn params_parsing(crit: &mut Criterion) {
+	let raw = format!(r#"["{}"]"#, "x".repeat(1024 * 1024));
+	let params = Params::new(Some(&raw));
+
+	crit.bench_function("params_one_owned_1mb_string", |b| {
+		b.iter(|| {
+			let s: String = params.one().unwrap();
+			black_box(s);
+		})
+	});
+
+	crit.bench_function("params_one_borrowed_1mb_string", |b| {
+		b.iter(|| {
+			let s: &str = params.one().unwrap();
+			black_box(s);
+		})
+	});
+}
+
 trait RequestBencher {
 	const REQUEST_TYPE: RequestType;
 
@@ -87,6 +108,22 @@ trait RequestBencher {
 		run_http_concurrent_connections(&rt, crit, &url, "http_concurrent_connections", Self::REQUEST_TYPE);
 	}
 
+	/// Same as `http_requests` but opening a new connection for each request, to measure what the connection pool
+	/// of the client saves.
+	fn http_requests_without_connection_reuse(crit: &mut Criterion) {
+		let rt = TokioRuntime::new().unwrap();
+		let (url, _server) = rt.block_on(helpers::http_server(rt.handle().clone()));
+		let client = Arc::new(
+			HttpClientBuilder::default()
+				.max_concurrent_requests(1024 * 1024)
+				.max_idle_connections(0)
+				.build(&url)
+				.unwrap(),
+		);
+		run_round_trip(&rt, crit, client.clone(), "http_round_trip_without_reuse", Self::REQUEST_TYPE);
+		run_concurrent_round_trip(&rt, crit, client, "http_concurrent_round_trip_without_reuse", Self::REQUEST_TYPE);
+	}
+
 	fn batched_http_requests(crit: &mut Criterion) {
 		let rt = TokioRuntime::new().unwrap();
 		let (url, _server) = rt.block_on(helpers::http_server(rt.handle().clone()));
@@ -24,25 +24,42 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::transport::HttpTransportClient;
-use crate::types::{ErrorResponse, Id, NotificationSer, ParamsSer, RequestSer, Response};
+use crate::transport::{HttpTransportClient, OutgoingRequest, PoolConfig};
+use crate::types::error::ErrorObject;
+use crate::types::{v1, ErrorResponse, Id, NotificationSer, ParamsSer, RequestSer, Response};
 use async_trait::async_trait;
-use jsonrpsee_core::client::{CertificateStore, ClientT, IdKind, RequestIdManager, Subscription, SubscriptionClientT};
-use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
+use jsonrpsee_core::client::{
+	error_response_into_error, CertificateStore, ClientT, IdKind, RequestIdManager, Subscription, SubscriptionClientT,
+};
+use jsonrpsee_core::{json_depth_exceeds, Error, JsonValue, MAX_JSON_DEPTH, TEN_MB_SIZE_BYTES};
 use rustc_hash::FxHashMap;
 use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+
+/// Name of the header carrying the key by which the server can recognize a call that is sent again.
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
 
 /// Http Client Builder.
 #[derive(Debug)]
 pub struct HttpClientBuilder {
 	max_request_body_size: u32,
+	max_response_body_size: Option<u32>,
 	request_timeout: Duration,
 	max_concurrent_requests: usize,
 	certificate_store: CertificateStore,
 	id_kind: IdKind,
+	id_sequence: (u64, u64),
+	pool: PoolConfig,
+	v1_compat: bool,
+	max_json_depth: u32,
+	tls_server_name: Option<String>,
+	idempotency_keys: bool,
 }
 
 impl HttpClientBuilder {
@@ -52,6 +69,12 @@ impl HttpClientBuilder {
 		self
 	}
 
+	/// Sets the maximum size of a response body in bytes (default is the maximum size of a request body).
+	pub fn max_response_body_size(mut self, size: u32) -> Self {
+		self.max_response_body_size = Some(size);
+		self
+	}
+
 	/// Set request timeout (default is 60 seconds).
 	pub fn request_timeout(mut self, timeout: Duration) -> Self {
 		self.request_timeout = timeout;
@@ -76,14 +99,105 @@ impl HttpClientBuilder {
 		self
 	}
 
+	/// Use the request IDs `start`, `start + stride`, `start + 2 * stride` and so on, wrapping around on overflow,
+	/// for instance to leave room for IDs that a proxy injects (default is 0 and 1).
+	///
+	/// A `stride` of zero is treated as one.
+	pub fn id_sequence(mut self, start: u64, stride: u64) -> Self {
+		self.id_sequence = (start, stride);
+		self
+	}
+
+	/// Set the maximum number of idle connections to the target that are kept alive for reuse (default is unlimited).
+	///
+	/// Reusing connections saves the TCP and TLS handshakes for subsequent requests, at the cost of keeping
+	/// sockets open on both ends while idle. Set it to zero to open a new connection for every request.
+	pub fn max_idle_connections(mut self, max: usize) -> Self {
+		self.pool.max_idle_connections = max;
+		self
+	}
+
+	/// Set the duration after which idle connections are closed, `None` keeps them open until the server
+	/// closes them (default is 90 seconds).
+	pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.pool.idle_timeout = timeout;
+		self
+	}
+
+	/// Speak [JSON-RPC 1.0](https://www.jsonrpc.org/specification_v1) to talk to legacy servers (default is false).
+	///
+	/// Requests are sent without the `jsonrpc` field and with positional params only, calls with named
+	/// params fail with [`Error::Custom`]. Responses are expected to carry both `result` and `error`, errors
+	/// that are shaped like JSON-RPC 2.0 error objects are reported just like in JSON-RPC 2.0 mode.
+	pub fn v1_compat(mut self, enabled: bool) -> Self {
+		self.v1_compat = enabled;
+		self
+	}
+
+	/// Sets the maximum nesting depth of JSON arrays and objects in a response (default is 128).
+	///
+	/// Deeper responses are rejected with [`Error::ParseError`] without being deserialized.
+	pub fn max_json_depth(mut self, depth: u32) -> Self {
+		self.max_json_depth = depth;
+		self
+	}
+
+	/// Set the name of the server to send as SNI and to validate its TLS certificate against, instead of the host
+	/// of the URL (default is the host of the URL).
+	///
+	/// Useful to connect to an IP address, or through a load balancer, to a server whose certificate is for a
+	/// hostname.
+	pub fn tls_server_name(mut self, name: impl Into<String>) -> Self {
+		self.tls_server_name = Some(name.into());
+		self
+	}
+
+	/// Send an `Idempotency-Key` header that is unique per call with requests and batch requests (default is false).
+	///
+	/// The server can use it to recognize a call that is sent again, for instance by a proxy retrying it. A key given
+	/// to [`HttpClient::request_with_headers`] is sent instead, which allows to reuse the key of a call when retrying
+	/// it.
+	pub fn idempotency_keys(mut self, enabled: bool) -> Self {
+		self.idempotency_keys = enabled;
+		self
+	}
+
 	/// Build the HTTP client with target to connect to.
+	///
+	/// Fails if the maximum request or response body size or the request timeout is zero, no call could succeed
+	/// with such a client.
 	pub fn build(self, target: impl AsRef<str>) -> Result<HttpClient, Error> {
-		let transport = HttpTransportClient::new(target, self.max_request_body_size, self.certificate_store)
-			.map_err(|e| Error::Transport(e.into()))?;
+		if self.max_request_body_size == 0 {
+			return Err(Error::Custom("Max request body size must be greater than zero".into()));
+		}
+		if self.max_response_body_size == Some(0) {
+			return Err(Error::Custom("Max response body size must be greater than zero".into()));
+		}
+		if self.request_timeout.is_zero() {
+			return Err(Error::Custom("Request timeout must be greater than zero".into()));
+		}
+
+		let mut transport = HttpTransportClient::new(
+			target,
+			self.max_request_body_size,
+			self.certificate_store,
+			self.pool,
+			self.tls_server_name,
+		)
+		.map_err(|e| Error::Transport(e.into()))?;
+		if let Some(size) = self.max_response_body_size {
+			transport = transport.with_max_response_body_size(size);
+		}
 		Ok(HttpClient {
 			transport,
-			id_manager: Arc::new(RequestIdManager::new(self.max_concurrent_requests, self.id_kind)),
-			request_timeout: self.request_timeout,
+			id_manager: Arc::new(
+				RequestIdManager::new(self.max_concurrent_requests, self.id_kind)
+					.with_id_sequence(self.id_sequence.0, self.id_sequence.1),
+			),
+			request_timeout: Arc::new(AtomicU64::new(duration_to_nanos(self.request_timeout))),
+			v1_compat: self.v1_compat,
+			max_json_depth: self.max_json_depth,
+			idempotency_keys: self.idempotency_keys.then(|| Arc::new(IdempotencyKeys::new())),
 		})
 	}
 }
@@ -92,10 +206,17 @@ impl Default for HttpClientBuilder {
 	fn default() -> Self {
 		Self {
 			max_request_body_size: TEN_MB_SIZE_BYTES,
+			max_response_body_size: None,
 			request_timeout: Duration::from_secs(60),
 			max_concurrent_requests: 256,
 			certificate_store: CertificateStore::Native,
 			id_kind: IdKind::Number,
+			id_sequence: (0, 1),
+			pool: PoolConfig::default(),
+			v1_compat: false,
+			max_json_depth: MAX_JSON_DEPTH,
+			tls_server_name: None,
+			idempotency_keys: false,
 		}
 	}
 }
@@ -105,35 +226,104 @@ impl Default for HttpClientBuilder {
 pub struct HttpClient {
 	/// HTTP transport client.
 	transport: HttpTransportClient,
-	/// Request timeout. Defaults to 60sec.
-	request_timeout: Duration,
+	/// Request timeout in nanoseconds, shared between clones so that it can be changed at runtime. Defaults to 60sec.
+	request_timeout: Arc<AtomicU64>,
 	/// Request ID manager.
 	id_manager: Arc<RequestIdManager>,
+	/// Speak JSON-RPC 1.0 instead of 2.0.
+	v1_compat: bool,
+	/// Max nesting depth of JSON arrays and objects in responses.
+	max_json_depth: u32,
+	/// Generator of the `Idempotency-Key` headers, if enabled.
+	idempotency_keys: Option<Arc<IdempotencyKeys>>,
 }
 
-#[async_trait]
-impl ClientT for HttpClient {
-	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
-		let notif = NotificationSer::new(method, params);
-		let fut = self.transport.send(serde_json::to_string(&notif).map_err(Error::ParseError)?);
-		match tokio::time::timeout(self.request_timeout, fut).await {
-			Ok(Ok(ok)) => Ok(ok),
-			Err(_) => Err(Error::RequestTimeout),
-			Ok(Err(e)) => Err(Error::Transport(e.into())),
-		}
+/// Generates `Idempotency-Key` header values, unique per call.
+#[derive(Debug)]
+struct IdempotencyKeys {
+	/// Random prefix so that the keys of different clients don't collide.
+	prefix: u64,
+	next: AtomicU64,
+}
+
+impl IdempotencyKeys {
+	fn new() -> Self {
+		Self { prefix: RandomState::new().build_hasher().finish(), next: AtomicU64::new(0) }
 	}
 
-	/// Perform a request towards the server.
-	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	fn next(&self) -> String {
+		format!("{:016x}{:016x}", self.prefix, self.next.fetch_add(1, Ordering::Relaxed))
+	}
+}
+
+impl HttpClient {
+	/// Returns the maximum size of a request body in bytes.
+	pub fn max_request_body_size(&self) -> u32 {
+		self.transport.max_request_body_size()
+	}
+
+	/// Set the maximum size of a request body in bytes for subsequent requests.
+	///
+	/// Requests that are already in flight keep the limit they were made with.
+	/// The limit is shared by all clones of this client.
+	pub fn set_max_request_body_size(&self, size: u32) {
+		self.transport.set_max_request_body_size(size);
+	}
+
+	/// Returns the maximum size of a response body in bytes.
+	pub fn max_response_body_size(&self) -> u32 {
+		self.transport.max_response_body_size()
+	}
+
+	/// Set the maximum size of a response body in bytes for subsequent requests.
+	///
+	/// Until it's set, here or with [`HttpClientBuilder::max_response_body_size`], the response limit follows
+	/// the request limit, also when the latter is changed at runtime. Setting it to zero restores that behavior.
+	///
+	/// Requests that are already in flight keep the limit they were made with.
+	/// The limit is shared by all clones of this client.
+	pub fn set_max_response_body_size(&self, size: u32) {
+		self.transport.set_max_response_body_size(size);
+	}
+
+	/// Run `signer` on each request of this client and its clones right before it is sent, for instance to add a
+	/// header with a signature of the body. Replaces the previous signer, if any.
+	pub fn set_request_signer<F>(&self, signer: F)
+	where
+		F: Fn(&mut OutgoingRequest) + Send + Sync + 'static,
+	{
+		self.transport.set_request_signer(signer);
+	}
+
+	/// Close the connections kept alive by this client and its clones right away rather than after the idle
+	/// timeout, for instance before a short-lived program exits. Calls made with the clones fail from then on.
+	pub fn shutdown(self) {
+		self.transport.shutdown();
+	}
+
+	/// Perform a request towards the server with additional HTTP headers.
+	///
+	/// The headers are only sent with this request, replacing the default ones with the same name.
+	pub async fn request_with_headers<'a, R>(
+		&self,
+		method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		mut headers: Vec<(String, String)>,
+	) -> Result<R, Error>
 	where
 		R: DeserializeOwned,
 	{
+		self.add_idempotency_key(&mut headers);
 		let guard = self.id_manager.next_request_id()?;
 		let id = guard.inner();
-		let request = RequestSer::new(&id, method, params);
+		let request = if self.v1_compat {
+			serialize_v1_request(&id, method, params)?
+		} else {
+			serde_json::to_string(&RequestSer::new(&id, method, params)).map_err(Error::ParseError)?
+		};
 
-		let fut = self.transport.send_and_read_body(serde_json::to_string(&request).map_err(Error::ParseError)?);
-		let body = match tokio::time::timeout(self.request_timeout, fut).await {
+		let fut = self.transport.send_and_read_body(request, &headers);
+		let body = match tokio::time::timeout(self.request_timeout(), fut).await {
 			Ok(Ok(body)) => body,
 			Err(_e) => {
 				return Err(Error::RequestTimeout);
@@ -142,12 +332,22 @@ impl ClientT for HttpClient {
 				return Err(Error::Transport(e.into()));
 			}
 		};
+		check_json_depth(&body, self.max_json_depth)?;
+
+		if self.v1_compat {
+			let response: v1::Response<_> = serde_json::from_slice(&body).map_err(Error::ParseError)?;
+			return if response.id == id {
+				v1_response_into_result(response, method)
+			} else {
+				Err(Error::InvalidRequestId)
+			};
+		}
 
 		let response: Response<_> = match serde_json::from_slice(&body) {
 			Ok(response) => response,
 			Err(_) => {
 				let err: ErrorResponse = serde_json::from_slice(&body).map_err(Error::ParseError)?;
-				return Err(Error::Request(err.to_string()));
+				return Err(error_response_into_error(&err, method));
 			}
 		};
 
@@ -158,6 +358,86 @@ impl ClientT for HttpClient {
 		}
 	}
 
+	/// Adds an `Idempotency-Key` header if enabled and the caller didn't provide one.
+	fn add_idempotency_key(&self, headers: &mut Vec<(String, String)>) {
+		if let Some(keys) = &self.idempotency_keys {
+			if !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case(IDEMPOTENCY_KEY_HEADER)) {
+				headers.push((IDEMPOTENCY_KEY_HEADER.to_owned(), keys.next()));
+			}
+		}
+	}
+
+	/// Returns the request timeout.
+	pub fn request_timeout(&self) -> Duration {
+		Duration::from_nanos(self.request_timeout.load(Ordering::Relaxed))
+	}
+
+	/// Set the request timeout for subsequent requests.
+	///
+	/// Requests that are already in flight keep the timeout they were made with.
+	/// The timeout is shared by all clones of this client.
+	pub fn set_request_timeout(&self, timeout: Duration) {
+		self.request_timeout.store(duration_to_nanos(timeout), Ordering::Relaxed);
+	}
+}
+
+fn duration_to_nanos(duration: Duration) -> u64 {
+	u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Reject a response `body` nested deeper than `max_depth` levels before deserializing it.
+fn check_json_depth(body: &[u8], max_depth: u32) -> Result<(), Error> {
+	if json_depth_exceeds(body, max_depth) {
+		let msg = format!("JSON nested deeper than {} levels", max_depth);
+		return Err(Error::ParseError(serde::de::Error::custom(msg)));
+	}
+	Ok(())
+}
+
+/// Serialize a JSON-RPC 1.0 request, which only supports positional params.
+fn serialize_v1_request(id: &Id, method: &str, params: Option<ParamsSer>) -> Result<String, Error> {
+	if matches!(params, Some(ParamsSer::Map(_))) {
+		return Err(Error::Custom("JSON-RPC 1.0 does not support named params".into()));
+	}
+	serde_json::to_string(&v1::RequestSer::new(id, method, params)).map_err(Error::ParseError)
+}
+
+/// Get the result out of a JSON-RPC 1.0 response, errors are mapped to JSON-RPC 2.0 error responses when possible.
+fn v1_response_into_result<R: DeserializeOwned>(response: v1::Response<R>, method: &str) -> Result<R, Error> {
+	match (response.error, response.result) {
+		(Some(err), _) => match serde_json::from_str::<ErrorObject>(err.get()) {
+			Ok(err) => Err(error_response_into_error(&ErrorResponse::new(err, response.id), method)),
+			Err(_) => Err(Error::Request(err.get().to_owned())),
+		},
+		(None, Some(result)) => Ok(result),
+		(None, None) => serde_json::from_value(JsonValue::Null).map_err(Error::ParseError),
+	}
+}
+
+#[async_trait]
+impl ClientT for HttpClient {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		let notif = if self.v1_compat {
+			serialize_v1_request(&Id::Null, method, params)?
+		} else {
+			serde_json::to_string(&NotificationSer::new(method, params)).map_err(Error::ParseError)?
+		};
+		let fut = self.transport.send(notif);
+		match tokio::time::timeout(self.request_timeout(), fut).await {
+			Ok(Ok(ok)) => Ok(ok),
+			Err(_) => Err(Error::RequestTimeout),
+			Ok(Err(e)) => Err(Error::Transport(e.into())),
+		}
+	}
+
+	/// Perform a request towards the server.
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		self.request_with_headers(method, params, Vec::new()).await
+	}
+
 	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
 	where
 		R: DeserializeOwned + Default + Clone,
@@ -169,37 +449,82 @@ impl ClientT for HttpClient {
 		// NOTE(niklasad1): `ID` is not necessarily monotonically increasing.
 		let mut ordered_requests = Vec::with_capacity(batch.len());
 		let mut request_set = FxHashMap::with_capacity_and_hasher(batch.len(), Default::default());
+		let mut methods = Vec::with_capacity(batch.len());
 
 		for (pos, (method, params)) in batch.into_iter().enumerate() {
-			batch_request.push(RequestSer::new(&ids[pos], method, params));
+			if self.v1_compat {
+				batch_request.push(serialize_v1_request(&ids[pos], method, params)?);
+			} else {
+				batch_request.push(
+					serde_json::to_string(&RequestSer::new(&ids[pos], method, params)).map_err(Error::ParseError)?,
+				);
+			}
 			ordered_requests.push(&ids[pos]);
 			request_set.insert(&ids[pos], pos);
+			methods.push(method);
 		}
 
-		let fut = self.transport.send_and_read_body(serde_json::to_string(&batch_request).map_err(Error::ParseError)?);
+		let mut headers = Vec::new();
+		self.add_idempotency_key(&mut headers);
+		let fut = self.transport.send_and_read_body(format!("[{}]", batch_request.join(",")), &headers);
 
-		let body = match tokio::time::timeout(self.request_timeout, fut).await {
+		let body = match tokio::time::timeout(self.request_timeout(), fut).await {
 			Ok(Ok(body)) => body,
 			Err(_e) => return Err(Error::RequestTimeout),
 			Ok(Err(e)) => return Err(Error::Transport(e.into())),
 		};
+		check_json_depth(&body, self.max_json_depth)?;
+
+		// NOTE: `R::default` is placeholder and will be replaced in loop below.
+		let mut responses = vec![R::default(); ordered_requests.len()];
+
+		if self.v1_compat {
+			let rps: Vec<v1::Response<_>> = serde_json::from_slice(&body).map_err(Error::ParseError)?;
+			for rp in rps {
+				let pos = match request_set.get(&rp.id) {
+					Some(pos) => *pos,
+					None => return Err(Error::InvalidRequestId),
+				};
+				responses[pos] = v1_response_into_result(rp, methods[pos])?;
+			}
+			return Ok(responses);
+		}
 
-		let rps: Vec<Response<_>> =
+		let rps: Vec<&RawValue> =
 			serde_json::from_slice(&body).map_err(|_| match serde_json::from_slice::<ErrorResponse>(&body) {
-				Ok(e) => Error::Request(e.to_string()),
+				Ok(e) => match request_set.get(&e.id) {
+					Some(pos) => error_response_into_error(&e, methods[*pos]),
+					None => Error::Request(e.to_string()),
+				},
 				Err(e) => Error::ParseError(e),
 			})?;
 
-		// NOTE: `R::default` is placeholder and will be replaced in loop below.
-		let mut responses = vec![R::default(); ordered_requests.len()];
+		// The error response to the first call of the batch that failed, if any.
+		let mut failed: Option<(usize, Error)> = None;
 		for rp in rps {
-			let pos = match request_set.get(&rp.id) {
+			let (id, result) = match serde_json::from_str::<Response<R>>(rp.get()) {
+				Ok(rp) => (rp.id, Ok(rp.result)),
+				Err(e) => match serde_json::from_str::<ErrorResponse>(rp.get()) {
+					Ok(err) => (err.id.clone(), Err(err)),
+					Err(_) => return Err(Error::ParseError(e)),
+				},
+			};
+			let pos = match request_set.get(&id) {
 				Some(pos) => *pos,
 				None => return Err(Error::InvalidRequestId),
 			};
-			responses[pos] = rp.result
+			match result {
+				Ok(result) => responses[pos] = result,
+				Err(err) if failed.as_ref().is_none_or(|(first, _)| pos < *first) => {
+					failed = Some((pos, error_response_into_error(&err, methods[pos])));
+				}
+				Err(_) => (),
+			}
+		}
+		match failed {
+			Some((_, err)) => Err(err),
+			None => Ok(responses),
 		}
-		Ok(responses)
 	}
 }
 
@@ -24,14 +24,15 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use crate::transport::Error as TransportError;
 use crate::types::error::{ErrorCode, ErrorObject, ErrorResponse};
 use crate::types::ParamsSer;
 use crate::HttpClientBuilder;
 use jsonrpsee_core::client::{ClientT, IdKind};
 use jsonrpsee_core::rpc_params;
-use jsonrpsee_core::Error;
+use jsonrpsee_core::{Error, JsonValue};
 use jsonrpsee_test_utils::helpers::*;
-use jsonrpsee_test_utils::mocks::Id;
+use jsonrpsee_test_utils::mocks::{Id, StatusCode};
 use jsonrpsee_test_utils::TimeoutFutureExt;
 
 #[tokio::test]
@@ -57,6 +58,22 @@ async fn method_call_with_wrong_id_kind() {
 	));
 }
 
+#[tokio::test]
+async fn method_call_with_custom_id_sequence() {
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(100)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().id_sequence(100, 10).build(&uri).unwrap();
+	let response: String = client.request("o", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(&response, "hello");
+
+	// The second request uses the ID 110, which doesn't match the hardcoded response anymore.
+	let err = client.request::<String>("o", None).with_default_timeout().await.unwrap();
+	assert!(matches!(err, Err(Error::InvalidRequestId)));
+}
+
 #[tokio::test]
 async fn method_call_with_id_str() {
 	let exp = "id as string";
@@ -70,6 +87,249 @@ async fn method_call_with_id_str() {
 	assert_eq!(&response, exp);
 }
 
+#[tokio::test]
+async fn max_request_body_size_can_be_changed_at_runtime() {
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+
+	let response: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(&response, "hello");
+
+	// Clones share the configuration.
+	client.clone().set_max_request_body_size(10);
+	assert_eq!(client.max_request_body_size(), 10);
+
+	let err = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::Transport(e) if e.to_string() == "The request body was too large"));
+}
+
+#[tokio::test]
+async fn response_body_size_follows_the_request_limit_unless_set() {
+	let big = "x".repeat(1000);
+	let server_addr =
+		http_server_with_hardcoded_response(ok_response(big.into(), Id::Num(0))).with_default_timeout().await.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	assert!(client.request::<String>("say_hello", None).with_default_timeout().await.unwrap().is_ok());
+
+	client.set_max_request_body_size(500);
+	assert_eq!(client.max_response_body_size(), 500);
+	let err = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::Transport(_)));
+
+	// The mock always answers with the id of the first call.
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	client.set_max_response_body_size(2000);
+	client.set_max_request_body_size(100);
+	assert_eq!(client.max_response_body_size(), 2000);
+	assert!(client.request::<String>("say_hello", None).with_default_timeout().await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn request_and_response_body_sizes_are_limited_separately() {
+	let big = "x".repeat(1000);
+	let server_addr = http_server_with_hardcoded_response(ok_response(big.clone().into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client =
+		HttpClientBuilder::default().max_request_body_size(100).max_response_body_size(2000).build(&uri).unwrap();
+	assert_eq!(client.max_request_body_size(), 100);
+	assert_eq!(client.max_response_body_size(), 2000);
+
+	let response: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, big);
+
+	let err =
+		client.request::<String>("say_hello", rpc_params![big]).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::Transport(e) if e.to_string() == "The request body was too large"));
+
+	client.set_max_response_body_size(500);
+	let err = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::Transport(_)));
+}
+
+#[tokio::test]
+async fn deeply_nested_responses_are_rejected() {
+	let nested = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{},"id":0}}"#, nested);
+	let server_addr = http_server_with_hardcoded_response(response).with_default_timeout().await.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+
+	let err = client.request::<JsonValue>("say_hello", None).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::ParseError(_)));
+}
+
+#[tokio::test]
+async fn per_request_headers_are_only_sent_with_that_request() {
+	let server_addr = http_server_echoing_header("idempotency-key").with_default_timeout().await.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+
+	let headers = vec![("Idempotency-Key".to_owned(), "abc".to_owned())];
+	let key: Option<String> =
+		client.request_with_headers("say_hello", None, headers).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(key.as_deref(), Some("abc"));
+
+	let key: Option<String> = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(key, None);
+
+	let headers = vec![("Idempotency Key".to_owned(), "abc".to_owned())];
+	let err = client
+		.request_with_headers::<Option<String>>("say_hello", None, headers)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert!(matches!(err, Error::Transport(e) if e.to_string() == "Invalid request header: Idempotency Key"));
+}
+
+#[tokio::test]
+async fn request_signer_adds_a_signature_of_the_body() {
+	use std::hash::{Hash, Hasher};
+	use std::sync::{Arc, Mutex};
+
+	// Stands in for an HMAC keyed with a secret shared with the server.
+	fn sign(body: &str) -> String {
+		let mut hasher = std::collections::hash_map::DefaultHasher::new();
+		"secret".hash(&mut hasher);
+		body.hash(&mut hasher);
+		format!("{:016x}", hasher.finish())
+	}
+
+	let server_addr = http_server_echoing_header("x-signature").with_default_timeout().await.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+
+	let signed_bodies = Arc::new(Mutex::new(Vec::new()));
+	{
+		let signed_bodies = signed_bodies.clone();
+		client.set_request_signer(move |req| {
+			assert_eq!(req.headers()["content-type"], "application/json");
+			let signature = sign(req.body()).parse().unwrap();
+			req.headers_mut().insert("x-signature", signature);
+			signed_bodies.lock().unwrap().push(req.body().to_owned());
+		});
+	}
+
+	let signature: Option<String> =
+		client.request("say_hello", rpc_params![1]).with_default_timeout().await.unwrap().unwrap();
+	let body = signed_bodies.lock().unwrap().pop().unwrap();
+	let call: JsonValue = serde_json::from_str(&body).unwrap();
+	assert_eq!(call["method"], "say_hello");
+	assert_eq!(call["params"], serde_json::json!([1]));
+	assert_eq!(signature, Some(sign(&body)));
+
+	// The signer is shared with the clones of the client.
+	let signature: Option<String> =
+		client.clone().request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	let body = signed_bodies.lock().unwrap().pop().unwrap();
+	assert_eq!(signature, Some(sign(&body)));
+}
+
+#[tokio::test]
+async fn idempotency_keys_are_unique_per_call() {
+	let server_addr = http_server_echoing_header("idempotency-key").with_default_timeout().await.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().idempotency_keys(true).build(&uri).unwrap();
+
+	let first: Option<String> = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	let second: Option<String> = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	let (first, second) = (first.unwrap(), second.unwrap());
+	assert_eq!(first.len(), 32);
+	assert_ne!(first, second);
+
+	// The key of a call can be sent again when retrying it.
+	let headers = vec![("idempotency-key".to_owned(), first.clone())];
+	let retried: Option<String> =
+		client.request_with_headers("say_hello", None, headers).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(retried, Some(first));
+}
+
+#[tokio::test]
+async fn request_failure_includes_response_body() {
+	let body = r#"{"error":"bad request"}"#;
+	let server_addr = http_server_with_hardcoded_status(StatusCode::BAD_REQUEST, body.to_owned())
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+
+	let err = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap().unwrap_err();
+	let err = match err {
+		Error::Transport(e) => e.downcast::<TransportError>().unwrap(),
+		e => panic!("Unexpected error: {:?}", e),
+	};
+	assert!(matches!(err, TransportError::RequestFailure { status_code: 400, body: b } if b == body));
+}
+
+#[tokio::test]
+async fn shutdown_closes_the_client_and_its_clones() {
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let clone = client.clone();
+
+	let res: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(res, "hello");
+	client.shutdown();
+
+	let err = clone.request::<String>("say_hello", None).with_default_timeout().await.unwrap().unwrap_err();
+	let err = match err {
+		Error::Transport(e) => e.downcast::<TransportError>().unwrap(),
+		e => panic!("Unexpected error: {:?}", e),
+	};
+	assert!(matches!(err, TransportError::Shutdown));
+}
+
+#[test]
+fn build_rejects_invalid_settings() {
+	let err = HttpClientBuilder::default().max_request_body_size(0).build("http://localhost:9933").unwrap_err();
+	assert!(matches!(err, Error::Custom(msg) if msg == "Max request body size must be greater than zero"));
+
+	let err = HttpClientBuilder::default().max_response_body_size(0).build("http://localhost:9933").unwrap_err();
+	assert!(matches!(err, Error::Custom(_)));
+
+	let err = HttpClientBuilder::default()
+		.request_timeout(std::time::Duration::ZERO)
+		.build("http://localhost:9933")
+		.unwrap_err();
+	assert!(matches!(err, Error::Custom(_)));
+}
+
+#[tokio::test]
+async fn request_timeout_can_be_changed_at_runtime() {
+	// Accepts connections but never answers.
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let uri = format!("http://{}", listener.local_addr().unwrap());
+	tokio::spawn(async move {
+		let mut sockets = Vec::new();
+		while let Ok((socket, _)) = listener.accept().await {
+			sockets.push(socket);
+		}
+	});
+
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	assert_eq!(client.request_timeout(), std::time::Duration::from_secs(60));
+
+	client.set_request_timeout(std::time::Duration::from_millis(100));
+	assert_eq!(client.clone().request_timeout(), std::time::Duration::from_millis(100));
+	// Gives up after the new timeout rather than the 60 seconds the client was built with.
+	let request = client.request::<String>("say_hello", None);
+	let err = request.with_timeout(std::time::Duration::from_secs(10)).await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::RequestTimeout));
+}
+
 #[tokio::test]
 async fn notification_works() {
 	let server_addr = http_server_with_hardcoded_response(String::new()).with_default_timeout().await.unwrap();
@@ -83,6 +343,34 @@ async fn notification_works() {
 		.unwrap();
 }
 
+#[tokio::test]
+async fn v1_compat_method_call_works() {
+	let response = r#"{"result":"hello","error":null,"id":0}"#.to_string();
+	let result = run_v1_request_with_response(response).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!("hello", &result);
+}
+
+#[tokio::test]
+async fn v1_compat_error_response() {
+	let response = r#"{"result":null,"error":{"code":-32601,"message":"Method not found"},"id":0}"#.to_string();
+	let err = run_v1_request_with_response(response).with_default_timeout().await.unwrap().unwrap_err();
+	assert_method_not_found(err, "say_hello");
+
+	let response = r#"{"result":null,"error":{"code":-32603,"message":"Internal error"},"id":0}"#.to_string();
+	let err = run_v1_request_with_response(response).with_default_timeout().await.unwrap().unwrap_err();
+	assert_jsonrpc_error_response(err, ErrorCode::InternalError.into());
+}
+
+#[tokio::test]
+async fn v1_compat_rejects_named_params() {
+	let server_addr = http_server_with_hardcoded_response(String::new()).with_default_timeout().await.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().v1_compat(true).build(&uri).unwrap();
+	let params = ParamsSer::Map(std::iter::once(("a", 1.into())).collect());
+	let res: Result<String, _> = client.request("say_hello", Some(params)).with_default_timeout().await.unwrap();
+	assert!(matches!(res, Err(Error::Custom(_))));
+}
+
 #[tokio::test]
 async fn response_with_wrong_id() {
 	let err = run_request_with_response(ok_response("hello".into(), Id::Num(99)))
@@ -97,7 +385,7 @@ async fn response_with_wrong_id() {
 async fn response_method_not_found() {
 	let err =
 		run_request_with_response(method_not_found(Id::Num(0))).with_default_timeout().await.unwrap().unwrap_err();
-	assert_jsonrpc_error_response(err, ErrorCode::MethodNotFound.into());
+	assert_method_not_found(err, "say_hello");
 }
 
 #[tokio::test]
@@ -154,6 +442,30 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+#[tokio::test]
+async fn batch_request_with_unknown_method() {
+	let batch_request = vec![("say_hello", None), ("say_goodbye", rpc_params![0_u64, 1, 2]), ("get_swag", None)];
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}, {"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}, {"jsonrpc":"2.0","result":"here's your swag","id":2}]"#.to_string();
+	let err = run_batch_request_with_response(batch_request, server_response)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert_method_not_found(err, "say_goodbye");
+}
+
+#[tokio::test]
+async fn batch_request_with_failed_call() {
+	let batch_request = vec![("say_hello", None), ("say_goodbye", rpc_params![0_u64, 1, 2])];
+	let server_response = r#"[{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error"},"id":1}, {"jsonrpc":"2.0","result":"hello","id":0}]"#.to_string();
+	let err = run_batch_request_with_response(batch_request, server_response)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert_jsonrpc_error_response(err, ErrorCode::InternalError.into());
+}
+
 async fn run_batch_request_with_response<'a>(
 	batch: Vec<(&'a str, Option<ParamsSer<'a>>)>,
 	response: String,
@@ -171,6 +483,13 @@ async fn run_request_with_response(response: String) -> Result<String, Error> {
 	client.request("say_hello", None).with_default_timeout().await.unwrap()
 }
 
+async fn run_v1_request_with_response(response: String) -> Result<String, Error> {
+	let server_addr = http_server_with_hardcoded_response(response).with_default_timeout().await.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().v1_compat(true).build(&uri).unwrap();
+	client.request("say_hello", None).with_default_timeout().await.unwrap()
+}
+
 fn assert_jsonrpc_error_response(err: Error, exp: ErrorObject) {
 	match &err {
 		Error::Request(e) => {
@@ -180,3 +499,13 @@ fn assert_jsonrpc_error_response(err: Error, exp: ErrorObject) {
 		e => panic!("Expected error: \"{}\", got: {:?}", err, e),
 	};
 }
+
+fn assert_method_not_found(err: Error, exp_method: &str) {
+	match err {
+		Error::RemoteMethodNotFound { method, error } => {
+			assert_eq!(method, exp_method);
+			assert_jsonrpc_error_response(Error::Request(error), ErrorCode::MethodNotFound.into());
+		}
+		e => panic!("Expected a method not found error, got: {:?}", e),
+	}
+}
@@ -5,8 +5,19 @@
 // servers through the same `hyper::Client`, we don't use that feature on purpose. The reason is
 // that we need to be guaranteed that hyper doesn't re-use an existing connection if we ever reset
 // the JSON-RPC request id to a value that might have already been used.
+//
+// Connections to the single target are pooled and kept alive though, which saves a TCP/TLS handshake
+// per request. This is safe because every response is read from the HTTP exchange of its request, so
+// request ids are never matched across pooled connections. The tradeoff is that idle connections hold
+// a socket on both ends until `idle_timeout` expires; set `max_idle_connections` to zero to disable reuse.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+use hyper::body::HttpBody;
 use hyper::client::{Client, HttpConnector};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
 use hyper::Uri;
 use jsonrpsee_core::client::CertificateStore;
 use jsonrpsee_core::error::GenericTransportError;
@@ -14,6 +25,23 @@ use jsonrpsee_core::http_helpers;
 use thiserror::Error;
 
 const CONTENT_TYPE_JSON: &str = "application/json";
+/// Maximum number of bytes of the response body kept in [`Error::RequestFailure`].
+const MAX_FAILURE_BODY_SIZE: usize = 4 * 1024;
+
+/// Connection pool configuration of the HTTP transport.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PoolConfig {
+	/// Maximum number of idle connections kept alive.
+	pub(crate) max_idle_connections: usize,
+	/// Duration after which idle connections are closed, `None` keeps them open forever.
+	pub(crate) idle_timeout: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+	fn default() -> Self {
+		Self { max_idle_connections: usize::MAX, idle_timeout: Some(Duration::from_secs(90)) }
+	}
+}
 
 #[derive(Debug, Clone)]
 enum HyperClient {
@@ -34,47 +62,97 @@ impl HyperClient {
 	}
 }
 
+/// Request about to be sent, given to the signer set with [`HttpTransportClient::set_request_signer`].
+#[derive(Debug)]
+pub struct OutgoingRequest<'a> {
+	body: &'a str,
+	headers: &'a mut HeaderMap,
+}
+
+impl<'a> OutgoingRequest<'a> {
+	/// Serialized body of the request, exactly as it is sent.
+	pub fn body(&self) -> &str {
+		self.body
+	}
+
+	/// Headers of the request.
+	pub fn headers(&self) -> &HeaderMap {
+		self.headers
+	}
+
+	/// Headers of the request, to add a signature for instance.
+	pub fn headers_mut(&mut self) -> &mut HeaderMap {
+		self.headers
+	}
+}
+
+type SignerFn = Arc<dyn Fn(&mut OutgoingRequest) + Send + Sync>;
+
+/// Signer of the requests, shared between the clones of a client.
+#[derive(Clone, Default)]
+struct RequestSigner(Arc<RwLock<Option<SignerFn>>>);
+
+impl std::fmt::Debug for RequestSigner {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let is_set = self.0.read().expect("lock is never poisoned; qed").is_some();
+		f.debug_tuple("RequestSigner").field(&is_set).finish()
+	}
+}
+
 /// HTTP Transport Client.
 #[derive(Debug, Clone)]
 pub struct HttpTransportClient {
 	/// Target to connect to.
 	target: Uri,
-	/// HTTP client
-	client: HyperClient,
-	/// Configurable max request body size
-	max_request_body_size: u32,
+	/// HTTP client, `None` once shut down. Shared between clones so that shutting down one shuts down all.
+	client: Arc<RwLock<Option<HyperClient>>>,
+	/// Configurable max request body size, shared between clones so that it can be changed at runtime.
+	max_request_body_size: Arc<AtomicU32>,
+	/// Configurable max response body size, shared between clones so that it can be changed at runtime.
+	/// Zero until set explicitly, in which case the max request body size applies.
+	max_response_body_size: Arc<AtomicU32>,
+	/// Runs on each request before it is sent, if set.
+	signer: RequestSigner,
 }
 
 impl HttpTransportClient {
-	/// Initializes a new HTTP client.
+	/// Initializes a new HTTP client, `max_request_body_size` limits the size of both the requests and the
+	/// responses, including after it's changed at runtime, until the latter is set explicitly.
+	///
+	/// `tls_server_name` replaces the host of `target` as SNI and as the name expected in the certificate.
 	pub(crate) fn new(
 		target: impl AsRef<str>,
 		max_request_body_size: u32,
 		cert_store: CertificateStore,
+		pool: PoolConfig,
+		tls_server_name: Option<String>,
 	) -> Result<Self, Error> {
 		let target: Uri = target.as_ref().parse().map_err(|e| Error::Url(format!("Invalid URL: {}", e)))?;
 		if target.port_u16().is_none() {
 			return Err(Error::Url("Port number is missing in the URL".into()));
 		}
 
+		let mut builder = Client::builder();
+		builder.pool_max_idle_per_host(pool.max_idle_connections).pool_idle_timeout(pool.idle_timeout);
+
 		let client = match target.scheme_str() {
 			Some("http") => {
 				let connector = HttpConnector::new();
-				let client = Client::builder().build::<_, hyper::Body>(connector);
+				let client = builder.build::<_, hyper::Body>(connector);
 				HyperClient::Http(client)
 			}
 			#[cfg(feature = "tls")]
 			Some("https") => {
 				let connector = match cert_store {
-					CertificateStore::Native => {
-						hyper_rustls::HttpsConnectorBuilder::new().with_native_roots().https_or_http().enable_http1()
-					}
-					CertificateStore::WebPki => {
-						hyper_rustls::HttpsConnectorBuilder::new().with_webpki_roots().https_or_http().enable_http1()
-					}
+					CertificateStore::Native => hyper_rustls::HttpsConnectorBuilder::new().with_native_roots(),
+					CertificateStore::WebPki => hyper_rustls::HttpsConnectorBuilder::new().with_webpki_roots(),
 					_ => return Err(Error::InvalidCertficateStore),
 				};
-				let client = Client::builder().build::<_, hyper::Body>(connector.build());
+				let connector = match tls_server_name {
+					Some(name) => connector.https_or_http().with_server_name(name),
+					None => connector.https_or_http(),
+				};
+				let client = builder.build::<_, hyper::Body>(connector.enable_http1().build());
 				HyperClient::Https(client)
 			}
 			_ => {
@@ -85,45 +163,136 @@ impl HttpTransportClient {
 				return Err(Error::Url(err.into()));
 			}
 		};
-		Ok(Self { target, client, max_request_body_size })
+		Ok(Self {
+			target,
+			client: Arc::new(RwLock::new(Some(client))),
+			max_request_body_size: Arc::new(AtomicU32::new(max_request_body_size)),
+			max_response_body_size: Arc::new(AtomicU32::new(0)),
+			signer: RequestSigner::default(),
+		})
+	}
+
+	/// Limits the size of the responses independently of the requests.
+	pub(crate) fn with_max_response_body_size(mut self, size: u32) -> Self {
+		self.max_response_body_size = Arc::new(AtomicU32::new(size));
+		self
+	}
+
+	/// Returns the max request body size.
+	pub(crate) fn max_request_body_size(&self) -> u32 {
+		self.max_request_body_size.load(Ordering::Relaxed)
+	}
+
+	/// Sets the max request body size for subsequent requests.
+	pub(crate) fn set_max_request_body_size(&self, size: u32) {
+		self.max_request_body_size.store(size, Ordering::Relaxed);
+	}
+
+	/// Returns the max response body size, which is the max request body size unless set explicitly.
+	pub(crate) fn max_response_body_size(&self) -> u32 {
+		match self.max_response_body_size.load(Ordering::Relaxed) {
+			0 => self.max_request_body_size(),
+			size => size,
+		}
 	}
 
-	async fn inner_send(&self, body: String) -> Result<hyper::Response<hyper::Body>, Error> {
+	/// Sets the max response body size for subsequent requests, zero makes it follow the max request body size.
+	pub(crate) fn set_max_response_body_size(&self, size: u32) {
+		self.max_response_body_size.store(size, Ordering::Relaxed);
+	}
+
+	/// Run `signer` on each request of this client and its clones right before it is sent, after all the other
+	/// headers were set, replacing the previous signer if any.
+	pub fn set_request_signer(&self, signer: impl Fn(&mut OutgoingRequest) + Send + Sync + 'static) {
+		*self.signer.0.write().expect("lock is never poisoned; qed") = Some(Arc::new(signer));
+	}
+
+	/// Close the pooled connections of this client and all its clones, which fail with [`Error::Shutdown`] from
+	/// then on. Requests that are in flight complete and close their connection afterwards.
+	pub fn shutdown(self) {
+		self.client.write().expect("lock is never poisoned; qed").take();
+	}
+
+	async fn inner_send(
+		&self,
+		body: String,
+		max_request_body_size: u32,
+		headers: &[(String, String)],
+	) -> Result<hyper::Response<hyper::Body>, Error> {
 		tracing::debug!("send: {}", body);
 
-		if body.len() > self.max_request_body_size as usize {
+		if body.len() > max_request_body_size as usize {
 			return Err(Error::RequestTooLarge);
 		}
 
-		let req = hyper::Request::post(&self.target)
-			.header(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON))
-			.header(hyper::header::ACCEPT, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON))
-			.body(From::from(body))
+		let mut req = hyper::Request::post(&self.target)
+			.header(hyper::header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE_JSON))
+			.header(hyper::header::ACCEPT, HeaderValue::from_static(CONTENT_TYPE_JSON))
+			.body(())
 			.expect("URI and request headers are valid; qed");
 
-		let response = self.client.request(req).await.map_err(|e| Error::Http(Box::new(e)))?;
+		for (name, value) in headers {
+			let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| Error::InvalidHeader(name.clone()))?;
+			let value = HeaderValue::from_str(value).map_err(|_| Error::InvalidHeader(name.to_string()))?;
+			req.headers_mut().insert(name, value);
+		}
+
+		let signer = self.signer.0.read().expect("lock is never poisoned; qed").clone();
+		if let Some(signer) = signer {
+			signer(&mut OutgoingRequest { body: &body, headers: req.headers_mut() });
+		}
+		let req = req.map(|()| hyper::Body::from(body));
+
+		let client = self.client.read().expect("lock is never poisoned; qed").clone().ok_or(Error::Shutdown)?;
+		let response = client.request(req).await.map_err(|e| Error::Http(Box::new(e)))?;
 		if response.status().is_success() {
 			Ok(response)
 		} else {
-			Err(Error::RequestFailure { status_code: response.status().into() })
+			let status_code = response.status().into();
+			let body = read_failure_body(response.into_body()).await;
+			Err(Error::RequestFailure { status_code, body })
 		}
 	}
 
 	/// Send serialized message and wait until all bytes from the HTTP message body have been read.
-	pub(crate) async fn send_and_read_body(&self, body: String) -> Result<Vec<u8>, Error> {
-		let response = self.inner_send(body).await?;
+	///
+	/// `headers` are added to the request, replacing the default ones with the same name.
+	pub(crate) async fn send_and_read_body(
+		&self,
+		body: String,
+		headers: &[(String, String)],
+	) -> Result<Vec<u8>, Error> {
+		// Read the limit once so that it stays the same for the whole request.
+		let max_response_body_size = self.max_response_body_size();
+		let response = self.inner_send(body, self.max_request_body_size(), headers).await?;
 		let (parts, body) = response.into_parts();
-		let (body, _) = http_helpers::read_body(&parts.headers, body, self.max_request_body_size).await?;
+		let (body, _) = http_helpers::read_body(&parts.headers, body, max_response_body_size).await?;
 		Ok(body)
 	}
 
 	/// Send serialized message without reading the HTTP message body.
 	pub(crate) async fn send(&self, body: String) -> Result<(), Error> {
-		let _ = self.inner_send(body).await?;
+		let _ = self.inner_send(body, self.max_request_body_size(), &[]).await?;
 		Ok(())
 	}
 }
 
+/// Read at most [`MAX_FAILURE_BODY_SIZE`] bytes of the body of a failed request.
+///
+/// Errors while reading are ignored, whatever was received until then is returned.
+async fn read_failure_body(mut body: hyper::Body) -> String {
+	let mut received = Vec::new();
+	while let Some(Ok(chunk)) = body.data().await {
+		let remaining = MAX_FAILURE_BODY_SIZE - received.len();
+		if chunk.len() >= remaining {
+			received.extend_from_slice(&chunk[..remaining]);
+			break;
+		}
+		received.extend_from_slice(&chunk);
+	}
+	String::from_utf8_lossy(&received).into_owned()
+}
+
 /// Error that can happen during a request.
 #[derive(Debug, Error)]
 pub enum Error {
@@ -136,10 +305,12 @@ pub enum Error {
 	Http(Box<dyn std::error::Error + Send + Sync>),
 
 	/// Server returned a non-success status code.
-	#[error("Server returned an error status code: {:?}", status_code)]
+	#[error("Server returned an error status code: {:?}, body: {}", status_code, body)]
 	RequestFailure {
 		/// Status code returned by the server.
 		status_code: u16,
+		/// Body returned by the server, truncated to 4 KiB.
+		body: String,
 	},
 
 	/// Request body too large.
@@ -153,6 +324,14 @@ pub enum Error {
 	/// Invalid certificate store.
 	#[error("Invalid certificate store")]
 	InvalidCertficateStore,
+
+	/// Invalid name or value of a request header.
+	#[error("Invalid request header: {0}")]
+	InvalidHeader(String),
+
+	/// The client was shut down.
+	#[error("The client was shut down")]
+	Shutdown,
 }
 
 impl<T> From<GenericTransportError<T>> for Error
@@ -170,7 +349,7 @@ where
 
 #[cfg(test)]
 mod tests {
-	use super::{CertificateStore, Error, HttpTransportClient};
+	use super::{CertificateStore, Error, HttpTransportClient, PoolConfig};
 
 	fn assert_target(
 		client: &HttpTransportClient,
@@ -184,41 +363,72 @@ mod tests {
 		assert_eq!(client.target.path_and_query().map(|pq| pq.as_str()), Some(path_and_query));
 		assert_eq!(client.target.host(), Some(host));
 		assert_eq!(client.target.port_u16(), Some(port));
-		assert_eq!(client.max_request_body_size, max_request_size);
+		assert_eq!(client.max_request_body_size(), max_request_size);
 	}
 
 	#[test]
 	fn invalid_http_url_rejected() {
-		let err = HttpTransportClient::new("ws://localhost:9933", 80, CertificateStore::Native).unwrap_err();
+		let err =
+			HttpTransportClient::new("ws://localhost:9933", 80, CertificateStore::Native, PoolConfig::default(), None)
+				.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[cfg(feature = "tls")]
 	#[test]
 	fn https_works() {
-		let client = HttpTransportClient::new("https://localhost:9933", 80, CertificateStore::Native).unwrap();
+		let client = HttpTransportClient::new(
+			"https://localhost:9933",
+			80,
+			CertificateStore::Native,
+			PoolConfig::default(),
+			None,
+		)
+		.unwrap();
 		assert_target(&client, "localhost", "https", "/", 9933, 80);
 	}
 
 	#[cfg(not(feature = "tls"))]
 	#[test]
 	fn https_fails_without_tls_feature() {
-		let err = HttpTransportClient::new("https://localhost:9933", 80, CertificateStore::Native).unwrap_err();
+		let err = HttpTransportClient::new(
+			"https://localhost:9933",
+			80,
+			CertificateStore::Native,
+			PoolConfig::default(),
+			None,
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[test]
 	fn faulty_port() {
-		let err = HttpTransportClient::new("http://localhost:-43", 80, CertificateStore::Native).unwrap_err();
+		let err =
+			HttpTransportClient::new("http://localhost:-43", 80, CertificateStore::Native, PoolConfig::default(), None)
+				.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
-		let err = HttpTransportClient::new("http://localhost:-99999", 80, CertificateStore::Native).unwrap_err();
+		let err = HttpTransportClient::new(
+			"http://localhost:-99999",
+			80,
+			CertificateStore::Native,
+			PoolConfig::default(),
+			None,
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[test]
 	fn url_with_path_works() {
-		let client =
-			HttpTransportClient::new("http://localhost:9944/my-special-path", 1337, CertificateStore::Native).unwrap();
+		let client = HttpTransportClient::new(
+			"http://localhost:9944/my-special-path",
+			1337,
+			CertificateStore::Native,
+			PoolConfig::default(),
+			None,
+		)
+		.unwrap();
 		assert_target(&client, "localhost", "http", "/my-special-path", 9944, 1337);
 	}
 
@@ -228,6 +438,8 @@ mod tests {
 			"http://127.0.0.1:9999/my?name1=value1&name2=value2",
 			u32::MAX,
 			CertificateStore::WebPki,
+			PoolConfig::default(),
+			None,
 		)
 		.unwrap();
 		assert_target(&client, "127.0.0.1", "http", "/my?name1=value1&name2=value2", 9999, u32::MAX);
@@ -235,16 +447,29 @@ mod tests {
 
 	#[test]
 	fn url_with_fragment_is_ignored() {
-		let client =
-			HttpTransportClient::new("http://127.0.0.1:9944/my.htm#ignore", 999, CertificateStore::Native).unwrap();
+		let client = HttpTransportClient::new(
+			"http://127.0.0.1:9944/my.htm#ignore",
+			999,
+			CertificateStore::Native,
+			PoolConfig::default(),
+			None,
+		)
+		.unwrap();
 		assert_target(&client, "127.0.0.1", "http", "/my.htm", 9944, 999);
 	}
 
 	#[tokio::test]
 	async fn request_limit_works() {
 		let eighty_bytes_limit = 80;
-		let client = HttpTransportClient::new("http://localhost:9933", 80, CertificateStore::WebPki).unwrap();
-		assert_eq!(client.max_request_body_size, eighty_bytes_limit);
+		let client = HttpTransportClient::new(
+			"http://localhost:9933",
+			80,
+			CertificateStore::WebPki,
+			PoolConfig::default(),
+			None,
+		)
+		.unwrap();
+		assert_eq!(client.max_request_body_size(), eighty_bytes_limit);
 
 		let body = "a".repeat(81);
 		assert_eq!(body.len(), 81);
@@ -34,8 +34,9 @@ use futures::io::{BufReader, BufWriter};
 use jsonrpsee_core::client::{CertificateStore, TransportReceiverT, TransportSenderT};
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
 use jsonrpsee_core::{async_trait, Cow};
-use soketto::connection;
+use soketto::connection::{self, CloseReason};
 use soketto::handshake::client::{Client as WsHandshakeClient, ServerResponse};
+use soketto::Incoming;
 use stream::EitherStream;
 use thiserror::Error;
 use tokio::net::TcpStream;
@@ -47,6 +48,8 @@ pub use soketto::handshake::client::Header;
 #[derive(Debug)]
 pub struct Sender {
 	inner: connection::Sender<BufReader<BufWriter<EitherStream>>>,
+	/// Max size in bytes of the messages sent.
+	max_request_body_size: u32,
 }
 
 /// Receiving end of WebSocket transport.
@@ -69,6 +72,8 @@ pub struct WsTransportClientBuilder<'a> {
 	pub max_request_body_size: u32,
 	/// Max number of redirections.
 	pub max_redirections: usize,
+	/// Name of the server expected in its TLS certificate and sent as SNI, if not the host of the URL.
+	pub tls_server_name: Option<String>,
 }
 
 impl<'a> Default for WsTransportClientBuilder<'a> {
@@ -79,6 +84,7 @@ impl<'a> Default for WsTransportClientBuilder<'a> {
 			connection_timeout: Duration::from_secs(10),
 			headers: Vec::new(),
 			max_redirections: 5,
+			tls_server_name: None,
 		}
 	}
 }
@@ -90,7 +96,9 @@ impl<'a> WsTransportClientBuilder<'a> {
 		self
 	}
 
-	/// Set max request body size (default is 10 MB).
+	/// Set max request body size, which also limits the size of the responses (default is 10 MB).
+	///
+	/// Larger requests fail with [`WsError::RequestTooLarge`] without being sent.
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.max_request_body_size = size;
 		self
@@ -116,6 +124,16 @@ impl<'a> WsTransportClientBuilder<'a> {
 		self.max_redirections = redirect;
 		self
 	}
+
+	/// Set the name of the server to send as SNI and to validate its TLS certificate against, instead of the host
+	/// of the URL (default is the host of the URL).
+	///
+	/// Useful to connect to an IP address, or through a load balancer, to a server whose certificate is for a
+	/// hostname. The name is used for every `wss` connection attempt, including redirections.
+	pub fn tls_server_name(mut self, name: impl Into<String>) -> Self {
+		self.tls_server_name = Some(name.into());
+		self
+	}
 }
 
 /// Stream mode, either plain TCP or TLS.
@@ -127,6 +145,10 @@ pub enum Mode {
 	Tls,
 }
 
+/// ALPN protocol ID of HTTP/1.1, the only HTTP version the WebSocket handshake works with.
+#[cfg(feature = "tls")]
+const HTTP_1_1_ALPN: &[u8] = b"http/1.1";
+
 /// Error that can happen during the WebSocket handshake.
 ///
 /// If multiple IP addresses are attempted, only the last error is returned, similar to how
@@ -145,23 +167,39 @@ pub enum WsHandshakeError {
 	#[error("Error when opening the TCP socket: {0}")]
 	Io(io::Error),
 
-	/// Error in the transport layer.
+	/// I/O error during the WebSocket handshake.
 	#[error("Error in the WebSocket handshake: {0}")]
 	Transport(#[source] soketto::handshake::Error),
 
+	/// The server answered the WebSocket handshake with a response that violates the protocol, for example
+	/// one without a valid `Sec-WebSocket-Accept` header.
+	#[error("Invalid WebSocket handshake response: {0}")]
+	InvalidServerResponse(#[source] soketto::handshake::Error),
+
+	/// The server doesn't speak HTTP/1.1, which the WebSocket handshake requires. This is typically the case
+	/// for HTTP/2-only endpoints, for instance behind load balancers that force HTTP/2.
+	#[error("Unsupported protocol: {0}")]
+	UnsupportedProtocol(Cow<'static, str>),
+
+	/// TLS handshake failed.
+	#[cfg(feature = "tls")]
+	#[error("TLS handshake failed: {0}")]
+	Tls(#[source] io::Error),
+
 	/// Invalid DNS name error for TLS
 	#[cfg(feature = "tls")]
 	#[error("Invalid DNS name: {0}")]
 	InvalidDnsName(#[source] tokio_rustls::webpki::InvalidDnsNameError),
 
-	/// Server rejected the handshake.
+	/// Server rejected the handshake with a status code other than `101 Switching Protocols`, which is
+	/// for instance the case when the server doesn't speak WebSocket.
 	#[error("Connection rejected with status code: {status_code}")]
 	Rejected {
 		/// HTTP status code that the server returned.
 		status_code: u16,
 	},
 
-	/// Timeout while trying to connect.
+	/// Timeout while trying to connect or while waiting for the server to complete the handshake.
 	#[error("Connection timeout exceeded: {0:?}")]
 	Timeout(Duration),
 
@@ -180,6 +218,22 @@ pub enum WsError {
 	/// Error in the WebSocket connection.
 	#[error("WebSocket connection error: {0}")]
 	Connection(#[source] soketto::connection::Error),
+	/// The server closed the connection with a close frame.
+	#[error("The server closed the connection with code {code}: {reason}")]
+	Closed {
+		/// Close code sent by the server.
+		code: u16,
+		/// Reason sent by the server, empty if none.
+		reason: String,
+	},
+	/// The request is larger than the configured max request body size, it wasn't sent.
+	#[error("Request of {size} bytes exceeds the max request body size of {max} bytes")]
+	RequestTooLarge {
+		/// Size of the request in bytes.
+		size: usize,
+		/// Max request body size in bytes.
+		max: u32,
+	},
 }
 
 #[async_trait]
@@ -190,6 +244,9 @@ impl TransportSenderT for Sender {
 	/// successfully sent.
 	async fn send(&mut self, body: String) -> Result<(), WsError> {
 		tracing::debug!("send: {}", body);
+		if body.len() > self.max_request_body_size as usize {
+			return Err(WsError::RequestTooLarge { size: body.len(), max: self.max_request_body_size });
+		}
 		self.inner.send_text(body).await?;
 		self.inner.flush().await?;
 		Ok(())
@@ -208,10 +265,40 @@ impl TransportReceiverT for Receiver {
 	/// Returns a `Future` resolving when the server sent us something back.
 	async fn receive(&mut self) -> Result<String, WsError> {
 		let mut message = Vec::new();
-		self.inner.receive_data(&mut message).await?;
+		loop {
+			match self.inner.receive(&mut message).await? {
+				Incoming::Data(_) => break,
+				Incoming::Pong(_) => continue,
+				Incoming::Closed(CloseReason { code, descr }) => {
+					return Err(WsError::Closed { code, reason: descr.unwrap_or_default() })
+				}
+			}
+		}
 		let s = String::from_utf8(message).expect("Found invalid UTF-8");
 		Ok(s)
 	}
+
+	fn is_connection_closed(err: &WsError) -> bool {
+		match err {
+			WsError::Connection(connection::Error::Closed) => true,
+			WsError::Connection(connection::Error::Io(e)) => matches!(
+				e.kind(),
+				io::ErrorKind::UnexpectedEof
+					| io::ErrorKind::ConnectionReset
+					| io::ErrorKind::ConnectionAborted
+					| io::ErrorKind::BrokenPipe
+			),
+			WsError::Connection(_) | WsError::RequestTooLarge { .. } => false,
+			WsError::Closed { .. } => true,
+		}
+	}
+
+	fn close_frame(err: &WsError) -> Option<(u16, String)> {
+		match err {
+			WsError::Closed { code, reason } => Some((*code, reason.clone())),
+			_ => None,
+		}
+	}
 }
 
 impl<'a> WsTransportClientBuilder<'a> {
@@ -238,7 +325,9 @@ impl<'a> WsTransportClientBuilder<'a> {
 			let sockaddrs = std::mem::take(&mut target.sockaddrs);
 			for sockaddr in &sockaddrs {
 				#[cfg(feature = "tls")]
-				let tcp_stream = match connect(*sockaddr, self.connection_timeout, &target.host, connector.as_ref()).await {
+				let server_name = self.tls_server_name.as_deref().unwrap_or(&target.host);
+				#[cfg(feature = "tls")]
+				let tcp_stream = match connect(*sockaddr, self.connection_timeout, server_name, connector.as_ref()).await {
 					Ok(stream) => stream,
 					Err(e) => {
 						tracing::debug!("Failed to connect to sockaddr: {:?}", sockaddr);
@@ -266,13 +355,23 @@ impl<'a> WsTransportClientBuilder<'a> {
 				client.set_headers(&self.headers);
 
 				// Perform the initial handshake.
-				match client.handshake().await {
+				let response = match tokio::time::timeout(self.connection_timeout, client.handshake()).await {
+					Ok(response) => response,
+					Err(_) => {
+						tracing::debug!("Handshake with sockaddr: {:?} timed out", sockaddr);
+						err = Some(Err(WsHandshakeError::Timeout(self.connection_timeout)));
+						continue;
+					}
+				};
+
+				match response {
 					Ok(ServerResponse::Accepted { .. }) => {
 						tracing::info!("Connection established to target: {:?}", target);
 						let mut builder = client.into_builder();
 						builder.set_max_message_size(self.max_request_body_size as usize);
 						let (sender, receiver) = builder.finish();
-						return Ok((Sender { inner: sender }, Receiver { inner: receiver }));
+						let sender = Sender { inner: sender, max_request_body_size: self.max_request_body_size };
+						return Ok((sender, Receiver { inner: receiver }));
 					}
 
 					Ok(ServerResponse::Rejected { status_code }) => {
@@ -364,8 +463,14 @@ async fn connect(
 				None => Ok(EitherStream::Plain(socket)),
 				Some(connector) => {
 					let server_name: tokio_rustls::rustls::ServerName = host.try_into().map_err(|e| WsHandshakeError::Url(format!("Invalid host: {} {:?}", host, e).into()))?;
-					let tls_stream = connector.connect(server_name, socket).await?;
-					Ok(EitherStream::Tls(tls_stream))
+					let tls_stream = connector.connect(server_name, socket).await.map_err(WsHandshakeError::Tls)?;
+					match tls_stream.get_ref().1.alpn_protocol() {
+						Some(protocol) if protocol != HTTP_1_1_ALPN => {
+							let msg = format!("server negotiated {} instead of http/1.1", String::from_utf8_lossy(protocol));
+							Err(WsHandshakeError::UnsupportedProtocol(msg.into()))
+						}
+						_ => Ok(EitherStream::Tls(tls_stream)),
+					}
 				}
 			}
 		}
@@ -404,7 +509,19 @@ impl From<tokio_rustls::webpki::InvalidDnsNameError> for WsHandshakeError {
 
 impl From<soketto::handshake::Error> for WsHandshakeError {
 	fn from(err: soketto::handshake::Error) -> WsHandshakeError {
-		WsHandshakeError::Transport(err)
+		match err {
+			soketto::handshake::Error::Io(_) => WsHandshakeError::Transport(err),
+			soketto::handshake::Error::UnsupportedHttpVersion => {
+				WsHandshakeError::UnsupportedProtocol("server answered with an HTTP version other than 1.1".into())
+			}
+			// An HTTP/2 server answers with binary frames, which can't be parsed as an HTTP/1.x response.
+			soketto::handshake::Error::Http(ref e)
+				if e.downcast_ref::<httparse::Error>() == Some(&httparse::Error::Version) =>
+			{
+				WsHandshakeError::UnsupportedProtocol("server didn't answer with HTTP/1.1, is it HTTP/2 only?".into())
+			}
+			err => WsHandshakeError::InvalidServerResponse(err),
+		}
 	}
 }
 
@@ -498,8 +615,11 @@ fn build_tls_config(cert_store: &CertificateStore) -> Result<tokio_rustls::TlsCo
 		}
 	};
 
-	let config =
+	let mut config =
 		rustls::ClientConfig::builder().with_safe_defaults().with_root_certificates(roots).with_no_client_auth();
+	// The WebSocket handshake only works over HTTP/1.1, ask for it explicitly so that servers which
+	// default to HTTP/2 either switch or are detected right after the TLS handshake.
+	config.alpn_protocols = vec![HTTP_1_1_ALPN.to_vec()];
 
 	Ok(std::sync::Arc::new(config).into())
 }
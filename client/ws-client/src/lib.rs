@@ -78,6 +78,9 @@ pub struct WsClientBuilder<'a> {
 	max_notifs_per_subscription: usize,
 	max_redirections: usize,
 	id_kind: IdKind,
+	id_sequence: (u64, u64),
+	cancel_dropped_requests: bool,
+	tls_server_name: Option<String>,
 }
 
 impl<'a> Default for WsClientBuilder<'a> {
@@ -92,6 +95,9 @@ impl<'a> Default for WsClientBuilder<'a> {
 			max_notifs_per_subscription: 1024,
 			max_redirections: 5,
 			id_kind: IdKind::Number,
+			id_sequence: (0, 1),
+			cancel_dropped_requests: false,
+			tls_server_name: None,
 		}
 	}
 }
@@ -151,6 +157,24 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
+	/// See documentation for [`ClientBuilder::id_sequence`] (default is 0 and 1).
+	pub fn id_sequence(mut self, start: u64, stride: u64) -> Self {
+		self.id_sequence = (start, stride);
+		self
+	}
+
+	/// See documentation for [`ClientBuilder::cancel_dropped_requests`] (default is `false`).
+	pub fn cancel_dropped_requests(mut self, enabled: bool) -> Self {
+		self.cancel_dropped_requests = enabled;
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::tls_server_name`] (default is the host of the URL).
+	pub fn tls_server_name(mut self, name: impl Into<String>) -> Self {
+		self.tls_server_name = Some(name.into());
+		self
+	}
+
 	/// Build the client with specified URL to connect to.
 	/// You must provide the port number in the URL.
 	///
@@ -164,6 +188,7 @@ impl<'a> WsClientBuilder<'a> {
 			headers: self.headers,
 			max_request_body_size: self.max_request_body_size,
 			max_redirections: self.max_redirections,
+			tls_server_name: self.tls_server_name,
 		};
 
 		let uri: Uri = url.as_ref().parse().map_err(|e: InvalidUri| Error::Transport(e.into()))?;
@@ -174,6 +199,8 @@ impl<'a> WsClientBuilder<'a> {
 			.request_timeout(self.request_timeout)
 			.max_concurrent_requests(self.max_concurrent_requests)
 			.id_format(self.id_kind)
+			.id_sequence(self.id_sequence.0, self.id_sequence.1)
+			.cancel_dropped_requests(self.cancel_dropped_requests)
 			.build(sender, receiver))
 	}
 }
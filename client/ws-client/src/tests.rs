@@ -28,12 +28,13 @@
 use crate::types::error::{ErrorCode, ErrorObject, ErrorResponse};
 use crate::types::ParamsSer;
 use crate::WsClientBuilder;
+use jsonrpsee_client_transport::ws::WsError;
 use jsonrpsee_core::client::{ClientT, SubscriptionClientT};
 use jsonrpsee_core::client::{IdKind, Subscription};
 use jsonrpsee_core::rpc_params;
 use jsonrpsee_core::Error;
 use jsonrpsee_test_utils::helpers::*;
-use jsonrpsee_test_utils::mocks::{Id, WebSocketTestServer};
+use jsonrpsee_test_utils::mocks::{ws_server_closing_with, Id, WebSocketTestServer};
 use jsonrpsee_test_utils::TimeoutFutureExt;
 use serde_json::Value as JsonValue;
 
@@ -108,7 +109,7 @@ async fn response_with_wrong_id() {
 async fn response_method_not_found() {
 	let err =
 		run_request_with_response(method_not_found(Id::Num(0))).with_default_timeout().await.unwrap().unwrap_err();
-	assert_error_response(err, ErrorCode::MethodNotFound.into());
+	assert_method_not_found(err, "say_hello");
 }
 
 #[tokio::test]
@@ -241,6 +242,30 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+#[tokio::test]
+async fn batch_request_with_unknown_method() {
+	let batch_request = vec![("say_hello", None), ("say_goodbye", rpc_params![0_u64, 1, 2]), ("get_swag", None)];
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}, {"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}, {"jsonrpc":"2.0","result":"here's your swag","id":2}]"#.to_string();
+	let err = run_batch_request_with_response(batch_request, server_response)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert_method_not_found(err, "say_goodbye");
+}
+
+#[tokio::test]
+async fn batch_request_with_failed_call() {
+	let batch_request = vec![("say_hello", None), ("say_goodbye", rpc_params![0_u64, 1, 2])];
+	let server_response = r#"[{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error"},"id":1}, {"jsonrpc":"2.0","result":"hello","id":0}]"#.to_string();
+	let err = run_batch_request_with_response(batch_request, server_response)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert_error_response(err, ErrorCode::InternalError.into());
+}
+
 #[tokio::test]
 async fn is_connected_works() {
 	let server = WebSocketTestServer::with_hardcoded_response(
@@ -259,6 +284,70 @@ async fn is_connected_works() {
 	assert!(!client.is_connected())
 }
 
+#[tokio::test]
+async fn oversized_request_is_rejected_before_sending() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default()
+		.max_request_body_size(100)
+		.build(&uri)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let big = "a".repeat(200);
+	let err =
+		client.request::<String>("say_hello", rpc_params![big]).with_default_timeout().await.unwrap().unwrap_err();
+	match err {
+		Error::Transport(e) => {
+			let e = e.downcast::<WsError>().expect("transport errors of the ws client are WsError; qed");
+			assert!(matches!(e, WsError::RequestTooLarge { max: 100, .. }), "unexpected error: {:?}", e);
+		}
+		e => panic!("Expected a transport error, got: {:?}", e),
+	}
+	// The connection is kept open.
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn oversized_batch_request_is_rejected_before_sending() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default()
+		.max_request_body_size(100)
+		.build(&uri)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let big = "a".repeat(200);
+	let batch = vec![("say_hello", rpc_params![big.clone()]), ("say_hello", rpc_params![big])];
+	let err = client.batch_request::<String>(batch).with_default_timeout().await.unwrap().unwrap_err();
+	match err {
+		Error::Transport(e) => {
+			let e = e.downcast::<WsError>().expect("transport errors of the ws client are WsError; qed");
+			assert!(matches!(e, WsError::RequestTooLarge { max: 100, .. }), "unexpected error: {:?}", e);
+		}
+		e => panic!("Expected a transport error, got: {:?}", e),
+	}
+	// The connection is kept open.
+	assert!(client.is_connected());
+}
+
 async fn run_batch_request_with_response<'a>(
 	batch: Vec<(&'a str, Option<ParamsSer<'a>>)>,
 	response: String,
@@ -292,6 +381,16 @@ fn assert_error_response(err: Error, exp: ErrorObject) {
 	};
 }
 
+fn assert_method_not_found(err: Error, exp_method: &str) {
+	match err {
+		Error::RemoteMethodNotFound { method, error } => {
+			assert_eq!(method, exp_method);
+			assert_error_response(Error::Request(error), ErrorCode::MethodNotFound.into());
+		}
+		e => panic!("Expected a method not found error, got: {:?}", e),
+	}
+}
+
 #[tokio::test]
 async fn redirections() {
 	let _ = env_logger::try_init();
@@ -322,3 +421,38 @@ async fn redirections() {
 	let response = client.request::<String>("anything", None).with_default_timeout().await.unwrap();
 	assert_eq!(response.unwrap(), String::from(expected));
 }
+
+#[tokio::test]
+async fn pending_request_fails_with_connection_closed() {
+	// The server never replies to method calls.
+	let mut server = WebSocketTestServer::with_hardcoded_notification(
+		"127.0.0.1:0".parse().unwrap(),
+		server_notification("test", "not a response".into()),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).with_default_timeout().await.unwrap().unwrap();
+
+	let (res, _) = tokio::join!(client.request::<String>("say_hello", None).with_default_timeout(), async {
+		tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+		server.close().await;
+	});
+
+	assert!(matches!(res.unwrap(), Err(Error::ConnectionClosed { .. })));
+}
+
+#[tokio::test]
+async fn close_code_of_the_server_is_reported() {
+	let addr = ws_server_closing_with(1009, "message too big").await;
+	let uri = to_ws_uri_string(addr);
+	let client = WsClientBuilder::default().build(&uri).with_default_timeout().await.unwrap().unwrap();
+
+	let err = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(
+		matches!(&err, Error::ConnectionClosed { code: 1009, reason } if reason == "message too big"),
+		"unexpected error: {:?}",
+		err
+	);
+}
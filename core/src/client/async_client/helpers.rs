@@ -27,27 +27,48 @@
 use std::time::Duration;
 
 use crate::client::async_client::manager::{RequestManager, RequestStatus};
-use crate::client::{RequestMessage, TransportSenderT};
+use crate::client::{error_response_into_error, RequestMessage, TransportSenderT};
 use crate::Error;
 
 use futures_channel::{mpsc, oneshot};
 use jsonrpsee_types::{
 	ErrorResponse, Id, Notification, ParamsSer, RequestSer, Response, SubscriptionId, SubscriptionResponse,
 };
+use serde_json::value::RawValue as JsonRawValue;
 use serde_json::Value as JsonValue;
 
+/// Parses a batch response, in which some of the calls may have failed.
+///
+/// Returns `None` if `raw` isn't an array of responses and error responses.
+pub(crate) fn parse_batch_response(raw: &str) -> Option<Vec<Result<Response<'_, JsonValue>, ErrorResponse<'_>>>> {
+	let rps: Vec<&JsonRawValue> = serde_json::from_str(raw).ok()?;
+	rps.into_iter()
+		.map(|rp| match serde_json::from_str::<Response<_>>(rp.get()) {
+			Ok(rp) => Some(Ok(rp)),
+			Err(_) => serde_json::from_str::<ErrorResponse>(rp.get()).ok().map(Err),
+		})
+		.collect()
+}
+
 /// Attempts to process a batch response.
 ///
-/// On success the result is sent to the frontend.
-pub(crate) fn process_batch_response(manager: &mut RequestManager, rps: Vec<Response<JsonValue>>) -> Result<(), Error> {
+/// On success the result is sent to the frontend, or the error response to the first call of the batch that failed
+/// as an [`Error::Request`].
+pub(crate) fn process_batch_response(
+	manager: &mut RequestManager,
+	rps: Vec<Result<Response<JsonValue>, ErrorResponse>>,
+) -> Result<(), Error> {
 	let mut digest = Vec::with_capacity(rps.len());
-	let mut ordered_responses = vec![JsonValue::Null; rps.len()];
+	let mut ordered_responses = vec![Ok(JsonValue::Null); rps.len()];
 	let mut rps_unordered: Vec<_> = Vec::with_capacity(rps.len());
 
 	for rp in rps {
-		let id = rp.id.into_owned();
+		let (id, rp) = match rp {
+			Ok(rp) => (rp.id.into_owned(), Ok(rp.result)),
+			Err(err) => (err.id.clone().into_owned(), Err(err.to_string())),
+		};
 		digest.push(id.clone());
-		rps_unordered.push((id, rp.result));
+		rps_unordered.push((id, rp));
 	}
 
 	digest.sort_unstable();
@@ -64,7 +85,7 @@ pub(crate) fn process_batch_response(manager: &mut RequestManager, rps: Vec<Resp
 			batch_state.order.get(&id).copied().expect("All request IDs valid checked by RequestManager above; qed");
 		ordered_responses[pos] = rp;
 	}
-	let _ = batch_state.send_back.send(Ok(ordered_responses));
+	let _ = batch_state.send_back.send(ordered_responses.into_iter().collect::<Result<_, _>>().map_err(Error::Request));
 	Ok(())
 }
 
@@ -208,21 +229,37 @@ pub(crate) fn build_unsubscribe_message(
 /// Returns `Err(_)` if the response ID was not found.
 pub(crate) fn process_error_response(manager: &mut RequestManager, err: ErrorResponse) -> Result<(), Error> {
 	let id = err.id.clone().into_owned();
+	let err = Error::Request(err.to_string());
 	match manager.request_status(&id) {
 		RequestStatus::PendingMethodCall => {
 			let send_back = manager.complete_pending_call(id).expect("State checked above; qed");
-			let _ = send_back.map(|s| s.send(Err(Error::Request(err.to_string()))));
+			let _ = send_back.map(|s| s.send(Err(err)));
 			Ok(())
 		}
 		RequestStatus::PendingSubscription => {
 			let (_, send_back, _) = manager.complete_pending_subscription(id).expect("State checked above; qed");
-			let _ = send_back.send(Err(Error::Request(err.to_string())));
+			let _ = send_back.send(Err(err));
 			Ok(())
 		}
 		_ => Err(Error::InvalidRequestId),
 	}
 }
 
+/// Maps an [`Error::Request`] sent by the backend to a call with [`error_response_into_error`], `method_of` looks up
+/// the method of the call from the ID of the error response.
+///
+/// The backend doesn't know the methods of the calls, so it leaves this to the frontend.
+pub(crate) fn into_call_error<'a>(err: Error, method_of: impl FnOnce(&Id) -> Option<&'a str>) -> Error {
+	if let Error::Request(raw) = &err {
+		if let Ok(response) = serde_json::from_str::<ErrorResponse>(raw) {
+			if let Some(method) = method_of(&response.id) {
+				return error_response_into_error(&response, method);
+			}
+		}
+	}
+	err
+}
+
 /// Wait for a stream to complete within the given timeout.
 pub(crate) async fn call_with_timeout<T>(
 	timeout: Duration,
@@ -33,6 +33,8 @@
 //!    - SubscriptionId: unique ID generated by server
 
 use std::collections::{hash_map::Entry, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use crate::Error;
 use futures_channel::{mpsc, oneshot};
@@ -76,6 +78,33 @@ pub(crate) struct BatchState {
 	pub(crate) send_back: PendingBatchOneshot,
 }
 
+/// Number of pending requests and active subscriptions, shared with the frontend.
+#[derive(Debug, Default)]
+pub(crate) struct RequestStats {
+	/// Method calls, batches and subscriptions that are waiting for a response.
+	pub(crate) pending_requests: AtomicUsize,
+	/// Active subscriptions and notification handlers.
+	pub(crate) active_subscriptions: AtomicUsize,
+}
+
+impl RequestStats {
+	fn inc_pending(&self) {
+		self.pending_requests.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn dec_pending(&self) {
+		self.pending_requests.fetch_sub(1, Ordering::Relaxed);
+	}
+
+	fn inc_subscriptions(&self) {
+		self.active_subscriptions.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn dec_subscriptions(&self) {
+		self.active_subscriptions.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
 #[derive(Debug, Default)]
 /// Manages and monitors JSONRPC v2 method calls and subscriptions.
 pub(crate) struct RequestManager {
@@ -90,6 +119,8 @@ pub(crate) struct RequestManager {
 	batches: FxHashMap<Vec<RequestId>, BatchState>,
 	/// Registered Methods for incoming notifications
 	notification_handlers: HashMap<String, SubscriptionSink>,
+	/// Counters kept up to date as requests and subscriptions come and go.
+	stats: Arc<RequestStats>,
 }
 
 impl RequestManager {
@@ -98,6 +129,11 @@ impl RequestManager {
 		Self::default()
 	}
 
+	/// Get a handle to the counters of pending requests and active subscriptions.
+	pub(crate) fn stats(&self) -> Arc<RequestStats> {
+		self.stats.clone()
+	}
+
 	/// Tries to insert a new pending call.
 	///
 	/// Returns `Ok` if the pending request was successfully inserted otherwise `Err`.
@@ -107,13 +143,41 @@ impl RequestManager {
 		send_back: PendingCallOneshot,
 	) -> Result<(), PendingCallOneshot> {
 		if let Entry::Vacant(v) = self.requests.entry(id) {
+			// Calls without a `send_back` are unsubscribe requests nobody waits for.
+			let waiting = send_back.is_some();
 			v.insert(Kind::PendingMethodCall(send_back));
+			if waiting {
+				self.stats.inc_pending();
+			}
 			Ok(())
 		} else {
 			Err(send_back)
 		}
 	}
 
+	/// Rejects all pending calls, pending subscriptions and batch requests with the error produced by `err`.
+	///
+	/// Active subscriptions are dropped which closes their notification streams.
+	pub(crate) fn reject_all_pending(&mut self, err: impl Fn() -> Error) {
+		for (_, kind) in self.requests.drain() {
+			match kind {
+				Kind::PendingMethodCall(Some(send_back)) => {
+					let _ = send_back.send(Err(err()));
+				}
+				Kind::PendingSubscription((_, send_back, _)) => {
+					let _ = send_back.send(Err(err()));
+				}
+				Kind::PendingMethodCall(None) | Kind::Subscription(_) => (),
+			}
+		}
+		for (_, batch) in self.batches.drain() {
+			let _ = batch.send_back.send(Err(err()));
+		}
+		self.subscriptions.clear();
+		self.stats.pending_requests.store(0, Ordering::Relaxed);
+		self.stats.active_subscriptions.store(self.notification_handlers.len(), Ordering::Relaxed);
+	}
+
 	/// Tries to insert a new batch request
 	///
 	/// Returns `Ok` if the pending request was successfully inserted otherwise `Err`.
@@ -129,6 +193,7 @@ impl RequestManager {
 		batch.sort_unstable();
 		if let Entry::Vacant(v) = self.batches.entry(batch) {
 			v.insert(BatchState { order, send_back });
+			self.stats.inc_pending();
 			Ok(())
 		} else {
 			Err(send_back)
@@ -152,6 +217,7 @@ impl RequestManager {
 			self.requests
 				.insert(sub_req_id, Kind::PendingSubscription((unsub_req_id.clone(), send_back, unsubscribe_method)));
 			self.requests.insert(unsub_req_id, Kind::PendingMethodCall(None));
+			self.stats.inc_pending();
 			Ok(())
 		} else {
 			Err(send_back)
@@ -174,6 +240,7 @@ impl RequestManager {
 		{
 			request.insert(Kind::Subscription((unsub_req_id, send_back, unsubscribe_method)));
 			subscription.insert(sub_req_id);
+			self.stats.inc_subscriptions();
 			Ok(())
 		} else {
 			Err(send_back)
@@ -188,6 +255,7 @@ impl RequestManager {
 	) -> Result<(), Error> {
 		if let Entry::Vacant(handle) = self.notification_handlers.entry(method.to_owned()) {
 			handle.insert(send_back);
+			self.stats.inc_subscriptions();
 			Ok(())
 		} else {
 			Err(Error::MethodAlreadyRegistered(method.to_owned()))
@@ -197,6 +265,7 @@ impl RequestManager {
 	/// Removes a notification handler
 	pub(crate) fn remove_notification_handler(&mut self, method: String) -> Result<(), Error> {
 		if self.notification_handlers.remove(&method).is_some() {
+			self.stats.dec_subscriptions();
 			Ok(())
 		} else {
 			Err(Error::UnregisteredNotification(method))
@@ -213,6 +282,7 @@ impl RequestManager {
 		match self.requests.entry(request_id) {
 			Entry::Occupied(request) if matches!(request.get(), Kind::PendingSubscription(_)) => {
 				let (_req_id, kind) = request.remove_entry();
+				self.stats.dec_pending();
 				if let Kind::PendingSubscription(send_back) = kind {
 					Some(send_back)
 				} else {
@@ -230,6 +300,7 @@ impl RequestManager {
 		match self.batches.entry(batch) {
 			Entry::Occupied(request) => {
 				let (_digest, state) = request.remove_entry();
+				self.stats.dec_pending();
 				Some(state)
 			}
 			_ => None,
@@ -244,6 +315,9 @@ impl RequestManager {
 			Entry::Occupied(request) if matches!(request.get(), Kind::PendingMethodCall(_)) => {
 				let (_req_id, kind) = request.remove_entry();
 				if let Kind::PendingMethodCall(send_back) = kind {
+					if send_back.is_some() {
+						self.stats.dec_pending();
+					}
 					Some(send_back)
 				} else {
 					unreachable!("Pending call is Pending call checked above; qed");
@@ -267,6 +341,7 @@ impl RequestManager {
 			{
 				let (_req_id, kind) = request.remove_entry();
 				let (sub_id, _req_id) = subscription.remove_entry();
+				self.stats.dec_subscriptions();
 				if let Kind::Subscription((unsub_req_id, send_back, unsub)) = kind {
 					Some((unsub_req_id, send_back, unsub, sub_id))
 				} else {
@@ -318,6 +393,7 @@ mod tests {
 	use futures_channel::{mpsc, oneshot};
 	use jsonrpsee_types::{Id, SubscriptionId};
 	use serde_json::Value as JsonValue;
+	use std::sync::atomic::{AtomicUsize, Ordering};
 
 	#[test]
 	fn insert_remove_pending_request_works() {
@@ -477,4 +553,34 @@ mod tests {
 		assert!(manager.remove_subscription(Id::Number(3), SubscriptionId::Num(1)).is_none());
 		assert!(manager.remove_subscription(Id::Number(3), SubscriptionId::Num(0)).is_some());
 	}
+
+	#[test]
+	fn stats_track_pending_requests_and_subscriptions() {
+		let (request_tx, _) = oneshot::channel::<Result<JsonValue, Error>>();
+		let (pending_sub_tx, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
+		let (sub_tx, _) = mpsc::channel::<JsonValue>(1);
+		let mut manager = RequestManager::new();
+		let stats = manager.stats();
+		let count = |counter: &AtomicUsize| counter.load(Ordering::Relaxed);
+
+		assert!(manager.insert_pending_call(Id::Number(0), Some(request_tx)).is_ok());
+		assert!(manager
+			.insert_pending_subscription(Id::Number(1), Id::Number(2), pending_sub_tx, "unsub".into())
+			.is_ok());
+		assert_eq!(count(&stats.pending_requests), 2);
+
+		assert!(manager.complete_pending_subscription(Id::Number(1)).is_some());
+		assert!(manager
+			.insert_subscription(Id::Number(1), Id::Number(2), SubscriptionId::Num(7), sub_tx, "unsub".into())
+			.is_ok());
+		assert_eq!(count(&stats.pending_requests), 1);
+		assert_eq!(count(&stats.active_subscriptions), 1);
+
+		assert!(manager.complete_pending_call(Id::Number(0)).is_some());
+		assert!(manager.remove_subscription(Id::Number(1), SubscriptionId::Num(7)).is_some());
+		// The unsubscribe call has nobody waiting for it and isn't counted.
+		assert!(manager.complete_pending_call(Id::Number(2)).is_some());
+		assert_eq!(count(&stats.pending_requests), 0);
+		assert_eq!(count(&stats.active_subscriptions), 0);
+	}
 }
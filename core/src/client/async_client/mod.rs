@@ -1,17 +1,20 @@
 mod helpers;
 mod manager;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 use crate::client::{
-	BatchMessage, ClientT, RegisterNotificationMessage, RequestMessage, Subscription, SubscriptionClientT,
-	SubscriptionKind, SubscriptionMessage, TransportReceiverT, TransportSenderT,
+	BatchMessage, CancelRequestMessage, ClientT, RegisterNotificationMessage, RequestMessage, Subscription,
+	SubscriptionClientT, SubscriptionKind, SubscriptionMessage, TransportReceiverT, TransportSenderT,
 };
 use helpers::{
-	build_unsubscribe_message, call_with_timeout, process_batch_response, process_error_response, process_notification,
-	process_single_response, process_subscription_response, stop_subscription,
+	build_unsubscribe_message, call_with_timeout, into_call_error, parse_batch_response, process_batch_response,
+	process_error_response, process_notification, process_single_response, process_subscription_response,
+	stop_subscription,
 };
-use manager::RequestManager;
+use manager::{RequestManager, RequestStats};
 
 use crate::error::Error;
 use async_trait::async_trait;
@@ -21,12 +24,17 @@ use futures_util::sink::SinkExt;
 use futures_util::stream::StreamExt;
 use jsonrpsee_types::{
 	ErrorResponse, Id, Notification, NotificationSer, ParamsSer, RequestSer, Response, SubscriptionResponse,
+	CANCEL_REQUEST_METHOD,
 };
 use serde::de::DeserializeOwned;
+use serde_json::json;
 use tokio::sync::Mutex;
 
 use super::{FrontToBack, IdKind, RequestIdManager};
 
+/// Close code reported when the connection was closed without a close frame.
+const ABNORMAL_CLOSURE: u16 = 1006;
+
 /// Wrapper over a [`oneshot::Receiver`](futures_channel::oneshot::Receiver) that reads
 /// the underlying channel once and then stores the result in String.
 /// It is possible that the error is read more than once if several calls are made
@@ -64,6 +72,8 @@ pub struct ClientBuilder {
 	max_concurrent_requests: usize,
 	max_notifs_per_subscription: usize,
 	id_kind: IdKind,
+	id_sequence: (u64, u64),
+	cancel_dropped_requests: bool,
 }
 
 impl Default for ClientBuilder {
@@ -73,6 +83,8 @@ impl Default for ClientBuilder {
 			max_concurrent_requests: 256,
 			max_notifs_per_subscription: 1024,
 			id_kind: IdKind::Number,
+			id_sequence: (0, 1),
+			cancel_dropped_requests: false,
 		}
 	}
 }
@@ -110,6 +122,25 @@ impl ClientBuilder {
 		self
 	}
 
+	/// Use the request IDs `start`, `start + stride`, `start + 2 * stride` and so on, wrapping around on overflow,
+	/// for instance to leave room for IDs that a proxy injects on the same connection (default is 0 and 1).
+	///
+	/// A `stride` of zero is treated as one.
+	pub fn id_sequence(mut self, start: u64, stride: u64) -> Self {
+		self.id_sequence = (start, stride);
+		self
+	}
+
+	/// Send a [`CANCEL_REQUEST_METHOD`] notification to the server when a method call is dropped or times out before
+	/// its response arrived, so that the server can stop processing it (default is `false`).
+	///
+	/// Only enable this if the server supports request cancellation, other servers reply to the notification with
+	/// an error.
+	pub fn cancel_dropped_requests(mut self, enabled: bool) -> Self {
+		self.cancel_dropped_requests = enabled;
+		self
+	}
+
 	/// Build the client with given transport.
 	///
 	/// ## Panics
@@ -119,39 +150,100 @@ impl ClientBuilder {
 		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
 		let (err_tx, err_rx) = oneshot::channel();
 		let max_notifs_per_subscription = self.max_notifs_per_subscription;
+		let manager = RequestManager::new();
+		let stats = manager.stats();
 
 		tokio::spawn(async move {
-			background_task(sender, receiver, from_front, err_tx, max_notifs_per_subscription).await;
+			background_task(sender, receiver, from_front, err_tx, manager, max_notifs_per_subscription).await;
 		});
 		Client {
 			to_back,
+			stats,
 			request_timeout: self.request_timeout,
-			error: Mutex::new(ErrorFromBack::Unread(err_rx)),
-			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind),
+			error: Arc::new(Mutex::new(ErrorFromBack::Unread(err_rx))),
+			id_manager: Arc::new(
+				RequestIdManager::new(self.max_concurrent_requests, self.id_kind)
+					.with_id_sequence(self.id_sequence.0, self.id_sequence.1),
+			),
+			handles: Arc::new(AtomicUsize::new(1)),
+			cancel_dropped_requests: self.cancel_dropped_requests,
 		}
 	}
 }
 
 /// Generic asyncronous client.
+///
+/// Clones share the connection, the request limit and the counters of the original client. The connection is
+/// closed once the last clone is dropped, see [`Client::strong_count`].
 #[derive(Debug)]
 pub struct Client {
 	/// Channel to send requests to the background task.
 	to_back: mpsc::Sender<FrontToBack>,
 	/// If the background thread terminates the error is sent to this channel.
 	// NOTE(niklasad1): This is a Mutex to circumvent that the async fns takes immutable references.
-	error: Mutex<ErrorFromBack>,
+	error: Arc<Mutex<ErrorFromBack>>,
 	/// Request timeout. Defaults to 60sec.
 	request_timeout: Duration,
 	/// Request ID manager.
-	id_manager: RequestIdManager,
+	id_manager: Arc<RequestIdManager>,
+	/// Counters maintained by the background task.
+	stats: Arc<RequestStats>,
+	/// Number of clones of this client alive.
+	handles: Arc<AtomicUsize>,
+	/// Whether to tell the server about method calls that were dropped before their response arrived.
+	cancel_dropped_requests: bool,
+}
+
+impl Clone for Client {
+	fn clone(&self) -> Self {
+		self.handles.fetch_add(1, Ordering::Relaxed);
+		Self {
+			to_back: self.to_back.clone(),
+			error: self.error.clone(),
+			request_timeout: self.request_timeout,
+			id_manager: self.id_manager.clone(),
+			stats: self.stats.clone(),
+			handles: self.handles.clone(),
+			cancel_dropped_requests: self.cancel_dropped_requests,
+		}
+	}
 }
 
 impl Client {
+	/// Create a client with the default [`ClientBuilder`] settings that talks to the server over any pair of
+	/// transports, such as an in-memory channel or a Unix socket.
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub fn new<S: TransportSenderT, R: TransportReceiverT>(sender: S, receiver: R) -> Self {
+		ClientBuilder::default().build(sender, receiver)
+	}
+
 	/// Checks if the client is connected to the target.
 	pub fn is_connected(&self) -> bool {
 		!self.to_back.is_closed()
 	}
 
+	/// Returns the number of clones of this client alive, this one included. The connection is closed and the
+	/// background task stops when the last one is dropped.
+	pub fn strong_count(&self) -> usize {
+		self.handles.load(Ordering::Acquire)
+	}
+
+	/// Returns the number of method calls, batch requests and subscription requests waiting for a response.
+	///
+	/// Requests are counted once they were handed to the transport by the background task.
+	pub fn pending_requests(&self) -> usize {
+		self.stats.pending_requests.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of active subscriptions, including notification handlers registered with
+	/// [`SubscriptionClientT::subscribe_to_method`].
+	pub fn active_subscriptions(&self) -> usize {
+		self.stats.active_subscriptions.load(Ordering::Relaxed)
+	}
+
 	// Reads the error message from the backend thread.
 	async fn read_error_from_backend(&self) -> Error {
 		let mut err_lock = self.error.lock().await;
@@ -170,7 +262,11 @@ impl<S: TransportSenderT, R: TransportReceiverT> From<(S, R)> for Client {
 
 impl Drop for Client {
 	fn drop(&mut self) {
-		self.to_back.close_channel();
+		// Subscriptions keep a sender of their own, so close the channel explicitly rather than relying on all
+		// the senders being dropped.
+		if self.handles.fetch_sub(1, Ordering::AcqRel) == 1 {
+			self.to_back.close_channel();
+		}
 	}
 }
 
@@ -213,17 +309,24 @@ impl ClientT for Client {
 		if self
 			.to_back
 			.clone()
-			.send(FrontToBack::Request(RequestMessage { raw, id, send_back: Some(send_back_tx) }))
+			.send(FrontToBack::Request(RequestMessage { raw, id: id.clone(), send_back: Some(send_back_tx) }))
 			.await
 			.is_err()
 		{
 			return Err(self.read_error_from_backend().await);
 		}
 
+		let cancel_guard = self.cancel_dropped_requests.then(|| CancelOnDrop::new(self.to_back.clone(), &id));
 		let res = call_with_timeout(self.request_timeout, send_back_rx).await;
+		// A timed out call is still pending on the server.
+		if !matches!(res, Ok(Err(Error::RequestTimeout))) {
+			if let Some(guard) = cancel_guard {
+				guard.disarm();
+			}
+		}
 		let json_value = match res {
 			Ok(Ok(v)) => v,
-			Ok(Err(err)) => return Err(err),
+			Ok(Err(err)) => return Err(into_call_error(err, |_| Some(method))),
 			Err(_) => return Err(self.read_error_from_backend().await),
 		};
 		serde_json::from_value(json_value).map_err(Error::ParseError)
@@ -236,9 +339,11 @@ impl ClientT for Client {
 		let guard = self.id_manager.next_request_ids(batch.len())?;
 		let batch_ids: Vec<Id> = guard.inner();
 		let mut batches = Vec::with_capacity(batch.len());
+		let mut methods = Vec::with_capacity(batch.len());
 
 		for (idx, (method, params)) in batch.into_iter().enumerate() {
 			batches.push(RequestSer::new(&batch_ids[idx], method, params));
+			methods.push(method);
 		}
 
 		let (send_back_tx, send_back_rx) = oneshot::channel();
@@ -248,7 +353,7 @@ impl ClientT for Client {
 		if self
 			.to_back
 			.clone()
-			.send(FrontToBack::Batch(BatchMessage { raw, ids: batch_ids, send_back: send_back_tx }))
+			.send(FrontToBack::Batch(BatchMessage { raw, ids: batch_ids.clone(), send_back: send_back_tx }))
 			.await
 			.is_err()
 		{
@@ -258,7 +363,11 @@ impl ClientT for Client {
 		let res = call_with_timeout(self.request_timeout, send_back_rx).await;
 		let json_values = match res {
 			Ok(Ok(v)) => v,
-			Ok(Err(err)) => return Err(err),
+			Ok(Err(err)) => {
+				return Err(into_call_error(err, |id| {
+					batch_ids.iter().position(|batch_id| batch_id == id).map(|pos| methods[pos])
+				}))
+			}
 			Err(_) => return Err(self.read_error_from_backend().await),
 		};
 
@@ -317,7 +426,7 @@ impl SubscriptionClientT for Client {
 
 		let (notifs_rx, id) = match res {
 			Ok(Ok(val)) => val,
-			Ok(Err(err)) => return Err(err),
+			Ok(Err(err)) => return Err(into_call_error(err, |_| Some(subscribe_method))),
 			Err(_) => return Err(self.read_error_from_backend().await),
 		};
 		Ok(Subscription::new(self.to_back.clone(), notifs_rx, SubscriptionKind::Subscription(id)))
@@ -362,10 +471,9 @@ async fn background_task<S: TransportSenderT, R: TransportReceiverT>(
 	receiver: R,
 	mut frontend: mpsc::Receiver<FrontToBack>,
 	front_error: oneshot::Sender<Error>,
+	mut manager: RequestManager,
 	max_notifs_per_subscription: usize,
 ) {
-	let mut manager = RequestManager::new();
-
 	let backend_event = futures_util::stream::unfold(receiver, |mut receiver| async {
 		let res = receiver.receive().await;
 		Some((res, receiver))
@@ -397,7 +505,11 @@ async fn background_task<S: TransportSenderT, R: TransportReceiverT>(
 
 				if let Err(e) = sender.send(batch.raw).await {
 					tracing::warn!("[backend]: client batch request failed: {:?}", e);
-					manager.complete_pending_batch(batch.ids);
+					let mut digest = batch.ids;
+					digest.sort_unstable();
+					if let Some(state) = manager.complete_pending_batch(digest) {
+						let _ = state.send_back.send(Err(Error::Transport(e.into())));
+					}
 				}
 			}
 			// User called `notification` on the front-end
@@ -422,6 +534,17 @@ async fn background_task<S: TransportSenderT, R: TransportReceiverT>(
 				}
 			}
 
+			// User dropped a method call before its response arrived.
+			Either::Left((Some(FrontToBack::CancelRequest(cancel)), _)) => {
+				// The response may have arrived in the meantime, nothing to cancel then.
+				if manager.complete_pending_call(cancel.id.clone()).is_some() {
+					tracing::trace!("[backend]: client cancels request: {:?}", cancel.id);
+					if let Err(e) = sender.send(cancel.raw).await {
+						tracing::warn!("[backend]: client cancel request failed: {:?}", e);
+					}
+				}
+			}
+
 			// User called `subscribe` on the front-end.
 			Either::Left((Some(FrontToBack::Subscribe(sub)), _)) => match sender.send(sub.raw).await {
 				Ok(_) => manager
@@ -450,6 +573,21 @@ async fn background_task<S: TransportSenderT, R: TransportReceiverT>(
 				}
 			}
 
+			// User closed a subscription without unsubscribing.
+			Either::Left((Some(FrontToBack::CloseSubscription(kind)), _)) => {
+				tracing::trace!("[backend]: closing subscription without unsubscribing: {:?}", kind);
+				match kind {
+					SubscriptionKind::Subscription(sub_id) => {
+						if let Some(req_id) = manager.get_request_id_by_subscription_id(&sub_id) {
+							let _ = manager.remove_subscription(req_id, sub_id);
+						}
+					}
+					SubscriptionKind::Method(method) => {
+						let _ = manager.remove_notification_handler(method);
+					}
+				}
+			}
+
 			// User called `register_notification` on the front-end.
 			Either::Left((Some(FrontToBack::RegisterNotification(reg)), _)) => {
 				tracing::trace!("[backend] registering notification handler: {:?}", reg.method);
@@ -494,8 +632,8 @@ async fn background_task<S: TransportSenderT, R: TransportReceiverT>(
 					tracing::debug!("[backend]: recv notification {:?}", notif);
 					let _ = process_notification(&mut manager, notif);
 				}
-				// Batch response.
-				else if let Ok(batch) = serde_json::from_str::<Vec<Response<_>>>(&raw) {
+				// Batch response, some of the calls may have failed.
+				else if let Some(batch) = parse_batch_response(&raw) {
 					tracing::debug!("[backend]: recv batch {:?}", batch);
 					if let Err(e) = process_batch_response(&mut manager, batch) {
 						let _ = front_error.send(e);
@@ -520,6 +658,13 @@ async fn background_task<S: TransportSenderT, R: TransportReceiverT>(
 					break;
 				}
 			}
+			Either::Right((Some(Err(e)), _)) if R::is_connection_closed(&e) => {
+				tracing::debug!("[backend]: connection closed by the remote peer: {:?}; terminate client", e);
+				let (code, reason) = R::close_frame(&e).unwrap_or((ABNORMAL_CLOSURE, String::new()));
+				manager.reject_all_pending(|| Error::ConnectionClosed { code, reason: reason.clone() });
+				let _ = front_error.send(Error::ConnectionClosed { code, reason });
+				break;
+			}
 			Either::Right((Some(Err(e)), _)) => {
 				tracing::error!("Error: {:?} terminating client", e);
 				let _ = front_error.send(Error::Transport(e.into()));
@@ -535,3 +680,31 @@ async fn background_task<S: TransportSenderT, R: TransportReceiverT>(
 	// Send close message to the server.
 	let _ = sender.close().await;
 }
+
+/// Asks the background task to cancel a method call when dropped, unless disarmed first.
+struct CancelOnDrop {
+	to_back: Option<mpsc::Sender<FrontToBack>>,
+	id: Id<'static>,
+}
+
+impl CancelOnDrop {
+	fn new(to_back: mpsc::Sender<FrontToBack>, id: &Id<'static>) -> Self {
+		Self { to_back: Some(to_back), id: id.clone() }
+	}
+
+	fn disarm(mut self) {
+		self.to_back = None;
+	}
+}
+
+impl Drop for CancelOnDrop {
+	fn drop(&mut self) {
+		if let Some(mut to_back) = self.to_back.take() {
+			let notif = NotificationSer::new(CANCEL_REQUEST_METHOD, Some(ParamsSer::Array(vec![json!(self.id)])));
+			let raw = serde_json::to_string(&notif).expect("valid JSON; qed");
+			let id = std::mem::replace(&mut self.id, Id::Null);
+			// Best effort, the background task may be busy or already gone.
+			let _ = to_back.try_send(FrontToBack::CancelRequest(CancelRequestMessage { raw, id }));
+		}
+	}
+}
@@ -37,8 +37,10 @@ use futures_channel::{mpsc, oneshot};
 use futures_util::future::FutureExt;
 use futures_util::sink::SinkExt;
 use futures_util::stream::{Stream, StreamExt};
-use jsonrpsee_types::{Id, ParamsSer, SubscriptionId};
+use jsonrpsee_types::error::{ErrorCode, ErrorResponse};
+use jsonrpsee_types::{Id, ParamsSer, SubscriptionChunk, SubscriptionId};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::value::RawValue as JsonRawValue;
 use serde_json::Value as JsonValue;
 
 #[doc(hidden)]
@@ -74,6 +76,31 @@ pub trait ClientT {
 	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
 	where
 		R: DeserializeOwned + Default + Clone;
+
+	/// Send the calls as separate [method call requests](https://www.jsonrpc.org/specification#request_object),
+	/// with at most `concurrency` of them in flight at the same time.
+	///
+	/// Unlike [`ClientT::batch_request`], each call succeeds or fails on its own. The results are returned in the
+	/// same order as the calls. A `concurrency` of `0` is treated as `1`, that is the calls are sent one after the
+	/// other.
+	async fn request_many<'a, R>(
+		&self,
+		calls: Vec<(&'a str, Option<ParamsSer<'a>>)>,
+		concurrency: usize,
+	) -> Vec<Result<R, Error>>
+	where
+		R: DeserializeOwned + Send,
+	{
+		let calls: Vec<_> = calls
+			.into_iter()
+			.enumerate()
+			.map(|(idx, (method, params))| async move { (idx, self.request(method, params).await) })
+			.collect();
+		let mut results: Vec<_> =
+			futures_util::stream::iter(calls).buffer_unordered(concurrency.max(1)).collect().await;
+		results.sort_unstable_by_key(|(idx, _)| *idx);
+		results.into_iter().map(|(_, result)| result).collect()
+	}
 }
 
 /// [JSON-RPC](https://www.jsonrpc.org/specification) client interface that can make requests, notifications and subscriptions.
@@ -107,6 +134,112 @@ pub trait SubscriptionClientT: ClientT {
 	async fn subscribe_to_method<'a, Notif>(&self, method: &'a str) -> Result<Subscription<Notif>, Error>
 	where
 		Notif: DeserializeOwned;
+
+	/// Same as [`SubscriptionClientT::subscribe`], but the notifications are returned as received, without being
+	/// deserialized, for instance to inspect or forward them.
+	async fn subscribe_raw<'a>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<Subscription<Box<JsonRawValue>>, Error> {
+		self.subscribe(subscribe_method, params, unsubscribe_method).await
+	}
+}
+
+/// Error of a call made with an [`ErrorDataClient`], with the `data` of JSON-RPC errors deserialized into `D`.
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorWithData<D> {
+	/// The server answered the call with a JSON-RPC error.
+	#[error("Server call failed: code: {code}, message: {message}")]
+	Call {
+		/// JSON-RPC error code.
+		code: i32,
+		/// Short description of the error.
+		message: String,
+		/// Additional information about the error, `None` if the server didn't send any.
+		data: Option<D>,
+	},
+	/// Any other error, including JSON-RPC errors whose `data` couldn't be deserialized into `D`.
+	#[error(transparent)]
+	Other(Error),
+}
+
+impl<D: DeserializeOwned> From<Error> for ErrorWithData<D> {
+	fn from(err: Error) -> Self {
+		let parsed = match &err {
+			Error::Request(response) | Error::RemoteMethodNotFound { error: response, .. } => {
+				serde_json::from_str::<ErrorResponse>(response).ok().and_then(|response| {
+					let data = response.error.data.map(|data| serde_json::from_str(data.get())).transpose().ok()?;
+					Some((response.error.code.code(), response.error.message.into_owned(), data))
+				})
+			}
+			_ => None,
+		};
+		match parsed {
+			Some((code, message, data)) => Self::Call { code, message, data },
+			None => Self::Other(err),
+		}
+	}
+}
+
+/// Converts the error response of the server to a call of `method` into [`Error::RemoteMethodNotFound`] if the server
+/// doesn't know the method and into [`Error::Request`] otherwise.
+pub fn error_response_into_error(err: &ErrorResponse, method: &str) -> Error {
+	let error = err.to_string();
+	if err.error.code == ErrorCode::MethodNotFound {
+		Error::RemoteMethodNotFound { method: method.to_owned(), error }
+	} else {
+		Error::Request(error)
+	}
+}
+
+/// Wraps a client to deserialize the `data` of the JSON-RPC errors returned by the server into `D`, rather than
+/// leaving each caller to parse it out of [`Error::Request`].
+///
+/// ```no_run
+/// use jsonrpsee_core::client::{ClientT, ErrorDataClient, ErrorWithData};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Reason {
+///     retry_after: u64,
+/// }
+///
+/// async fn call(client: impl ClientT + Sync) {
+///     let client = ErrorDataClient::<_, Reason>::new(client);
+///     if let Err(ErrorWithData::Call { data: Some(reason), .. }) = client.request::<String>("say_hello", None).await {
+///         println!("Retry in {} seconds", reason.retry_after);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ErrorDataClient<C, D> {
+	client: C,
+	_data: PhantomData<fn() -> D>,
+}
+
+impl<C, D> ErrorDataClient<C, D>
+where
+	C: ClientT + Sync,
+	D: DeserializeOwned,
+{
+	/// Wrap `client`.
+	pub fn new(client: C) -> Self {
+		Self { client, _data: PhantomData }
+	}
+
+	/// Returns the wrapped client, to make the calls whose errors don't need to be deserialized.
+	pub fn inner(&self) -> &C {
+		&self.client
+	}
+
+	/// Same as [`ClientT::request`], with the `data` of JSON-RPC errors deserialized.
+	pub async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, ErrorWithData<D>>
+	where
+		R: DeserializeOwned,
+	{
+		self.client.request(method, params).await.map_err(Into::into)
+	}
 }
 
 /// Transport interface to send data asynchronous.
@@ -133,6 +266,18 @@ pub trait TransportReceiverT: Send + 'static {
 
 	/// Receive.
 	async fn receive(&mut self) -> Result<String, Self::Error>;
+
+	/// Returns `true` if the error means that the connection was closed by the remote peer
+	/// (default is `false`).
+	fn is_connection_closed(_err: &Self::Error) -> bool {
+		false
+	}
+
+	/// Returns the close code and reason sent by the remote peer if the error means that it closed the connection
+	/// with a close frame (default is `None`).
+	fn close_frame(_err: &Self::Error) -> Option<(u16, String)> {
+		None
+	}
 }
 
 #[macro_export]
@@ -183,8 +328,8 @@ pub struct Subscription<Notif> {
 	to_back: mpsc::Sender<FrontToBack>,
 	/// Channel from which we receive notifications from the server, as encoded `JsonValue`s.
 	notifs_rx: mpsc::Receiver<JsonValue>,
-	/// Callback kind.
-	kind: SubscriptionKind,
+	/// Callback kind, `None` once the subscription was closed.
+	kind: Option<SubscriptionKind>,
 	/// Marker in order to pin the `Notif` parameter.
 	marker: PhantomData<Notif>,
 }
@@ -200,7 +345,63 @@ impl<Notif> Subscription<Notif> {
 		notifs_rx: mpsc::Receiver<JsonValue>,
 		kind: SubscriptionKind,
 	) -> Self {
-		Self { to_back, notifs_rx, kind, marker: PhantomData }
+		Self { to_back, notifs_rx, kind: Some(kind), marker: PhantomData }
+	}
+
+	/// Create a subscription that yields the notifications sent on `notifs_rx`, without a client behind it.
+	///
+	/// Meant for testing the code that consumes a subscription: each value is decoded like a notification from the
+	/// server and dropping or closing the subscription doesn't unsubscribe from anything.
+	pub fn from_channel(notifs_rx: mpsc::Receiver<JsonValue>) -> Self {
+		let (to_back, _) = mpsc::channel(0);
+		Self { to_back, notifs_rx, kind: None, marker: PhantomData }
+	}
+
+	/// Close the subscription on the client side only: the notifications that are buffered are discarded and the
+	/// client stops buffering new ones for this subscription, but unlike dropping the subscription no unsubscribe
+	/// call is made to the server. Useful when the connection is known to be gone.
+	///
+	/// This is best effort, if the client is too busy to take note of it the subscription is dropped instead.
+	pub fn close(mut self) {
+		self.notifs_rx.close();
+		if let Some(kind) = self.kind.take() {
+			let _ = self.to_back.send(FrontToBack::CloseSubscription(kind)).now_or_never();
+		}
+	}
+
+	/// Returns the next result that the server sent as a sequence of [`SubscriptionChunk`]s, for example with
+	/// `SubscriptionSink::send_chunked`, once all of its chunks have arrived.
+	///
+	/// Returns `None` if the subscription was terminated and an error if a chunk is missing, out of order,
+	/// announces a different total than the first chunk or if the reassembled result couldn't be parsed as `T`.
+	pub async fn next_chunked<T: DeserializeOwned>(&mut self) -> Option<Result<T, Error>> {
+		let mut buf = String::new();
+		let mut expected_seq = 0;
+		let mut expected_total = None;
+
+		loop {
+			let chunk =
+				match serde_json::from_value::<NotifResponse<SubscriptionChunk<'static>>>(self.notifs_rx.next().await?)
+				{
+					Ok(NotifResponse::Ok(chunk)) => chunk,
+					Ok(NotifResponse::Err(e)) => return Some(Err(Error::SubscriptionClosed(e))),
+					Err(e) => return Some(Err(Error::ParseError(e))),
+				};
+
+			let total = *expected_total.get_or_insert(chunk.total);
+			if chunk.seq != expected_seq || chunk.total != total || chunk.seq >= total {
+				return Some(Err(Error::Custom(format!(
+					"Unexpected subscription chunk {}/{}, expected chunk {}/{}",
+					chunk.seq, chunk.total, expected_seq, total
+				))));
+			}
+			buf.push_str(&chunk.data);
+			expected_seq += 1;
+
+			if expected_seq == total {
+				return Some(serde_json::from_str(&buf).map_err(Error::ParseError));
+			}
+		}
 	}
 }
 
@@ -243,6 +444,15 @@ pub struct SubscriptionMessage {
 	pub send_back: oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, SubscriptionId<'static>), Error>>,
 }
 
+/// Message to cancel a method call whose response is no longer awaited.
+#[derive(Debug)]
+pub struct CancelRequestMessage {
+	/// Serialized cancel notification.
+	pub raw: String,
+	/// Request ID of the call to cancel.
+	pub id: Id<'static>,
+}
+
 /// RegisterNotification message.
 #[derive(Debug)]
 pub struct RegisterNotificationMessage {
@@ -265,6 +475,8 @@ pub enum FrontToBack {
 	Request(RequestMessage),
 	/// Send a subscription request to the server.
 	Subscribe(SubscriptionMessage),
+	/// Forget about a pending method call and tell the server to stop processing it.
+	CancelRequest(CancelRequestMessage),
 	/// Register a notification handler
 	RegisterNotification(RegisterNotificationMessage),
 	/// Unregister a notification handler
@@ -275,6 +487,8 @@ pub enum FrontToBack {
 	// Such operations will be blocked until a response is received or the background
 	// thread has been terminated.
 	SubscriptionClosed(SubscriptionId<'static>),
+	/// Stop buffering the notifications of a subscription without unsubscribing from the server.
+	CloseSubscription(SubscriptionKind),
 }
 
 impl<Notif> Subscription<Notif>
@@ -292,6 +506,16 @@ where
 	pub async fn next(&mut self) -> Option<Result<Notif, Error>> {
 		StreamExt::next(self).await
 	}
+
+	/// Same as [`Subscription::next`], but gives up after `timeout`, for instance when waiting for the event that
+	/// answers an action of the caller.
+	#[cfg(feature = "async-client")]
+	pub async fn next_timeout(
+		&mut self,
+		timeout: std::time::Duration,
+	) -> Result<Option<Result<Notif, Error>>, tokio::time::error::Elapsed> {
+		tokio::time::timeout(timeout, self.next()).await
+	}
 }
 
 impl<Notif> Stream for Subscription<Notif>
@@ -301,10 +525,13 @@ where
 	type Item = Result<Notif, Error>;
 	fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Option<Self::Item>> {
 		let n = futures_util::ready!(self.notifs_rx.poll_next_unpin(cx));
-		let res = n.map(|n| match serde_json::from_value::<NotifResponse<Notif>>(n) {
-			Ok(NotifResponse::Ok(parsed)) => Ok(parsed),
-			Ok(NotifResponse::Err(e)) => Err(Error::SubscriptionClosed(e)),
-			Err(e) => Err(Error::ParseError(e)),
+		// Same as deserializing a `NotifResponse<Notif>`, which can't buffer `RawValue` notifications as it's untagged.
+		let res = n.map(|n| match Notif::deserialize(&n) {
+			Ok(parsed) => Ok(parsed),
+			Err(e) => match SubscriptionClosed::deserialize(&n) {
+				Ok(closed) => Err(Error::SubscriptionClosed(closed)),
+				Err(_) => Err(Error::ParseError(e)),
+			},
 		});
 		task::Poll::Ready(res)
 	}
@@ -316,7 +543,10 @@ impl<Notif> Drop for Subscription<Notif> {
 		// the channel's buffer will be full.
 		// However, when a notification arrives, the background task will realize that the channel
 		// to the `Callback` has been closed.
-		let kind = std::mem::replace(&mut self.kind, SubscriptionKind::Subscription(SubscriptionId::Num(0)));
+		let kind = match self.kind.take() {
+			Some(kind) => kind,
+			None => return,
+		};
 
 		let msg = match kind {
 			SubscriptionKind::Method(notif) => FrontToBack::UnregisterNotification(notif),
@@ -335,6 +565,8 @@ pub struct RequestIdManager {
 	max_concurrent_requests: usize,
 	/// Get the next request ID.
 	current_id: AtomicU64,
+	/// Difference between two consecutive request IDs.
+	id_stride: u64,
 	/// Request ID type.
 	id_kind: IdKind,
 }
@@ -342,7 +574,25 @@ pub struct RequestIdManager {
 impl RequestIdManager {
 	/// Create a new `RequestIdGuard` with the provided concurrency limit.
 	pub fn new(limit: usize, id_kind: IdKind) -> Self {
-		Self { current_pending: Arc::new(()), max_concurrent_requests: limit, current_id: AtomicU64::new(0), id_kind }
+		Self {
+			current_pending: Arc::new(()),
+			max_concurrent_requests: limit,
+			current_id: AtomicU64::new(0),
+			id_stride: 1,
+			id_kind,
+		}
+	}
+
+	/// Hand out the request IDs `start`, `start + stride`, `start + 2 * stride` and so on, wrapping around on
+	/// overflow. A `stride` of zero is treated as one.
+	pub fn with_id_sequence(mut self, start: u64, stride: u64) -> Self {
+		self.current_id = AtomicU64::new(start);
+		self.id_stride = stride.max(1);
+		self
+	}
+
+	fn next_id(&self) -> Id<'static> {
+		self.id_kind.into_id(self.current_id.fetch_add(self.id_stride, Ordering::SeqCst))
 	}
 
 	fn get_slot(&self) -> Result<Arc<()>, Error> {
@@ -359,7 +609,7 @@ impl RequestIdManager {
 	/// Fails if request limit has been exceeded.
 	pub fn next_request_id(&self) -> Result<RequestIdGuard<Id<'static>>, Error> {
 		let rc = self.get_slot()?;
-		let id = self.id_kind.into_id(self.current_id.fetch_add(1, Ordering::SeqCst));
+		let id = self.next_id();
 		Ok(RequestIdGuard { _rc: rc, id })
 	}
 
@@ -370,8 +620,7 @@ impl RequestIdManager {
 		let rc = self.get_slot()?;
 		let mut ids = Vec::with_capacity(len);
 		for _ in 0..len {
-			let id = self.id_kind.into_id(self.current_id.fetch_add(1, Ordering::SeqCst));
-			ids.push(id);
+			ids.push(self.next_id());
 		}
 		Ok(RequestIdGuard { _rc: rc, id: ids })
 	}
@@ -422,7 +671,28 @@ impl IdKind {
 
 #[cfg(test)]
 mod tests {
-	use super::{IdKind, RequestIdManager};
+	use super::{IdKind, RequestIdManager, Subscription};
+	use futures_channel::mpsc;
+	use jsonrpsee_types::{Id, SubscriptionChunk};
+
+	fn chunk(seq: u32, total: u32, data: &'static str) -> serde_json::Value {
+		serde_json::to_value(SubscriptionChunk { seq, total, data: data.into() }).unwrap()
+	}
+
+	#[tokio::test]
+	async fn subscription_from_channel_yields_the_sent_items() {
+		let (mut tx, rx) = mpsc::channel(3);
+		let mut sub = Subscription::<u32>::from_channel(rx);
+		for n in 1..=3 {
+			tx.try_send(n.into()).unwrap();
+		}
+		drop(tx);
+
+		for n in 1..=3 {
+			assert_eq!(sub.next().await.unwrap().unwrap(), n);
+		}
+		assert!(sub.next().await.is_none());
+	}
 
 	#[test]
 	fn request_id_guard_works() {
@@ -437,4 +707,33 @@ mod tests {
 
 		assert!(manager.next_request_id().is_ok());
 	}
+
+	#[test]
+	fn request_ids_follow_the_configured_sequence() {
+		let manager = RequestIdManager::new(4, IdKind::Number).with_id_sequence(100, 10);
+		assert_eq!(manager.next_request_id().unwrap().inner(), Id::Number(100));
+		assert_eq!(manager.next_request_ids(2).unwrap().inner(), vec![Id::Number(110), Id::Number(120)]);
+
+		let manager = RequestIdManager::new(4, IdKind::String).with_id_sequence(u64::MAX, 2);
+		assert_eq!(manager.next_request_id().unwrap().inner(), Id::Str(u64::MAX.to_string().into()));
+		assert_eq!(manager.next_request_id().unwrap().inner(), Id::Str("1".into()));
+	}
+
+	#[tokio::test]
+	async fn chunked_results_with_inconsistent_framing_are_rejected() {
+		let (mut tx, rx) = mpsc::channel(4);
+		let mut sub = Subscription::<u32>::from_channel(rx);
+		tx.try_send(chunk(0, 2, "\"ab")).unwrap();
+		tx.try_send(chunk(1, 2, "c\"")).unwrap();
+		assert_eq!(sub.next_chunked::<String>().await.unwrap().unwrap(), "abc");
+
+		// The total changes after the first chunk.
+		tx.try_send(chunk(0, 3, "\"ab")).unwrap();
+		tx.try_send(chunk(1, 2, "c\"")).unwrap();
+		assert!(sub.next_chunked::<String>().await.unwrap().is_err());
+
+		// The index is out of range.
+		tx.try_send(chunk(0, 0, "\"ab\"")).unwrap();
+		assert!(sub.next_chunked::<String>().await.unwrap().is_err());
+	}
 }
@@ -67,6 +67,17 @@ pub enum Error {
 	/// Frontend/backend channel error.
 	#[error("Frontend/backend channel error: {0}")]
 	Internal(#[from] futures_channel::mpsc::SendError),
+	/// The connection was closed by the remote peer while requests were pending.
+	///
+	/// `code` and `reason` are taken from the close frame of the peer, the code is 1006 (abnormal closure) and the
+	/// reason is empty if the connection was closed without one.
+	#[error("The connection was closed by the remote peer with code {code}: {reason}")]
+	ConnectionClosed {
+		/// Close code.
+		code: u16,
+		/// Close reason.
+		reason: String,
+	},
 	/// Invalid response,
 	#[error("Invalid response: {0}")]
 	InvalidResponse(Mismatch<String>),
@@ -94,6 +105,14 @@ pub enum Error {
 	/// Method with that name has not yet been registered.
 	#[error("Method: {0} has not yet been registered")]
 	MethodNotFound(String),
+	/// The server that the client called doesn't know the method, it answered with a method not found error.
+	#[error("Method: {method} not found on the server: {error:?}")]
+	RemoteMethodNotFound {
+		/// Name of the called method.
+		method: String,
+		/// Error response of the server, in the same form as [`Error::Request`].
+		error: String,
+	},
 	/// Subscribe and unsubscribe method names are the same.
 	#[error("Cannot use the same method name for subscribe and unsubscribe, used: {0}")]
 	SubscriptionNameConflict(String),
@@ -112,6 +131,9 @@ pub enum Error {
 	/// List passed into `set_allowed_origins` was empty
 	#[error("Must set at least one allowed value for the {0} header")]
 	EmptyAllowList(&'static str),
+	/// Invalid IP address range, expected CIDR notation such as `10.0.0.0/8`.
+	#[error("Invalid IP range: {0}")]
+	InvalidIpRange(String),
 	/// Failed to execute a method because a resource was already at capacity
 	#[error("Resource at capacity: {0}")]
 	ResourceAtCapacity(&'static str),
@@ -145,6 +167,57 @@ impl Error {
 	{
 		Error::Call(CallError::from_std_error(err))
 	}
+
+	/// Returns the coarse category of the error, for instance to count failures by kind.
+	pub fn category(&self) -> ErrorCategory {
+		match self {
+			Error::RequestTimeout => ErrorCategory::Timeout,
+			Error::Transport(_) | Error::Internal(_) | Error::ConnectionClosed { .. } | Error::RestartNeeded(_) => {
+				ErrorCategory::Connection
+			}
+			Error::InvalidResponse(_)
+			| Error::InvalidSubscriptionId
+			| Error::InvalidRequestId
+			| Error::UnregisteredNotification(_)
+			| Error::DuplicateRequestId => ErrorCategory::Protocol,
+			Error::Call(_)
+			| Error::Request(_)
+			| Error::MethodNotFound(_)
+			| Error::RemoteMethodNotFound { .. }
+			| Error::SubscriptionClosed(_) => ErrorCategory::Server,
+			Error::ParseError(_) => ErrorCategory::Parse,
+			Error::MaxSlotsExceeded
+			| Error::MethodAlreadyRegistered(_)
+			| Error::SubscriptionNameConflict(_)
+			| Error::AlreadyStopped
+			| Error::EmptyAllowList(_)
+			| Error::InvalidIpRange(_)
+			| Error::ResourceAtCapacity(_)
+			| Error::ResourceNameAlreadyTaken(_)
+			| Error::ResourceNameNotFoundForMethod(_, _)
+			| Error::UninitializedMethod(_)
+			| Error::MaxResourcesReached
+			| Error::Custom(_)
+			| Error::HttpNotImplemented => ErrorCategory::Other,
+		}
+	}
+}
+
+/// Coarse category of an [`Error`], see [`Error::category`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+	/// The request timed out.
+	Timeout,
+	/// The connection failed or was closed.
+	Connection,
+	/// The peer violated the JSON-RPC protocol, for instance with a response to an unknown request.
+	Protocol,
+	/// The server answered with an error.
+	Server,
+	/// A message could not be parsed.
+	Parse,
+	/// Misuse of the API or local limits, such as registering a method twice.
+	Other,
 }
 
 /// A type with a special `subscription_closed` field to detect that
@@ -229,7 +302,50 @@ impl From<hyper::Error> for Error {
 
 #[cfg(test)]
 mod tests {
-	use super::{SubscriptionClosed, SubscriptionClosedReason};
+	use super::{Error, ErrorCategory, Mismatch, SubscriptionClosed, SubscriptionClosedReason};
+	use jsonrpsee_types::error::CallError;
+
+	#[test]
+	fn error_categories() {
+		let parse_error = serde_json::from_str::<u8>("x").unwrap_err();
+		let (mut closed, _) = futures_channel::mpsc::channel::<()>(0);
+		let send_error = closed.try_send(()).unwrap_err().into_send_error();
+		let errors = vec![
+			(Error::RequestTimeout, ErrorCategory::Timeout),
+			(Error::Transport(anyhow::anyhow!("reset")), ErrorCategory::Connection),
+			(Error::Internal(send_error), ErrorCategory::Connection),
+			(Error::ConnectionClosed { code: 1000, reason: String::new() }, ErrorCategory::Connection),
+			(Error::RestartNeeded("closed".into()), ErrorCategory::Connection),
+			(Error::InvalidResponse(Mismatch { expected: "a".into(), got: "b".into() }), ErrorCategory::Protocol),
+			(Error::InvalidSubscriptionId, ErrorCategory::Protocol),
+			(Error::InvalidRequestId, ErrorCategory::Protocol),
+			(Error::UnregisteredNotification("n".into()), ErrorCategory::Protocol),
+			(Error::DuplicateRequestId, ErrorCategory::Protocol),
+			(Error::Call(CallError::InvalidParams(anyhow::anyhow!("bad"))), ErrorCategory::Server),
+			(Error::Request("error".into()), ErrorCategory::Server),
+			(Error::MethodNotFound("m".into()), ErrorCategory::Server),
+			(Error::RemoteMethodNotFound { method: "m".into(), error: "error".into() }, ErrorCategory::Server),
+			(Error::SubscriptionClosed(SubscriptionClosedReason::Unsubscribed.into()), ErrorCategory::Server),
+			(Error::ParseError(parse_error), ErrorCategory::Parse),
+			(Error::MaxSlotsExceeded, ErrorCategory::Other),
+			(Error::MethodAlreadyRegistered("m".into()), ErrorCategory::Other),
+			(Error::SubscriptionNameConflict("m".into()), ErrorCategory::Other),
+			(Error::AlreadyStopped, ErrorCategory::Other),
+			(Error::EmptyAllowList("Host"), ErrorCategory::Other),
+			(Error::InvalidIpRange("x".into()), ErrorCategory::Other),
+			(Error::ResourceAtCapacity("cpu"), ErrorCategory::Other),
+			(Error::ResourceNameAlreadyTaken("cpu"), ErrorCategory::Other),
+			(Error::ResourceNameNotFoundForMethod("cpu", "m"), ErrorCategory::Other),
+			(Error::UninitializedMethod("m".into()), ErrorCategory::Other),
+			(Error::MaxResourcesReached, ErrorCategory::Other),
+			(Error::Custom("x".into()), ErrorCategory::Other),
+			(Error::HttpNotImplemented, ErrorCategory::Other),
+		];
+
+		for (err, category) in errors {
+			assert_eq!(err.category(), category, "{:?}", err);
+		}
+	}
 
 	#[test]
 	fn subscription_closed_ser_deser_works() {
@@ -33,12 +33,34 @@ use futures_util::stream::StreamExt;
 ///
 /// Returns `Ok((bytes, single))` if the body was in valid size range; and a bool indicating whether the JSON-RPC
 /// request is a single or a batch.
-/// Returns `Err` if the body was too large or the body couldn't be read.
+/// Returns `Err` if the body was too large, the body couldn't be read or isn't a JSON object or array.
+///
+/// Bodies without a `Content-Length` header, such as chunked ones, are rejected as soon as the data received
+/// so far exceeds `max_request_body_size`, without waiting for the rest of the body.
 pub async fn read_body(
 	headers: &hyper::HeaderMap,
-	mut body: hyper::Body,
+	body: hyper::Body,
 	max_request_body_size: u32,
 ) -> Result<(Vec<u8>, bool), GenericTransportError<hyper::Error>> {
+	let body = read_body_bytes(headers, body, max_request_body_size).await?;
+
+	let single = match body.first() {
+		Some(b'{') => true,
+		Some(b'[') => false,
+		_ => return Err(GenericTransportError::Malformed),
+	};
+
+	Ok((body, single))
+}
+
+/// Read the data from a [`hyper::Body`] like [`read_body`], but without checking that it is JSON.
+///
+/// Used for bodies in other encodings, such as MessagePack, that are converted to JSON afterwards.
+pub async fn read_body_bytes(
+	headers: &hyper::HeaderMap,
+	mut body: hyper::Body,
+	max_request_body_size: u32,
+) -> Result<Vec<u8>, GenericTransportError<hyper::Error>> {
 	// NOTE(niklasad1): Values bigger than `u32::MAX` will be turned into zero here. This is unlikely to occur in
 	// practice and for that case we fallback to allocating in the while-loop below instead of pre-allocating.
 	let body_size = read_header_content_length(headers).unwrap_or(0);
@@ -54,12 +76,6 @@ pub async fn read_body(
 		return Err(GenericTransportError::TooLarge);
 	}
 
-	let single = match first_chunk.get(0) {
-		Some(b'{') => true,
-		Some(b'[') => false,
-		_ => return Err(GenericTransportError::Malformed),
-	};
-
 	let mut received_data = Vec::with_capacity(body_size as usize);
 	received_data.extend_from_slice(&first_chunk);
 
@@ -71,7 +87,7 @@ pub async fn read_body(
 		}
 		received_data.extend_from_slice(&chunk);
 	}
-	Ok((received_data, single))
+	Ok(received_data)
 }
 
 /// Read the `Content-Length` HTTP Header. Must fit into a `u32`; returns `None` otherwise.
@@ -106,6 +122,7 @@ pub fn read_header_values<'a>(
 #[cfg(test)]
 mod tests {
 	use super::{read_body, read_header_content_length};
+	use crate::error::GenericTransportError;
 
 	#[tokio::test]
 	async fn body_to_bytes_size_limit_works() {
@@ -114,6 +131,21 @@ mod tests {
 		assert!(read_body(&headers, body, 127).await.is_err());
 	}
 
+	#[tokio::test]
+	async fn streamed_body_is_rejected_as_soon_as_it_is_too_large() {
+		let headers = hyper::header::HeaderMap::new();
+		let (mut sender, body) = hyper::Body::channel();
+		let reader = tokio::spawn(async move { read_body(&headers, body, 100).await });
+
+		sender.send_data(vec![b'['; 64].into()).await.unwrap();
+		sender.send_data(vec![b' '; 64].into()).await.unwrap();
+
+		// The sender never finishes the body, the limit alone must end the read.
+		let res = tokio::time::timeout(std::time::Duration::from_secs(5), reader).await.unwrap().unwrap();
+		assert!(matches!(res, Err(GenericTransportError::TooLarge)));
+		drop(sender);
+	}
+
 	#[test]
 	fn read_content_length_works() {
 		let mut headers = hyper::header::HeaderMap::new();
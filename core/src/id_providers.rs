@@ -26,21 +26,42 @@
 
 use rand::distributions::Alphanumeric;
 use rand::Rng;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::traits::IdProvider;
 use jsonrpsee_types::SubscriptionId;
 
-/// Generates random integers as subscription ID.
-#[derive(Debug, Clone)]
+/// Mask to keep integers within the range that a JavaScript `Number` can represent without loss of precision.
+const JS_NUM_MASK: u64 = !0 >> 11;
+
+/// Generates random integers within the range that a JavaScript `Number` can represent exactly (53 bits) as
+/// subscription ID.
+///
+/// JavaScript clients lose precision above `2^53` and would fail to unsubscribe from larger IDs, so servers with
+/// such clients should opt into this provider rather than the default [`FullRangeIntegerIdProvider`], at the cost
+/// of more collisions.
+#[derive(Debug, Clone, Copy)]
 pub struct RandomIntegerIdProvider;
 
 impl IdProvider for RandomIntegerIdProvider {
 	fn next_id(&self) -> SubscriptionId<'static> {
-		const JS_NUM_MASK: u64 = !0 >> 11;
 		(rand::random::<u64>() & JS_NUM_MASK).into()
 	}
 }
 
+/// Generates random integers across the full `u64` range as subscription ID.
+///
+/// The default of the servers and of [`Methods`](crate::server::rpc_module::Methods). Not suitable for JavaScript
+/// clients, see [`RandomIntegerIdProvider`].
+#[derive(Debug, Clone, Copy)]
+pub struct FullRangeIntegerIdProvider;
+
+impl IdProvider for FullRangeIntegerIdProvider {
+	fn next_id(&self) -> SubscriptionId<'static> {
+		rand::random::<u64>().into()
+	}
+}
+
 /// Generates random strings of length `len` as subscription ID.
 #[derive(Debug, Clone)]
 pub struct RandomStringIdProvider {
@@ -61,6 +82,28 @@ impl IdProvider for RandomStringIdProvider {
 	}
 }
 
+/// Generates consecutive integers as subscription ID, starting at a given value.
+///
+/// The IDs are predictable and therefore only suitable for tests, where they make it possible to assert on
+/// the subscription IDs handed out by a server or [`Methods`](crate::server::rpc_module::Methods).
+#[derive(Debug, Default)]
+pub struct SequentialIdProvider {
+	next: AtomicU64,
+}
+
+impl SequentialIdProvider {
+	/// Create a new provider whose first ID is `start`.
+	pub fn new(start: u64) -> Self {
+		Self { next: AtomicU64::new(start) }
+	}
+}
+
+impl IdProvider for SequentialIdProvider {
+	fn next_id(&self) -> SubscriptionId<'static> {
+		self.next.fetch_add(1, Ordering::Relaxed).into()
+	}
+}
+
 /// No-op implementation to be used for servers that don't support subscriptions.
 #[derive(Debug, Clone)]
 pub struct NoopIdProvider;
@@ -54,7 +54,7 @@ pub mod server;
 pub mod client;
 
 pub use async_trait::async_trait;
-pub use error::Error;
+pub use error::{Error, ErrorCategory};
 
 /// JSON-RPC result.
 pub type RpcResult<T> = std::result::Result<T, Error>;
@@ -63,6 +63,7 @@ pub type RpcResult<T> = std::result::Result<T, Error>;
 /// dependencies to be explicitly added on the client side.
 #[doc(hidden)]
 pub mod __reexports {
+	pub use anyhow;
 	pub use async_trait::async_trait;
 	pub use serde;
 	pub use serde_json;
@@ -77,3 +78,47 @@ pub use serde_json::{
 
 /// Ten megabytes.
 pub const TEN_MB_SIZE_BYTES: u32 = 10 * 1024 * 1024;
+
+/// Default maximum nesting depth of JSON arrays and objects.
+pub const MAX_JSON_DEPTH: u32 = 128;
+
+/// Returns `true` if arrays and objects in `data` are nested deeper than `max_depth`.
+///
+/// The input is scanned without being deserialized, so that it is cheap to check before parsing untrusted JSON.
+/// Invalid JSON is not detected, such input is rejected when it is parsed.
+///
+/// ```
+///    use jsonrpsee_core::json_depth_exceeds;
+///
+///    assert!(!json_depth_exceeds(br#"{"a":[1,"]]]"]}"#, 2));
+///    assert!(json_depth_exceeds(b"[[[]]]", 2));
+/// ```
+pub fn json_depth_exceeds(data: &[u8], max_depth: u32) -> bool {
+	let mut depth = 0u32;
+	let mut in_string = false;
+	let mut escaped = false;
+
+	for &b in data {
+		if in_string {
+			match b {
+				_ if escaped => escaped = false,
+				b'\\' => escaped = true,
+				b'"' => in_string = false,
+				_ => {}
+			}
+			continue;
+		}
+		match b {
+			b'"' => in_string = true,
+			b'[' | b'{' => {
+				depth += 1;
+				if depth > max_depth {
+					return true;
+				}
+			}
+			b']' | b'}' => depth = depth.saturating_sub(1),
+			_ => {}
+		}
+	}
+	false
+}
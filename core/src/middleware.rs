@@ -26,6 +26,12 @@
 
 //! Middleware for `jsonrpsee` servers.
 
+use jsonrpsee_types::Version;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 /// Defines a middleware with callbacks during the RPC request life-cycle. The primary use case for
 /// this is to collect timings for a larger metrics collection solution but the only constraints on
 /// the associated type is that it be [`Send`] and [`Copy`], giving users some freedom to do what
@@ -48,12 +54,19 @@ pub trait Middleware: Send + Sync + Clone + 'static {
 	/// Called on each JSON-RPC method call, batch requests will trigger `on_call` multiple times.
 	fn on_call(&self, _name: &str) {}
 
+	/// Called right after `on_call` with the JSON-RPC version of the request of the call.
+	fn on_version(&self, _name: &str, _version: Version) {}
+
 	/// Called on each JSON-RPC method completion, batch requests will trigger `on_result` multiple times.
 	fn on_result(&self, _name: &str, _success: bool, _started_at: Self::Instant) {}
 
 	/// Called once the JSON-RPC request is finished and response is sent to the output buffer.
 	fn on_response(&self, _started_at: Self::Instant) {}
 
+	/// Called when a message is received with the number of requests of the connection that are still pending
+	/// (WebSocket only)
+	fn on_queue_depth(&self, _depth: usize) {}
+
 	/// Called when a client disconnects (WebSocket only)
 	fn on_disconnect(&self) {}
 }
@@ -80,6 +93,11 @@ where
 		self.1.on_call(name);
 	}
 
+	fn on_version(&self, name: &str, version: Version) {
+		self.0.on_version(name, version);
+		self.1.on_version(name, version);
+	}
+
 	fn on_result(&self, name: &str, success: bool, started_at: Self::Instant) {
 		self.0.on_result(name, success, started_at.0);
 		self.1.on_result(name, success, started_at.1);
@@ -89,4 +107,152 @@ where
 		self.0.on_response(started_at.0);
 		self.1.on_response(started_at.1);
 	}
+
+	fn on_queue_depth(&self, depth: usize) {
+		self.0.on_queue_depth(depth);
+		self.1.on_queue_depth(depth);
+	}
+}
+
+type LatencyCallback = Arc<dyn Fn(&str, Duration) + Send + Sync>;
+
+/// Name under which [`LatencyHistogram`] records the calls to unknown methods.
+pub const UNKNOWN_METHOD: &str = "<unknown>";
+
+/// Maximum number of methods [`LatencyHistogram`] keeps a histogram for, unless they are given explicitly.
+const MAX_METHODS: usize = 1024;
+
+/// Middleware recording the latency of every method call into a per-method histogram.
+///
+/// The latency of a call is measured from the moment its request was received until the method completed.
+/// Clones share the same histograms, so keep a clone around to read them with [`LatencyHistogram::method`]
+/// or [`LatencyHistogram::snapshot`].
+///
+/// The servers report calls to methods that don't exist under the name sent by the client. Such calls are recorded
+/// under [`UNKNOWN_METHOD`] when the methods are given with [`LatencyHistogram::with_methods`], or otherwise once
+/// 1024 different names were recorded.
+#[derive(Clone)]
+pub struct LatencyHistogram {
+	buckets: Arc<[Duration]>,
+	histograms: Arc<Mutex<HashMap<String, Histogram>>>,
+	methods: Option<Arc<HashSet<String>>>,
+	callback: Option<LatencyCallback>,
+}
+
+/// Latencies recorded for a single method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+	/// Upper bounds (inclusive) of the buckets, in ascending order.
+	pub buckets: Vec<Duration>,
+	/// Number of calls per bucket. Has one more entry than `buckets`, counting the calls slower than the last bound.
+	pub counts: Vec<u64>,
+	/// Sum of the latencies of all calls.
+	pub sum: Duration,
+}
+
+impl Histogram {
+	fn new(buckets: &[Duration]) -> Self {
+		Self { buckets: buckets.to_vec(), counts: vec![0; buckets.len() + 1], sum: Duration::ZERO }
+	}
+
+	fn record(&mut self, latency: Duration) {
+		let idx = self.buckets.partition_point(|bound| *bound < latency);
+		self.counts[idx] += 1;
+		self.sum += latency;
+	}
+
+	/// Total number of calls recorded.
+	pub fn count(&self) -> u64 {
+		self.counts.iter().sum()
+	}
+}
+
+impl LatencyHistogram {
+	/// Create a new latency histogram with the given bucket upper bounds.
+	///
+	/// The bounds are sorted and deduplicated.
+	pub fn new(mut buckets: Vec<Duration>) -> Self {
+		buckets.sort_unstable();
+		buckets.dedup();
+		Self { buckets: buckets.into(), histograms: Default::default(), methods: None, callback: None }
+	}
+
+	/// Only keep histograms for the given methods, such as the `method_names()` of the module that is served.
+	/// Calls to other methods are recorded under [`UNKNOWN_METHOD`].
+	pub fn with_methods<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+		self.methods = Some(Arc::new(names.into_iter().map(ToOwned::to_owned).collect()));
+		self
+	}
+
+	/// Call `f` with the method name and latency of every completed call, for example to export
+	/// them to a metrics system.
+	pub fn on_latency(mut self, f: impl Fn(&str, Duration) + Send + Sync + 'static) -> Self {
+		self.callback = Some(Arc::new(f));
+		self
+	}
+
+	/// Histogram of the given method, if it has been called.
+	pub fn method(&self, name: &str) -> Option<Histogram> {
+		self.histograms.lock().expect("lock is never poisoned; qed").get(name).cloned()
+	}
+
+	/// Histograms of all the methods called so far.
+	pub fn snapshot(&self) -> HashMap<String, Histogram> {
+		self.histograms.lock().expect("lock is never poisoned; qed").clone()
+	}
+}
+
+impl Default for LatencyHistogram {
+	/// Buckets from 1 ms to 10 s.
+	fn default() -> Self {
+		Self::new(
+			[1, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000]
+				.into_iter()
+				.map(Duration::from_millis)
+				.collect(),
+		)
+	}
+}
+
+impl fmt::Debug for LatencyHistogram {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("LatencyHistogram")
+			.field("buckets", &self.buckets)
+			.field("histograms", &self.histograms)
+			.field("methods", &self.methods)
+			.field("callback", &self.callback.is_some())
+			.finish()
+	}
+}
+
+impl Middleware for LatencyHistogram {
+	type Instant = Instant;
+
+	fn on_request(&self) -> Instant {
+		Instant::now()
+	}
+
+	fn on_result(&self, name: &str, _success: bool, started_at: Instant) {
+		let latency = started_at.elapsed();
+		let name = {
+			let mut histograms = self.histograms.lock().expect("lock is never poisoned; qed");
+			let known = match &self.methods {
+				Some(methods) => methods.contains(name),
+				None => histograms.len() < MAX_METHODS || histograms.contains_key(name),
+			};
+			let name = if known { name } else { UNKNOWN_METHOD };
+			match histograms.get_mut(name) {
+				Some(histogram) => histogram.record(latency),
+				None => {
+					let mut histogram = Histogram::new(&self.buckets);
+					histogram.record(latency);
+					histograms.insert(name.to_owned(), histogram);
+				}
+			}
+			name
+		};
+		if let Some(callback) = &self.callback {
+			callback(name, latency);
+		}
+	}
 }
@@ -0,0 +1,116 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::sync::Arc;
+
+use crate::server::rpc_module::ConnectionId;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+/// A map from [`ConnectionId`] to a state of type `T`.
+///
+/// The map is cheap to clone and all clones share the same states. Hand one clone to the server, which
+/// inserts a state when a connection is accepted and removes it once the connection is closed, and capture
+/// another in the method handlers to look up the state of the connection they were called on.
+#[derive(Debug)]
+pub struct ConnectionStates<T> {
+	inner: Arc<Mutex<FxHashMap<ConnectionId, T>>>,
+}
+
+impl<T> Clone for ConnectionStates<T> {
+	fn clone(&self) -> Self {
+		Self { inner: self.inner.clone() }
+	}
+}
+
+impl<T> Default for ConnectionStates<T> {
+	fn default() -> Self {
+		Self { inner: Arc::new(Mutex::new(FxHashMap::default())) }
+	}
+}
+
+impl<T> ConnectionStates<T> {
+	/// Create an empty map.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the state of a connection, returning the previous state if there was one.
+	pub fn insert(&self, conn_id: ConnectionId, state: T) -> Option<T> {
+		self.inner.lock().insert(conn_id, state)
+	}
+
+	/// Remove the state of a connection.
+	pub fn remove(&self, conn_id: ConnectionId) -> Option<T> {
+		self.inner.lock().remove(&conn_id)
+	}
+
+	/// Run `f` on the state of a connection, `None` if the connection has no state.
+	///
+	/// The map is locked while `f` runs, so keep it short and don't call back into the map.
+	pub fn with<R>(&self, conn_id: ConnectionId, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+		self.inner.lock().get_mut(&conn_id).map(f)
+	}
+
+	/// Get a copy of the state of a connection.
+	pub fn get(&self, conn_id: ConnectionId) -> Option<T>
+	where
+		T: Clone,
+	{
+		self.inner.lock().get(&conn_id).cloned()
+	}
+
+	/// Number of connections with a state.
+	pub fn len(&self) -> usize {
+		self.inner.lock().len()
+	}
+
+	/// Returns whether no connection has a state.
+	pub fn is_empty(&self) -> bool {
+		self.inner.lock().is_empty()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ConnectionStates;
+
+	#[test]
+	fn states_are_shared_between_clones() {
+		let states = ConnectionStates::new();
+		let handler_states = states.clone();
+
+		assert!(states.insert(1, "alice".to_string()).is_none());
+		assert_eq!(handler_states.get(1).as_deref(), Some("alice"));
+
+		handler_states.with(1, |user| user.push_str("@example"));
+		assert_eq!(states.get(1).as_deref(), Some("alice@example"));
+		assert_eq!(states.with(2, |_| ()), None);
+
+		assert_eq!(states.remove(1).as_deref(), Some("alice@example"));
+		assert!(handler_states.is_empty());
+	}
+}
@@ -0,0 +1,56 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::fmt::Debug;
+
+use futures_util::future::BoxFuture;
+
+/// Runs the tasks of a server, such as the ones serving the connections, to completion in the background.
+///
+/// Implement it to control which tokio runtime, or which threads of a runtime, run the tasks of the WebSocket server
+/// and the streams forwarded by [`RpcModule::register_stream_subscription`](crate::server::rpc_module::RpcModule::register_stream_subscription).
+/// The tasks use tokio's sockets and timers, so they must be polled within the context of a tokio runtime or
+/// tokio panics.
+pub trait Executor: Send + Sync + Debug {
+	/// Spawn a task.
+	fn spawn(&self, task: BoxFuture<'static, ()>);
+}
+
+/// [`Executor`] spawning the tasks with [`tokio::spawn`], that is on the runtime the server is started on.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioExecutor;
+
+impl Executor for TokioExecutor {
+	fn spawn(&self, task: BoxFuture<'static, ()>) {
+		tokio::spawn(task);
+	}
+}
+
+impl Executor for tokio::runtime::Handle {
+	fn spawn(&self, task: BoxFuture<'static, ()>) {
+		tokio::runtime::Handle::spawn(self, task);
+	}
+}
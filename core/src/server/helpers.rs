@@ -24,8 +24,15 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::future::Future;
 use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Instant;
 
+use crate::server::rpc_module::RpcModule;
 use crate::{to_json_raw_value, Error};
 use futures_channel::mpsc;
 use futures_util::StreamExt;
@@ -33,8 +40,13 @@ use jsonrpsee_types::error::{
 	CallError, ErrorCode, ErrorObject, ErrorResponse, CALL_EXECUTION_FAILED_CODE, OVERSIZED_RESPONSE_CODE,
 	OVERSIZED_RESPONSE_MSG, UNKNOWN_ERROR_CODE,
 };
-use jsonrpsee_types::{Id, InvalidRequest, Response};
+use jsonrpsee_types::{Id, InvalidRequest, Notification, Request, Response};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
 use serde::Serialize;
+use serde_json::value::RawValue;
+use tokio::sync::Notify;
 
 /// Bounded writer that allows writing at most `max_len` bytes.
 ///
@@ -81,6 +93,144 @@ impl<'a> io::Write for &'a mut BoundedWriter {
 	}
 }
 
+/// Counts the messages sent on a [`MethodSink`] that the transport hasn't written to the connection yet, so that
+/// producers can wait for a slow client to catch up, see [`MethodSink::ready`].
+#[derive(Clone, Debug)]
+pub struct BufferedMessages {
+	inner: Arc<BufferedMessagesInner>,
+}
+
+#[derive(Debug)]
+struct BufferedMessagesInner {
+	len: AtomicUsize,
+	capacity: usize,
+	closed: AtomicBool,
+	changed: Notify,
+}
+
+impl BufferedMessages {
+	/// Create a new counter, the sink is ready as long as less than `capacity` messages are buffered.
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			inner: Arc::new(BufferedMessagesInner {
+				len: AtomicUsize::new(0),
+				capacity,
+				closed: AtomicBool::new(false),
+				changed: Notify::new(),
+			}),
+		}
+	}
+
+	/// Returns the number of messages that are buffered.
+	pub fn len(&self) -> usize {
+		self.inner.len.load(Ordering::Acquire)
+	}
+
+	/// Returns `true` if no message is buffered.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// To be called by the transport after it wrote a message to the connection.
+	pub fn written(&self) {
+		self.inner.len.fetch_sub(1, Ordering::AcqRel);
+		self.inner.changed.notify_waiters();
+	}
+
+	/// To be called by the transport once it stops writing, to release the producers waiting for it.
+	pub fn close(&self) {
+		self.inner.closed.store(true, Ordering::Release);
+		self.inner.changed.notify_waiters();
+	}
+
+	fn buffered(&self) {
+		self.inner.len.fetch_add(1, Ordering::AcqRel);
+	}
+
+	async fn ready(&self) {
+		loop {
+			// Registered before checking the length to not miss a wake-up in between.
+			let changed = self.inner.changed.notified();
+			if self.len() < self.inner.capacity || self.inner.closed.load(Ordering::Acquire) {
+				return;
+			}
+			changed.await;
+		}
+	}
+}
+
+/// Closes a subscription once its connection is reset.
+type ResetCallback = Box<dyn FnOnce() + Send>;
+
+/// Tracks the active subscriptions of a connection, see [`MethodSink::with_active_subscriptions`].
+#[derive(Clone, Default)]
+pub struct ActiveSubscriptions {
+	inner: Arc<Mutex<ActiveSubscriptionsInner>>,
+}
+
+#[derive(Default)]
+struct ActiveSubscriptionsInner {
+	next_id: u64,
+	on_reset: FxHashMap<u64, ResetCallback>,
+}
+
+impl fmt::Debug for ActiveSubscriptions {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ActiveSubscriptions").field("count", &self.count()).finish()
+	}
+}
+
+impl ActiveSubscriptions {
+	/// Create a new tracker.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the number of active subscriptions.
+	pub fn count(&self) -> usize {
+		self.inner.lock().on_reset.len()
+	}
+
+	/// Close the subscriptions that are still active once the connection is closed, such that they are removed
+	/// from their module and reported as unsubscribed.
+	pub fn reset(&self) {
+		let on_reset = std::mem::take(&mut self.inner.lock().on_reset);
+		for (_, callback) in on_reset {
+			callback();
+		}
+	}
+
+	fn track(&self, on_reset: ResetCallback) -> ActiveSubscription {
+		let mut inner = self.inner.lock();
+		let id = inner.next_id;
+		inner.next_id += 1;
+		inner.on_reset.insert(id, on_reset);
+		ActiveSubscription { id, inner: Arc::downgrade(&self.inner) }
+	}
+}
+
+/// Tracked in [`ActiveSubscriptions`] until dropped.
+pub(crate) struct ActiveSubscription {
+	id: u64,
+	inner: Weak<Mutex<ActiveSubscriptionsInner>>,
+}
+
+impl fmt::Debug for ActiveSubscription {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("ActiveSubscription").field(&self.id).finish()
+	}
+}
+
+impl Drop for ActiveSubscription {
+	fn drop(&mut self) {
+		if let Some(inner) = self.inner.upgrade() {
+			// Dropped outside of the lock, the callback may own anything.
+			let on_reset = inner.lock().on_reset.remove(&self.id);
+			drop(on_reset);
+		}
+	}
+}
+
 /// Sink that is used to send back the result to the server for a specific method.
 #[derive(Clone, Debug)]
 pub struct MethodSink {
@@ -88,17 +238,85 @@ pub struct MethodSink {
 	tx: mpsc::UnboundedSender<String>,
 	/// Max response size in bytes for a executed call.
 	max_response_size: u32,
+	/// Messages not written to the connection yet, if the transport keeps track of them.
+	buffered: Option<BufferedMessages>,
+	/// Active subscriptions of the connection, if the transport keeps track of them.
+	subscriptions: Option<ActiveSubscriptions>,
+	/// Whether the transport delivers messages sent before the response, see [`MethodSink::without_notifications`].
+	notifications: bool,
 }
 
 impl MethodSink {
 	/// Create a new `MethodSink` with unlimited response size
 	pub fn new(tx: mpsc::UnboundedSender<String>) -> Self {
-		MethodSink { tx, max_response_size: u32::MAX }
+		MethodSink { tx, max_response_size: u32::MAX, buffered: None, subscriptions: None, notifications: true }
 	}
 
 	/// Create a new `MethodSink` with a limited response size
 	pub fn new_with_limit(tx: mpsc::UnboundedSender<String>, max_response_size: u32) -> Self {
-		MethodSink { tx, max_response_size }
+		MethodSink { tx, max_response_size, buffered: None, subscriptions: None, notifications: true }
+	}
+
+	/// Count the messages sent on this sink in `buffered`, the transport must call [`BufferedMessages::written`]
+	/// for each message it takes off the channel.
+	pub fn with_buffered_messages(mut self, buffered: BufferedMessages) -> Self {
+		self.buffered = Some(buffered);
+		self
+	}
+
+	/// Count the subscriptions created on this sink in `subscriptions`, until they are closed.
+	pub fn with_active_subscriptions(mut self, subscriptions: ActiveSubscriptions) -> Self {
+		self.subscriptions = Some(subscriptions);
+		self
+	}
+
+	/// Mark the sink as only delivering the response of a call, as for HTTP and batches where the first message or
+	/// every message is taken as a response. The progress of calls is then not sent.
+	pub fn without_notifications(mut self) -> Self {
+		self.notifications = false;
+		self
+	}
+
+	/// Returns whether notifications sent before the response of a call reach the client.
+	pub fn supports_notifications(&self) -> bool {
+		self.notifications
+	}
+
+	/// Start tracking a new subscription, if the subscriptions are tracked, which is closed with `on_reset` if it's
+	/// still active when the connection is closed.
+	pub(crate) fn track_subscription(&self, on_reset: impl FnOnce() + Send + 'static) -> Option<ActiveSubscription> {
+		self.subscriptions.as_ref().map(|subscriptions| subscriptions.track(Box::new(on_reset)))
+	}
+
+	/// Waits until the transport has caught up with the messages sent on this sink, that is until fewer than the
+	/// capacity of its [`BufferedMessages`] are waiting to be written. Returns immediately if the messages aren't
+	/// counted or the connection is closed.
+	pub async fn ready(&self) {
+		if let Some(buffered) = &self.buffered {
+			buffered.ready().await;
+		}
+	}
+
+	/// Same as [`MethodSink::ready`] but the returned future doesn't borrow the sink.
+	pub(crate) fn ready_owned(&self) -> impl Future<Output = ()> + Send + 'static {
+		let buffered = self.buffered.clone();
+		async move {
+			if let Some(buffered) = buffered {
+				buffered.ready().await;
+			}
+		}
+	}
+
+	fn send(&self, json: String) -> Result<(), mpsc::TrySendError<String>> {
+		// Counted before sending because the transport may write the message right away.
+		if let Some(buffered) = &self.buffered {
+			buffered.buffered();
+		}
+		let res = self.tx.unbounded_send(json);
+		if let (Err(_), Some(buffered)) = (&res, &self.buffered) {
+			buffered.written();
+		}
+		res
 	}
 
 	/// Returns whether this channel is closed without needing a context.
@@ -108,7 +326,15 @@ impl MethodSink {
 
 	/// Send a JSON-RPC response to the client. If the serialization of `result` exceeds `max_response_size`,
 	/// an error will be sent instead.
+	///
+	/// Returns `false` if an error was sent instead or if the sink is closed, use
+	/// [`MethodSink::try_send_response`] to tell them apart.
 	pub fn send_response(&self, id: Id, result: impl Serialize) -> bool {
+		self.try_send_response(id, result).unwrap_or(false)
+	}
+
+	/// Same as [`MethodSink::send_response`] but fails with [`SinkClosed`] if nothing could be sent.
+	pub fn try_send_response(&self, id: Id, result: impl Serialize) -> Result<bool, SinkClosed> {
 		let mut writer = BoundedWriter::new(self.max_response_size as usize);
 
 		let json = match serde_json::to_writer(&mut writer, &Response::new(result, id.clone())) {
@@ -120,47 +346,70 @@ impl MethodSink {
 				tracing::error!("Error serializing response: {:?}", err);
 
 				if err.is_io() {
-					let data = to_json_raw_value(&format!("Exceeded max limit {}", self.max_response_size)).ok();
-					let err = ErrorObject {
-						code: ErrorCode::ServerError(OVERSIZED_RESPONSE_CODE),
-						message: OVERSIZED_RESPONSE_MSG.into(),
-						data: data.as_deref(),
-					};
-					return self.send_error(id, err);
+					return self.try_send_oversized_error(id);
 				} else {
-					return self.send_error(id, ErrorCode::InternalError.into());
+					return self.try_send_error(id, ErrorCode::InternalError.into());
 				}
 			}
 		};
 
-		if let Err(err) = self.tx.unbounded_send(json) {
-			tracing::error!("Error sending response to the client: {:?}", err);
-			false
-		} else {
-			true
+		if let Err(err) = self.send(json) {
+			tracing::debug!("Could not send response to the client, the connection is closed: {:?}", err);
+			return Err(SinkClosed);
 		}
+		Ok(true)
 	}
 
-	/// Send a JSON-RPC error to the client
+	/// Send the error answered to calls whose response exceeds the `max_response_size` of the sink.
+	pub fn send_oversized_error(&self, id: Id) -> bool {
+		self.try_send_oversized_error(id).unwrap_or(false)
+	}
+
+	/// Same as [`MethodSink::send_oversized_error`] but fails with [`SinkClosed`] if the error could not be sent.
+	pub fn try_send_oversized_error(&self, id: Id) -> Result<bool, SinkClosed> {
+		let data = to_json_raw_value(&format!("Exceeded max limit {}", self.max_response_size)).ok();
+		let err = ErrorObject {
+			code: ErrorCode::ServerError(OVERSIZED_RESPONSE_CODE),
+			message: OVERSIZED_RESPONSE_MSG.into(),
+			data: data.as_deref(),
+		};
+		self.try_send_error(id, err)
+	}
+
+	/// Send a JSON-RPC error to the client.
+	///
+	/// Always returns `false` as the call failed, use [`MethodSink::try_send_error`] to find out whether the error
+	/// could be delivered.
 	pub fn send_error(&self, id: Id, error: ErrorObject) -> bool {
+		self.try_send_error(id, error).unwrap_or(false)
+	}
+
+	/// Same as [`MethodSink::send_error`] but fails with [`SinkClosed`] if the error could not be sent.
+	pub fn try_send_error(&self, id: Id, error: ErrorObject) -> Result<bool, SinkClosed> {
 		let json = match serde_json::to_string(&ErrorResponse::new(error, id)) {
 			Ok(json) => json,
 			Err(err) => {
 				tracing::error!("Error serializing error message: {:?}", err);
 
-				return false;
+				return Ok(false);
 			}
 		};
 
-		if let Err(err) = self.tx.unbounded_send(json) {
-			tracing::error!("Could not send error response to the client: {:?}", err)
+		if let Err(err) = self.send(json) {
+			tracing::debug!("Could not send error response to the client, the connection is closed: {:?}", err);
+			return Err(SinkClosed);
 		}
 
-		false
+		Ok(false)
 	}
 
 	/// Helper for sending the general purpose `Error` as a JSON-RPC errors to the client
 	pub fn send_call_error(&self, id: Id, err: Error) -> bool {
+		self.try_send_call_error(id, err).unwrap_or(false)
+	}
+
+	/// Same as [`MethodSink::send_call_error`] but fails with [`SinkClosed`] if the error could not be sent.
+	pub fn try_send_call_error(&self, id: Id, err: Error) -> Result<bool, SinkClosed> {
 		let (code, message, data) = match err {
 			Error::Call(CallError::InvalidParams(e)) => (ErrorCode::InvalidParams, e.to_string(), None),
 			Error::Call(CallError::Failed(e)) => {
@@ -174,13 +423,13 @@ impl MethodSink {
 
 		let err = ErrorObject { code, message: message.into(), data: data.as_deref() };
 
-		self.send_error(id, err)
+		self.try_send_error(id, err)
 	}
 
 	/// Send a raw JSON-RPC message to the client, `MethodSink` does not check verify the validity
 	/// of the JSON being sent.
 	pub fn send_raw(&self, raw_json: String) -> Result<(), mpsc::TrySendError<String>> {
-		self.tx.unbounded_send(raw_json)
+		self.send(raw_json)
 	}
 
 	/// Close the channel for any further messages.
@@ -189,6 +438,123 @@ impl MethodSink {
 	}
 }
 
+/// The receiving end of a [`MethodSink`] is gone, such as when the connection is closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("The connection is closed, the message could not be sent")]
+pub struct SinkClosed;
+
+/// Notification with its params left unparsed.
+pub type RawNotification<'a> = Notification<'a, Option<&'a RawValue>>;
+
+/// Message received from a client, as parsed by [`parse_request`].
+#[derive(Debug)]
+pub enum ParsedRequest<'a> {
+	/// A single method call.
+	Call(Request<'a>),
+	/// A single notification, which must not be answered.
+	Notification(RawNotification<'a>),
+	/// A non-empty batch of method calls.
+	Batch(Vec<Request<'a>>),
+	/// A non-empty batch made of notifications only, which must not be answered.
+	NotificationBatch(Vec<RawNotification<'a>>),
+}
+
+/// Reason why [`parse_request`] rejected a message.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum RequestParseError<'a> {
+	/// The message is not valid JSON.
+	#[error("Invalid JSON")]
+	InvalidJson,
+	/// The message is valid JSON but not a valid request, the ID is extracted if there is one.
+	#[error("Invalid request")]
+	InvalidRequest(Id<'a>),
+	/// The message is an empty batch.
+	#[error("Empty batch")]
+	EmptyBatch,
+}
+
+impl<'a> RequestParseError<'a> {
+	/// Returns the ID to answer the message with.
+	pub fn id(&self) -> Id<'a> {
+		match self {
+			Self::InvalidRequest(id) => id.clone(),
+			Self::InvalidJson | Self::EmptyBatch => Id::Null,
+		}
+	}
+
+	/// Returns the JSON-RPC error code to answer the message with.
+	pub fn code(&self) -> ErrorCode {
+		match self {
+			Self::InvalidJson => ErrorCode::ParseError,
+			Self::InvalidRequest(_) | Self::EmptyBatch => ErrorCode::InvalidRequest,
+		}
+	}
+}
+
+/// Parse a message received from a client into a call, a notification or a batch of either.
+///
+/// This is the parser shared by the transports, it never panics, whatever the input is. A batch that mixes calls
+/// and notifications is rejected as an invalid request.
+///
+/// The params are kept as raw JSON, which is skipped over without a depth limit, such that nesting isn't limited
+/// here. The transports check it with [`json_depth_exceeds`](crate::json_depth_exceeds) beforehand.
+///
+/// ```
+///    use jsonrpsee_core::server::helpers::{parse_request, ParsedRequest, RequestParseError};
+///
+///    assert!(matches!(parse_request(br#"{"jsonrpc":"2.0","method":"a","id":1}"#), Ok(ParsedRequest::Call(_))));
+///    assert_eq!(parse_request(b"[]").unwrap_err(), RequestParseError::EmptyBatch);
+/// ```
+pub fn parse_request(data: &[u8]) -> Result<ParsedRequest<'_>, RequestParseError<'_>> {
+	match data.iter().find(|b| !b.is_ascii_whitespace()) {
+		Some(b'{') => {
+			if let Ok(call) = serde_json::from_slice::<Request>(data) {
+				return Ok(ParsedRequest::Call(call));
+			}
+			// Unknown members are ignored, a call with an invalid ID must not be mistaken for a notification.
+			if let Ok(notif) = serde_json::from_slice::<RawNotification>(data) {
+				if !has_id(data) {
+					return Ok(ParsedRequest::Notification(notif));
+				}
+			}
+		}
+		Some(b'[') => {
+			if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(data) {
+				return if batch.is_empty() {
+					Err(RequestParseError::EmptyBatch)
+				} else {
+					Ok(ParsedRequest::Batch(batch))
+				};
+			}
+			if let Ok(batch) = serde_json::from_slice::<Vec<RawNotification>>(data) {
+				if !has_id(data) {
+					return Ok(ParsedRequest::NotificationBatch(batch));
+				}
+			}
+		}
+		_ => return Err(RequestParseError::InvalidJson),
+	}
+
+	match prepare_error(data) {
+		(id, ErrorCode::InvalidRequest) => Err(RequestParseError::InvalidRequest(id)),
+		_ => Err(RequestParseError::InvalidJson),
+	}
+}
+
+/// Returns `true` if the request object in `data`, or any request object of a batch, has an `id` member.
+fn has_id(data: &[u8]) -> bool {
+	request_members(data).iter().any(|members| members.contains_key("id"))
+}
+
+/// Error answered to a batch with more than `max_batch_size` calls, which is not processed at all.
+pub fn batch_too_large(max_batch_size: u32) -> ErrorObject<'static> {
+	ErrorObject {
+		code: ErrorCode::InvalidRequest,
+		message: format!("Batch too large, at most {} calls are accepted", max_batch_size).into(),
+		data: None,
+	}
+}
+
 /// Figure out if this is a sufficiently complete request that we can extract an [`Id`] out of, or just plain
 /// unparseable garbage.
 pub fn prepare_error(data: &[u8]) -> (Id<'_>, ErrorCode) {
@@ -198,6 +564,147 @@ pub fn prepare_error(data: &[u8]) -> (Id<'_>, ErrorCode) {
 	}
 }
 
+/// Returns `true` if any JSON object in `data` contains the same key more than once.
+///
+/// Lenient parsers disagree on which of the duplicated values wins, which can be abused to smuggle requests past
+/// proxies that inspect the payload.
+///
+/// Fails if `data` can't be checked, that is if it's invalid JSON or nested deeper than the 128 levels `serde_json`
+/// supports, such input must be rejected as it may contain duplicate keys.
+///
+/// ```
+///    use jsonrpsee_core::server::helpers::has_duplicate_keys;
+///
+///    assert!(has_duplicate_keys(br#"{"a":1,"b":{"c":2,"c":3}}"#).unwrap());
+///    assert!(!has_duplicate_keys(br#"[{"a":1},{"a":2}]"#).unwrap());
+///    assert!(has_duplicate_keys(b"not json").is_err());
+/// ```
+pub fn has_duplicate_keys(data: &[u8]) -> Result<bool, serde_json::Error> {
+	serde_json::from_slice::<UniqueKeys>(data).map(|UniqueKeys(unique)| !unique)
+}
+
+/// Error answered to a request that has duplicate keys, [`ErrorCode::InvalidRequest`], or that can't be checked for
+/// them, [`ErrorCode::ParseError`]. Returns `None` if the request can be processed.
+pub fn duplicate_keys_error(data: &[u8]) -> Option<ErrorCode> {
+	match has_duplicate_keys(data) {
+		Ok(false) => None,
+		Ok(true) => Some(ErrorCode::InvalidRequest),
+		Err(_) => Some(ErrorCode::ParseError),
+	}
+}
+
+/// Any JSON value, `false` if some object within it has duplicate keys.
+struct UniqueKeys(bool);
+
+impl<'de> Deserialize<'de> for UniqueKeys {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_any(UniqueKeysVisitor)
+	}
+}
+
+struct UniqueKeysVisitor;
+
+impl<'de> Visitor<'de> for UniqueKeysVisitor {
+	type Value = UniqueKeys;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("any valid JSON value")
+	}
+
+	fn visit_bool<E: de::Error>(self, _: bool) -> Result<Self::Value, E> {
+		Ok(UniqueKeys(true))
+	}
+
+	fn visit_i64<E: de::Error>(self, _: i64) -> Result<Self::Value, E> {
+		Ok(UniqueKeys(true))
+	}
+
+	fn visit_u64<E: de::Error>(self, _: u64) -> Result<Self::Value, E> {
+		Ok(UniqueKeys(true))
+	}
+
+	fn visit_f64<E: de::Error>(self, _: f64) -> Result<Self::Value, E> {
+		Ok(UniqueKeys(true))
+	}
+
+	fn visit_str<E: de::Error>(self, _: &str) -> Result<Self::Value, E> {
+		Ok(UniqueKeys(true))
+	}
+
+	fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+		Ok(UniqueKeys(true))
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+		let mut unique = true;
+		while let Some(UniqueKeys(u)) = seq.next_element()? {
+			unique &= u;
+		}
+		Ok(UniqueKeys(unique))
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+		let mut keys = HashSet::new();
+		let mut unique = true;
+		while let Some(key) = map.next_key::<String>()? {
+			unique &= keys.insert(key);
+			let UniqueKeys(u) = map.next_value()?;
+			unique &= u;
+		}
+		Ok(UniqueKeys(unique))
+	}
+}
+
+/// Members a request object may have according to the JSON-RPC 2.0 spec.
+const REQUEST_MEMBERS: [&str; 4] = ["jsonrpc", "method", "params", "id"];
+
+/// Returns `true` if the request object in `data`, or any request object of a batch, has members other than
+/// `jsonrpc`, `method`, `params` and `id`.
+///
+/// Invalid JSON is regarded as not having unknown fields, such input is rejected when the request is parsed.
+///
+/// ```
+///    use jsonrpsee_core::server::helpers::has_unknown_fields;
+///
+///    assert!(has_unknown_fields(br#"{"jsonrpc":"2.0","method":"a","id":1,"foo":1}"#));
+///    assert!(!has_unknown_fields(br#"[{"jsonrpc":"2.0","method":"a","params":{"foo":1}}]"#));
+/// ```
+pub fn has_unknown_fields(data: &[u8]) -> bool {
+	request_members(data).iter().any(|members| members.keys().any(|key| !REQUEST_MEMBERS.contains(&key.as_str())))
+}
+
+/// Members of the request object in `data` or of each request object of a batch, empty if `data` isn't made of
+/// JSON objects.
+fn request_members(data: &[u8]) -> Vec<BTreeMap<String, IgnoredAny>> {
+	match data.iter().find(|b| !b.is_ascii_whitespace()) {
+		Some(b'{') => serde_json::from_slice(data).map(|req| vec![req]).unwrap_or_default(),
+		Some(b'[') => serde_json::from_slice(data).unwrap_or_default(),
+		_ => Vec::new(),
+	}
+}
+
+/// Response of the built-in health endpoint, see [`health_module`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct Health {
+	/// Always `true`, the server is healthy if it answers at all.
+	pub ok: bool,
+	/// Seconds elapsed since the server was started.
+	pub uptime: u64,
+}
+
+/// Build a module with a single method called `name` that answers with a [`Health`] report, the uptime is
+/// counted from the moment the module is created.
+pub fn health_module(name: &'static str) -> RpcModule<Instant> {
+	let mut module = RpcModule::new(Instant::now());
+	module
+		.register_method(name, |_, started| Ok(Health { ok: true, uptime: started.elapsed().as_secs() }))
+		.expect("The module is empty; qed");
+	module
+}
+
 /// Read all the results of all method calls in a batch request from the ['Stream']. Format the result into a single
 /// `String` appropriately wrapped in `[`/`]`.
 pub async fn collect_batch_response(rx: mpsc::UnboundedReceiver<String>) -> String {
@@ -218,7 +725,61 @@ pub async fn collect_batch_response(rx: mpsc::UnboundedReceiver<String>) -> Stri
 
 #[cfg(test)]
 mod tests {
-	use super::{BoundedWriter, Id, Response};
+	use super::{
+		has_duplicate_keys, has_unknown_fields, parse_request, BoundedWriter, Id, ParsedRequest, RequestParseError,
+		Response,
+	};
+
+	#[test]
+	fn parse_request_handles_well_formed_messages() {
+		let call = br#"{"jsonrpc":"2.0","method":"a","params":[1],"id":1}"#;
+		assert!(matches!(parse_request(call), Ok(ParsedRequest::Call(req)) if req.id == Id::Number(1)));
+		let notif = br#" {"jsonrpc":"2.0","method":"a"}"#;
+		assert!(matches!(parse_request(notif), Ok(ParsedRequest::Notification(_))));
+		let batch = br#"[{"jsonrpc":"2.0","method":"a","id":1},{"jsonrpc":"2.0","method":"b","id":2}]"#;
+		assert!(matches!(parse_request(batch), Ok(ParsedRequest::Batch(b)) if b.len() == 2));
+		let notifs = br#"[{"jsonrpc":"2.0","method":"a"}]"#;
+		assert!(matches!(parse_request(notifs), Ok(ParsedRequest::NotificationBatch(b)) if b.len() == 1));
+	}
+
+	#[test]
+	fn parse_request_distinguishes_null_id_from_missing_id() {
+		let call = br#"{"jsonrpc":"2.0","method":"a","id":null}"#;
+		assert!(matches!(parse_request(call), Ok(ParsedRequest::Call(req)) if req.id == Id::Null));
+		let notif = br#"{"jsonrpc":"2.0","method":"a"}"#;
+		assert!(matches!(parse_request(notif), Ok(ParsedRequest::Notification(_))));
+		let batch = br#"[{"jsonrpc":"2.0","method":"a","id":null}]"#;
+		assert!(matches!(parse_request(batch), Ok(ParsedRequest::Batch(b)) if b[0].id == Id::Null));
+	}
+
+	#[test]
+	fn parse_request_rejects_crafted_messages() {
+		let deeply_nested = "[".repeat(100_000);
+		assert_eq!(parse_request(deeply_nested.as_bytes()).unwrap_err(), RequestParseError::InvalidJson);
+		let deeply_nested_params =
+			format!(r#"{{"jsonrpc":"2.0","method":"a","params":{}{},"id":1}}"#, "[".repeat(10_000), "]".repeat(10_000));
+		// Left to `json_depth_exceeds`, the raw params are skipped over without recursing.
+		assert!(matches!(parse_request(deeply_nested_params.as_bytes()), Ok(ParsedRequest::Call(_))));
+
+		let huge_number = format!(r#"{{"jsonrpc":"2.0","method":"a","params":[{}],"id":1}}"#, "9".repeat(10_000));
+		assert!(matches!(parse_request(huge_number.as_bytes()), Ok(ParsedRequest::Call(_))));
+		let huge_id = format!(r#"{{"jsonrpc":"2.0","method":"a","id":{}}}"#, "9".repeat(100));
+		assert!(parse_request(huge_id.as_bytes()).is_err());
+
+		let trailing_garbage = br#"{"jsonrpc":"2.0","method":"a","id":1}garbage"#;
+		assert_eq!(parse_request(trailing_garbage).unwrap_err(), RequestParseError::InvalidJson);
+		let truncated = br#"{"jsonrpc":"2.0","method":"a","id":1"#;
+		assert_eq!(parse_request(truncated).unwrap_err(), RequestParseError::InvalidJson);
+		let wrong_version = br#"{"jsonrpc":"1.0","method":"a","id":7}"#;
+		assert_eq!(parse_request(wrong_version).unwrap_err(), RequestParseError::InvalidRequest(Id::Number(7)));
+		let mixed_batch = br#"[{"jsonrpc":"2.0","method":"a","id":1},{"jsonrpc":"2.0","method":"a"}]"#;
+		assert!(parse_request(mixed_batch).is_err());
+
+		for input in [&b""[..], b"   ", b"\xff\xfe", b"null", b"\"{\"", b"[]"] {
+			let err = parse_request(input).unwrap_err();
+			assert!(matches!(err, RequestParseError::InvalidJson | RequestParseError::EmptyBatch), "{:?}", input);
+		}
+	}
 
 	#[test]
 	fn bounded_serializer_work() {
@@ -235,4 +796,46 @@ mod tests {
 		// NOTE: `"` is part of the serialization so 101 characters.
 		assert!(serde_json::to_writer(&mut writer, &"x".repeat(99)).is_err());
 	}
+
+	#[test]
+	fn duplicate_keys_are_detected() {
+		assert!(has_duplicate_keys(br#"{"jsonrpc":"2.0","method":"a","method":"b","id":1}"#).unwrap());
+		assert!(has_duplicate_keys(br#"{"jsonrpc":"2.0","method":"a","params":{"x":1,"x":2},"id":1}"#).unwrap());
+		assert!(has_duplicate_keys(br#"[{"id":1},{"id":2,"id":3}]"#).unwrap());
+
+		assert!(!has_duplicate_keys(br#"{"jsonrpc":"2.0","method":"a","params":[{"x":1},{"x":2}],"id":1}"#).unwrap());
+		assert!(!has_duplicate_keys(br#"{"a":{"b":1},"b":{"a":1}}"#).unwrap());
+		assert!(has_duplicate_keys(b"not json").is_err());
+
+		// Too deep for serde_json to check, even though the duplicate key comes after the nested value.
+		let deep = format!(r#"{{"a":{}{},"a":1}}"#, "[".repeat(129), "]".repeat(129));
+		assert!(has_duplicate_keys(deep.as_bytes()).is_err());
+	}
+
+	#[test]
+	fn unknown_fields_are_detected() {
+		assert!(has_unknown_fields(br#"{"jsonrpc":"2.0","method":"a","id":1,"foo":1}"#));
+		assert!(has_unknown_fields(
+			br#" [{"jsonrpc":"2.0","method":"a","id":1},{"jsonrpc":"2.0","method":"b","x":1}]"#
+		));
+
+		assert!(!has_unknown_fields(br#"{"jsonrpc":"2.0","method":"a","params":{"foo":1},"id":1}"#));
+		assert!(!has_unknown_fields(br#"[{"jsonrpc":"2.0","method":"a"}]"#));
+		assert!(!has_unknown_fields(br#"[1, 2]"#));
+		assert!(!has_unknown_fields(b"not json"));
+	}
+
+	#[test]
+	fn parse_request_ignores_unknown_fields() {
+		let call = br#"{"jsonrpc":"2.0","method":"a","id":1,"foo":1}"#;
+		assert!(matches!(parse_request(call), Ok(ParsedRequest::Call(_))));
+		let notif = br#"{"jsonrpc":"2.0","method":"a","foo":1}"#;
+		assert!(matches!(parse_request(notif), Ok(ParsedRequest::Notification(_))));
+
+		// Calls with an invalid ID are not notifications.
+		let call = br#"{"jsonrpc":"2.0","method":"a","id":{}}"#;
+		assert!(parse_request(call).is_err());
+		let batch = br#"[{"jsonrpc":"2.0","method":"a"},{"jsonrpc":"2.0","method":"b","id":{}}]"#;
+		assert!(parse_request(batch).is_err());
+	}
 }
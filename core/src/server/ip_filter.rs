@@ -0,0 +1,179 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use crate::Error;
+
+/// A range of IP addresses in CIDR notation, such as `10.0.0.0/8` or `fe80::/10`.
+///
+/// A single address such as `127.0.0.1` is the range of that address only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+	addr: IpAddr,
+	prefix_len: u8,
+}
+
+impl IpRange {
+	/// Returns whether `ip` is in this range.
+	pub fn contains(&self, ip: IpAddr) -> bool {
+		match (self.addr, canonical(ip)) {
+			(IpAddr::V4(range), IpAddr::V4(ip)) => {
+				let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+				u32::from(range) & mask == u32::from(ip) & mask
+			}
+			(IpAddr::V6(range), IpAddr::V6(ip)) => {
+				let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+				u128::from(range) & mask == u128::from(ip) & mask
+			}
+			_ => false,
+		}
+	}
+}
+
+impl FromStr for IpRange {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || Error::InvalidIpRange(s.to_owned());
+		let (addr, prefix_len) = match s.split_once('/') {
+			Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+			None => (s, None),
+		};
+		let addr = canonical(addr.parse().map_err(|_| invalid())?);
+		let max_prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+		let prefix_len = match prefix_len {
+			Some(len) => len.parse().ok().filter(|len| *len <= max_prefix_len).ok_or_else(invalid)?,
+			None => max_prefix_len,
+		};
+		Ok(Self { addr, prefix_len })
+	}
+}
+
+/// Regard IPv4-mapped IPv6 addresses, as reported by dual-stack sockets, as the IPv4 address.
+fn canonical(ip: IpAddr) -> IpAddr {
+	match ip {
+		IpAddr::V6(v6) => match v6.octets() {
+			[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => IpAddr::from([a, b, c, d]),
+			_ => ip,
+		},
+		IpAddr::V4(_) => ip,
+	}
+}
+
+/// Policy by which to accept or drop incoming connections based on the IP address of the peer.
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter {
+	/// Ranges to accept connections from, all if `None`.
+	allowed: Option<Vec<IpRange>>,
+	/// Ranges to drop connections from, even if they are allowed.
+	denied: Vec<IpRange>,
+}
+
+impl IpFilter {
+	/// Only accept connections from the IP addresses in `ranges`.
+	pub fn set_allowed<List, Range>(&mut self, ranges: List) -> Result<(), Error>
+	where
+		List: IntoIterator<Item = Range>,
+		Range: AsRef<str>,
+	{
+		self.allowed = Some(parse_ranges(ranges)?);
+		Ok(())
+	}
+
+	/// Drop connections from the IP addresses in `ranges`.
+	pub fn set_denied<List, Range>(&mut self, ranges: List) -> Result<(), Error>
+	where
+		List: IntoIterator<Item = Range>,
+		Range: AsRef<str>,
+	{
+		self.denied = parse_ranges(ranges)?;
+		Ok(())
+	}
+
+	/// Returns whether a connection from `ip` is accepted.
+	pub fn is_allowed(&self, ip: IpAddr) -> bool {
+		let allowed = match &self.allowed {
+			Some(allowed) => allowed.iter().any(|range| range.contains(ip)),
+			None => true,
+		};
+		allowed && !self.denied.iter().any(|range| range.contains(ip))
+	}
+}
+
+fn parse_ranges<List, Range>(ranges: List) -> Result<Vec<IpRange>, Error>
+where
+	List: IntoIterator<Item = Range>,
+	Range: AsRef<str>,
+{
+	ranges.into_iter().map(|range| range.as_ref().parse()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{IpFilter, IpRange};
+
+	#[test]
+	fn ip_range_contains() {
+		let range: IpRange = "10.1.0.0/16".parse().unwrap();
+		assert!(range.contains("10.1.2.3".parse().unwrap()));
+		assert!(!range.contains("10.2.0.1".parse().unwrap()));
+		assert!(!range.contains("::1".parse().unwrap()));
+
+		let range: IpRange = "fe80::/10".parse().unwrap();
+		assert!(range.contains("fe80::1".parse().unwrap()));
+		assert!(!range.contains("::1".parse().unwrap()));
+
+		let single: IpRange = "127.0.0.1".parse().unwrap();
+		assert!(single.contains("127.0.0.1".parse().unwrap()));
+		assert!(single.contains("::ffff:127.0.0.1".parse().unwrap()));
+		assert!(!single.contains("127.0.0.2".parse().unwrap()));
+
+		let all: IpRange = "0.0.0.0/0".parse().unwrap();
+		assert!(all.contains("192.168.1.1".parse().unwrap()));
+	}
+
+	#[test]
+	fn invalid_ip_ranges_are_rejected() {
+		assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+		assert!("::/129".parse::<IpRange>().is_err());
+		assert!("10.0.0/8".parse::<IpRange>().is_err());
+		assert!("localhost".parse::<IpRange>().is_err());
+	}
+
+	#[test]
+	fn denied_ranges_take_precedence() {
+		let mut filter = IpFilter::default();
+		assert!(filter.is_allowed("192.168.1.1".parse().unwrap()));
+
+		filter.set_allowed(["192.168.0.0/16"]).unwrap();
+		filter.set_denied(["192.168.1.0/24"]).unwrap();
+		assert!(filter.is_allowed("192.168.2.1".parse().unwrap()));
+		assert!(!filter.is_allowed("192.168.1.1".parse().unwrap()));
+		assert!(!filter.is_allowed("10.0.0.1".parse().unwrap()));
+	}
+}
@@ -26,8 +26,14 @@
 
 //! Shared modules for the JSON-RPC servers.
 
+/// Per-connection state shared between the servers and the method handlers.
+pub mod connection_state;
+/// Spawn the tasks of the servers on a custom executor.
+pub mod executor;
 /// Helpers.
 pub mod helpers;
+/// Accept or drop connections based on the IP address of the peer.
+pub mod ip_filter;
 /// Resource limiting. Create generic "resources" and configure their limits to ensure servers are not overloaded.
 pub mod resource_limiting;
 /// JSON-RPC "modules" group sets of methods that belong together and handles method/subscription registration.
@@ -24,26 +24,39 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
 use std::fmt::{self, Debug};
 use std::future::Future;
+use std::hash::Hash;
+use std::net::SocketAddr;
 use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 use crate::error::{Error, SubscriptionClosed, SubscriptionClosedReason};
-use crate::id_providers::RandomIntegerIdProvider;
-use crate::server::helpers::MethodSink;
+use crate::id_providers::FullRangeIntegerIdProvider;
+use crate::server::executor::{Executor, TokioExecutor};
+use crate::server::helpers::{parse_request, ActiveSubscription, MethodSink, ParsedRequest};
 use crate::server::resource_limiting::{ResourceGuard, ResourceTable, ResourceVec, Resources};
 use crate::traits::{IdProvider, ToRpcParams};
+use crate::JsonRawValue;
 use futures_channel::{mpsc, oneshot};
 use futures_util::future::Either;
 use futures_util::pin_mut;
-use futures_util::{future::BoxFuture, FutureExt, Stream, StreamExt};
-use jsonrpsee_types::error::{ErrorCode, CALL_EXECUTION_FAILED_CODE};
+use futures_util::stream::BoxStream;
+use futures_util::{
+	future::{self, BoxFuture},
+	FutureExt, Sink, Stream, StreamExt, TryFutureExt, TryStreamExt,
+};
+use jsonrpsee_types::error::{CallError, ErrorCode, CALL_EXECUTION_FAILED_CODE};
 use jsonrpsee_types::{
-	Id, Params, Request, Response, SubscriptionId as RpcSubscriptionId, SubscriptionPayload, SubscriptionResponse,
+	ErrorResponse, Id, Params, ProgressNotification, ProgressPayload, Request, Response, SubscriptionChunk,
+	SubscriptionId as RpcSubscriptionId, SubscriptionPayload, SubscriptionResponse,
 };
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use rustc_hash::FxHashMap;
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::sync::Notify;
@@ -51,19 +64,105 @@ use tokio::sync::Notify;
 /// A `MethodCallback` is an RPC endpoint, callable with a standard JSON-RPC request,
 /// implemented as a function pointer to a `Fn` function taking four arguments:
 /// the `id`, `params`, a channel the function uses to communicate the result (or error)
-/// back to `jsonrpsee`, and the connection ID (useful for the websocket transport).
-pub type SyncMethod = Arc<dyn Send + Sync + Fn(Id, Params, &MethodSink) -> bool>;
+/// back to `jsonrpsee`, and information about the connection the call was made on.
+pub type SyncMethod = Arc<dyn Send + Sync + Fn(Id, Params, &MethodSink, &ConnectionInfo) -> bool>;
 /// Similar to [`SyncMethod`], but represents an asynchronous handler and takes an additional argument containing a [`ResourceGuard`] if configured.
 pub type AsyncMethod<'a> = Arc<
-	dyn Send + Sync + Fn(Id<'a>, Params<'a>, MethodSink, ConnectionId, Option<ResourceGuard>) -> BoxFuture<'a, bool>,
+	dyn Send + Sync + Fn(Id<'a>, Params<'a>, MethodSink, ConnectionInfo, Option<ResourceGuard>) -> BoxFuture<'a, bool>,
 >;
 /// Method callback for subscriptions.
 pub type SubscriptionMethod = Arc<dyn Send + Sync + Fn(Id, Params, &MethodSink, ConnState) -> bool>;
+/// Callback of a method registered with [`RpcModule::register_streaming_method`], producing the serialized result in
+/// fragments which the HTTP server writes to the response as they come.
+pub type StreamingMethod = Arc<
+	dyn Send
+		+ Sync
+		+ Fn(Params<'static>) -> BoxFuture<'static, Result<BoxStream<'static, Result<String, Error>>, Error>>,
+>;
+
+/// Callback for calls to methods that aren't registered, taking the `id`, the name of the method, the raw `params`
+/// if any, a channel to send the response on and the ID of the connection the call was made on.
+pub type FallbackMethod = Arc<
+	dyn Send
+		+ Sync
+		+ Fn(Id<'static>, String, Option<Box<JsonRawValue>>, MethodSink, ConnectionId) -> BoxFuture<'static, bool>,
+>;
+
+/// [`FallbackMethod`] of a [`Methods`], if any.
+#[derive(Clone, Default)]
+struct Fallback(Option<FallbackMethod>);
+
+impl Debug for Fallback {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("Fallback").field(&self.0.is_some()).finish()
+	}
+}
+
+/// [`StreamingMethod`] of a [`MethodCallback`], if any.
+#[derive(Clone, Default)]
+struct Streaming(Option<StreamingMethod>);
+
+impl Debug for Streaming {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("Streaming").field(&self.0.is_some()).finish()
+	}
+}
 
 /// Connection ID, used for stateful protocol such as WebSockets.
 /// For stateless protocols such as http it's unused, so feel free to set it some hardcoded value.
 pub type ConnectionId = usize;
 
+/// Information about the connection that a call was made on.
+///
+/// The servers don't terminate TLS themselves, so the peer identity is limited to the remote address
+/// as seen by the server (i.e, the address of the proxy if the server runs behind one).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+	/// Connection ID, always `0` for stateless protocols such as HTTP.
+	pub id: ConnectionId,
+	/// Remote address of the peer, `None` if the call wasn't made over a network connection.
+	pub remote_addr: Option<SocketAddr>,
+	/// WebSocket subprotocol selected during the handshake, if any.
+	pub protocol: Option<Arc<str>>,
+}
+
+impl ConnectionInfo {
+	/// Create a new [`ConnectionInfo`].
+	pub fn new(id: ConnectionId, remote_addr: Option<SocketAddr>) -> Self {
+		Self { id, remote_addr, protocol: None }
+	}
+
+	/// Set the WebSocket subprotocol of the connection.
+	pub fn with_protocol(mut self, protocol: Option<Arc<str>>) -> Self {
+		self.protocol = protocol;
+		self
+	}
+}
+
+/// Check that there are `count` positional params, unless they are passed by name.
+fn check_params_count(params: &Params, count: usize) -> Result<(), Error> {
+	let got = params.len();
+	if got == count || params.is_object() {
+		Ok(())
+	} else {
+		Err(CallError::InvalidParams(anyhow::anyhow!("Expected {} params, got {}", count, got)).into())
+	}
+}
+
+/// Log a failed call along with its method name and request ID, to correlate it with what the client saw.
+///
+/// Invalid params are caused by the client and custom errors are answers chosen by the method, so both are only
+/// logged at debug level. Other errors, such as [`CallError::Failed`], mean the method itself failed.
+fn log_call_error(method_name: &str, id: &Id, err: &Error) {
+	let id = serde_json::to_string(id).unwrap_or_default();
+	match err {
+		Error::Call(CallError::InvalidParams(_) | CallError::Custom { .. }) => {
+			tracing::debug!("Call failed with [method={} id={}]: {}", method_name, id, err)
+		}
+		_ => tracing::warn!("Call failed with [method={} id={}]: {}", method_name, id, err),
+	}
+}
+
 /// Raw response from an RPC
 /// A 3-tuple containing:
 ///   - Call result as a `String`,
@@ -87,7 +186,132 @@ impl<'a> std::fmt::Debug for ConnState<'a> {
 	}
 }
 
-type Subscribers = Arc<Mutex<FxHashMap<SubscriptionKey, (MethodSink, oneshot::Receiver<()>)>>>;
+type Subscribers = Arc<Mutex<FxHashMap<SubscriptionKey, Subscriber>>>;
+
+/// [`Subscribers`] of the methods replaced by a subscribe method, see [`Methods::carry_over_subscriptions`].
+type RetiredSubscribers = Arc<Mutex<Vec<Subscribers>>>;
+
+/// [`Subscribers`] of each subscribe method, by subscribe and notification method name, along with the
+/// [`RetiredSubscribers`] its unsubscribe method also closes the subscriptions of.
+type ModuleSubscribers = Arc<RwLock<Vec<(&'static str, &'static str, Subscribers, RetiredSubscribers)>>>;
+
+/// Subscriptions of a subscribe method by connection and raw params, to find the duplicates of a subscribe call when
+/// subscriptions are deduplicated. Only locked while holding the lock of the [`Subscribers`] of the method, or on its
+/// own.
+type DeduplicationIndex = Arc<Mutex<FxHashMap<(ConnectionId, Option<Box<str>>), RpcSubscriptionId<'static>>>>;
+
+/// Entry of a subscription in the [`DeduplicationIndex`], removed along with the subscription.
+#[derive(Debug)]
+struct Deduplicated {
+	index: DeduplicationIndex,
+	key: (ConnectionId, Option<Box<str>>),
+}
+
+impl Drop for Deduplicated {
+	fn drop(&mut self) {
+		self.index.lock().remove(&self.key);
+	}
+}
+
+/// When a subscription was opened and last sent a notification, for the [`SubscriptionStats`].
+#[derive(Debug)]
+struct SubscriptionTimes {
+	created_at: Instant,
+	/// Microseconds from `created_at` to the last notification plus one, or `0` if nothing was sent yet. Updated on
+	/// every send, so it's an atomic rather than a lock.
+	last_sent: AtomicU64,
+}
+
+impl SubscriptionTimes {
+	fn new() -> Self {
+		Self { created_at: Instant::now(), last_sent: AtomicU64::new(0) }
+	}
+
+	fn sent(&self) {
+		let micros = u64::try_from(self.created_at.elapsed().as_micros()).unwrap_or(u64::MAX);
+		self.last_sent.store(micros.saturating_add(1), Ordering::Relaxed);
+	}
+
+	fn last_sent(&self) -> Option<Instant> {
+		match self.last_sent.load(Ordering::Relaxed) {
+			0 => None,
+			micros => Some(self.created_at + Duration::from_micros(micros - 1)),
+		}
+	}
+}
+
+/// Entry of an active subscription in the [`Subscribers`] of its subscribe method.
+#[derive(Debug)]
+struct Subscriber {
+	sink: MethodSink,
+	/// Dropped when the subscription is closed, which the [`SubscriptionSink`] notices.
+	_conn_rx: oneshot::Receiver<()>,
+	/// When the subscription was opened and last sent a notification, updated by the [`SubscriptionSink`].
+	times: Arc<SubscriptionTimes>,
+	/// Entry in the index of subscriptions to reuse, if subscriptions were deduplicated when it was opened.
+	_deduplicated: Option<Deduplicated>,
+	/// Counts the subscription as active on its connection until it is removed.
+	_active: Option<ActiveSubscription>,
+}
+
+/// Diagnostics about an active subscription, see [`RpcModule::register_subscription_stats`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionStats {
+	/// Name of the subscribe method.
+	pub method: String,
+	/// ID of the subscription.
+	pub subscription: RpcSubscriptionId<'static>,
+	/// Connection the subscription belongs to.
+	pub conn_id: ConnectionId,
+	/// Milliseconds since the subscription was opened.
+	pub age_ms: u64,
+	/// Milliseconds since the last notification was sent, `None` if nothing was sent yet.
+	pub idle_ms: Option<u64>,
+}
+
+/// A subscription was opened or closed, see [`RpcModule::on_subscription_event`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionEvent<'a> {
+	/// A subscription was opened.
+	Subscribed {
+		/// Name of the subscribe method.
+		method: &'static str,
+		/// Connection the subscription belongs to.
+		conn_id: ConnectionId,
+		/// ID of the subscription.
+		sub_id: &'a RpcSubscriptionId<'static>,
+	},
+	/// A subscription was closed, by the subscriber unsubscribing or by the server.
+	Unsubscribed {
+		/// Name of the subscribe method.
+		method: &'static str,
+		/// Connection the subscription belonged to.
+		conn_id: ConnectionId,
+		/// ID of the subscription.
+		sub_id: &'a RpcSubscriptionId<'static>,
+	},
+}
+
+type SubscriptionEventCallback = Box<dyn Fn(SubscriptionEvent) + Send + Sync>;
+
+/// Callback for [`SubscriptionEvent`]s, shared by a module and all the subscriptions registered on it.
+#[derive(Clone, Default)]
+struct SubscriptionEventHook(Arc<RwLock<Option<SubscriptionEventCallback>>>);
+
+impl SubscriptionEventHook {
+	fn notify(&self, event: SubscriptionEvent) {
+		if let Some(callback) = &*self.0.read() {
+			callback(event);
+		}
+	}
+}
+
+impl Debug for SubscriptionEventHook {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("SubscriptionEventHook").field(&self.0.read().is_some()).finish()
+	}
+}
 
 /// Represent a unique subscription entry based on [`RpcSubscriptionId`] and [`ConnectionId`].
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -122,6 +346,8 @@ enum MethodResources {
 pub struct MethodCallback {
 	callback: MethodKind,
 	resources: MethodResources,
+	description: Option<&'static str>,
+	streaming: Streaming,
 }
 
 /// Result of a method, either direct value or a future of one.
@@ -164,17 +390,23 @@ impl<'a> Drop for MethodResourcesBuilder<'a> {
 
 impl MethodCallback {
 	fn new_sync(callback: SyncMethod) -> Self {
-		MethodCallback { callback: MethodKind::Sync(callback), resources: MethodResources::Uninitialized([].into()) }
+		Self::new(MethodKind::Sync(callback))
 	}
 
 	fn new_async(callback: AsyncMethod<'static>) -> Self {
-		MethodCallback { callback: MethodKind::Async(callback), resources: MethodResources::Uninitialized([].into()) }
+		Self::new(MethodKind::Async(callback))
 	}
 
 	fn new_subscription(callback: SubscriptionMethod) -> Self {
+		Self::new(MethodKind::Subscription(callback))
+	}
+
+	fn new(callback: MethodKind) -> Self {
 		MethodCallback {
-			callback: MethodKind::Subscription(callback),
+			callback,
 			resources: MethodResources::Uninitialized([].into()),
+			description: None,
+			streaming: Streaming::default(),
 		}
 	}
 
@@ -191,6 +423,19 @@ impl MethodCallback {
 	pub fn inner(&self) -> &MethodKind {
 		&self.callback
 	}
+
+	/// Returns the description of the method, if it has one.
+	pub fn description(&self) -> Option<&'static str> {
+		self.description
+	}
+
+	/// Returns the callback producing the result in fragments, if the method was registered with
+	/// [`RpcModule::register_streaming_method`].
+	///
+	/// [`MethodCallback::inner`] answers such calls with the fragments joined into a single response.
+	pub fn streaming(&self) -> Option<&StreamingMethod> {
+		self.streaming.0.as_ref()
+	}
 }
 
 impl Debug for MethodKind {
@@ -207,6 +452,15 @@ impl Debug for MethodKind {
 #[derive(Default, Debug, Clone)]
 pub struct Methods {
 	callbacks: Arc<FxHashMap<&'static str, MethodCallback>>,
+	/// Registered names of the methods by their lowercase name, if names that don't match exactly are looked up
+	/// ignoring ASCII case.
+	lowercase_names: Option<Arc<FxHashMap<String, &'static str>>>,
+	/// Subscription ID provider used when the methods are called directly rather than through a server.
+	id_provider: Option<Arc<dyn IdProvider>>,
+	/// Called instead of answering with a method not found error.
+	fallback: Fallback,
+	/// Subscribers of each subscribe method, shared between clones and extended with those of the merged methods.
+	subscribers: ModuleSubscribers,
 }
 
 impl Methods {
@@ -215,10 +469,74 @@ impl Methods {
 		Self::default()
 	}
 
+	/// Set the subscription ID provider used by [`Methods::call`], [`Methods::subscribe`] and the other helpers
+	/// that invoke methods without a server (default is [`FullRangeIntegerIdProvider`]).
+	///
+	/// Use a [`SequentialIdProvider`](crate::id_providers::SequentialIdProvider) to get predictable subscription
+	/// IDs in tests.
+	///
+	/// Servers use their own ID provider, which is configured on the server builder.
+	pub fn set_id_provider(&mut self, id_provider: impl IdProvider + 'static) {
+		self.id_provider = Some(Arc::new(id_provider));
+	}
+
+	/// Call `fallback` rather than answering calls to methods that aren't registered with a method not found error.
+	///
+	/// The future returned by the fallback must send the answer on the sink before resolving, it may for instance
+	/// forward the call to another server first.
+	///
+	/// The fallback is kept when merging other methods into these ones, or taken from the other methods if these
+	/// don't have one.
+	pub fn set_fallback<R, F>(&mut self, fallback: F)
+	where
+		R: Future<Output = bool> + Send + 'static,
+		F: Fn(Id<'static>, String, Option<Box<JsonRawValue>>, MethodSink, ConnectionId) -> R + Send + Sync + 'static,
+	{
+		self.fallback = Fallback(Some(Arc::new(move |id, method_name, params, sink, conn_id| {
+			fallback(id, method_name, params, sink, conn_id).boxed()
+		})));
+	}
+
+	/// Answer a call to `method_name`, which isn't registered, with the fallback or with a method not found error
+	/// without one.
+	///
+	/// A call with an empty method name is malformed rather than unknown and is answered with an invalid request
+	/// error, without calling the fallback.
+	///
+	/// Errors are sent right away, the returned future only has to be awaited to run the fallback.
+	pub fn call_fallback(
+		&self,
+		id: Id,
+		method_name: &str,
+		params: Option<&JsonRawValue>,
+		sink: &MethodSink,
+		conn_id: ConnectionId,
+	) -> BoxFuture<'static, bool> {
+		if method_name.is_empty() {
+			return future::ready(sink.send_error(id, ErrorCode::InvalidRequest.into())).boxed();
+		}
+		match &self.fallback.0 {
+			Some(fallback) => {
+				fallback(id.into_owned(), method_name.to_owned(), params.map(ToOwned::to_owned), sink.clone(), conn_id)
+			}
+			None => future::ready(sink.send_error(id, ErrorCode::MethodNotFound.into())).boxed(),
+		}
+	}
+
+	fn local_id_provider(&self) -> &dyn IdProvider {
+		match &self.id_provider {
+			Some(provider) => &**provider,
+			None => &FullRangeIntegerIdProvider,
+		}
+	}
+
 	fn verify_method_name(&mut self, name: &'static str) -> Result<(), Error> {
 		if self.callbacks.contains_key(name) {
 			return Err(Error::MethodAlreadyRegistered(name.into()));
 		}
+		if self.lowercase_names.as_ref().is_some_and(|names| names.contains_key(&name.to_ascii_lowercase())) {
+			return Err(Error::MethodAlreadyRegistered(name.into()));
+		}
 
 		Ok(())
 	}
@@ -230,10 +548,17 @@ impl Methods {
 		name: &'static str,
 		callback: MethodCallback,
 	) -> Result<&mut MethodCallback, Error> {
-		match self.mut_callbacks().entry(name) {
-			Entry::Occupied(_) => Err(Error::MethodAlreadyRegistered(name.into())),
-			Entry::Vacant(vacant) => Ok(vacant.insert(callback)),
+		self.verify_method_name(name)?;
+		self.insert(name, callback);
+		Ok(self.mut_callbacks().get_mut(name).expect("inserted above; qed"))
+	}
+
+	/// Inserts the method callback for a given name, which must have been verified.
+	fn insert(&mut self, name: &'static str, callback: MethodCallback) {
+		if let Some(names) = &mut self.lowercase_names {
+			Arc::make_mut(names).insert(name.to_ascii_lowercase(), name);
 		}
+		self.mut_callbacks().insert(name, callback);
 	}
 
 	/// Initialize resources for all methods in this collection. This method has no effect if called more than once.
@@ -266,6 +591,25 @@ impl Methods {
 		Ok(self)
 	}
 
+	/// Look up methods ignoring ASCII case when there is no exact match, so that a call to `FOO` is answered by the
+	/// method `foo`. This also applies to methods registered afterwards, which fail to register if they only differ
+	/// in case from an existing method.
+	///
+	/// Fails with [`Error::MethodAlreadyRegistered`] if two methods only differ in case.
+	pub fn into_case_insensitive(mut self) -> Result<Self, Error> {
+		let mut names = FxHashMap::default();
+		for &name in self.callbacks.keys() {
+			let lowercase = name.to_ascii_lowercase();
+			if names.contains_key(&lowercase) {
+				return Err(Error::MethodAlreadyRegistered(lowercase));
+			}
+			names.insert(lowercase, name);
+		}
+
+		self.lowercase_names = Some(Arc::new(names));
+		Ok(self)
+	}
+
 	/// Helper for obtaining a mut ref to the callbacks HashMap.
 	fn mut_callbacks(&mut self) -> &mut FxHashMap<&'static str, MethodCallback> {
 		Arc::make_mut(&mut self.callbacks)
@@ -273,6 +617,9 @@ impl Methods {
 
 	/// Merge two [`Methods`]'s by adding all [`MethodCallback`]s from `other` into `self`.
 	/// Fails if any of the methods in `other` is present already.
+	///
+	/// The active subscriptions of `other` are tracked along with those of `self`, so that the
+	/// [`SubscriptionCloser`] and the [`SubscriptionStats`] of the module the methods are merged into cover them.
 	pub fn merge(&mut self, other: impl Into<Methods>) -> Result<(), Error> {
 		let mut other = other.into();
 
@@ -280,24 +627,91 @@ impl Methods {
 			self.verify_method_name(name)?;
 		}
 
-		let callbacks = self.mut_callbacks();
-
 		for (name, callback) in other.mut_callbacks().drain() {
-			callbacks.insert(name, callback);
+			self.insert(name, callback);
+		}
+
+		if !Arc::ptr_eq(&self.subscribers, &other.subscribers) {
+			let merged = other.subscribers.read().clone();
+			self.subscribers.write().extend(merged);
+		}
+
+		if self.fallback.0.is_none() {
+			self.fallback = other.fallback;
+		}
+
+		Ok(())
+	}
+
+	/// Let the unsubscribe methods close the subscriptions opened with the subscribe methods of the same name of
+	/// `previous`, which these methods replace, such as when swapping the methods of a running server.
+	///
+	/// The notifications of those subscriptions are still sent by the callbacks of `previous`.
+	pub fn carry_over_subscriptions(&self, previous: &Methods) {
+		if Arc::ptr_eq(&self.subscribers, &previous.subscribers) {
+			return;
+		}
+		let previous = previous.subscribers.read().clone();
+		for (method, _, subscribers, retired) in self.subscribers.read().iter() {
+			let mut carried = Vec::new();
+			for (previous_method, _, previous_subscribers, previous_retired) in &previous {
+				if previous_method != method || Arc::ptr_eq(previous_subscribers, subscribers) {
+					continue;
+				}
+				carried.push(previous_subscribers.clone());
+				carried.extend(previous_retired.lock().iter().cloned());
+			}
+
+			let mut retired = retired.lock();
+			for subscribers in carried {
+				if !retired.iter().any(|known| Arc::ptr_eq(known, &subscribers)) {
+					retired.push(subscribers);
+				}
+			}
+			// Forget the registries of the subscriptions that were all closed meanwhile.
+			retired.retain(|subscribers| !subscribers.lock().is_empty());
+		}
+	}
+
+	/// Merge two [`Methods`]'s like [`merge`](Methods::merge), but on conflict return the names of all the
+	/// methods in `other` that are present already rather than only the first one. Nothing is merged if
+	/// there is any conflict.
+	pub fn try_merge(&mut self, other: impl Into<Methods>) -> Result<(), Vec<String>> {
+		let other = other.into();
+
+		let mut conflicts: Vec<String> = other
+			.callbacks
+			.keys()
+			.filter(|name| self.verify_method_name(name).is_err())
+			.map(|name| name.to_string())
+			.collect();
+
+		if !conflicts.is_empty() {
+			conflicts.sort_unstable();
+			return Err(conflicts);
 		}
 
+		self.merge(other).expect("all method names verified above; qed");
 		Ok(())
 	}
 
 	/// Returns the method callback.
 	pub fn method(&self, method_name: &str) -> Option<&MethodCallback> {
-		self.callbacks.get(method_name)
+		self.method_with_name(method_name).map(|(_, callback)| callback)
 	}
 
 	/// Returns the method callback along with its name. The returned name is same as the
-	/// `method_name`, but its lifetime bound is `'static`.
+	/// `method_name`, but its lifetime bound is `'static`. If method names are
+	/// [case-insensitive](Methods::into_case_insensitive), it's the name the method was registered with.
 	pub fn method_with_name(&self, method_name: &str) -> Option<(&'static str, &MethodCallback)> {
-		self.callbacks.get_key_value(method_name).map(|(k, v)| (*k, v))
+		let (name, callback) = match self.callbacks.get_key_value(method_name) {
+			Some(found) => found,
+			None => {
+				let name = self.lowercase_names.as_ref()?.get(&method_name.to_ascii_lowercase())?;
+				self.callbacks.get_key_value(name)?
+			}
+		};
+		Some((*name, callback))
 	}
 
 	/// Helper to call a method on the `RPC module` without having to spin up a server.
@@ -330,7 +744,7 @@ impl Methods {
 		let params = params.to_rpc_params()?;
 		let req = Request::new(method.into(), Some(&params), Id::Number(0));
 		tracing::trace!("[Methods::call] Calling method: {:?}, params: {:?}", method, params);
-		let (resp, _, _) = self.inner_call(req).await;
+		let (resp, _, _) = self.inner_call(req, false).await;
 		if let Ok(res) = serde_json::from_str::<Response<T>>(&resp) {
 			return Ok(res.result);
 		}
@@ -367,34 +781,115 @@ impl Methods {
 	pub async fn raw_json_request(&self, call: &str) -> Result<(String, mpsc::UnboundedReceiver<String>), Error> {
 		tracing::trace!("[Methods::raw_json_request] {:?}", call);
 		let req: Request = serde_json::from_str(call)?;
-		let (resp, rx, _) = self.inner_call(req).await;
+		let (resp, rx, _) = self.inner_call(req, true).await;
 		Ok((resp, rx))
 	}
 
+	/// Dispatch a raw JSON-RPC message, a call or a batch of calls, to the methods without having to spin up a server.
+	///
+	/// Returns the response a server would send, or `None` for notifications which are not answered. Unlike
+	/// [`Methods::raw_json_request`], invalid messages are answered with an error response, and the notifications
+	/// of subscriptions are dropped.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::RpcModule;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	///
+	///     let resp = module.dispatch(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).await;
+	///     assert_eq!(resp.as_deref(), Some(r#"{"jsonrpc":"2.0","result":"lo","id":1}"#));
+	/// }
+	/// ```
+	pub async fn dispatch(&self, request: &str) -> Option<String> {
+		tracing::trace!("[Methods::dispatch] {:?}", request);
+		match parse_request(request.as_bytes()) {
+			Ok(ParsedRequest::Call(req)) => Some(self.inner_call(req, false).await.0),
+			Ok(ParsedRequest::Batch(batch)) => {
+				let mut responses = Vec::with_capacity(batch.len());
+				for req in batch {
+					responses.push(self.inner_call(req, false).await.0);
+				}
+				Some(format!("[{}]", responses.join(",")))
+			}
+			Ok(ParsedRequest::Notification(_)) | Ok(ParsedRequest::NotificationBatch(_)) => None,
+			Err(err) => {
+				let resp = ErrorResponse::new(err.code().into(), err.id());
+				Some(serde_json::to_string(&resp).expect("Error responses are valid JSON; qed"))
+			}
+		}
+	}
+
 	/// Execute a callback.
-	async fn inner_call(&self, req: Request<'_>) -> RawRpcResponse {
+	///
+	/// Without `notifications`, the response is always the first message even for calls that report their progress.
+	async fn inner_call(&self, req: Request<'_>, notifications: bool) -> RawRpcResponse {
 		let (tx_sink, mut rx_sink) = mpsc::unbounded();
-		let sink = MethodSink::new(tx_sink);
-		let id = req.id.clone();
-		let params = Params::new(req.params.map(|params| params.get()));
+		let sink =
+			if notifications { MethodSink::new(tx_sink) } else { MethodSink::new(tx_sink).without_notifications() };
 		let notify = Arc::new(Notify::new());
 
-		let _result = match self.method(&req.method).map(|c| &c.callback) {
-			None => sink.send_error(req.id, ErrorCode::MethodNotFound.into()),
-			Some(MethodKind::Sync(cb)) => (cb)(id, params, &sink),
-			Some(MethodKind::Async(cb)) => (cb)(id.into_owned(), params.into_owned(), sink, 0, None).await,
-			Some(MethodKind::Subscription(cb)) => {
-				let close_notify = notify.clone();
-				let conn_state = ConnState { conn_id: 0, close_notify, id_provider: &RandomIntegerIdProvider };
-				(cb)(id, params, &sink, conn_state)
-			}
-		};
+		let _result = self.execute(req, &sink, ConnectionInfo::new(0, None), notify.clone()).await;
 
 		let resp = rx_sink.next().await.expect("tx and rx still alive; qed");
 
 		(resp, rx_sink, notify)
 	}
 
+	/// Run the method of a request, looked up like [`Methods::method`], sending the response to `sink`.
+	///
+	/// This is what the servers do for each call, minus the transport and resource limiting, such that the methods
+	/// can be embedded into another runtime. Calls to unknown methods go to the fallback, if any. Subscriptions
+	/// send their notifications to `sink` as well, and are closed once `close_notify` is notified.
+	///
+	/// Returns `true` if the call was answered with a success response, use [`MethodSink::is_closed`] to find out
+	/// whether the answer could be sent.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use std::sync::Arc;
+	///     use futures_util::StreamExt;
+	///     use jsonrpsee::{RpcModule, core::server::helpers::MethodSink, core::server::rpc_module::ConnectionInfo};
+	///     use jsonrpsee::types::Request;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	///
+	///     let (tx, mut rx) = futures_channel::mpsc::unbounded();
+	///     let req: Request = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).unwrap();
+	///     let conn = ConnectionInfo::new(0, None);
+	///     assert!(module.execute(req, &MethodSink::new(tx), conn, Arc::new(tokio::sync::Notify::new())).await);
+	///     assert_eq!(rx.next().await.unwrap(), r#"{"jsonrpc":"2.0","result":"lo","id":1}"#);
+	/// }
+	/// ```
+	pub async fn execute(
+		&self,
+		req: Request<'_>,
+		sink: &MethodSink,
+		conn: ConnectionInfo,
+		close_notify: Arc<Notify>,
+	) -> bool {
+		let id = req.id.clone();
+		let params = Params::new(req.params.map(|params| params.get())).with_version(req.jsonrpc);
+
+		match self.method(&req.method).map(|c| &c.callback) {
+			None => self.call_fallback(req.id, &req.method, req.params, sink, conn.id).await,
+			Some(MethodKind::Sync(cb)) => (cb)(id, params, sink, &conn),
+			Some(MethodKind::Async(cb)) => (cb)(id.into_owned(), params.into_owned(), sink.clone(), conn, None).await,
+			Some(MethodKind::Subscription(cb)) => {
+				let conn_state = ConnState { conn_id: conn.id, close_notify, id_provider: self.local_id_provider() };
+				(cb)(id, params, sink, conn_state)
+			}
+		}
+	}
+
 	/// Helper to create a subscription on the `RPC module` without having to spin up a server.
 	///
 	/// The params must be serializable as JSON array, see [`ToRpcParams`] for further documentation.
@@ -424,7 +919,7 @@ impl Methods {
 		let params = params.to_rpc_params()?;
 		let req = Request::new(sub_method.into(), Some(&params), Id::Number(0));
 		tracing::trace!("[Methods::subscribe] Calling subscription method: {:?}, params: {:?}", sub_method, params);
-		let (response, rx, close_notify) = self.inner_call(req).await;
+		let (response, rx, close_notify) = self.inner_call(req, true).await;
 		let subscription_response = serde_json::from_str::<Response<RpcSubscriptionId>>(&response)?;
 		let sub_id = subscription_response.result.into_owned();
 		let close_notify = Some(close_notify);
@@ -435,8 +930,190 @@ impl Methods {
 	pub fn method_names(&self) -> impl Iterator<Item = &'static str> + '_ {
 		self.callbacks.keys().copied()
 	}
+
+	/// Describe what a registered method does, for the [OpenRPC document](Methods::to_openrpc).
+	///
+	/// The methods generated by the `rpc` proc macro are described by their doc comments.
+	pub fn set_method_description(&mut self, method_name: &str, description: &'static str) -> Result<(), Error> {
+		match self.mut_callbacks().get_mut(method_name) {
+			Some(callback) => {
+				callback.description = Some(description);
+				Ok(())
+			}
+			None => Err(Error::MethodNotFound(method_name.into())),
+		}
+	}
+
+	/// Cache the successful responses of a method for `ttl`, keyed by the params of the call, and answer calls with
+	/// identical params from the cache without running the method. Calls of an asynchronous method with the same
+	/// params arriving while it runs wait for its result rather than running it again.
+	///
+	/// Only use it for methods whose result depends on nothing but their params for the duration of `ttl`, errors
+	/// are never cached. At most 1024 results are kept, see [`ResponseCache::set_max_entries`]. Returns a handle to
+	/// invalidate the cache when the data behind the method changes.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use std::time::Duration;
+	///     use jsonrpsee::RpcModule;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("header", |params, _| params.one::<u64>().map(|n| n * 2).map_err(Into::into)).unwrap();
+	///     let cache = module.cache_responses("header", Duration::from_secs(6)).unwrap();
+	///
+	///     let header: u64 = module.call("header", [21_u64]).await.unwrap();
+	///     assert_eq!(header, 42);
+	///
+	///     // A new block was imported.
+	///     cache.clear();
+	/// }
+	/// ```
+	pub fn cache_responses(&mut self, method_name: &str, ttl: Duration) -> Result<ResponseCache, Error> {
+		let method = match self.mut_callbacks().get_mut(method_name) {
+			Some(method) => method,
+			None => return Err(Error::MethodNotFound(method_name.into())),
+		};
+		let cache = ResponseCache::new(ttl);
+
+		method.callback = match method.callback.clone() {
+			MethodKind::Sync(callback) => {
+				let cache = cache.clone();
+				MethodKind::Sync(Arc::new(move |id, params, sink, conn| {
+					let key = params.as_str().map(Into::into);
+					let epoch = match cache.get(&key) {
+						Ok(result) => return sink.send_response(id, &*result),
+						Err(epoch) => epoch,
+					};
+					let (tx, mut rx) = mpsc::unbounded();
+					callback(id.clone(), params, &MethodSink::new(tx), conn);
+					match rx.try_recv() {
+						Ok(resp) => cache.finish(&key, epoch, resp, id, sink),
+						_ => false,
+					}
+				}))
+			}
+			MethodKind::Async(callback) => {
+				let cache = cache.clone();
+				MethodKind::Async(Arc::new(move |id, params, sink, conn, guard| {
+					let callback = callback.clone();
+					let cache = cache.clone();
+					async move {
+						let key: Option<Box<str>> = params.as_str().map(Into::into);
+						loop {
+							let epoch = match cache.lookup(&key) {
+								Lookup::Hit(result) => return sink.send_response(id, &*result),
+								// Looked up again if the running call failed or the cache was cleared meanwhile.
+								Lookup::Wait(rx) => match rx.await {
+									Ok(Some(result)) => return sink.send_response(id, &*result),
+									_ => continue,
+								},
+								Lookup::Miss(epoch) => epoch,
+							};
+							let mut pending = PendingCall { cache: &cache, key: &key, epoch, done: false };
+							let (tx, mut rx) = mpsc::unbounded();
+							callback(id.clone(), params, MethodSink::new(tx), conn, guard).await;
+							let res = match rx.next().await {
+								Some(resp) => cache.finish(&key, epoch, resp, id, &sink),
+								None => {
+									cache.complete(&key, epoch, None);
+									false
+								}
+							};
+							pending.done = true;
+							return res;
+						}
+					}
+					.boxed()
+				}))
+			}
+			MethodKind::Subscription(_) => {
+				return Err(Error::Custom(format!("Responses of subscription method {} can't be cached", method_name)))
+			}
+		};
+
+		Ok(cache)
+	}
+
+	/// Reject calls of a method that don't have exactly `count` positional params with an invalid params error
+	/// saying how many were expected, rather than the error of the first param that fails to deserialize.
+	///
+	/// Params by name (a JSON object) are not checked.
+	pub fn expect_params(&mut self, method_name: &str, count: usize) -> Result<(), Error> {
+		let method = match self.mut_callbacks().get_mut(method_name) {
+			Some(method) => method,
+			None => return Err(Error::MethodNotFound(method_name.into())),
+		};
+
+		method.callback = match method.callback.clone() {
+			MethodKind::Sync(callback) => {
+				MethodKind::Sync(Arc::new(move |id, params, sink, conn| match check_params_count(&params, count) {
+					Ok(()) => callback(id, params, sink, conn),
+					Err(err) => sink.send_call_error(id, err),
+				}))
+			}
+			MethodKind::Async(callback) => MethodKind::Async(Arc::new(move |id, params, sink, conn, guard| {
+				match check_params_count(&params, count) {
+					Ok(()) => callback(id, params, sink, conn, guard),
+					Err(err) => futures_util::future::ready(sink.send_call_error(id, err)).boxed(),
+				}
+			})),
+			MethodKind::Subscription(callback) => MethodKind::Subscription(Arc::new(move |id, params, sink, conn| {
+				match check_params_count(&params, count) {
+					Ok(()) => callback(id, params, sink, conn),
+					Err(err) => sink.send_call_error(id, err),
+				}
+			})),
+		};
+
+		Ok(())
+	}
+
+	/// Returns an [OpenRPC](https://spec.open-rpc.org) document listing the registered methods, sorted by name,
+	/// with their descriptions.
+	///
+	/// Parameters and results are not described, tools that need them can fill them in, as well as the `info` of
+	/// the API which is a placeholder.
+	///
+	/// ```
+	/// use jsonrpsee::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	/// module.set_method_description("say_hello", "Greets the caller.").unwrap();
+	///
+	/// let doc = module.to_openrpc();
+	/// assert_eq!(doc["methods"][0]["name"], "say_hello");
+	/// assert_eq!(doc["methods"][0]["description"], "Greets the caller.");
+	/// ```
+	pub fn to_openrpc(&self) -> serde_json::Value {
+		let mut names: Vec<_> = self.method_names().collect();
+		names.sort_unstable();
+
+		let methods: Vec<_> = names
+			.into_iter()
+			.map(|name| {
+				let mut method = serde_json::json!({ "name": name, "params": [] });
+				if let Some(description) = self.callbacks[name].description {
+					method["description"] = description.into();
+				}
+				method
+			})
+			.collect();
+
+		serde_json::json!({
+			"openrpc": OPENRPC_VERSION,
+			"info": { "title": "JSON-RPC API", "version": "0.0.0" },
+			"methods": methods,
+		})
+	}
 }
 
+/// Version of the OpenRPC specification that [`Methods::to_openrpc`] follows.
+const OPENRPC_VERSION: &str = "1.2.6";
+
 impl<Context> Deref for RpcModule<Context> {
 	type Target = Methods;
 
@@ -458,12 +1135,26 @@ impl<Context> DerefMut for RpcModule<Context> {
 pub struct RpcModule<Context> {
 	ctx: Arc<Context>,
 	methods: Methods,
+	subscription_event_hook: SubscriptionEventHook,
+	/// Whether to warn about subscription callbacks that close the subscription without sending anything.
+	warn_on_silent_subscriptions: Arc<AtomicBool>,
+	/// Whether identical subscribe calls on a connection share a subscription.
+	deduplicate_subscriptions: Arc<AtomicBool>,
+	/// Runs the tasks forwarding the streams of [`RpcModule::register_stream_subscription`].
+	stream_executor: Arc<RwLock<Arc<dyn Executor>>>,
 }
 
 impl<Context> RpcModule<Context> {
 	/// Create a new module with a given shared `Context`.
 	pub fn new(ctx: Context) -> Self {
-		Self { ctx: Arc::new(ctx), methods: Default::default() }
+		Self {
+			ctx: Arc::new(ctx),
+			methods: Default::default(),
+			subscription_event_hook: Default::default(),
+			warn_on_silent_subscriptions: Default::default(),
+			deduplicate_subscriptions: Default::default(),
+			stream_executor: Arc::new(RwLock::new(Arc::new(TokioExecutor))),
+		}
 	}
 
 	/// Transform a module into an `RpcModule<()>` (unit context).
@@ -472,32 +1163,197 @@ impl<Context> RpcModule<Context> {
 		module.methods = self.methods;
 		module
 	}
-}
 
-impl<Context> From<RpcModule<Context>> for Methods {
-	fn from(module: RpcModule<Context>) -> Methods {
-		module.methods
+	/// Take the methods registered so far out of the module, leaving it empty but with the same context, so
+	/// that it can be used to register more methods.
+	///
+	/// The module keeps the registry of active subscribers, which it shares with the returned [`Methods`], such
+	/// that the subscriptions opened through them can still be reached with [`RpcModule::broadcast`] and the
+	/// [`SubscriptionCloser`] of the module.
+	pub fn take_methods(&mut self) -> Methods {
+		let methods = std::mem::take(&mut self.methods);
+		self.methods.subscribers = methods.subscribers.clone();
+		methods
 	}
-}
 
-impl<Context: Send + Sync + 'static> RpcModule<Context> {
-	/// Register a new synchronous RPC method, which computes the response with the given callback.
-	pub fn register_method<R, F>(
-		&mut self,
-		method_name: &'static str,
-		callback: F,
-	) -> Result<MethodResourcesBuilder, Error>
+	/// Send `result` to all the active subscriptions of `subscribe_method_name` and return how many it was sent to.
+	///
+	/// Fails without sending anything if `result` can't be serialized.
+	pub fn broadcast<T: Serialize>(&self, subscribe_method_name: &str, result: &T) -> Result<usize, Error> {
+		let result = serde_json::value::to_raw_value(result)?;
+		let mut sent = 0;
+		for (method, notif_method, subscribers, _) in self.methods.subscribers.read().iter() {
+			if *method != subscribe_method_name {
+				continue;
+			}
+			for (key, subscriber) in subscribers.lock().iter() {
+				let msg = serde_json::to_string(&SubscriptionResponse::new(
+					(*notif_method).into(),
+					SubscriptionPayload { subscription: key.sub_id.clone(), result: &result },
+				))?;
+				if subscriber.sink.send_raw(msg).is_ok() {
+					subscriber.times.sent();
+					sent += 1;
+				}
+			}
+		}
+		Ok(sent)
+	}
+
+	/// Call `callback` whenever a subscription registered on this module is opened or closed, for instance to
+	/// keep metrics of the active subscriptions. Replaces any previously set callback.
+	///
+	/// The callback applies to subscriptions registered before and after it's set, but not to the subscriptions
+	/// of other modules merged into this one.
+	pub fn on_subscription_event<F>(&mut self, callback: F)
 	where
-		Context: Send + Sync + 'static,
-		R: Serialize,
-		F: Fn(Params, &Context) -> Result<R, Error> + Send + Sync + 'static,
+		F: Fn(SubscriptionEvent) + Send + Sync + 'static,
 	{
-		let ctx = self.ctx.clone();
-		let callback = self.methods.verify_and_insert(
+		*self.subscription_event_hook.0.write() = Some(Box::new(callback));
+	}
+
+	/// Spawn the tasks forwarding the streams of [`RpcModule::register_stream_subscription`] on `executor`, such as
+	/// the one the WebSocket server is configured with (default is [`TokioExecutor`]).
+	///
+	/// Applies to the subscriptions registered before and after it's set.
+	pub fn set_stream_executor(&mut self, executor: impl Executor + 'static) {
+		*self.stream_executor.write() = Arc::new(executor);
+	}
+
+	/// Returns a handle to close all the active subscriptions of this module at once, for instance when a resource
+	/// they depend on, such as a database held by the context, shuts down.
+	///
+	/// The handle applies to subscriptions registered before and after it's created, including the subscriptions
+	/// of other modules merged into this one.
+	pub fn subscription_closer(&self) -> SubscriptionCloser {
+		SubscriptionCloser {
+			subscribers: self.methods.subscribers.clone(),
+			event_hook: self.subscription_event_hook.clone(),
+		}
+	}
+
+	/// Log a warning whenever a subscription callback of this module returns `Ok(())` after dropping its
+	/// [`SubscriptionSink`] without sending anything, which closes the subscription right after it was
+	/// opened. That's usually a callback that forgot to spawn the task producing the notifications.
+	///
+	/// Disabled by default because a subscription may legitimately have nothing to send.
+	pub fn warn_on_silent_subscriptions(&mut self, enabled: bool) {
+		self.warn_on_silent_subscriptions.store(enabled, Ordering::Relaxed);
+	}
+
+	/// Answer a subscribe call with the ID of an existing subscription when the same connection already subscribed
+	/// with the same method and params, rather than opening another subscription. The params must be identical
+	/// JSON text.
+	///
+	/// Unsubscribing then closes the subscription for all the calls that got its ID. Only the subscriptions opened
+	/// while this is enabled are reused. Disabled by default.
+	pub fn deduplicate_subscriptions(&mut self, enabled: bool) {
+		self.deduplicate_subscriptions.store(enabled, Ordering::Relaxed);
+	}
+}
+
+impl<Context> From<RpcModule<Context>> for Methods {
+	fn from(module: RpcModule<Context>) -> Methods {
+		module.methods
+	}
+}
+
+/// Wraps the methods in a module without context, for example to register hand-written methods next to the
+/// methods of the modules generated by `#[rpc(server)]` that were merged into the [`Methods`].
+impl From<Methods> for RpcModule<()> {
+	fn from(methods: Methods) -> RpcModule<()> {
+		RpcModule { methods, ..RpcModule::new(()) }
+	}
+}
+
+impl<Context: Send + Sync + 'static> RpcModule<Context> {
+	/// Register a new synchronous RPC method, which computes the response with the given callback.
+	pub fn register_method<R, F>(
+		&mut self,
+		method_name: &'static str,
+		callback: F,
+	) -> Result<MethodResourcesBuilder, Error>
+	where
+		Context: Send + Sync + 'static,
+		R: Serialize,
+		F: Fn(Params, &Context) -> Result<R, Error> + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_sync(Arc::new(move |id, params, sink, _| match callback(params, &*ctx) {
+				Ok(res) => sink.send_response(id, res),
+				Err(err) => {
+					log_call_error(method_name, &id, &err);
+					sink.send_call_error(id, err)
+				}
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new synchronous RPC method, similar to [`register_method`](RpcModule::register_method),
+	/// but the callback also receives the [`ConnectionInfo`] of the connection the call was made on.
+	pub fn register_method_with_connection<R, F>(
+		&mut self,
+		method_name: &'static str,
+		callback: F,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		Context: Send + Sync + 'static,
+		R: Serialize,
+		F: Fn(Params, &Context, &ConnectionInfo) -> Result<R, Error> + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let callback = self.methods.verify_and_insert(
 			method_name,
-			MethodCallback::new_sync(Arc::new(move |id, params, sink| match callback(params, &*ctx) {
+			MethodCallback::new_sync(Arc::new(move |id, params, sink, conn| match callback(params, &*ctx, conn) {
 				Ok(res) => sink.send_response(id, res),
-				Err(err) => sink.send_call_error(id, err),
+				Err(err) => {
+					log_call_error(method_name, &id, &err);
+					sink.send_call_error(id, err)
+				}
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new synchronous RPC method that works on the raw JSON of the call, for proxies and gateways
+	/// that forward calls without looking into them.
+	///
+	/// The callback receives the raw params, `null` if there are none, and must send the response itself
+	/// with [`MethodSink::send_raw`] or any of the other `MethodSink::send_*` methods. It returns whether
+	/// the call succeeded, which is reported to the middleware.
+	///
+	/// ```
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_raw_method("echo", |id, params, sink, _conn_id| {
+	///     let id = serde_json::to_string(&id).unwrap();
+	///     let response = format!(r#"{{"jsonrpc":"2.0","result":{},"id":{}}}"#, params.get(), id);
+	///     sink.send_raw(response).is_ok()
+	/// }).unwrap();
+	/// ```
+	pub fn register_raw_method<F>(
+		&mut self,
+		method_name: &'static str,
+		callback: F,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		F: Fn(Id, &JsonRawValue, &MethodSink, ConnectionId) -> bool + Send + Sync + 'static,
+	{
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_sync(Arc::new(move |id, params, sink, conn| match params.parse::<&JsonRawValue>() {
+				Ok(raw) => callback(id, raw, sink, conn.id),
+				Err(err) => {
+					let err = err.into();
+					log_call_error(method_name, &id, &err);
+					sink.send_call_error(id, err)
+				}
 			})),
 		)?;
 
@@ -523,7 +1379,200 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 				let future = async move {
 					let result = match callback(params, ctx).await {
 						Ok(res) => sink.send_response(id, res),
-						Err(err) => sink.send_call_error(id, err),
+						Err(err) => {
+							log_call_error(method_name, &id, &err);
+							sink.send_call_error(id, err)
+						}
+					};
+
+					// Release claimed resources
+					drop(claimed);
+
+					result
+				};
+				future.boxed()
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new asynchronous RPC method whose result is produced by a stream of serialized JSON fragments, for
+	/// results too large to be buffered.
+	///
+	/// The HTTP server writes the fragments to the response body as they come, with chunked transfer encoding, so
+	/// that the client can start processing the result before it's complete. The other transports, batches and
+	/// MessagePack responses join the fragments into a single response instead. The fragments must add up to a
+	/// valid JSON value, which can only be verified when they are joined. A stream without fragments results in
+	/// `null`.
+	///
+	/// A failed fragment is answered with the error if nothing was sent yet. Once the HTTP server has started
+	/// writing the result, it aborts the response instead, as it does when the result exceeds the maximum response
+	/// size, such that the client doesn't mistake it for a complete one.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_streaming_method("keys", |_, _| async move {
+	///     // Produces `["key0","key1",...,"key999"]`.
+	///     let keys = (0..1000).map(|i| format!(r#"{}"key{}""#, if i == 0 { '[' } else { ',' }, i));
+	///     Ok(futures_util::stream::iter(keys.chain(std::iter::once("]".to_string())).map(Ok)))
+	/// }).unwrap();
+	/// ```
+	pub fn register_streaming_method<S, Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		callback: Fun,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		S: Stream<Item = Result<String, Error>> + Send + 'static,
+		Fut: Future<Output = Result<S, Error>> + Send + 'static,
+		Fun: (Fn(Params<'static>, Arc<Context>) -> Fut) + Copy + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let streaming: StreamingMethod = Arc::new(move |params| {
+			let fut = callback(params, ctx.clone());
+			async move { fut.await.map(StreamExt::boxed) }.boxed()
+		});
+
+		let joined = streaming.clone();
+		let mut method = MethodCallback::new_async(Arc::new(move |id, params, sink, _, claimed| {
+			let streaming = joined.clone();
+			let future = async move {
+				let result = match streaming(params).and_then(|fragments| fragments.try_collect::<Vec<_>>()).await {
+					Ok(fragments) => {
+						let mut result = fragments.concat();
+						if result.is_empty() {
+							result = "null".into();
+						}
+						match JsonRawValue::from_string(result) {
+							Ok(result) => sink.send_response(id, result),
+							Err(err) => {
+								tracing::error!("Result of {} is not valid JSON: {:?}", method_name, err);
+								sink.send_error(id, ErrorCode::InternalError.into())
+							}
+						}
+					}
+					Err(err) => {
+						log_call_error(method_name, &id, &err);
+						sink.send_call_error(id, err)
+					}
+				};
+
+				// Release claimed resources
+				drop(claimed);
+
+				result
+			};
+			future.boxed()
+		}));
+		method.streaming = Streaming(Some(streaming));
+		let callback = self.methods.verify_and_insert(method_name, method)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new asynchronous RPC method, similar to [`register_async_method`](RpcModule::register_async_method),
+	/// but the callback also receives the [`ConnectionInfo`] of the connection the call was made on.
+	pub fn register_async_method_with_connection<R, Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		callback: Fun,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		R: Serialize + Send + Sync + 'static,
+		Fut: Future<Output = Result<R, Error>> + Send,
+		Fun: (Fn(Params<'static>, Arc<Context>, ConnectionInfo) -> Fut) + Copy + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_async(Arc::new(move |id, params, sink, conn, claimed| {
+				let ctx = ctx.clone();
+				let future = async move {
+					let result = match callback(params, ctx, conn).await {
+						Ok(res) => sink.send_response(id, res),
+						Err(err) => {
+							log_call_error(method_name, &id, &err);
+							sink.send_call_error(id, err)
+						}
+					};
+
+					// Release claimed resources
+					drop(claimed);
+
+					result
+				};
+				future.boxed()
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new asynchronous RPC method that can report its progress before answering.
+	///
+	/// The callback receives a [`ProgressSink`] whose messages are sent as `progress_method_name` notifications
+	/// carrying the ID of the call, followed by the response computed by the callback. The response is always the
+	/// last message about the call, progress sent after it is rejected.
+	///
+	/// The progress is only sent on transports that deliver notifications before the response, that is over
+	/// WebSocket outside of batches. Over HTTP, in batches and with [`Methods::call`] or [`Methods::dispatch`],
+	/// [`ProgressSink::send`] does nothing and the client only gets the response.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee_core::{server::rpc_module::RpcModule, Error};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method_with_progress("download", "download_progress", |_, progress, _| async move {
+	///     for percent in [50_u32, 100] {
+	///         progress.send(&percent)?;
+	///     }
+	///     Ok::<_, Error>("done")
+	/// }).unwrap();
+	/// ```
+	pub fn register_method_with_progress<R, Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		progress_method_name: &'static str,
+		callback: Fun,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		R: Serialize + Send + Sync + 'static,
+		Fut: Future<Output = Result<R, Error>> + Send,
+		Fun: (Fn(Params<'static>, ProgressSink, Arc<Context>) -> Fut) + Copy + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_async(Arc::new(move |id, params, sink, _, claimed| {
+				let ctx = ctx.clone();
+				let future = async move {
+					let progress = ProgressSink {
+						inner: sink.clone(),
+						method: progress_method_name,
+						id: id.clone(),
+						answered: Arc::new(Mutex::new(false)),
+					};
+					let answered = progress.answered.clone();
+					let result = callback(params, progress, ctx).await;
+
+					let result = {
+						// Held while responding, such that no progress can be sent in between.
+						let mut answered = answered.lock();
+						*answered = true;
+						match result {
+							Ok(res) => sink.send_response(id, res),
+							Err(err) => {
+								log_call_error(method_name, &id, &err);
+								sink.send_call_error(id, err)
+							}
+						}
 					};
 
 					// Release claimed resources
@@ -559,7 +1608,10 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 				tokio::task::spawn_blocking(move || {
 					let result = match callback(params, ctx) {
 						Ok(res) => sink.send_response(id, res),
-						Err(err) => sink.send_call_error(id, err),
+						Err(err) => {
+							log_call_error(method_name, &id, &err);
+							sink.send_call_error(id, err)
+						}
 					};
 
 					// Release claimed resources
@@ -605,6 +1657,14 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	///     - [`SubscriptionSink`]: A sink to send messages to the subscriber.
 	///     - Context: Any type that can be embedded into the [`RpcModule`].
 	///
+	/// The subscription ID is sent back to the subscriber before `callback` is invoked, on the same
+	/// connection, so anything the callback sends before returning, such as an initial snapshot of the
+	/// state, is guaranteed to arrive right after the subscribe response and before any later notifications.
+	///
+	/// Use [`RpcModule::register_alias`] to accept more names for the subscribe or the unsubscribe method, for
+	/// instance both `unsubscribe_hello` and `hello_unsubscribe`. Every alias of the unsubscribe method can end any
+	/// subscription made through the subscribe method or its aliases.
+	///
 	/// # Examples
 	///
 	/// ```no_run
@@ -641,26 +1701,83 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 
 		let ctx = self.ctx.clone();
 		let subscribers = Subscribers::default();
+		let retired = RetiredSubscribers::default();
+		self.methods.subscribers.write().push((
+			subscribe_method_name,
+			notif_method_name,
+			subscribers.clone(),
+			retired.clone(),
+		));
 
 		// Subscribe
 		{
 			let subscribers = subscribers.clone();
-			self.methods.mut_callbacks().insert(
+			let event_hook = self.subscription_event_hook.clone();
+			let warn_on_silent = self.warn_on_silent_subscriptions.clone();
+			let deduplicate = self.deduplicate_subscriptions.clone();
+			let deduplication_index = DeduplicationIndex::default();
+			self.methods.insert(
 				subscribe_method_name,
 				MethodCallback::new_subscription(Arc::new(move |id, params, method_sink, conn| {
 					let (conn_tx, conn_rx) = oneshot::channel::<()>();
+					let times = Arc::new(SubscriptionTimes::new());
+					let weak_subscribers = Arc::downgrade(&subscribers);
 
 					let sub_id = {
+						let mut subscribers = subscribers.lock();
+						let dedup_key =
+							deduplicate.load(Ordering::Relaxed).then(|| (conn.conn_id, params.as_str().map(Box::from)));
+						if let Some(dedup_key) = &dedup_key {
+							let existing = deduplication_index.lock().get(dedup_key).cloned();
+							if let Some(sub_id) = existing {
+								tracing::debug!("Reusing subscription {:?} for request id={:?}", sub_id, id);
+								return method_sink.send_response(id, &sub_id);
+							}
+						}
+
 						let sub_id: RpcSubscriptionId = conn.id_provider.next_id().into_owned();
 						let uniq_sub = SubscriptionKey { conn_id: conn.conn_id, sub_id: sub_id.clone() };
+						// Closes the subscription if it's still active when the connection is closed.
+						let on_reset = {
+							let event_hook = event_hook.clone();
+							let key = uniq_sub.clone();
+							move || {
+								let removed =
+									weak_subscribers.upgrade().and_then(|subscribers| subscribers.lock().remove(&key));
+								if removed.is_some() {
+									event_hook.notify(SubscriptionEvent::Unsubscribed {
+										method: subscribe_method_name,
+										conn_id: key.conn_id,
+										sub_id: &key.sub_id,
+									});
+								}
+							}
+						};
+						let subscriber = Subscriber {
+							sink: method_sink.clone(),
+							_conn_rx: conn_rx,
+							times: times.clone(),
+							_deduplicated: dedup_key.map(|key| {
+								deduplication_index.lock().insert(key.clone(), sub_id.clone());
+								Deduplicated { index: deduplication_index.clone(), key }
+							}),
+							_active: method_sink.track_subscription(on_reset),
+						};
 
-						subscribers.lock().insert(uniq_sub, (method_sink.clone(), conn_rx));
+						subscribers.insert(uniq_sub, subscriber);
+						drop(subscribers);
+						event_hook.notify(SubscriptionEvent::Subscribed {
+							method: subscribe_method_name,
+							conn_id: conn.conn_id,
+							sub_id: &sub_id,
+						});
 
 						sub_id
 					};
 
-					method_sink.send_response(id.clone(), &sub_id);
+					let sent = method_sink.try_send_response(id.clone(), &sub_id);
 
+					let activity = warn_on_silent.load(Ordering::Relaxed).then(|| Arc::new(AtomicBool::new(false)));
 					let sink = SubscriptionSink {
 						inner: method_sink.clone(),
 						close_notify: Some(conn.close_notify),
@@ -668,16 +1785,32 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 						subscribers: subscribers.clone(),
 						uniq_sub: SubscriptionKey { conn_id: conn.conn_id, sub_id },
 						is_connected: Some(conn_tx),
+						rate_limit: None,
+						subscribe_method: subscribe_method_name,
+						event_hook: event_hook.clone(),
+						activity: activity.clone(),
+						times,
+						pending_ready: None,
 					};
+					if sent.is_err() {
+						// Dropping the sink forgets the subscription, nobody would receive its notifications.
+						drop(sink);
+						return false;
+					}
 					if let Err(err) = callback(params, sink, ctx.clone()) {
-						tracing::error!(
-							"subscribe call '{}' failed: {:?}, request id={:?}",
-							subscribe_method_name,
-							err,
-							id
-						);
+						log_call_error(subscribe_method_name, &id, &err);
 						method_sink.send_error(id, ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE).into())
 					} else {
+						// The sink holds the only other reference, so it's gone if we hold the last one.
+						if let Some(activity) = activity {
+							if Arc::strong_count(&activity) == 1 && !activity.load(Ordering::Relaxed) {
+								tracing::warn!(
+									"Subscription callback of '{}' dropped its sink without sending anything, request id={:?}",
+									subscribe_method_name,
+									id
+								);
+							}
+						}
 						true
 					}
 				})),
@@ -686,7 +1819,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 
 		// Unsubscribe
 		{
-			self.methods.mut_callbacks().insert(
+			let event_hook = self.subscription_event_hook.clone();
+			self.methods.insert(
 				unsubscribe_method_name,
 				MethodCallback::new_subscription(Arc::new(move |id, params, sink, conn| {
 					let sub_id = match params.one::<RpcSubscriptionId>() {
@@ -703,10 +1837,18 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 					};
 					let sub_id = sub_id.into_owned();
 
-					let result = subscribers
-						.lock()
-						.remove(&SubscriptionKey { conn_id: conn.conn_id, sub_id: sub_id.clone() })
-						.is_some();
+					let key = SubscriptionKey { conn_id: conn.conn_id, sub_id: sub_id.clone() };
+					// Subscriptions opened with the methods these ones replaced are closed as well.
+					let result = subscribers.lock().remove(&key).is_some()
+						|| retired.lock().iter().any(|subscribers| subscribers.lock().remove(&key).is_some());
+
+					if result {
+						event_hook.notify(SubscriptionEvent::Unsubscribed {
+							method: subscribe_method_name,
+							conn_id: conn.conn_id,
+							sub_id: &sub_id,
+						});
+					}
 
 					sink.send_response(id, result)
 				})),
@@ -716,7 +1858,135 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		Ok(())
 	}
 
+	/// Register a subscription like [`RpcModule::register_subscription`], but rather than handing the
+	/// [`SubscriptionSink`] to a callback, put it in `registry` under the key computed by `key` from the params,
+	/// for producers living outside of the module to send to all the subscribers of a key.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::rpc_module::{RpcModule, SubscriptionRegistry};
+	///
+	/// let registry = SubscriptionRegistry::<String>::new();
+	/// let mut module = RpcModule::new(());
+	/// module.register_subscription_with_registry("sub_account", "account", "unsub_account", registry.clone(), |params, _| {
+	///     params.one::<String>().map_err(Into::into)
+	/// });
+	///
+	/// // Somewhere else, when an account changes.
+	/// registry.send(&"alice".to_string(), &42_u64);
+	/// ```
+	pub fn register_subscription_with_registry<K, F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		registry: SubscriptionRegistry<K>,
+		key: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		K: Eq + Hash + Send + 'static,
+		F: Fn(Params, &Context) -> Result<K, Error> + Send + Sync + 'static,
+	{
+		self.register_subscription(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			move |params, sink, ctx| {
+				registry.insert(key(params, &ctx)?, sink);
+				Ok(())
+			},
+		)
+	}
+
+	/// Register a subscription like [`RpcModule::register_subscription`], where `factory` returns the stream of
+	/// results to send to the subscriber rather than driving a [`SubscriptionSink`].
+	///
+	/// The stream is forwarded with [`SubscriptionSink::pipe_from_stream`] on a task spawned on the executor set with
+	/// [`RpcModule::set_stream_executor`], and dropped when the subscriber unsubscribes or disconnects. The
+	/// subscription is closed once the stream ends.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_stream_subscription("sub_count", "count", "unsub_count", |params, _| {
+	///     let to: u32 = params.one()?;
+	///     Ok(futures_util::stream::iter(0..to))
+	/// });
+	/// ```
+	pub fn register_stream_subscription<T, S, F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		factory: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		T: Serialize + Send + 'static,
+		S: Stream<Item = T> + Send + 'static,
+		F: Fn(Params, Arc<Context>) -> Result<S, Error> + Send + Sync + 'static,
+	{
+		let executor = self.stream_executor.clone();
+		self.register_subscription(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			move |params, sink, ctx| {
+				let stream = factory(params, ctx)?;
+				let forward = async move {
+					if let Err(err) = sink.pipe_from_stream(Box::pin(stream)).await {
+						tracing::warn!("Subscription '{}' failed to send an item: {}", subscribe_method_name, err);
+					}
+				};
+				executor.read().spawn(forward.boxed());
+				Ok(())
+			},
+		)
+	}
+
+	/// Register a method called `method_name` that answers with the [`SubscriptionStats`] of the active
+	/// subscriptions of this module, to diagnose leaked subscriptions or stalled producers.
+	///
+	/// The stats cover the subscriptions registered on this module before and after this call, including the
+	/// subscriptions of other modules merged into this one, of all the connections. Only expose the method to
+	/// trusted clients.
+	pub fn register_subscription_stats(&mut self, method_name: &'static str) -> Result<(), Error> {
+		let subscribers = self.methods.subscribers.clone();
+		self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_sync(Arc::new(move |id, _, sink, _| {
+				let now = Instant::now();
+				let mut stats = Vec::new();
+				for (method, _, subscribers, _) in subscribers.read().iter() {
+					for (key, subscriber) in subscribers.lock().iter() {
+						stats.push(SubscriptionStats {
+							method: method.to_string(),
+							subscription: key.sub_id.clone(),
+							conn_id: key.conn_id,
+							age_ms: now.saturating_duration_since(subscriber.times.created_at).as_millis() as u64,
+							idle_ms: subscriber
+								.times
+								.last_sent()
+								.map(|t| now.saturating_duration_since(t).as_millis() as u64),
+						});
+					}
+				}
+				sink.send_response(id, stats)
+			})),
+		)?;
+
+		Ok(())
+	}
+
 	/// Register an alias for an existing_method. Alias uniqueness is enforced.
+	///
+	/// The alias shares the callback of the method, so the alias of an unsubscribe method ends the same
+	/// subscriptions as the method itself.
 	pub fn register_alias(&mut self, alias: &'static str, existing_method: &'static str) -> Result<(), Error> {
 		self.methods.verify_method_name(alias)?;
 
@@ -725,12 +1995,405 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 			None => return Err(Error::MethodNotFound(existing_method.into())),
 		};
 
-		self.methods.mut_callbacks().insert(alias, callback);
+		self.methods.insert(alias, callback);
 
 		Ok(())
 	}
 }
 
+/// Sends the progress of a call registered with [`RpcModule::register_method_with_progress`].
+#[derive(Debug, Clone)]
+pub struct ProgressSink {
+	/// Sink.
+	inner: MethodSink,
+	/// Method of the progress notifications.
+	method: &'static str,
+	/// ID of the call.
+	id: Id<'static>,
+	/// Set once the call was answered.
+	answered: Arc<Mutex<bool>>,
+}
+
+impl ProgressSink {
+	/// Send the progress of the call to the client, fails if the call was answered already or the connection is
+	/// closed. Does nothing if the transport can't deliver notifications before the response.
+	pub fn send<T: Serialize>(&self, progress: &T) -> Result<(), Error> {
+		let answered = self.answered.lock();
+		if *answered {
+			return Err(Error::Custom(format!("Call {:?} was answered already", self.id)));
+		}
+		if !self.inner.supports_notifications() {
+			return Ok(());
+		}
+		let msg = serde_json::to_string(&ProgressNotification::new(
+			self.method.into(),
+			ProgressPayload { id: self.id.clone(), progress },
+		))?;
+		self.inner.send_raw(msg).map_err(|err| err.into_send_error().into())
+	}
+
+	/// ID of the call whose progress is reported.
+	pub fn call_id(&self) -> &Id<'static> {
+		&self.id
+	}
+}
+
+/// Cache of the responses of a method, see [`Methods::cache_responses`].
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+	state: Arc<Mutex<CacheState>>,
+	ttl: Duration,
+}
+
+/// Default maximum number of results kept by a [`ResponseCache`].
+const MAX_CACHED_RESPONSES: usize = 1024;
+
+#[derive(Debug)]
+struct CacheState {
+	/// Incremented when the cache is cleared, such that calls started before don't fill it with stale results.
+	epoch: u64,
+	entries: FxHashMap<Option<Box<str>>, CacheEntry>,
+	/// Cached results by expiry, oldest first, as all of them live for the same `ttl`.
+	expiries: VecDeque<(Instant, Option<Box<str>>)>,
+	/// Maximum number of cached results, the oldest are evicted beyond.
+	max_entries: usize,
+}
+
+impl CacheState {
+	/// Drop the expired results and the oldest ones until at most `max` are left.
+	fn evict(&mut self, now: Instant, max: usize) {
+		while let Some((expires, key)) = self.expiries.front() {
+			if *expires > now && self.expiries.len() <= max {
+				break;
+			}
+			// The key may have been marked as running again since it expired.
+			if matches!(self.entries.get(key), Some(CacheEntry::Ready(_, e)) if e == expires) {
+				self.entries.remove(key);
+			}
+			self.expiries.pop_front();
+		}
+	}
+}
+
+#[derive(Debug)]
+enum CacheEntry {
+	/// Result of a successful call and when it expires.
+	Ready(Arc<JsonRawValue>, Instant),
+	/// The method is running, its result is sent to the calls waiting for it.
+	Pending(Vec<oneshot::Sender<Option<Arc<JsonRawValue>>>>),
+}
+
+enum Lookup {
+	Hit(Arc<JsonRawValue>),
+	Wait(oneshot::Receiver<Option<Arc<JsonRawValue>>>),
+	Miss(u64),
+}
+
+impl ResponseCache {
+	fn new(ttl: Duration) -> Self {
+		let state = CacheState {
+			epoch: 0,
+			entries: Default::default(),
+			expiries: VecDeque::new(),
+			max_entries: MAX_CACHED_RESPONSES,
+		};
+		Self { state: Arc::new(Mutex::new(state)), ttl }
+	}
+
+	/// Set the maximum number of cached results, the oldest are evicted to make room for new ones (default is 1024).
+	pub fn set_max_entries(&self, max: usize) {
+		let mut state = self.state.lock();
+		state.max_entries = max;
+		state.evict(Instant::now(), max);
+	}
+
+	/// Drop all the cached results, calls running meanwhile don't cache theirs.
+	pub fn clear(&self) {
+		let mut state = self.state.lock();
+		state.epoch += 1;
+		state.entries.clear();
+		state.expiries.clear();
+	}
+
+	/// Returns the cached result of the params or the current epoch.
+	fn get(&self, key: &Option<Box<str>>) -> Result<Arc<JsonRawValue>, u64> {
+		let state = self.state.lock();
+		match state.entries.get(key) {
+			Some(CacheEntry::Ready(result, expires)) if *expires > Instant::now() => Ok(result.clone()),
+			_ => Err(state.epoch),
+		}
+	}
+
+	/// Same as [`ResponseCache::get`], but waits for a call with the same params that is running, or marks the
+	/// params as running otherwise.
+	fn lookup(&self, key: &Option<Box<str>>) -> Lookup {
+		let mut state = self.state.lock();
+		let epoch = state.epoch;
+		match state.entries.get_mut(key) {
+			Some(CacheEntry::Ready(result, expires)) if *expires > Instant::now() => Lookup::Hit(result.clone()),
+			Some(CacheEntry::Pending(waiters)) => {
+				let (tx, rx) = oneshot::channel();
+				waiters.push(tx);
+				Lookup::Wait(rx)
+			}
+			_ => {
+				state.entries.insert(key.clone(), CacheEntry::Pending(Vec::new()));
+				Lookup::Miss(epoch)
+			}
+		}
+	}
+
+	/// Cache the result of a call, if it succeeded, and hand it to the calls waiting for it.
+	fn complete(&self, key: &Option<Box<str>>, epoch: u64, result: Option<Arc<JsonRawValue>>) {
+		let mut state = self.state.lock();
+		if state.epoch != epoch {
+			return;
+		}
+		let waiters = match state.entries.remove(key) {
+			Some(CacheEntry::Pending(waiters)) => waiters,
+			_ => Vec::new(),
+		};
+		match &result {
+			Some(result) if state.max_entries > 0 => {
+				let now = Instant::now();
+				let max = state.max_entries;
+				state.evict(now, max - 1);
+				let expires = now + self.ttl;
+				state.entries.insert(key.clone(), CacheEntry::Ready(result.clone(), expires));
+				state.expiries.push_back((expires, key.clone()));
+			}
+			_ => (),
+		}
+		for waiter in waiters {
+			let _ = waiter.send(result.clone());
+		}
+	}
+
+	/// Complete the call with the response the method sent and forward it to `sink`.
+	fn finish(&self, key: &Option<Box<str>>, epoch: u64, resp: String, id: Id, sink: &MethodSink) -> bool {
+		#[derive(serde::Deserialize)]
+		struct Success {
+			result: Box<JsonRawValue>,
+		}
+
+		match serde_json::from_str::<Success>(&resp) {
+			Ok(Success { result }) => {
+				let result: Arc<JsonRawValue> = result.into();
+				self.complete(key, epoch, Some(result.clone()));
+				sink.send_response(id, &*result)
+			}
+			Err(_) => {
+				self.complete(key, epoch, None);
+				let _ = sink.send_raw(resp);
+				false
+			}
+		}
+	}
+}
+
+/// Call running an asynchronous method on a cache miss, which hands over to the calls waiting for it if it's
+/// dropped before completing, for instance because the connection was closed.
+struct PendingCall<'a> {
+	cache: &'a ResponseCache,
+	key: &'a Option<Box<str>>,
+	epoch: u64,
+	done: bool,
+}
+
+impl Drop for PendingCall<'_> {
+	fn drop(&mut self) {
+		if !self.done {
+			self.cache.complete(self.key, self.epoch, None);
+		}
+	}
+}
+
+/// Closes the active subscriptions of a module, see [`RpcModule::subscription_closer`].
+#[derive(Debug, Clone)]
+pub struct SubscriptionCloser {
+	subscribers: ModuleSubscribers,
+	event_hook: SubscriptionEventHook,
+}
+
+impl SubscriptionCloser {
+	/// Close all the active subscriptions and return how many were closed.
+	///
+	/// The subscribers are sent a [`SubscriptionClosedReason::Server`] with `reason`, and the
+	/// [`SubscriptionSink`]s fail to send from then on, so that their producers can stop.
+	pub fn close_all(&self, reason: &str) -> usize {
+		let close_reason: SubscriptionClosed = SubscriptionClosedReason::Server(reason.to_owned()).into();
+		let mut closed = 0;
+		for (subscribe_method, notif_method, subscribers) in self.snapshot() {
+			// Taken first, such that the lock is released before calling the event hook.
+			let taken = std::mem::take(&mut *subscribers.lock());
+			for (key, subscriber) in taken {
+				self.close_subscriber(subscribe_method, notif_method, key, subscriber, &close_reason);
+				closed += 1;
+			}
+		}
+		closed
+	}
+
+	/// Returns the IDs of the active subscriptions of a connection.
+	pub fn subscriptions_for_connection(&self, conn_id: ConnectionId) -> Vec<RpcSubscriptionId<'static>> {
+		let mut sub_ids = Vec::new();
+		for (_, _, subscribers) in self.snapshot() {
+			let subscribers = subscribers.lock();
+			sub_ids.extend(subscribers.keys().filter(|key| key.conn_id == conn_id).map(|key| key.sub_id.clone()));
+		}
+		sub_ids
+	}
+
+	/// Close a subscription of a connection, like [`SubscriptionCloser::close_all`] does, and return whether it
+	/// was active.
+	pub fn close(&self, conn_id: ConnectionId, sub_id: &RpcSubscriptionId, reason: &str) -> bool {
+		let key = SubscriptionKey { conn_id, sub_id: sub_id.clone().into_owned() };
+		for (subscribe_method, notif_method, subscribers) in self.snapshot() {
+			let subscriber = subscribers.lock().remove(&key);
+			if let Some(subscriber) = subscriber {
+				let close_reason = SubscriptionClosedReason::Server(reason.to_owned()).into();
+				self.close_subscriber(subscribe_method, notif_method, key, subscriber, &close_reason);
+				return true;
+			}
+		}
+		false
+	}
+
+	/// The subscriptions of each method, along with the ones it carried over from the methods it replaced, such that
+	/// the list isn't locked while calling the event hook.
+	fn snapshot(&self) -> Vec<(&'static str, &'static str, Subscribers)> {
+		let mut snapshot = Vec::new();
+		for (subscribe_method, notif_method, subscribers, retired) in self.subscribers.read().iter() {
+			snapshot.push((*subscribe_method, *notif_method, subscribers.clone()));
+			snapshot.extend(retired.lock().iter().map(|retired| (*subscribe_method, *notif_method, retired.clone())));
+		}
+		snapshot
+	}
+
+	fn close_subscriber(
+		&self,
+		subscribe_method: &'static str,
+		notif_method: &'static str,
+		key: SubscriptionKey,
+		subscriber: Subscriber,
+		close_reason: &SubscriptionClosed,
+	) {
+		tracing::debug!("Closing subscription: {:?} reason: {:?}", key.sub_id, close_reason);
+		self.event_hook.notify(SubscriptionEvent::Unsubscribed {
+			method: subscribe_method,
+			conn_id: key.conn_id,
+			sub_id: &key.sub_id,
+		});
+		let msg = serde_json::to_string(&SubscriptionResponse::new(
+			notif_method.into(),
+			SubscriptionPayload { subscription: key.sub_id, result: close_reason },
+		))
+		.expect("valid json infallible; qed");
+		let _ = subscriber.sink.send_raw(msg);
+	}
+}
+
+/// [`SubscriptionSink`]s grouped by a key of the application, such as the account the subscribers are interested in,
+/// see [`RpcModule::register_subscription_with_registry`].
+///
+/// Clones share the same sinks. The sinks of closed subscriptions are removed on the next send to their key, or when
+/// the registry is swept for them on insert.
+pub struct SubscriptionRegistry<K> {
+	state: Arc<Mutex<RegistryState<K>>>,
+}
+
+struct RegistryState<K> {
+	sinks: FxHashMap<K, Vec<SubscriptionSink>>,
+	/// Number of sinks in `sinks`.
+	len: usize,
+	/// Number of sinks left by the last sweep, all of them are swept again once there are twice as many.
+	swept_len: usize,
+}
+
+impl<K> Clone for SubscriptionRegistry<K> {
+	fn clone(&self) -> Self {
+		Self { state: self.state.clone() }
+	}
+}
+
+impl<K> Default for SubscriptionRegistry<K> {
+	fn default() -> Self {
+		let state = RegistryState { sinks: Default::default(), len: 0, swept_len: 0 };
+		Self { state: Arc::new(Mutex::new(state)) }
+	}
+}
+
+impl<K> Debug for SubscriptionRegistry<K> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("SubscriptionRegistry").field("keys", &self.state.lock().sinks.len()).finish()
+	}
+}
+
+impl<K: Eq + Hash> SubscriptionRegistry<K> {
+	/// Create an empty registry.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add `sink` to the subscribers of `key`.
+	///
+	/// The sinks of closed subscriptions are swept from all the keys whenever the registry doubled in size since the
+	/// last sweep, such that subscriptions to keys that are never sent to don't pile up.
+	pub fn insert(&self, key: K, sink: SubscriptionSink) {
+		let mut closed = Vec::new();
+		let mut state = self.state.lock();
+		if state.len >= 2 * state.swept_len.max(16) {
+			state.sinks.retain(|_, sinks| {
+				let (open, gone): (Vec<_>, Vec<_>) = std::mem::take(sinks).into_iter().partition(|s| s.is_subscribed());
+				*sinks = open;
+				closed.extend(gone);
+				!sinks.is_empty()
+			});
+			state.len -= closed.len();
+			state.swept_len = state.len;
+		}
+		state.sinks.entry(key).or_default().push(sink);
+		state.len += 1;
+		drop(state);
+		// Closing the sinks may run the subscription event hook, which must not be called under the lock.
+		drop(closed);
+	}
+
+	/// Send `result` to all the subscribers of `key` and return how many it was sent to.
+	///
+	/// Fails without sending anything if `result` can't be serialized.
+	pub fn send<T: Serialize>(&self, key: &K, result: &T) -> Result<usize, Error> {
+		let result = serde_json::value::to_raw_value(result)?;
+		let mut closed = Vec::new();
+		let mut state = self.state.lock();
+		let subscribers = match state.sinks.get_mut(key) {
+			Some(subscribers) => subscribers,
+			None => return Ok(0),
+		};
+		for mut sink in std::mem::take(subscribers) {
+			match sink.send_raw(&result) {
+				Ok(()) => subscribers.push(sink),
+				Err(_) => closed.push(sink),
+			}
+		}
+		let sent = subscribers.len();
+		if sent == 0 {
+			state.sinks.remove(key);
+		}
+		state.len -= closed.len();
+		drop(state);
+		// Closing the sinks may run the subscription event hook, which must not be called under the lock.
+		drop(closed);
+		Ok(sent)
+	}
+
+	/// Returns the number of subscribers of `key`, including the ones that closed the subscription since the last
+	/// send to `key`.
+	pub fn subscribers(&self, key: &K) -> usize {
+		self.state.lock().sinks.get(key).map_or(0, Vec::len)
+	}
+}
+
 /// Represents a single subscription.
 #[derive(Debug)]
 pub struct SubscriptionSink {
@@ -748,16 +2411,156 @@ pub struct SubscriptionSink {
 	///
 	/// None - implies that the subscription as been closed.
 	is_connected: Option<oneshot::Sender<()>>,
+	/// Set by [`SubscriptionSink::with_rate_limit`].
+	rate_limit: Option<RateLimit>,
+	/// Name of the subscribe method, for the [`SubscriptionEvent`]s.
+	subscribe_method: &'static str,
+	/// Notified when the subscription is closed.
+	event_hook: SubscriptionEventHook,
+	/// Set once anything was sent or the subscription was closed explicitly, if silent subscriptions are reported.
+	activity: Option<Arc<AtomicBool>>,
+	/// Updated on each notification sent, shared with the entry in `subscribers`.
+	times: Arc<SubscriptionTimes>,
+	/// Readiness awaited by [`Sink::poll_ready`].
+	pending_ready: Option<PendingReady>,
+}
+
+/// Notifications of a [`SubscriptionSink`] held back by [`SubscriptionSink::with_rate_limit`].
+#[derive(Debug)]
+struct RateLimit {
+	/// Minimum time between two notifications.
+	period: Duration,
+	/// When the last notification was sent.
+	last_sent: Option<tokio::time::Instant>,
+	/// Most recent notification produced since then, if any.
+	pending: Option<String>,
+}
+
+impl RateLimit {
+	/// Returns `msg` if the period since the last notification is over, holds it back in place of the previous
+	/// pending notification otherwise.
+	fn throttle(&mut self, msg: String) -> Option<String> {
+		let now = tokio::time::Instant::now();
+		match self.last_sent {
+			Some(last_sent) if now < last_sent + self.period => {
+				self.pending = Some(msg);
+				None
+			}
+			_ => {
+				self.last_sent = Some(now);
+				self.pending = None;
+				Some(msg)
+			}
+		}
+	}
+
+	/// When the pending notification may be sent, if there is one.
+	fn pending_deadline(&self) -> Option<tokio::time::Instant> {
+		match (self.last_sent, &self.pending) {
+			(Some(last_sent), Some(_)) => Some(last_sent + self.period),
+			_ => None,
+		}
+	}
+
+	/// Takes the pending notification to send it now.
+	fn take_pending(&mut self) -> Option<String> {
+		let msg = self.pending.take()?;
+		self.last_sent = Some(tokio::time::Instant::now());
+		Some(msg)
+	}
+}
+
+/// Future of [`SubscriptionSink::ready`] that is polled across calls to [`Sink::poll_ready`], behind a mutex to keep
+/// the sink `Sync`. It's only ever accessed mutably, so the lock is never taken.
+struct PendingReady(Mutex<BoxFuture<'static, ()>>);
+
+impl Debug for PendingReady {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("PendingReady")
+	}
 }
 
 impl SubscriptionSink {
+	/// Cap the number of notifications sent per second.
+	///
+	/// Items sent faster than that are coalesced: an item sent within the window of the previous notification is
+	/// held back and replaced by any more recent one, the others are dropped. The item held back is sent by
+	/// [`SubscriptionSink::pipe_from_stream`] at the end of the window, otherwise it's superseded by the next item
+	/// sent after the window, or sent when the sink is dropped. A `max_per_sec` of zero is treated as one. Default
+	/// is no limit.
+	pub fn with_rate_limit(mut self, max_per_sec: u32) -> Self {
+		let period = Duration::from_secs(1) / max_per_sec.max(1);
+		self.rate_limit = Some(RateLimit { period, last_sent: None, pending: None });
+		self
+	}
+
 	/// Send a message back to subscribers.
 	pub fn send<T: Serialize>(&mut self, result: &T) -> Result<(), Error> {
 		if self.is_closed() {
 			return Err(Error::SubscriptionClosed(SubscriptionClosedReason::ConnectionReset.into()));
 		}
 		let msg = self.build_message(result)?;
-		self.inner_send(msg).map_err(Into::into)
+		let subscribed = self.is_subscribed();
+		match self.rate_limit.as_mut() {
+			Some(rate_limit) if subscribed => match rate_limit.throttle(msg) {
+				Some(msg) => self.inner_send(msg),
+				None => {
+					self.mark_active();
+					Ok(())
+				}
+			},
+			_ => self.inner_send(msg),
+		}
+	}
+
+	/// Send an already serialized result back to subscribers.
+	///
+	/// The raw value is embedded in the notification as is, which lets gateways forward upstream subscription items
+	/// without deserializing and serializing them again.
+	pub fn send_raw(&mut self, result: &JsonRawValue) -> Result<(), Error> {
+		self.send(&result)
+	}
+
+	/// Waits until the connection has caught up with the messages sent on this subscription and the other calls of
+	/// the connection, such that sending another one doesn't pile up in memory behind a slow client.
+	///
+	/// The messages are queued without limit otherwise, producers that can slow down should call this before each
+	/// [`SubscriptionSink::send`]. Returns immediately on connections that don't apply backpressure or that are
+	/// closed, in which case the next send fails.
+	pub async fn ready(&self) {
+		self.inner.ready().await;
+	}
+
+	/// Same as [`SubscriptionSink::send`] after awaiting [`SubscriptionSink::ready`], but gives up if the connection
+	/// hasn't caught up within `timeout`.
+	///
+	/// Returns [`Error::RequestTimeout`] without sending the message in that case, the caller may then drop the
+	/// message or close the subscription of a client that doesn't read.
+	pub async fn send_timeout<T: Serialize>(&mut self, result: &T, timeout: Duration) -> Result<(), Error> {
+		if tokio::time::timeout(timeout, self.ready()).await.is_err() {
+			return Err(Error::RequestTimeout);
+		}
+		self.send(result)
+	}
+
+	/// Send a result that may be too large for a single message, split into [`SubscriptionChunk`]s carrying at most
+	/// `chunk_size` bytes of the serialized result each.
+	///
+	/// The chunks are sent back to back and the client reassembles them with
+	/// `Subscription::next_chunked`. Returns an error if `chunk_size` is zero, if the value couldn't be serialized or
+	/// if the subscription was closed while sending the chunks.
+	pub fn send_chunked<T: Serialize>(&mut self, result: &T, chunk_size: usize) -> Result<(), Error> {
+		if chunk_size == 0 {
+			return Err(Error::Custom("Chunk size must be greater than zero".into()));
+		}
+		let json = serde_json::to_string(result)?;
+		let chunks = split_at_char_boundaries(&json, chunk_size);
+		let total = u32::try_from(chunks.len()).map_err(|_| Error::Custom("Too many chunks".into()))?;
+
+		for (seq, data) in chunks.into_iter().enumerate() {
+			self.send(&SubscriptionChunk { seq: seq as u32, total, data: data.into() })?;
+		}
+		Ok(())
 	}
 
 	/// Consumes the `SubscriptionSink` and reads data from the `stream` and sends back data on the subscription
@@ -789,9 +2592,16 @@ impl SubscriptionSink {
 			let closed_fut = close_notify.notified();
 			pin_mut!(closed_fut);
 			loop {
-				match futures_util::future::select(stream_item, closed_fut).await {
+				// Resolves when the item held back by the rate limit, if any, is due.
+				let pending_due = match self.rate_limit.as_ref().and_then(RateLimit::pending_deadline) {
+					Some(deadline) => Either::Left(Box::pin(tokio::time::sleep_until(deadline))),
+					None => Either::Right(future::pending()),
+				};
+				match futures_util::future::select(futures_util::future::select(stream_item, pending_due), closed_fut)
+					.await
+				{
 					// The app sent us a value to send back to the subscribers
-					Either::Left((Some(result), next_closed_fut)) => {
+					Either::Left((Either::Left((Some(result), _)), next_closed_fut)) => {
 						match self.send(&result) {
 							Ok(_) => (),
 							Err(Error::SubscriptionClosed(close_reason)) => {
@@ -806,7 +2616,19 @@ impl SubscriptionSink {
 						closed_fut = next_closed_fut;
 					}
 					// Stream terminated.
-					Either::Left((None, _)) => break Ok(()),
+					Either::Left((Either::Left((None, _)), _)) => {
+						self.flush_rate_limited().await;
+						break Ok(());
+					}
+					// The item held back by the rate limit is due.
+					Either::Left((Either::Right(((), next_stream_item)), next_closed_fut)) => {
+						if let Err(Error::SubscriptionClosed(close_reason)) = self.send_pending() {
+							self.close(&close_reason);
+							break Ok(());
+						}
+						stream_item = next_stream_item;
+						closed_fut = next_closed_fut;
+					}
 					// The subscriber went away without telling us.
 					Either::Right(((), _)) => {
 						self.close(&SubscriptionClosed::new(SubscriptionClosedReason::ConnectionReset));
@@ -820,11 +2642,33 @@ impl SubscriptionSink {
 		}
 	}
 
+	/// Send the notification held back by the rate limit, if any.
+	fn send_pending(&mut self) -> Result<(), Error> {
+		match self.rate_limit.as_mut().and_then(RateLimit::take_pending) {
+			Some(msg) => self.inner_send(msg),
+			None => Ok(()),
+		}
+	}
+
+	/// Wait for the notification held back by the rate limit to be due, if any, and send it.
+	async fn flush_rate_limited(&mut self) {
+		if let Some(deadline) = self.rate_limit.as_ref().and_then(RateLimit::pending_deadline) {
+			tokio::time::sleep_until(deadline).await;
+			// Closes the sink if it fails.
+			let _ = self.send_pending();
+		}
+	}
+
 	/// Returns whether this channel is closed without needing a context.
 	pub fn is_closed(&self) -> bool {
 		self.inner.is_closed() || self.close_notify.is_none()
 	}
 
+	/// Returns whether notifications can still be sent, that is the subscription wasn't closed by either side.
+	fn is_subscribed(&self) -> bool {
+		!self.is_closed() && self.is_connected.as_ref().is_some_and(|conn| !conn.is_canceled())
+	}
+
 	fn build_message<T: Serialize>(&self, result: &T) -> Result<String, Error> {
 		serde_json::to_string(&SubscriptionResponse::new(
 			self.method.into(),
@@ -836,6 +2680,8 @@ impl SubscriptionSink {
 	fn inner_send(&mut self, msg: String) -> Result<(), Error> {
 		let res = match self.is_connected.as_ref() {
 			Some(conn) if !conn.is_canceled() => {
+				self.mark_active();
+				self.times.sent();
 				// unbounded send only fails if the receiver has been dropped.
 				self.inner.send_raw(msg).map_err(|_| Some(SubscriptionClosedReason::ConnectionReset))
 			}
@@ -858,19 +2704,34 @@ impl SubscriptionSink {
 
 	/// Close the subscription sink with a customized error message.
 	pub fn close_with_custom_message(&mut self, msg: &str) {
+		self.mark_active();
 		let close_reason = SubscriptionClosedReason::Server(msg.to_string()).into();
 		self.inner_close(Some(&close_reason));
 	}
 
 	/// Close the subscription sink with the provided [`SubscriptionClosed`].
 	pub fn close(&mut self, close_reason: &SubscriptionClosed) {
+		self.mark_active();
 		self.inner_close(Some(close_reason));
 	}
 
+	fn mark_active(&self) {
+		if let Some(activity) = &self.activity {
+			activity.store(true, Ordering::Relaxed);
+		}
+	}
+
 	fn inner_close(&mut self, close_reason: Option<&SubscriptionClosed>) {
 		self.is_connected.take();
-		if let Some((sink, _)) = self.subscribers.lock().remove(&self.uniq_sub) {
+		// Bound first, such that the lock is released before calling the event hook.
+		let removed = self.subscribers.lock().remove(&self.uniq_sub);
+		if let Some(Subscriber { sink, .. }) = removed {
 			tracing::debug!("Closing subscription: {:?} reason: {:?}", self.uniq_sub.sub_id, close_reason);
+			self.event_hook.notify(SubscriptionEvent::Unsubscribed {
+				method: self.subscribe_method,
+				conn_id: self.uniq_sub.conn_id,
+				sub_id: &self.uniq_sub.sub_id,
+			});
 			if let Some(close_reason) = close_reason {
 				let msg = self.build_message(close_reason).expect("valid json infallible; qed");
 				let _ = sink.send_raw(msg);
@@ -879,8 +2740,46 @@ impl SubscriptionSink {
 	}
 }
 
+/// Sends each item as a notification, such that a stream can be forwarded to the subscriber with
+/// `stream.map(Ok).forward(sink)`.
+///
+/// The sink is ready under the same conditions as [`SubscriptionSink::ready`]. Flushing and closing are no-ops, the
+/// subscription is closed when the `SubscriptionSink` is dropped.
+impl<T: Serialize> Sink<T> for SubscriptionSink {
+	type Error = Error;
+
+	fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		let this = self.get_mut();
+		if this.is_closed() {
+			return Poll::Ready(Err(Error::SubscriptionClosed(SubscriptionClosedReason::ConnectionReset.into())));
+		}
+		let inner = &this.inner;
+		let PendingReady(ready) =
+			this.pending_ready.get_or_insert_with(|| PendingReady(Mutex::new(inner.ready_owned().boxed())));
+		let res = ready.get_mut().poll_unpin(cx);
+		if res.is_ready() {
+			this.pending_ready = None;
+		}
+		res.map(Ok)
+	}
+
+	fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+		self.get_mut().send(&item)
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+}
+
 impl Drop for SubscriptionSink {
 	fn drop(&mut self) {
+		// Closes the sink if it fails.
+		let _ = self.send_pending();
 		let err = SubscriptionClosedReason::Server("No close reason provided".into()).into();
 		self.inner_close(Some(&err));
 	}
@@ -935,3 +2834,23 @@ impl Drop for Subscription {
 		self.close();
 	}
 }
+
+// Splits `s` into slices of at most `max_len` bytes without cutting through a UTF-8 character, a slice only exceeds
+// `max_len` if a single character is longer than that.
+fn split_at_char_boundaries(s: &str, max_len: usize) -> Vec<&str> {
+	let mut chunks = Vec::with_capacity(s.len() / max_len + 1);
+	let mut rest = s;
+	while !rest.is_empty() {
+		let mut end = max_len.min(rest.len());
+		while !rest.is_char_boundary(end) {
+			end -= 1;
+		}
+		if end == 0 {
+			end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+		}
+		let (chunk, tail) = rest.split_at(end);
+		chunks.push(chunk);
+		rest = tail;
+	}
+	chunks
+}
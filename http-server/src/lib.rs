@@ -43,7 +43,7 @@ pub use access_control::{
 };
 pub use jsonrpsee_core::server::rpc_module::RpcModule;
 pub use jsonrpsee_types as types;
-pub use server::{Builder as HttpServerBuilder, Server as HttpServer, ServerHandle as HttpServerHandle};
+pub use server::{Builder as HttpServerBuilder, RpcService, Server as HttpServer, ServerHandle as HttpServerHandle};
 pub use tracing;
 
 #[cfg(test)]
@@ -31,6 +31,8 @@ use crate::types::Id;
 
 const JSON: &str = "application/json; charset=utf-8";
 const TEXT: &str = "text/plain";
+#[cfg(feature = "msgpack")]
+const MSGPACK: &str = "application/msgpack";
 
 /// Create a response for json internal error.
 pub fn internal_error() -> hyper::Response<hyper::Body> {
@@ -108,6 +110,17 @@ pub fn ok_response(body: String) -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::OK, body, JSON)
 }
 
+/// Create a valid JSON response whose body is sent as it's produced, with chunked transfer encoding.
+pub fn ok_streaming_response(body: hyper::Body) -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::OK, body, JSON)
+}
+
+/// Create a valid MessagePack response.
+#[cfg(feature = "msgpack")]
+pub fn ok_msgpack_response(body: Vec<u8>) -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::OK, body, MSGPACK)
+}
+
 /// Create a response for unsupported content type.
 pub fn unsupported_content_type() -> hyper::Response<hyper::Body> {
 	from_template(
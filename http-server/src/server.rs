@@ -25,41 +25,62 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::cmp;
+use std::error::Error as StdError;
 use std::future::Future;
 use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use crate::response::{internal_error, malformed};
 use crate::{response, AccessControl};
 use futures_channel::mpsc;
-use futures_util::{future::join_all, stream::StreamExt, FutureExt};
+use futures_util::future::{self, join_all};
+use futures_util::stream::{self, BoxStream, StreamExt};
+use futures_util::{FutureExt, TryFutureExt};
 use hyper::header::{HeaderMap, HeaderValue};
-use hyper::server::{conn::AddrIncoming, Builder as HyperBuilder};
-use hyper::service::{make_service_fn, service_fn};
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use hyper::server::Builder as HyperBuilder;
+use hyper::service::{make_service_fn, Service};
 use hyper::{Error as HyperError, Method};
 use jsonrpsee_core::error::{Error, GenericTransportError};
-use jsonrpsee_core::http_helpers::{self, read_body};
+use jsonrpsee_core::http_helpers::{self, read_body, read_body_bytes};
 use jsonrpsee_core::middleware::Middleware;
-use jsonrpsee_core::server::helpers::{collect_batch_response, prepare_error, MethodSink};
-use jsonrpsee_core::server::resource_limiting::Resources;
-use jsonrpsee_core::server::rpc_module::{MethodKind, Methods};
-use jsonrpsee_core::TEN_MB_SIZE_BYTES;
+use jsonrpsee_core::server::helpers::{
+	batch_too_large, collect_batch_response, duplicate_keys_error, has_unknown_fields, health_module, parse_request,
+	prepare_error, MethodSink, ParsedRequest,
+};
+use jsonrpsee_core::server::ip_filter::IpFilter;
+use jsonrpsee_core::server::resource_limiting::{ResourceGuard, Resources};
+use jsonrpsee_core::server::rpc_module::{ConnectionInfo, MethodKind, Methods};
+use jsonrpsee_core::{json_depth_exceeds, MAX_JSON_DEPTH, TEN_MB_SIZE_BYTES};
 use jsonrpsee_types::error::ErrorCode;
-use jsonrpsee_types::{Id, Notification, Params, Request};
-use serde_json::value::RawValue;
+use jsonrpsee_types::{Id, Params};
 use socket2::{Domain, Socket, Type};
+use tokio::sync::Notify;
+use tower::layer::util::Identity;
+use tower::Layer;
 
 /// Builder to create JSON-RPC HTTP server.
 #[derive(Debug)]
-pub struct Builder<M = ()> {
+pub struct Builder<M = (), L = Identity> {
 	access_control: AccessControl,
+	ip_filter: IpFilter,
 	resources: Resources,
 	max_request_body_size: u32,
 	keep_alive: bool,
+	reject_duplicate_keys: bool,
+	reject_unknown_fields: bool,
+	max_json_depth: u32,
+	max_batch_size: Option<u32>,
+	health_endpoint: Option<&'static str>,
+	case_insensitive_method_names: bool,
+	graceful_shutdown_timeout: Option<Duration>,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
 	middleware: M,
+	service_builder: tower::ServiceBuilder<L>,
 }
 
 impl Default for Builder {
@@ -68,9 +89,18 @@ impl Default for Builder {
 			max_request_body_size: TEN_MB_SIZE_BYTES,
 			resources: Resources::default(),
 			access_control: AccessControl::default(),
+			ip_filter: IpFilter::default(),
 			keep_alive: true,
+			reject_duplicate_keys: false,
+			reject_unknown_fields: false,
+			max_json_depth: MAX_JSON_DEPTH,
+			max_batch_size: None,
+			health_endpoint: None,
+			case_insensitive_method_names: false,
+			graceful_shutdown_timeout: None,
 			tokio_runtime: None,
 			middleware: (),
+			service_builder: tower::ServiceBuilder::new(),
 		}
 	}
 }
@@ -82,7 +112,7 @@ impl Builder {
 	}
 }
 
-impl<M> Builder<M> {
+impl<M, L> Builder<M, L> {
 	/// Add a middleware to the builder [`Middleware`](../jsonrpsee_core/middleware/trait.Middleware.html).
 	///
 	/// ```
@@ -108,14 +138,58 @@ impl<M> Builder<M> {
 	///
 	/// let builder = HttpServerBuilder::new().set_middleware(MyMiddleware);
 	/// ```
-	pub fn set_middleware<T: Middleware>(self, middleware: T) -> Builder<T> {
+	pub fn set_middleware<T: Middleware>(self, middleware: T) -> Builder<T, L> {
 		Builder {
 			max_request_body_size: self.max_request_body_size,
 			resources: self.resources,
 			access_control: self.access_control,
+			ip_filter: self.ip_filter,
 			keep_alive: self.keep_alive,
+			reject_duplicate_keys: self.reject_duplicate_keys,
+			reject_unknown_fields: self.reject_unknown_fields,
+			max_json_depth: self.max_json_depth,
+			max_batch_size: self.max_batch_size,
+			health_endpoint: self.health_endpoint,
+			case_insensitive_method_names: self.case_insensitive_method_names,
+			graceful_shutdown_timeout: self.graceful_shutdown_timeout,
 			tokio_runtime: self.tokio_runtime,
 			middleware,
+			service_builder: self.service_builder,
+		}
+	}
+
+	/// Wrap the JSON-RPC service in a stack of [`tower`] layers, to reuse existing tower middleware such as
+	/// authentication, rate limiting or tracing in front of the server. Default is no layers.
+	///
+	/// The layers get every HTTP request before the server does any processing, access control included,
+	/// and the HTTP response it produces.
+	///
+	/// ```
+	/// use jsonrpsee_http_server::HttpServerBuilder;
+	///
+	/// let layers = tower::ServiceBuilder::new().map_response(|mut res: hyper::Response<hyper::Body>| {
+	///     res.headers_mut().insert("x-served-by", hyper::header::HeaderValue::from_static("jsonrpsee"));
+	///     res
+	/// });
+	/// let builder = HttpServerBuilder::new().set_tower_layers(layers);
+	/// ```
+	pub fn set_tower_layers<T>(self, service_builder: tower::ServiceBuilder<T>) -> Builder<M, T> {
+		Builder {
+			max_request_body_size: self.max_request_body_size,
+			resources: self.resources,
+			access_control: self.access_control,
+			ip_filter: self.ip_filter,
+			keep_alive: self.keep_alive,
+			reject_duplicate_keys: self.reject_duplicate_keys,
+			reject_unknown_fields: self.reject_unknown_fields,
+			max_json_depth: self.max_json_depth,
+			max_batch_size: self.max_batch_size,
+			health_endpoint: self.health_endpoint,
+			case_insensitive_method_names: self.case_insensitive_method_names,
+			graceful_shutdown_timeout: self.graceful_shutdown_timeout,
+			tokio_runtime: self.tokio_runtime,
+			middleware: self.middleware,
+			service_builder,
 		}
 	}
 
@@ -131,6 +205,32 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Only accept connections from IP addresses in `ranges`, given in CIDR notation such as `192.168.0.0/16`.
+	/// Connections from other addresses are dropped before reading any request.
+	///
+	/// Default is to accept connections from any address.
+	pub fn set_allowed_ips<Range, List>(mut self, ranges: List) -> Result<Self, Error>
+	where
+		List: IntoIterator<Item = Range>,
+		Range: AsRef<str>,
+	{
+		self.ip_filter.set_allowed(ranges)?;
+		Ok(self)
+	}
+
+	/// Drop connections from IP addresses in `ranges`, given in CIDR notation such as `10.0.0.0/8`, before reading
+	/// any request. Takes precedence over [`set_allowed_ips`](Builder::set_allowed_ips).
+	///
+	/// Default is to deny no address.
+	pub fn set_denied_ips<Range, List>(mut self, ranges: List) -> Result<Self, Error>
+	where
+		List: IntoIterator<Item = Range>,
+		Range: AsRef<str>,
+	{
+		self.ip_filter.set_denied(ranges)?;
+		Ok(self)
+	}
+
 	/// Enables or disables HTTP keep-alive.
 	///
 	/// Default is true.
@@ -139,6 +239,74 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Reject requests that contain JSON objects with duplicate keys, such as `{"a":1,"a":2}`,
+	/// with an invalid request error.
+	///
+	/// See [`has_duplicate_keys`](jsonrpsee_core::server::helpers::has_duplicate_keys) for why and how they're
+	/// detected, requests that can't be checked are answered with a parse error.
+	///
+	/// Default is false.
+	pub fn reject_duplicate_keys(mut self, reject: bool) -> Self {
+		self.reject_duplicate_keys = reject;
+		self
+	}
+
+	/// Reject requests with members other than `jsonrpc`, `method`, `params` and `id`, such as
+	/// `{"jsonrpc":"2.0","method":"foo","id":1,"extra":1}`, with an invalid request error, as strict
+	/// JSON-RPC 2.0 validation requires.
+	///
+	/// Default is false, unknown members are ignored.
+	pub fn reject_unknown_fields(mut self, reject: bool) -> Self {
+		self.reject_unknown_fields = reject;
+		self
+	}
+
+	/// Sets the maximum nesting depth of JSON arrays and objects in a request (default is 128).
+	///
+	/// Deeper requests are answered with a parse error without being deserialized.
+	pub fn max_json_depth(mut self, depth: u32) -> Self {
+		self.max_json_depth = depth;
+		self
+	}
+
+	/// Sets the maximum number of calls in a batch request (default is no limit).
+	///
+	/// Larger batches are answered with an invalid request error without processing any of their calls.
+	pub fn max_batch_size(mut self, size: u32) -> Self {
+		self.max_batch_size = Some(size);
+		self
+	}
+
+	/// Answer calls to the method `name` with `{"ok":true,"uptime":<seconds since start>}`, for liveness probes.
+	///
+	/// [`Server::start`] fails with [`Error::MethodAlreadyRegistered`] if the given methods already contain `name`.
+	///
+	/// Default is disabled.
+	pub fn with_health_endpoint(mut self, name: &'static str) -> Self {
+		self.health_endpoint = Some(name);
+		self
+	}
+
+	/// Answer calls to methods whose name only differs in ASCII case from a registered method, for instance
+	/// `System_Chain` for `system_chain`, when no method matches exactly.
+	///
+	/// [`Server::start`] fails with [`Error::MethodAlreadyRegistered`] if two of the given methods only differ in case.
+	///
+	/// Default is false.
+	pub fn case_insensitive_method_names(mut self, enabled: bool) -> Self {
+		self.case_insensitive_method_names = enabled;
+		self
+	}
+
+	/// Limit how long [`ServerHandle::stop`] waits for in-flight requests to be answered.
+	///
+	/// The server refuses new connections as soon as it's stopped, the stopped server completes once the requests
+	/// are answered or after `timeout`, whichever comes first. Default is to wait for all the requests.
+	pub fn graceful_shutdown_timeout(mut self, timeout: Duration) -> Self {
+		self.graceful_shutdown_timeout = Some(timeout);
+		self
+	}
+
 	/// Register a new resource kind. Errors if `label` is already registered, or if the number of
 	/// registered resources on this server instance would exceed 8.
 	///
@@ -152,6 +320,9 @@ impl<M> Builder<M> {
 
 	/// Configure a custom [`tokio::runtime::Handle`] to run the server on.
 	///
+	/// Unlike the WebSocket server, the HTTP server can't run on a custom `Executor`: hyper drives its connections
+	/// with tokio.
+	///
 	/// Default: [`tokio::spawn`]
 	pub fn custom_tokio_runtime(mut self, rt: tokio::runtime::Handle) -> Self {
 		self.tokio_runtime = Some(rt);
@@ -173,7 +344,7 @@ impl<M> Builder<M> {
 	///   assert!(jsonrpsee_http_server::HttpServerBuilder::default().build(addrs).is_ok());
 	/// }
 	/// ```
-	pub fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<M>, Error> {
+	pub fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<M, L>, Error> {
 		let mut err: Option<Error> = None;
 
 		for addr in addrs.to_socket_addrs()? {
@@ -189,10 +360,19 @@ impl<M> Builder<M> {
 				listener,
 				local_addr,
 				access_control: self.access_control,
+				ip_filter: self.ip_filter,
 				max_request_body_size: self.max_request_body_size,
+				reject_duplicate_keys: self.reject_duplicate_keys,
+				reject_unknown_fields: self.reject_unknown_fields,
+				max_json_depth: self.max_json_depth,
+				max_batch_size: self.max_batch_size,
+				health_endpoint: self.health_endpoint,
+				case_insensitive_method_names: self.case_insensitive_method_names,
+				graceful_shutdown_timeout: self.graceful_shutdown_timeout,
 				resources: self.resources,
 				tokio_runtime: self.tokio_runtime,
 				middleware: self.middleware,
+				service_builder: self.service_builder,
 			});
 		}
 
@@ -230,6 +410,9 @@ pub struct ServerHandle {
 
 impl ServerHandle {
 	/// Requests server to stop. Returns an error if server was already stopped.
+	///
+	/// New connections are refused right away, the returned handle completes once the in-flight requests are
+	/// answered, see [`Builder::graceful_shutdown_timeout`].
 	pub fn stop(mut self) -> Result<tokio::task::JoinHandle<()>, Error> {
 		let stop = self.stop_sender.try_send(()).map(|_| self.handle.take());
 		match stop {
@@ -254,108 +437,99 @@ impl Future for ServerHandle {
 
 /// An HTTP JSON RPC server.
 #[derive(Debug)]
-pub struct Server<M = ()> {
+pub struct Server<M = (), L = Identity> {
 	/// Hyper server.
 	listener: HyperBuilder<AddrIncoming>,
 	/// Local address
 	local_addr: Option<SocketAddr>,
 	/// Max request body size.
 	max_request_body_size: u32,
+	/// Whether to reject requests that contain JSON objects with duplicate keys.
+	reject_duplicate_keys: bool,
+	/// Whether to reject requests with members other than `jsonrpc`, `method`, `params` and `id`.
+	reject_unknown_fields: bool,
+	/// Max nesting depth of JSON arrays and objects in requests.
+	max_json_depth: u32,
+	/// Max number of calls in a batch request.
+	max_batch_size: Option<u32>,
+	/// Name of the built-in health method, if enabled.
+	health_endpoint: Option<&'static str>,
+	/// Whether method names are looked up ignoring ASCII case when there is no exact match.
+	case_insensitive_method_names: bool,
+	/// How long in-flight requests may run after the server is stopped, if limited.
+	graceful_shutdown_timeout: Option<Duration>,
 	/// Access control
 	access_control: AccessControl,
+	/// Policy by which to accept or drop incoming connections based on the IP address of the peer.
+	ip_filter: IpFilter,
 	/// Tracker for currently used resources on the server
 	resources: Resources,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
 	middleware: M,
+	/// Tower layers wrapping the JSON-RPC service.
+	service_builder: tower::ServiceBuilder<L>,
 }
 
-impl<M: Middleware> Server<M> {
+impl<M: Middleware, L> Server<M, L> {
 	/// Returns socket address to which the server is bound.
 	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
 		self.local_addr.ok_or_else(|| Error::Custom("Local address not found".into()))
 	}
 
 	/// Start the server.
-	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
+	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error>
+	where
+		L: Layer<RpcService<M>> + Send + Sync + 'static,
+		L::Service: Service<hyper::Request<hyper::Body>, Response = hyper::Response<hyper::Body>> + Send + 'static,
+		<L::Service as Service<hyper::Request<hyper::Body>>>::Error: Into<Box<dyn StdError + Send + Sync>>,
+		<L::Service as Service<hyper::Request<hyper::Body>>>::Future: Send,
+	{
 		let max_request_body_size = self.max_request_body_size;
+		let reject_duplicate_keys = self.reject_duplicate_keys;
+		let reject_unknown_fields = self.reject_unknown_fields;
+		let max_json_depth = self.max_json_depth;
+		let max_batch_size = self.max_batch_size;
 		let access_control = self.access_control;
+		let ip_filter = self.ip_filter;
 		let (tx, mut rx) = mpsc::channel(1);
 		let listener = self.listener;
 		let resources = self.resources;
 		let middleware = self.middleware;
-		let methods = methods.into().initialize_resources(&resources)?;
-
-		let make_service = make_service_fn(move |_| {
-			let methods = methods.clone();
-			let access_control = access_control.clone();
-			let resources = resources.clone();
-			let middleware = middleware.clone();
-
-			async move {
-				Ok::<_, HyperError>(service_fn(move |request| {
-					let methods = methods.clone();
-					let access_control = access_control.clone();
-					let resources = resources.clone();
-					let middleware = middleware.clone();
-
-					// Run some validation on the http request, then read the body and try to deserialize it into one of
-					// two cases: a single RPC request or a batch of RPC requests.
-					async move {
-						if let Err(e) = access_control_is_valid(&access_control, &request) {
-							return Ok::<_, HyperError>(e);
-						}
+		let mut methods = methods.into();
+		if let Some(name) = self.health_endpoint {
+			methods.merge(health_module(name))?;
+		}
+		if self.case_insensitive_method_names {
+			methods = methods.into_case_insensitive()?;
+		}
+		let methods = methods.initialize_resources(&resources)?;
 
-						// Only `POST` and `OPTIONS` methods are allowed.
-						match *request.method() {
-							// An OPTIONS request is a CORS preflight request. We've done our access check
-							// above so we just need to tell the browser that the request is OK.
-							Method::OPTIONS => {
-								let origin = match http_helpers::read_header_value(request.headers(), "origin") {
-									Some(origin) => origin,
-									None => return Ok(malformed()),
-								};
-								let allowed_headers = access_control.allowed_headers().to_cors_header_value();
-								let allowed_header_bytes = allowed_headers.as_bytes();
-
-								let res = hyper::Response::builder()
-									.header("access-control-allow-origin", origin)
-									.header("access-control-allow-methods", "POST")
-									.header("access-control-allow-headers", allowed_header_bytes)
-									.body(hyper::Body::empty())
-									.unwrap_or_else(|e| {
-										tracing::error!("Error forming preflight response: {}", e);
-										internal_error()
-									});
-
-								Ok(res)
-							}
-							// The actual request. If it's a CORS request we need to remember to add
-							// the access-control-allow-origin header (despite preflight) to allow it
-							// to be read in a browser.
-							Method::POST if content_type_is_json(&request) => {
-								let origin = return_origin_if_different_from_host(request.headers()).cloned();
-								let mut res = process_validated_request(
-									request,
-									middleware,
-									methods,
-									resources,
-									max_request_body_size,
-								)
-								.await?;
-
-								if let Some(origin) = origin {
-									res.headers_mut().insert("access-control-allow-origin", origin);
-								}
-								Ok(res)
-							}
-							// Error scenarios:
-							Method::POST => Ok(response::unsupported_content_type()),
-							_ => Ok(response::method_not_allowed()),
-						}
-					}
-				}))
-			}
+		let service_builder = self.service_builder;
+
+		let make_service = make_service_fn(move |conn: &AddrStream| {
+			let remote_addr = conn.remote_addr();
+			// Hyper drops the connection when no service is made for it.
+			let service = if ip_filter.is_allowed(remote_addr.ip()) {
+				let service = RpcService {
+					methods: methods.clone(),
+					access_control: access_control.clone(),
+					resources: resources.clone(),
+					middleware: middleware.clone(),
+					max_request_body_size,
+					reject_duplicate_keys,
+					reject_unknown_fields,
+					max_json_depth,
+					max_batch_size,
+					remote_addr,
+				};
+				Ok(service_builder.service(service))
+			} else {
+				tracing::warn!("Dropping connection from denied address {}", remote_addr);
+				Err(Error::Custom(format!("Connection from {} denied", remote_addr)))
+			};
+
+			async move { service }
 		});
 
 		let rt = match self.tokio_runtime.take() {
@@ -363,15 +537,203 @@ impl<M: Middleware> Server<M> {
 			None => tokio::runtime::Handle::current(),
 		};
 
+		let graceful_shutdown_timeout = self.graceful_shutdown_timeout;
 		let handle = rt.spawn(async move {
-			let server = listener.serve(make_service);
-			let _ = server.with_graceful_shutdown(async move { rx.next().await.map_or((), |_| ()) }).await;
+			let stopped = Arc::new(Notify::new());
+			let shutdown_signal = {
+				let stopped = stopped.clone();
+				async move {
+					let _ = rx.next().await;
+					stopped.notify_one();
+				}
+			};
+			// New connections are refused once stopped, the open ones are closed when their requests are answered.
+			let server = listener.serve(make_service).with_graceful_shutdown(shutdown_signal);
+
+			match graceful_shutdown_timeout {
+				Some(timeout) => {
+					let deadline = async move {
+						stopped.notified().await;
+						tokio::time::sleep(timeout).await;
+					};
+					tokio::select! {
+						_ = server => {}
+						_ = deadline => tracing::warn!("Graceful shutdown timed out; not waiting for in-flight requests"),
+					}
+				}
+				None => {
+					let _ = server.await;
+				}
+			}
 		});
 
 		Ok(ServerHandle { handle: Some(handle), stop_sender: tx })
 	}
 }
 
+/// The JSON-RPC service of the HTTP server for a single connection, which the layers given to
+/// [`Builder::set_tower_layers`] wrap.
+#[derive(Clone)]
+pub struct RpcService<M> {
+	methods: Methods,
+	access_control: AccessControl,
+	resources: Resources,
+	middleware: M,
+	max_request_body_size: u32,
+	reject_duplicate_keys: bool,
+	reject_unknown_fields: bool,
+	max_json_depth: u32,
+	max_batch_size: Option<u32>,
+	remote_addr: SocketAddr,
+}
+
+impl<M> RpcService<M> {
+	/// Create the service for a connection from `remote_addr`, to serve `methods` over HTTP from a server of another
+	/// transport, such as the WebSocket server.
+	///
+	/// Requests are checked as by a server with the default [`Builder`] settings, apart from the given limits.
+	pub fn new(
+		methods: Methods,
+		resources: Resources,
+		middleware: M,
+		max_request_body_size: u32,
+		max_json_depth: u32,
+		remote_addr: SocketAddr,
+	) -> Self {
+		Self {
+			methods,
+			access_control: AccessControl::default(),
+			resources,
+			middleware,
+			max_request_body_size,
+			reject_duplicate_keys: false,
+			reject_unknown_fields: false,
+			max_json_depth,
+			max_batch_size: None,
+			remote_addr,
+		}
+	}
+
+	/// Reject requests that contain JSON objects with duplicate keys (default is false).
+	pub fn reject_duplicate_keys(mut self, reject: bool) -> Self {
+		self.reject_duplicate_keys = reject;
+		self
+	}
+
+	/// Reject requests with members other than `jsonrpc`, `method`, `params` and `id` (default is false).
+	pub fn reject_unknown_fields(mut self, reject: bool) -> Self {
+		self.reject_unknown_fields = reject;
+		self
+	}
+
+	/// Reject batches with more calls than `max_batch_size`, if any (default is no limit).
+	pub fn max_batch_size(mut self, max_batch_size: Option<u32>) -> Self {
+		self.max_batch_size = max_batch_size;
+		self
+	}
+}
+
+impl<M> std::fmt::Debug for RpcService<M> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("RpcService")
+			.field("methods", &self.methods)
+			.field("access_control", &self.access_control)
+			.field("resources", &self.resources)
+			.field("max_request_body_size", &self.max_request_body_size)
+			.field("reject_duplicate_keys", &self.reject_duplicate_keys)
+			.field("reject_unknown_fields", &self.reject_unknown_fields)
+			.field("max_json_depth", &self.max_json_depth)
+			.field("max_batch_size", &self.max_batch_size)
+			.field("remote_addr", &self.remote_addr)
+			.finish()
+	}
+}
+
+impl<M: Middleware> Service<hyper::Request<hyper::Body>> for RpcService<M> {
+	type Response = hyper::Response<hyper::Body>;
+	type Error = HyperError;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, request: hyper::Request<hyper::Body>) -> Self::Future {
+		let methods = self.methods.clone();
+		let access_control = self.access_control.clone();
+		let resources = self.resources.clone();
+		let middleware = self.middleware.clone();
+		let max_request_body_size = self.max_request_body_size;
+		let reject_duplicate_keys = self.reject_duplicate_keys;
+		let reject_unknown_fields = self.reject_unknown_fields;
+		let max_json_depth = self.max_json_depth;
+		let max_batch_size = self.max_batch_size;
+		let remote_addr = self.remote_addr;
+
+		// Run some validation on the http request, then read the body and try to deserialize it into one of
+		// two cases: a single RPC request or a batch of RPC requests.
+		async move {
+			if let Err(e) = access_control_is_valid(&access_control, &request) {
+				return Ok::<_, HyperError>(e);
+			}
+
+			// Only `POST` and `OPTIONS` methods are allowed.
+			match *request.method() {
+				// An OPTIONS request is a CORS preflight request. We've done our access check
+				// above so we just need to tell the browser that the request is OK.
+				Method::OPTIONS => {
+					let origin = match http_helpers::read_header_value(request.headers(), "origin") {
+						Some(origin) => origin,
+						None => return Ok(malformed()),
+					};
+					let allowed_headers = access_control.allowed_headers().to_cors_header_value();
+					let allowed_header_bytes = allowed_headers.as_bytes();
+
+					let res = hyper::Response::builder()
+						.header("access-control-allow-origin", origin)
+						.header("access-control-allow-methods", "POST")
+						.header("access-control-allow-headers", allowed_header_bytes)
+						.body(hyper::Body::empty())
+						.unwrap_or_else(|e| {
+							tracing::error!("Error forming preflight response: {}", e);
+							internal_error()
+						});
+
+					Ok(res)
+				}
+				// The actual request. If it's a CORS request we need to remember to add
+				// the access-control-allow-origin header (despite preflight) to allow it
+				// to be read in a browser.
+				Method::POST if content_type_is_supported(&request) => {
+					let origin = return_origin_if_different_from_host(request.headers()).cloned();
+					let mut res = process_validated_request(
+						request,
+						middleware,
+						methods,
+						resources,
+						max_request_body_size,
+						reject_duplicate_keys,
+						reject_unknown_fields,
+						max_json_depth,
+						max_batch_size,
+						remote_addr,
+					)
+					.await?;
+
+					if let Some(origin) = origin {
+						res.headers_mut().insert("access-control-allow-origin", origin);
+					}
+					Ok(res)
+				}
+				// Error scenarios:
+				Method::POST => Ok(response::unsupported_content_type()),
+				_ => Ok(response::method_not_allowed()),
+			}
+		}
+		.boxed()
+	}
+}
+
 // Checks the origin and host headers. If they both exist, return the origin if it does not match the host.
 // If one of them doesn't exist (origin most probably), or they are identical, return None.
 fn return_origin_if_different_from_host(headers: &HeaderMap) -> Option<&HeaderValue> {
@@ -404,8 +766,13 @@ fn access_control_is_valid(
 }
 
 /// Checks that content type of received request is valid for JSON-RPC.
-fn content_type_is_json(request: &hyper::Request<hyper::Body>) -> bool {
-	is_json(request.headers().get("content-type"))
+fn content_type_is_supported(request: &hyper::Request<hyper::Body>) -> bool {
+	let content_type = request.headers().get("content-type");
+	#[cfg(feature = "msgpack")]
+	if is_msgpack(content_type) {
+		return true;
+	}
+	is_json(content_type)
 }
 
 /// Returns true if the `content_type` header indicates a valid JSON message.
@@ -422,18 +789,144 @@ fn is_json(content_type: Option<&hyper::header::HeaderValue>) -> bool {
 	}
 }
 
-/// Process a verified request, it implies a POST request with content type JSON.
+/// Returns true if the `content_type` header indicates a MessagePack message.
+#[cfg(feature = "msgpack")]
+fn is_msgpack(content_type: Option<&hyper::header::HeaderValue>) -> bool {
+	match content_type.and_then(|val| val.to_str().ok()) {
+		Some(content) => {
+			content.eq_ignore_ascii_case("application/msgpack") || content.eq_ignore_ascii_case("application/x-msgpack")
+		}
+		None => false,
+	}
+}
+
+/// Returns true if one of the media types in the `accept` header is MessagePack, quality values are ignored.
+#[cfg(feature = "msgpack")]
+fn accepts_msgpack(headers: &HeaderMap) -> bool {
+	headers.get_all("accept").iter().filter_map(|val| val.to_str().ok()).flat_map(|val| val.split(',')).any(|media| {
+		let media = media.split(';').next().unwrap_or_default().trim();
+		media.eq_ignore_ascii_case("application/msgpack") || media.eq_ignore_ascii_case("application/x-msgpack")
+	})
+}
+
+/// Converts a MessagePack request body to JSON, returns `None` if the body isn't valid MessagePack.
+#[cfg(feature = "msgpack")]
+fn msgpack_to_json(body: &[u8]) -> Option<Vec<u8>> {
+	let value: serde_json::Value = rmp_serde::from_slice(body).ok()?;
+	serde_json::to_vec(&value).ok()
+}
+
+/// Converts a JSON response to MessagePack, maps are encoded with their keys to keep the JSON-RPC member names.
+#[cfg(feature = "msgpack")]
+fn json_to_msgpack(response: &str) -> Option<Vec<u8>> {
+	let value: serde_json::Value = serde_json::from_str(response).ok()?;
+	rmp_serde::to_vec_named(&value).ok()
+}
+
+/// Returns `true` if `method_name` resolves to a method registered with `RpcModule::register_streaming_method`.
+fn is_streaming(methods: &Methods, method_name: &str) -> bool {
+	methods.method_with_name(method_name).is_some_and(|(_, callback)| callback.streaming().is_some())
+}
+
+/// Fragments of a result produced by a method registered with `RpcModule::register_streaming_method`.
+type Fragments = BoxStream<'static, Result<String, Error>>;
+
+/// Waits for the first non-empty fragment of a streamed result, `None` if the stream ends without any.
+async fn first_fragment(fragments: &mut Fragments) -> Result<Option<String>, Error> {
+	while let Some(fragment) = fragments.next().await {
+		let fragment = fragment?;
+		if !fragment.is_empty() {
+			return Ok(Some(fragment));
+		}
+	}
+	Ok(None)
+}
+
+/// Releases the resources claimed by a streamed call and reports its response to the middleware once dropped, that
+/// is once the body is complete or aborted.
+struct StreamedResponse<M: Middleware> {
+	middleware: M,
+	started_at: M::Instant,
+	_guard: ResourceGuard,
+}
+
+impl<M: Middleware> Drop for StreamedResponse<M> {
+	fn drop(&mut self) {
+		self.middleware.on_response(self.started_at);
+	}
+}
+
+/// Body of the response to the call `id`, made of the `first` fragment of the result followed by the `rest` as they
+/// are produced.
+///
+/// The body is aborted, such that the client doesn't mistake it for a complete response, if a fragment fails or the
+/// result exceeds `max_response_size`.
+fn stream_response<M: Middleware>(
+	id: Id,
+	first: String,
+	rest: Fragments,
+	max_response_size: u32,
+	done: StreamedResponse<M>,
+) -> hyper::Body {
+	let id = serde_json::to_string(&id).expect("IDs are valid JSON; qed");
+	let mut size = first.len();
+	let rest = rest.map(move |fragment| -> Result<String, StreamError> {
+		let fragment = fragment.map_err(|err| {
+			tracing::debug!("Streamed result failed: {:?}; aborting the response", err);
+			StreamError::from(err.to_string())
+		})?;
+		size += fragment.len();
+		if size > max_response_size as usize {
+			tracing::debug!("Streamed result exceeds {} bytes; aborting the response", max_response_size);
+			return Err(format!("Exceeded max limit {}", max_response_size).into());
+		}
+		Ok(fragment)
+	});
+	let head = format!(r#"{{"jsonrpc":"2.0","result":{}"#, first);
+	let tail = async move {
+		drop(done);
+		Ok::<_, StreamError>(format!(r#","id":{}}}"#, id))
+	};
+
+	hyper::Body::wrap_stream(
+		stream::once(future::ready(Ok::<_, StreamError>(head))).chain(rest).chain(stream::once(tail)),
+	)
+}
+
+type StreamError = Box<dyn StdError + Send + Sync>;
+
+/// Process a verified request, it implies a POST request with content type JSON or, with the `msgpack` feature,
+/// MessagePack.
+#[allow(clippy::too_many_arguments)]
 async fn process_validated_request(
 	request: hyper::Request<hyper::Body>,
 	middleware: impl Middleware,
 	methods: Methods,
 	resources: Resources,
 	max_request_body_size: u32,
+	reject_duplicate_keys: bool,
+	reject_unknown_fields: bool,
+	max_json_depth: u32,
+	max_batch_size: Option<u32>,
+	remote_addr: SocketAddr,
 ) -> Result<hyper::Response<hyper::Body>, HyperError> {
 	let (parts, body) = request.into_parts();
+	let conn = ConnectionInfo::new(0, Some(remote_addr));
 
-	let (body, mut is_single) = match read_body(&parts.headers, body, max_request_body_size).await {
-		Ok(r) => r,
+	// MessagePack requests are converted to JSON and the JSON response back to MessagePack.
+	#[cfg(feature = "msgpack")]
+	let msgpack_request = is_msgpack(parts.headers.get("content-type"));
+	#[cfg(not(feature = "msgpack"))]
+	let msgpack_request = false;
+
+	let body = if msgpack_request {
+		read_body_bytes(&parts.headers, body, max_request_body_size).await
+	} else {
+		read_body(&parts.headers, body, max_request_body_size).await.map(|(body, _)| body)
+	};
+
+	let body = match body {
+		Ok(body) => body,
 		Err(GenericTransportError::TooLarge) => return Ok(response::too_large()),
 		Err(GenericTransportError::Malformed) => return Ok(response::malformed()),
 		Err(GenericTransportError::Inner(e)) => {
@@ -442,88 +935,107 @@ async fn process_validated_request(
 		}
 	};
 
+	#[cfg(feature = "msgpack")]
+	let body = if msgpack_request {
+		match msgpack_to_json(&body) {
+			Some(json) => json,
+			None => return Ok(response::malformed()),
+		}
+	} else {
+		body
+	};
+	#[cfg(feature = "msgpack")]
+	let respond_with_msgpack = accepts_msgpack(&parts.headers);
+
+	// The results of streaming methods are converted to MessagePack as a whole.
+	#[cfg(feature = "msgpack")]
+	let can_stream = !respond_with_msgpack;
+	#[cfg(not(feature = "msgpack"))]
+	let can_stream = true;
+
 	let request_start = middleware.on_request();
 
 	// NOTE(niklasad1): it's a channel because it's needed for batch requests.
 	let (tx, mut rx) = mpsc::unbounded::<String>();
-	let sink = MethodSink::new_with_limit(tx, max_request_body_size);
-
-	type Notif<'a> = Notification<'a, Option<&'a RawValue>>;
+	let sink = MethodSink::new_with_limit(tx, max_request_body_size).without_notifications();
+	let is_single;
 
-	// Single request or notification
-	if is_single {
-		if let Ok(req) = serde_json::from_slice::<Request>(&body) {
-			let method = req.method.as_ref();
-			middleware.on_call(method);
-
-			let id = req.id.clone();
-			let params = Params::new(req.params.map(|params| params.get()));
+	// Request nested too deeply, or with duplicate keys which is ambiguous to parse.
+	if json_depth_exceeds(&body, max_json_depth) {
+		tracing::debug!("recv request nested deeper than {}; rejecting", max_json_depth);
+		is_single = true;
+		sink.send_error(Id::Null, ErrorCode::ParseError.into());
+	} else if let Some(code) = reject_duplicate_keys.then(|| duplicate_keys_error(&body)).flatten() {
+		tracing::debug!("recv request with duplicate keys or which can't be checked for them; rejecting");
+		is_single = true;
+		sink.send_error(Id::Null, code.into());
+	} else if reject_unknown_fields && has_unknown_fields(&body) {
+		tracing::debug!("recv request with unknown fields; rejecting");
+		is_single = true;
+		sink.send_error(prepare_error(&body).0, ErrorCode::InvalidRequest.into());
+	} else {
+		match parse_request(&body) {
+			// Single request to a method that writes its result as it's produced.
+			Ok(ParsedRequest::Call(req)) if can_stream && is_streaming(&methods, &req.method) => {
+				is_single = true;
+				let (name, method_callback) = methods.method_with_name(&req.method).expect("checked above; qed");
+				let streaming = method_callback.streaming().expect("checked above; qed");
+				middleware.on_call(name);
+				middleware.on_version(name, req.jsonrpc);
 
-			let result = match methods.method_with_name(method) {
-				None => {
-					sink.send_error(req.id, ErrorCode::MethodNotFound.into());
-					false
-				}
-				Some((name, method_callback)) => match method_callback.inner() {
-					MethodKind::Sync(callback) => match method_callback.claim(&req.method, &resources) {
-						Ok(guard) => {
-							let result = (callback)(id, params, &sink);
-							drop(guard);
-							result
+				let params = Params::new(req.params.map(|p| p.get())).with_version(req.jsonrpc).into_owned();
+				match method_callback.claim(name, &resources) {
+					Ok(guard) => match streaming(params)
+						.and_then(|mut fragments| async move {
+							first_fragment(&mut fragments).await.map(|first| first.map(|first| (first, fragments)))
+						})
+						.await
+					{
+						Ok(Some((first, _))) if first.len() > max_request_body_size as usize => {
+							sink.send_oversized_error(req.id);
+							middleware.on_result(name, false, request_start);
 						}
-						Err(err) => {
-							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
-							sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-							false
+						Ok(Some((first, rest))) => {
+							middleware.on_result(name, true, request_start);
+							let done = StreamedResponse { middleware, started_at: request_start, _guard: guard };
+							let body = stream_response(req.id, first, rest, max_request_body_size, done);
+							return Ok(response::ok_streaming_response(body));
 						}
-					},
-					MethodKind::Async(callback) => match method_callback.claim(name, &resources) {
-						Ok(guard) => {
-							let result =
-								(callback)(id.into_owned(), params.into_owned(), sink.clone(), 0, Some(guard)).await;
-							result
+						// The stream ended without producing anything.
+						Ok(None) => {
+							let result = sink.send_response(req.id, ());
+							middleware.on_result(name, result, request_start);
 						}
 						Err(err) => {
-							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
-							sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-							false
+							sink.send_call_error(req.id, err);
+							middleware.on_result(name, false, request_start);
 						}
 					},
-					MethodKind::Subscription(_) => {
-						tracing::error!("Subscriptions not supported on HTTP");
-						sink.send_error(req.id, ErrorCode::InternalError.into());
-						false
+					Err(err) => {
+						tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
+						sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+						middleware.on_result(name, false, request_start);
 					}
-				},
-			};
-			middleware.on_result(&req.method, result, request_start);
-		} else if let Ok(_req) = serde_json::from_slice::<Notif>(&body) {
-			return Ok::<_, HyperError>(response::ok_response("".into()));
-		} else {
-			let (id, code) = prepare_error(&body);
-			sink.send_error(id, code.into());
-		}
-	// Batch of requests or notifications
-	} else if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(&body) {
-		if !batch.is_empty() {
-			let middleware = &middleware;
+				}
+			}
+			// Single request
+			Ok(ParsedRequest::Call(req)) => {
+				is_single = true;
+				let method = req.method.as_ref();
+				middleware.on_call(method);
+				middleware.on_version(method, req.jsonrpc);
 
-			join_all(batch.into_iter().filter_map(move |req| {
 				let id = req.id.clone();
-				let params = Params::new(req.params.map(|params| params.get()));
+				let params = Params::new(req.params.map(|params| params.get())).with_version(req.jsonrpc);
 
-				match methods.method_with_name(&req.method) {
-					None => {
-						sink.send_error(req.id, ErrorCode::MethodNotFound.into());
-						None
-					}
+				let result = match methods.method_with_name(method) {
+					None => methods.call_fallback(req.id, method, req.params, &sink, conn.id).await,
 					Some((name, method_callback)) => match method_callback.inner() {
-						MethodKind::Sync(callback) => match method_callback.claim(name, &resources) {
+						MethodKind::Sync(callback) => match method_callback.claim(&req.method, &resources) {
 							Ok(guard) => {
-								let result = (callback)(id, params, &sink);
-								middleware.on_result(name, result, request_start);
+								let result = (callback)(id, params, &sink, &conn);
 								drop(guard);
-								None
+								result
 							}
 							Err(err) => {
 								tracing::error!(
@@ -531,21 +1043,15 @@ async fn process_validated_request(
 									err
 								);
 								sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-								middleware.on_result(name, false, request_start);
-								None
+								false
 							}
 						},
 						MethodKind::Async(callback) => match method_callback.claim(name, &resources) {
 							Ok(guard) => {
-								let sink = sink.clone();
-								let id = id.into_owned();
-								let params = params.into_owned();
-								let callback = callback.clone();
-
-								Some(async move {
-									let result = (callback)(id, params, sink, 0, Some(guard)).await;
-									middleware.on_result(name, result, request_start);
-								})
+								let result =
+									(callback)(id.into_owned(), params.into_owned(), sink.clone(), conn, Some(guard))
+										.await;
+								result
 							}
 							Err(err) => {
 								tracing::error!(
@@ -553,36 +1059,104 @@ async fn process_validated_request(
 									err
 								);
 								sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-								middleware.on_result(name, false, request_start);
-								None
+								false
 							}
 						},
 						MethodKind::Subscription(_) => {
 							tracing::error!("Subscriptions not supported on HTTP");
 							sink.send_error(req.id, ErrorCode::InternalError.into());
-							middleware.on_result(&req.method, false, request_start);
-							None
+							false
 						}
 					},
-				}
-			}))
-			.await;
-		} else {
+				};
+				middleware.on_result(&req.method, result, request_start);
+			}
+			Ok(ParsedRequest::Batch(batch)) if max_batch_size.is_some_and(|max| batch.len() > max as usize) => {
+				tracing::debug!("recv batch len={}; rejecting", batch.len());
+				is_single = true;
+				sink.send_error(Id::Null, batch_too_large(max_batch_size.expect("checked above; qed")));
+			}
+			// Batch of requests
+			Ok(ParsedRequest::Batch(batch)) => {
+				is_single = false;
+				let middleware = &middleware;
+				let conn = &conn;
+
+				join_all(batch.into_iter().filter_map(move |req| {
+					let id = req.id.clone();
+					let params = Params::new(req.params.map(|params| params.get())).with_version(req.jsonrpc);
+
+					match methods.method_with_name(&req.method) {
+						None => Some(
+							methods.call_fallback(req.id, &req.method, req.params, &sink, conn.id).map(|_| ()).boxed(),
+						),
+						Some((name, method_callback)) => match method_callback.inner() {
+							MethodKind::Sync(callback) => match method_callback.claim(name, &resources) {
+								Ok(guard) => {
+									let result = (callback)(id, params, &sink, conn);
+									middleware.on_result(name, result, request_start);
+									drop(guard);
+									None
+								}
+								Err(err) => {
+									tracing::error!(
+										"[Methods::execute_with_resources] failed to lock resources: {:?}",
+										err
+									);
+									sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+									middleware.on_result(name, false, request_start);
+									None
+								}
+							},
+							MethodKind::Async(callback) => match method_callback.claim(name, &resources) {
+								Ok(guard) => {
+									let sink = sink.clone();
+									let id = id.into_owned();
+									let params = params.into_owned();
+									let callback = callback.clone();
+									let conn = conn.clone();
+
+									Some(
+										async move {
+											let result = (callback)(id, params, sink, conn, Some(guard)).await;
+											middleware.on_result(name, result, request_start);
+										}
+										.boxed(),
+									)
+								}
+								Err(err) => {
+									tracing::error!(
+										"[Methods::execute_with_resources] failed to lock resources: {:?}",
+										err
+									);
+									sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+									middleware.on_result(name, false, request_start);
+									None
+								}
+							},
+							MethodKind::Subscription(_) => {
+								tracing::error!("Subscriptions not supported on HTTP");
+								sink.send_error(req.id, ErrorCode::InternalError.into());
+								middleware.on_result(&req.method, false, request_start);
+								None
+							}
+						},
+					}
+				}))
+				.await;
+			}
+			// Notifications are not answered.
+			Ok(ParsedRequest::Notification(_)) | Ok(ParsedRequest::NotificationBatch(_)) => {
+				return Ok(response::ok_response("".into()));
+			}
 			// "If the batch rpc call itself fails to be recognized as an valid JSON or as an
 			// Array with at least one value, the response from the Server MUST be a single
 			// Response object." – The Spec.
-			is_single = true;
-			sink.send_error(Id::Null, ErrorCode::InvalidRequest.into());
+			Err(err) => {
+				is_single = true;
+				sink.send_error(err.id(), err.code().into());
+			}
 		}
-	} else if let Ok(_batch) = serde_json::from_slice::<Vec<Notif>>(&body) {
-		return Ok(response::ok_response("".into()));
-	} else {
-		// "If the batch rpc call itself fails to be recognized as an valid JSON or as an
-		// Array with at least one value, the response from the Server MUST be a single
-		// Response object." – The Spec.
-		is_single = true;
-		let (id, code) = prepare_error(&body);
-		sink.send_error(id, code.into());
 	}
 
 	// Closes the receiving half of a channel without dropping it. This prevents any further
@@ -595,5 +1169,13 @@ async fn process_validated_request(
 	};
 	tracing::debug!("[service_fn] sending back: {:?}", &response[..cmp::min(response.len(), 1024)]);
 	middleware.on_response(request_start);
+
+	#[cfg(feature = "msgpack")]
+	if respond_with_msgpack {
+		return Ok(match json_to_msgpack(&response) {
+			Some(msgpack) => response::ok_msgpack_response(msgpack),
+			None => response::internal_error(),
+		});
+	}
 	Ok(response::ok_response(response))
 }
@@ -30,7 +30,9 @@ use std::net::SocketAddr;
 use std::time::Duration;
 
 use crate::types::error::CallError;
+use crate::types::Response;
 use crate::{server::ServerHandle, HttpServerBuilder, RpcModule};
+use jsonrpsee_core::server::helpers::Health;
 use jsonrpsee_core::Error;
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::{Id, StatusCode, TestContext};
@@ -319,6 +321,34 @@ async fn should_return_method_not_found() {
 	assert_eq!(response.body, method_not_found(Id::Str("foo".into())));
 }
 
+#[tokio::test]
+async fn unknown_methods_are_routed_to_the_fallback() {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.set_fallback(|id, method, params, sink, _| async move {
+		// Stands in for forwarding the call to another server.
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		let params = params.map(|params| params.get().to_owned());
+		sink.send_response(id, (method, params))
+	});
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"upstream_call","params":[1,2],"id":2}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(serde_json::json!(["upstream_call", "[1,2]"]), Id::Num(2)));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"upstream_call","id":3}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, format!("[{}]", ok_response(serde_json::json!(["upstream_call", null]), Id::Num(3))));
+}
+
 #[tokio::test]
 async fn invalid_json_id_missing_value() {
 	let (addr, _handle) = server().with_default_timeout().await.unwrap();
@@ -336,12 +366,150 @@ async fn invalid_request_object() {
 	let (addr, _handle) = server().with_default_timeout().await.unwrap();
 	let uri = to_http_uri(addr);
 
-	let req = r#"{"jsonrpc":"2.0","method":"bar","id":1,"is_not_request_object":1}"#;
+	let req = r#"{"jsonrpc":"2.0","method":1,"id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, invalid_request(Id::Num(1)));
+}
+
+#[tokio::test]
+async fn empty_method_is_an_invalid_request() {
+	let (addr, _handle) = server().with_default_timeout().await.unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"","id":1}"#;
 	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
 	assert_eq!(response.status, StatusCode::OK);
 	assert_eq!(response.body, invalid_request(Id::Num(1)));
 }
 
+#[tokio::test]
+async fn duplicate_keys_rejected_in_strict_mode() {
+	let server = HttpServerBuilder::default().reject_duplicate_keys(true).build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","method":"say_goodbye","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, invalid_request(Id::Null));
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","params":{"a":1,"a":2},"id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, invalid_request(Id::Null));
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn duplicate_keys_too_deep_to_be_checked_are_rejected() {
+	let server =
+		HttpServerBuilder::default().reject_duplicate_keys(true).max_json_depth(200).build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let params = format!("{}{}", "[".repeat(129), "]".repeat(129));
+	let req = format!(r#"{{"jsonrpc":"2.0","method":"say_hello","params":{},"method":"say_hello","id":1}}"#, params);
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, parse_error(Id::Null));
+}
+
+#[tokio::test]
+async fn duplicate_keys_accepted_by_default() {
+	let (addr, _handle) = server().with_default_timeout().await.unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","params":{"a":1,"a":2},"id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn unknown_fields_rejected_in_strict_mode() {
+	let server = HttpServerBuilder::default().reject_unknown_fields(true).build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1,"foo":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, invalid_request(Id::Num(1)));
+
+	let req =
+		r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"say_hello","id":2,"foo":1}]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, invalid_request(Id::Null));
+
+	// Members of the params are not request members.
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","params":{"foo":1},"id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn unknown_fields_ignored_by_default() {
+	let (addr, _handle) = server().with_default_timeout().await.unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1,"foo":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn deeply_nested_requests_are_rejected() {
+	let server = HttpServerBuilder::default().max_json_depth(4).build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let nested = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+	let req = format!(r#"{{"jsonrpc":"2.0","method":"say_hello","params":{},"id":1}}"#, nested);
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, parse_error(Id::Null));
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","params":[[["]]]]"]]],"id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn batches_over_the_limit_are_rejected() {
+	let server = HttpServerBuilder::default().max_batch_size(2).build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"say_hello","id":2}]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, r#"[{"jsonrpc":"2.0","result":"lo","id":1},{"jsonrpc":"2.0","result":"lo","id":2}]"#);
+
+	let req = r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"say_hello","id":2},{"jsonrpc":"2.0","method":"say_hello","id":3}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(
+		response.body,
+		r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Batch too large, at most 2 calls are accepted"},"id":null}"#
+	);
+}
+
 #[tokio::test]
 async fn notif_works() {
 	let (addr, _handle) = server().with_default_timeout().await.unwrap();
@@ -353,6 +521,22 @@ async fn notif_works() {
 	assert_eq!(response.body, "");
 }
 
+#[tokio::test]
+async fn null_id_is_answered_but_missing_id_is_not() {
+	let (addr, _handle) = server().with_default_timeout().await.unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":null}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Null));
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello"}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, "");
+}
+
 #[tokio::test]
 async fn can_register_modules() {
 	let cx = String::new();
@@ -385,6 +569,154 @@ async fn stop_works() {
 	assert!(matches!(server_handle.stop().unwrap().await, Ok(_)));
 }
 
+#[tokio::test]
+async fn streaming_method_result_is_sent_in_chunks() {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_streaming_method("keys", |_, _| async move {
+			let keys = (0..10_000).map(|i| format!("{}{}", if i == 0 { '[' } else { ',' }, i));
+			Ok(futures_util::stream::iter(keys.chain(std::iter::once("]".to_string())).map(Ok)))
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+	let expected: Vec<u32> = (0..10_000).collect();
+
+	let req = r#"{"jsonrpc":"2.0","method":"keys","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.header.get("transfer-encoding").unwrap(), "chunked");
+	assert_eq!(response.body, ok_response(serde_json::to_value(&expected).unwrap(), Id::Num(1)));
+
+	// Batches join the fragments into a single response.
+	let req = r#"[{"jsonrpc":"2.0","method":"keys","id":1}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	let response: Vec<Response<Vec<u32>>> = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(response[0].result, expected);
+}
+
+#[tokio::test]
+async fn progress_is_not_sent_over_http() {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_method_with_progress("download", "download_progress", |_, progress, _| async move {
+			progress.send(&50_u32)?;
+			progress.send(&100_u32)?;
+			Ok::<_, Error>("done")
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"download","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, ok_response("done".into(), Id::Num(1)));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"download","id":1},{"jsonrpc":"2.0","method":"download","id":2}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, r#"[{"jsonrpc":"2.0","result":"done","id":1},{"jsonrpc":"2.0","result":"done","id":2}]"#);
+}
+
+#[tokio::test]
+async fn streaming_method_failures_are_reported() {
+	use futures_util::stream;
+
+	let server = HttpServerBuilder::default().max_request_body_size(100).build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_streaming_method("empty", |_, _| async move { Ok(stream::empty()) }).unwrap();
+	module
+		.register_streaming_method("fails_first", |_, _| async move {
+			Ok(stream::iter(vec![Err(Error::Custom("no keys".into()))]))
+		})
+		.unwrap();
+	module
+		.register_streaming_method("fails_later", |_, _| async move {
+			Ok(stream::iter(vec![Ok("[1".to_string()), Err(Error::Custom("gone".into()))]))
+		})
+		.unwrap();
+	module
+		.register_streaming_method("too_large", |_, _| async move {
+			let keys = (0..100).map(|i| format!("{}{}", if i == 0 { '[' } else { ',' }, i));
+			Ok(stream::iter(keys.chain(std::iter::once("]".to_string())).map(Ok)))
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	// Answered like any other call as long as nothing was written.
+	let req = r#"{"jsonrpc":"2.0","method":"empty","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::Null, Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"fails_first","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	let response: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(response["error"]["message"], "Custom error: no keys");
+
+	// The response is aborted once it was started.
+	let req = r#"{"jsonrpc":"2.0","method":"fails_later","id":1}"#;
+	assert!(http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().is_err());
+	let req = r#"{"jsonrpc":"2.0","method":"too_large","id":1}"#;
+	assert!(http_request(req.into(), uri).with_default_timeout().await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn stop_drains_in_flight_requests_and_refuses_new_connections() {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep", |_, _| async move {
+			tokio::time::sleep(Duration::from_millis(500)).await;
+			Ok("Yawn!")
+		})
+		.unwrap();
+	let server_handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"sleep","id":1}"#;
+	let in_flight = tokio::spawn(http_request(req.into(), uri.clone()));
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	let stopped = server_handle.stop().unwrap();
+
+	let response = in_flight.with_default_timeout().await.unwrap().unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("Yawn!".to_owned()), Id::Num(1)));
+	assert!(stopped.with_default_timeout().await.unwrap().is_ok());
+
+	let req = r#"{"jsonrpc":"2.0","method":"sleep","id":2}"#;
+	assert!(http_request(req.into(), uri).with_default_timeout().await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn stop_gives_up_on_in_flight_requests_after_the_timeout() {
+	let server = HttpServerBuilder::default()
+		.graceful_shutdown_timeout(Duration::from_millis(100))
+		.build("127.0.0.1:0")
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep", |_, _| async move {
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			Ok("Yawn!")
+		})
+		.unwrap();
+	let server_handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"sleep","id":1}"#;
+	let _in_flight = tokio::spawn(http_request(req.into(), to_http_uri(addr)));
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	assert!(server_handle.stop().unwrap().with_timeout(Duration::from_secs(5)).await.unwrap().is_ok());
+}
+
 #[tokio::test]
 async fn run_forever() {
 	const TIMEOUT: Duration = Duration::from_millis(200);
@@ -405,3 +737,104 @@ async fn run_forever() {
 	server_handle.handle.take();
 	server_handle.with_timeout(TIMEOUT).await.unwrap();
 }
+
+#[tokio::test]
+async fn health_endpoint_works() {
+	let server = HttpServerBuilder::default().with_health_endpoint("system_health").build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(RpcModule::new(())).unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"system_health","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	let response: Response<Health> = serde_json::from_str(&response.body).unwrap();
+	assert!(response.result.ok);
+}
+
+#[tokio::test]
+async fn health_endpoint_conflicting_with_user_method_fails() {
+	let server = HttpServerBuilder::default().with_health_endpoint("say_hello").build("127.0.0.1:0").unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	assert!(matches!(server.start(module), Err(Error::MethodAlreadyRegistered(name)) if name == "say_hello"));
+}
+
+#[tokio::test]
+async fn tower_layers_wrap_the_server() {
+	let layers = tower::ServiceBuilder::new().map_response(|mut res: hyper::Response<hyper::Body>| {
+		res.headers_mut().insert("x-served-by", hyper::header::HeaderValue::from_static("jsonrpsee"));
+		res
+	});
+	let server = HttpServerBuilder::default().set_tower_layers(layers).build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.header.get("x-served-by").unwrap(), "jsonrpsee");
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn msgpack_requests_and_responses_are_negotiated() {
+	let (addr, _handle) = server().with_default_timeout().await.unwrap();
+	let uri = to_http_uri(addr);
+	let req = serde_json::json!({"jsonrpc": "2.0", "method": "add", "params": [1, 2, 3], "id": 1});
+
+	let send = |accept: &'static str| {
+		let body = rmp_serde::to_vec_named(&req).unwrap();
+		let request = hyper::Request::post(uri.clone())
+			.header("content-type", "application/msgpack")
+			.header("accept", accept)
+			.body(hyper::Body::from(body))
+			.unwrap();
+		async move {
+			let response = hyper::Client::new().request(request).await.unwrap();
+			let content_type = response.headers().get("content-type").unwrap().to_str().unwrap().to_owned();
+			(content_type, hyper::body::to_bytes(response.into_body()).await.unwrap())
+		}
+	};
+
+	let (content_type, body) = send("application/msgpack").with_default_timeout().await.unwrap();
+	assert_eq!(content_type, "application/msgpack");
+	let response: JsonValue = rmp_serde::from_slice(&body).unwrap();
+	assert_eq!(response, serde_json::json!({"jsonrpc": "2.0", "result": 6, "id": 1}));
+
+	// Falls back to JSON for clients that don't ask for MessagePack.
+	let (content_type, body) = send("application/json").with_default_timeout().await.unwrap();
+	assert_eq!(content_type, "application/json; charset=utf-8");
+	assert_eq!(std::str::from_utf8(&body).unwrap(), ok_response(6.into(), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn connections_are_filtered_by_ip() {
+	let _ = env_logger::try_init();
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+
+	let server = HttpServerBuilder::default()
+		.set_allowed_ips(["127.0.0.0/8"])
+		.unwrap()
+		.set_denied_ips(["10.0.0.0/8"])
+		.unwrap()
+		.build("127.0.0.1:0")
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+
+	let server = HttpServerBuilder::default().set_denied_ips(["127.0.0.1"]).unwrap().build("127.0.0.1:0").unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	assert!(http_request(req.into(), uri).with_default_timeout().await.unwrap().is_err());
+	assert!(HttpServerBuilder::default().set_denied_ips(["127.0.0.1/33"]).is_err());
+}
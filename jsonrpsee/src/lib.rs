@@ -43,9 +43,16 @@
 //! - **`client`** - Enables `http-client` and `ws-client` features.
 //! - **`server`** - Enables `http-server` and `ws-server` features.
 //! - **`full`** - Enables `client`, `server` and `macros` features.
+//! - **`arbitrary-precision`** - Enables `serde_json/arbitrary_precision` to preserve integers that
+//!   don't fit in `u64`/`i64` (such as `u128`/`i128`) when params or results travel through `serde_json::Value`.
 //! - **`async-client`** - Enables the async client without any transport.
 //! - **`client-ws-transport`** - Enables `ws` transport with TLS.
 //! - **`client-ws-transport-no-tls`** - Enables `ws` transport without TLS.
+//!
+//! Note that JavaScript clients can only represent integers up to `2^53` without loss of precision,
+//! so large integers should be encoded as strings if such clients need to be supported. The same goes for
+//! subscription IDs, which span the full `u64` range unless the server is configured with a
+//! `RandomIntegerIdProvider`.
 
 /// JSON-RPC HTTP client.
 #[cfg(feature = "jsonrpsee-http-client")]
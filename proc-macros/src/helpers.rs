@@ -181,11 +181,43 @@ pub(crate) fn extract_doc_comments(attrs: &[syn::Attribute]) -> TokenStream2 {
 	quote! ( #(#docs)* )
 }
 
+/// Joins the lines of the doc comments of an item, `None` if it's undocumented.
+pub(crate) fn doc_comment_text(attrs: &[syn::Attribute]) -> Option<String> {
+	let lines: Vec<String> = attrs
+		.iter()
+		.filter(|attr| attr.path.is_ident("doc"))
+		.filter_map(|attr| match attr.parse_meta() {
+			Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(line), .. })) => Some(line.value()),
+			_ => None,
+		})
+		.collect();
+	let text = lines.iter().map(|line| line.strip_prefix(' ').unwrap_or(line)).collect::<Vec<_>>().join("\n");
+	let text = text.trim();
+	(!text.is_empty()).then(|| text.to_owned())
+}
+
 #[cfg(test)]
 mod tests {
-	use super::is_option;
+	use super::{doc_comment_text, is_option};
 	use syn::parse_quote;
 
+	#[test]
+	fn doc_comment_text_joins_the_lines() {
+		let item: syn::TraitItemMethod = parse_quote! {
+			/// Says hello.
+			///
+			/// Twice.
+			#[method(name = "hello")]
+			fn hello(&self);
+		};
+		assert_eq!(doc_comment_text(&item.attrs).as_deref(), Some("Says hello.\n\nTwice."));
+
+		let item: syn::TraitItemMethod = parse_quote!(
+			fn hello(&self);
+		);
+		assert_eq!(doc_comment_text(&item.attrs), None);
+	}
+
 	#[test]
 	fn is_option_works() {
 		assert!(is_option(&parse_quote!(Option<T>)));
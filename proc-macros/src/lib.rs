@@ -202,6 +202,21 @@ pub(crate) mod visitor;
 ///
 /// - have input parameters or not.
 ///
+/// ### `param` attribute
+///
+/// `param` attribute can be applied to input parameters of methods and subscriptions.
+///
+/// **Arguments:**
+///
+/// - `validate`: Rust expression, as a string, that must evaluate to `true` for the call to proceed. It is
+///   checked by the server after all the parameters are decoded and may refer to any of them.
+///   If it evaluates to `false` the call is rejected with an "Invalid params" error.
+///
+/// ```ignore
+/// #[method(name = "block")]
+/// fn block(&self, #[param(validate = "height <= 1000")] height: u64) -> RpcResult<String>;
+/// ```
+///
 /// ## Full workflow example
 ///
 /// ```rust
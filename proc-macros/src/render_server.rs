@@ -112,6 +112,14 @@ impl RpcDescription {
 			}}
 		}
 
+		// Sets the doc comments of the Rust method as the description of the RPC method.
+		fn describe(rpc_name: &str, description: Option<&str>) -> TokenStream2 {
+			match description {
+				Some(text) => handle_register_result(quote!(rpc.set_method_description(#rpc_name, #text))),
+				None => TokenStream2::new(),
+			}
+		}
+
 		let methods = self
 			.methods
 			.iter()
@@ -124,7 +132,7 @@ impl RpcDescription {
 				// provided `Params` object.
 				// `params_seq` is the comma-delimited sequence of parameters we're passing to the rust function
 				// called..
-				let (parsing, params_seq) = self.render_params_decoding(&method.params);
+				let (parsing, params_seq) = self.render_params_decoding(&method.params, &method.validations);
 
 				check_name(&rpc_method_name, rust_method_name.span());
 
@@ -144,7 +152,9 @@ impl RpcDescription {
 					}
 				};
 
-				if method.signature.sig.asyncness.is_some() {
+				let description = describe(&rpc_method_name, method.description.as_deref());
+
+				let register = if method.signature.sig.asyncness.is_some() {
 					handle_register_result(quote! {
 						rpc.register_async_method(#rpc_method_name, |params, context| async move {
 							#parsing
@@ -163,7 +173,9 @@ impl RpcDescription {
 						})
 						#resources
 					})
-				}
+				};
+
+				quote!( #register #description )
 			})
 			.collect::<Vec<_>>();
 
@@ -182,7 +194,7 @@ impl RpcDescription {
 				// `parsing` is the code associated with parsing structure from the
 				// provided `Params` object.
 				// `params_seq` is the comma-delimited sequence of parameters.
-				let (parsing, params_seq) = self.render_params_decoding(&sub.params);
+				let (parsing, params_seq) = self.render_params_decoding(&sub.params, &sub.validations);
 
 				check_name(&rpc_sub_name, rust_method_name.span());
 				check_name(&rpc_unsub_name, rust_method_name.span());
@@ -195,12 +207,15 @@ impl RpcDescription {
 					None => rpc_sub_name.clone(),
 				};
 
-				handle_register_result(quote! {
+				let register = handle_register_result(quote! {
 					rpc.register_subscription(#rpc_sub_name, #rpc_notif_name, #rpc_unsub_name, |params, sink, context| {
 						#parsing
 						context.as_ref().#rust_method_name(sink, #params_seq)
 					})
-				})
+				});
+				let description = describe(&rpc_sub_name, sub.description.as_deref());
+
+				quote!( #register #description )
 			})
 			.collect::<Vec<_>>();
 
@@ -286,7 +301,11 @@ impl RpcDescription {
 		})
 	}
 
-	fn render_params_decoding(&self, params: &[(syn::PatIdent, syn::Type)]) -> (TokenStream2, TokenStream2) {
+	fn render_params_decoding(
+		&self,
+		params: &[(syn::PatIdent, syn::Type)],
+		validations: &[syn::Expr],
+	) -> (TokenStream2, TokenStream2) {
 		if params.is_empty() {
 			return (TokenStream2::default(), TokenStream2::default());
 		}
@@ -353,12 +372,27 @@ impl RpcDescription {
 			}
 		};
 
+		// Code to reject the call if any of the `#[param(validate = "...")]` checks fail.
+		let validate = {
+			let call_error = self.jrps_server_item(quote! { types::error::CallError });
+			let anyhow = self.jrps_server_item(quote! { core::__reexports::anyhow });
+
+			validations.iter().map(move |expr| {
+				quote! {
+					if !(#expr) {
+						return Err(#call_error::InvalidParams(#anyhow::anyhow!(concat!("Parameter validation failed: ", stringify!(#expr)))).into());
+					}
+				}
+			})
+		};
+
 		let parsing = quote! {
 			let (#params_fields) = if params.is_object() {
 				#decode_map
 			} else {
 				#decode_array
 			};
+			#(#validate)*
 		};
 
 		(parsing, params_fields)
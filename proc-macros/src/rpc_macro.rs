@@ -31,7 +31,7 @@ use std::borrow::Cow;
 use crate::attributes::{
 	optional, parse_param_kind, Aliases, Argument, AttributeMeta, MissingArgument, NameMapping, ParamKind, Resource,
 };
-use crate::helpers::extract_doc_comments;
+use crate::helpers::{doc_comment_text, extract_doc_comments};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::spanned::Spanned;
@@ -42,8 +42,12 @@ pub struct RpcMethod {
 	pub name: String,
 	pub blocking: bool,
 	pub docs: TokenStream2,
+	/// Text of the doc comments, used as the description of the method.
+	pub description: Option<String>,
 	pub deprecated: TokenStream2,
 	pub params: Vec<(syn::PatIdent, syn::Type)>,
+	/// Expressions from `#[param(validate = "...")]` that must hold before the method is called.
+	pub validations: Vec<syn::Expr>,
 	pub param_kind: ParamKind,
 	pub returns: Option<syn::Type>,
 	pub signature: syn::TraitItemMethod,
@@ -61,9 +65,11 @@ impl RpcMethod {
 		let name = name?.string()?;
 		let param_kind = parse_param_kind(param_kind)?;
 		let resources = optional(resources, Argument::group)?.unwrap_or_default();
+		let validations = parse_param_validations(&mut method.sig)?;
 
 		let sig = method.sig.clone();
 		let docs = extract_doc_comments(&method.attrs);
+		let description = doc_comment_text(&method.attrs);
 		let deprecated = match find_attr(&method.attrs, "deprecated") {
 			Some(attr) => quote!(#attr),
 			None => quote!(),
@@ -105,10 +111,12 @@ impl RpcMethod {
 			blocking,
 			name,
 			params,
+			validations,
 			param_kind,
 			returns,
 			signature: method,
 			docs,
+			description,
 			resources,
 			deprecated,
 		})
@@ -126,8 +134,12 @@ pub struct RpcSubscription {
 	/// If no override is provided, the subscription method name is used.
 	pub notif_name_override: Option<String>,
 	pub docs: TokenStream2,
+	/// Text of the doc comments, used as the description of the subscription.
+	pub description: Option<String>,
 	pub unsubscribe: String,
 	pub params: Vec<(syn::PatIdent, syn::Type)>,
+	/// Expressions from `#[param(validate = "...")]` that must hold before the subscription is set up.
+	pub validations: Vec<syn::Expr>,
 	pub param_kind: ParamKind,
 	pub item: syn::Type,
 	pub signature: syn::TraitItemMethod,
@@ -147,9 +159,11 @@ impl RpcSubscription {
 		let item = item?.value()?;
 		let param_kind = parse_param_kind(param_kind)?;
 		let unsubscribe_aliases = parse_aliases(unsubscribe_aliases)?;
+		let validations = parse_param_validations(&mut sub.sig)?;
 
 		let sig = sub.sig.clone();
 		let docs = extract_doc_comments(&sub.attrs);
+		let description = doc_comment_text(&sub.attrs);
 		let unsubscribe = match parse_subscribe(unsubscribe)? {
 			Some(unsub) => unsub,
 			None => build_unsubscribe_method(&name).unwrap_or_else(||
@@ -178,11 +192,13 @@ impl RpcSubscription {
 			unsubscribe,
 			unsubscribe_aliases,
 			params,
+			validations,
 			param_kind,
 			item,
 			signature: sub,
 			aliases,
 			docs,
+			description,
 		})
 	}
 }
@@ -346,6 +362,30 @@ fn parse_subscribe(arg: Result<Argument, MissingArgument>) -> syn::Result<Option
 	Ok(unsub)
 }
 
+/// Strips the `#[param(...)]` attributes from the method arguments and returns the
+/// validation expressions declared with them.
+fn parse_param_validations(sig: &mut syn::Signature) -> syn::Result<Vec<syn::Expr>> {
+	let mut validations = Vec::new();
+
+	for arg in sig.inputs.iter_mut() {
+		let arg = match arg {
+			syn::FnArg::Typed(arg) => arg,
+			syn::FnArg::Receiver(_) => continue,
+		};
+
+		let (param_attrs, other_attrs): (Vec<_>, Vec<_>) =
+			std::mem::take(&mut arg.attrs).into_iter().partition(|attr| attr.path.is_ident("param"));
+		arg.attrs = other_attrs;
+
+		for attr in param_attrs {
+			let [validate] = AttributeMeta::parse(attr)?.retain(["validate"])?;
+			validations.push(validate?.value::<syn::LitStr>()?.parse()?);
+		}
+	}
+
+	Ok(validations)
+}
+
 fn find_attr<'a>(attrs: &'a [Attribute], ident: &str) -> Option<&'a Attribute> {
 	attrs.iter().find(|a| a.path.is_ident(ident))
 }
@@ -0,0 +1,58 @@
+//! Example of using `#[param(validate = ...)]` to reject out-of-range parameters on the server.
+
+use std::net::SocketAddr;
+
+use jsonrpsee::core::{async_trait, Error, RpcResult};
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::ws_client::*;
+use jsonrpsee::ws_server::WsServerBuilder;
+
+#[rpc(client, server)]
+pub trait Rpc {
+	#[method(name = "block")]
+	async fn block(&self, #[param(validate = "height <= 1000")] height: u64) -> RpcResult<u64>;
+
+	#[method(name = "range", param_kind = map)]
+	fn range(&self, #[param(validate = "from <= to")] from: u64, to: u64) -> RpcResult<u64>;
+}
+
+pub struct RpcServerImpl;
+
+#[async_trait]
+impl RpcServer for RpcServerImpl {
+	async fn block(&self, height: u64) -> RpcResult<u64> {
+		assert!(height <= 1000);
+		Ok(height)
+	}
+
+	fn range(&self, from: u64, to: u64) -> RpcResult<u64> {
+		assert!(from <= to);
+		Ok(to - from)
+	}
+}
+
+pub async fn websocket_server() -> SocketAddr {
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+
+	server.start(RpcServerImpl.into_rpc()).unwrap();
+
+	addr
+}
+
+fn is_invalid_params<T>(res: Result<T, Error>) -> bool {
+	matches!(res, Err(Error::Request(e)) if e.contains("-32602"))
+}
+
+#[tokio::main]
+async fn main() {
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(client.block(1000).await.unwrap(), 1000);
+	assert!(is_invalid_params(client.block(1001).await));
+
+	assert_eq!(client.range(1, 3).await.unwrap(), 2);
+	assert!(is_invalid_params(client.range(3, 1).await));
+}
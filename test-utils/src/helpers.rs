@@ -27,7 +27,7 @@
 use std::convert::Infallible;
 use std::net::SocketAddr;
 
-use crate::mocks::{Body, HttpResponse, Id, Uri};
+use crate::mocks::{Body, HttpResponse, Id, StatusCode, Uri};
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Request, Response, Server};
 use serde::Serialize;
@@ -128,6 +128,13 @@ pub fn server_error(id: Id) -> String {
 	)
 }
 
+pub fn server_is_busy(id: Id) -> String {
+	format!(
+		r#"{{"jsonrpc":"2.0","error":{{"code":-32604,"message":"Server is busy, try again later"}},"id":{}}}"#,
+		serde_json::to_string(&id).unwrap()
+	)
+}
+
 /// Hardcoded server response when a client initiates a new subscription.
 ///
 /// NOTE: works only for one subscription because the subscription ID is hardcoded.
@@ -160,7 +167,7 @@ pub async fn http_request(body: Body, uri: Uri) -> Result<HttpResponse, String>
 	let res = client.request(r).await.map_err(|e| format!("{:?}", e))?;
 
 	let (parts, body) = res.into_parts();
-	let bytes = hyper::body::to_bytes(body).await.unwrap();
+	let bytes = hyper::body::to_bytes(body).await.map_err(|e| format!("{:?}", e))?;
 
 	Ok(HttpResponse { status: parts.status, header: parts.headers, body: String::from_utf8(bytes.to_vec()).unwrap() })
 }
@@ -194,3 +201,63 @@ pub async fn http_server_with_hardcoded_response(response: String) -> SocketAddr
 
 	rx.await.unwrap()
 }
+
+/// Spawn HTTP server that responds with a hardcoded status code and response.
+//
+// NOTE: This must be spawned on tokio because hyper only works with tokio.
+pub async fn http_server_with_hardcoded_status(status: StatusCode, response: String) -> SocketAddr {
+	let make_service = make_service_fn(move |_| {
+		let response = response.clone();
+		async move {
+			Ok::<_, Infallible>(service_fn(move |_req: Request<Body>| {
+				let response = response.clone();
+				async move {
+					let mut response = Response::new(hyper::Body::from(response));
+					*response.status_mut() = status;
+					Ok::<_, Infallible>(response)
+				}
+			}))
+		}
+	});
+
+	let (tx, rx) = futures_channel::oneshot::channel::<SocketAddr>();
+
+	tokio::spawn(async {
+		let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+		let server = Server::bind(&addr).serve(make_service);
+		tx.send(server.local_addr()).unwrap();
+		server.await.unwrap()
+	});
+
+	rx.await.unwrap()
+}
+
+/// Spawn HTTP server that answers each call with the value of the `header` request header, or `null` without it.
+//
+// NOTE: This must be spawned on tokio because hyper only works with tokio.
+pub async fn http_server_echoing_header(header: &'static str) -> SocketAddr {
+	async fn process_request(req: Request<Body>, header: &'static str) -> Result<Response<Body>, Infallible> {
+		let value = req.headers().get(header).map(|v| Value::String(v.to_str().unwrap().to_owned()));
+		let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+		let call: Value = serde_json::from_slice(&body).unwrap();
+		let response = serde_json::json!({ "jsonrpc": "2.0", "result": value, "id": call["id"] });
+		Ok(Response::new(hyper::Body::from(response.to_string())))
+	}
+
+	let make_service = make_service_fn(move |_| async move {
+		Ok::<_, Infallible>(service_fn(move |req| async move {
+			Ok::<_, Infallible>(process_request(req, header).await.unwrap())
+		}))
+	});
+
+	let (tx, rx) = futures_channel::oneshot::channel::<SocketAddr>();
+
+	tokio::spawn(async move {
+		let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+		let server = Server::bind(&addr).serve(make_service);
+		tx.send(server.local_addr()).unwrap();
+		server.await.unwrap()
+	});
+
+	rx.await.unwrap()
+}
@@ -31,7 +31,7 @@ use std::time::Duration;
 use futures_channel::mpsc::{self, Receiver, Sender};
 use futures_channel::oneshot;
 use futures_util::future::FutureExt;
-use futures_util::io::{BufReader, BufWriter};
+use futures_util::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
 use futures_util::sink::SinkExt;
 use futures_util::stream::{self, StreamExt};
 use futures_util::{pin_mut, select};
@@ -102,12 +102,23 @@ impl From<io::Error> for WebSocketTestError {
 
 impl WebSocketTestClient {
 	pub async fn new(url: SocketAddr) -> Result<Self, WebSocketTestError> {
+		Self::with_protocols(url, &[]).await.map(|(client, _)| client)
+	}
+
+	/// Connect requesting the given subprotocols, returns the client with the subprotocol selected by the server.
+	pub async fn with_protocols(
+		url: SocketAddr,
+		protocols: &[&str],
+	) -> Result<(Self, Option<String>), WebSocketTestError> {
 		let socket = TcpStream::connect(url).await?;
 		let mut client = handshake::Client::new(BufReader::new(BufWriter::new(socket.compat())), "test-client", "/");
+		for &protocol in protocols {
+			client.add_protocol(protocol);
+		}
 		match client.handshake().await {
-			Ok(handshake::ServerResponse::Accepted { .. }) => {
+			Ok(handshake::ServerResponse::Accepted { protocol }) => {
 				let (tx, rx) = client.into_builder().finish();
-				Ok(Self { tx, rx })
+				Ok((Self { tx, rx }, protocol))
 			}
 			Ok(handshake::ServerResponse::Redirect { .. }) => Err(WebSocketTestError::Redirect),
 			Ok(handshake::ServerResponse::Rejected { status_code }) => {
@@ -133,11 +144,104 @@ impl WebSocketTestClient {
 		String::from_utf8(data).map_err(Into::into)
 	}
 
+	pub async fn send(&mut self, msg: impl AsRef<str>) -> Result<(), Error> {
+		self.tx.send_text(msg).await?;
+		self.tx.flush().await.map_err(Into::into)
+	}
+
+	pub async fn receive(&mut self) -> Result<String, Error> {
+		let mut data = Vec::new();
+		self.rx.receive_data(&mut data).await?;
+		String::from_utf8(data).map_err(Into::into)
+	}
+
+	/// Send a ping with `payload` and return the payload of the pong answering it, skipping data messages.
+	pub async fn ping(&mut self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+		let payload = soketto::data::ByteSlice125::try_from(payload).map_err(|_| "Ping payload is too large")?;
+		self.tx.send_ping(payload).await?;
+		self.tx.flush().await?;
+		let mut data = Vec::new();
+		loop {
+			match self.rx.receive(&mut data).await? {
+				soketto::Incoming::Pong(pong) => return Ok(pong.to_vec()),
+				soketto::Incoming::Data(_) => data.clear(),
+				soketto::Incoming::Closed(_) => return Err("Connection closed before the pong".into()),
+			}
+		}
+	}
+
 	pub async fn close(&mut self) -> Result<(), Error> {
 		self.tx.close().await.map_err(Into::into)
 	}
 }
 
+/// Opcode of WebSocket text frames.
+pub const TEXT_OPCODE: u8 = 0x1;
+/// Opcode of WebSocket ping frames.
+pub const PING_OPCODE: u8 = 0x9;
+
+/// WebSocket client reading the raw frames sent by the server, including the pings that soketto answers
+/// without returning them.
+pub struct RawWebSocketTestClient {
+	socket: BufReader<BufWriter<Compat<TcpStream>>>,
+}
+
+impl RawWebSocketTestClient {
+	pub async fn new(url: SocketAddr) -> Result<Self, WebSocketTestError> {
+		let socket = TcpStream::connect(url).await?;
+		let mut client = handshake::Client::new(BufReader::new(BufWriter::new(socket.compat())), "test-client", "/");
+		match client.handshake().await {
+			Ok(handshake::ServerResponse::Accepted { .. }) => Ok(Self { socket: client.into_inner() }),
+			Ok(handshake::ServerResponse::Redirect { .. }) => Err(WebSocketTestError::Redirect),
+			Ok(handshake::ServerResponse::Rejected { status_code }) => {
+				Err(WebSocketTestError::RejectedWithStatusCode(status_code))
+			}
+			Err(err) => Err(WebSocketTestError::Soketto(err)),
+		}
+	}
+
+	/// Send `msg` in a single text frame, masked with an all-zero key.
+	pub async fn send_text(&mut self, msg: &str) -> io::Result<()> {
+		let len = msg.len();
+		let mut frame = vec![0x80 | TEXT_OPCODE];
+		if len < 126 {
+			frame.push(0x80 | len as u8);
+		} else if len <= u16::MAX as usize {
+			frame.push(0x80 | 126);
+			frame.extend_from_slice(&(len as u16).to_be_bytes());
+		} else {
+			frame.push(0x80 | 127);
+			frame.extend_from_slice(&(len as u64).to_be_bytes());
+		}
+		frame.extend_from_slice(&[0; 4]);
+		frame.extend_from_slice(msg.as_bytes());
+		self.socket.write_all(&frame).await?;
+		self.socket.flush().await
+	}
+
+	/// Receive the opcode and payload of the next frame, which the server doesn't mask.
+	pub async fn receive_frame(&mut self) -> io::Result<(u8, Vec<u8>)> {
+		let mut header = [0; 2];
+		self.socket.read_exact(&mut header).await?;
+		let len = match header[1] & 0x7F {
+			126 => {
+				let mut len = [0; 2];
+				self.socket.read_exact(&mut len).await?;
+				u16::from_be_bytes(len) as usize
+			}
+			127 => {
+				let mut len = [0; 8];
+				self.socket.read_exact(&mut len).await?;
+				u64::from_be_bytes(len) as usize
+			}
+			len => len as usize,
+		};
+		let mut payload = vec![0; len];
+		self.socket.read_exact(&mut payload).await?;
+		Ok((header[0] & 0x0F, payload))
+	}
+}
+
 #[derive(Debug, Clone)]
 pub enum ServerMode {
 	// Send out a hardcoded response on every connection.
@@ -212,6 +316,49 @@ impl WebSocketTestServer {
 	}
 }
 
+/// Spawn a WebSocket server that closes every connection with the close `code` and `reason` once the client sent
+/// its first message.
+pub async fn ws_server_closing_with(code: u16, reason: &'static str) -> SocketAddr {
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let local_addr = listener.local_addr().unwrap();
+
+	tokio::spawn(async move {
+		while let Ok((socket, _)) = listener.accept().await {
+			tokio::spawn(async move {
+				let mut socket = socket.compat();
+				let mut server = Server::new(&mut socket);
+				let key = match server.receive_request().await {
+					Ok(req) => req.key(),
+					Err(_) => return,
+				};
+				if server.send_response(&Response::Accept { key, protocol: None }).await.is_err() {
+					return;
+				}
+				drop(server);
+
+				// Wait for the first message of the client, such that the close frame answers it.
+				let mut buf = [0; 1024];
+				if !matches!(socket.read(&mut buf).await, Ok(n) if n > 0) {
+					return;
+				}
+
+				// Unmasked close frame, its payload is the code followed by the reason.
+				let mut frame = vec![0x88, 2 + reason.len() as u8];
+				frame.extend_from_slice(&code.to_be_bytes());
+				frame.extend_from_slice(reason.as_bytes());
+				if socket.write_all(&frame).await.is_err() || socket.flush().await.is_err() {
+					return;
+				}
+
+				// Wait for the client to hang up.
+				while matches!(socket.read(&mut buf).await, Ok(n) if n > 0) {}
+			});
+		}
+	});
+
+	local_addr
+}
+
 async fn server_backend(listener: tokio::net::TcpListener, mut exit: Receiver<()>, mode: ServerMode) {
 	let mut connections = Vec::new();
 
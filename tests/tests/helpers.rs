@@ -33,11 +33,22 @@ use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
 use jsonrpsee::RpcModule;
 
 pub async fn websocket_server_with_subscription() -> (SocketAddr, WsServerHandle) {
-	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	websocket_server_with_subscription_and_builder(WsServerBuilder::default()).await
+}
+
+pub async fn websocket_server_with_subscription_and_builder(builder: WsServerBuilder) -> (SocketAddr, WsServerHandle) {
+	let server = builder.build("127.0.0.1:0").await.unwrap();
 
 	let mut module = RpcModule::new(());
 	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
 
+	module
+		.register_async_method("slow_hello", |_, _| async {
+			tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+			Ok("hello")
+		})
+		.unwrap();
+
 	module
 		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
 			std::thread::spawn(move || loop {
@@ -109,6 +120,12 @@ pub async fn websocket_server() -> SocketAddr {
 		})
 		.unwrap();
 
+	module
+		.register_method_with_connection("remote_addr", |_, _, conn| {
+			Ok(conn.remote_addr.expect("WebSocket connections have a remote address; qed"))
+		})
+		.unwrap();
+
 	let addr = server.local_addr().unwrap();
 
 	server.start(module).unwrap();
@@ -126,6 +143,11 @@ pub async fn http_server_with_access_control(acl: AccessControl) -> (SocketAddr,
 	let addr = server.local_addr().unwrap();
 	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
 	module.register_method("notif", |_, _| Ok("")).unwrap();
+	module
+		.register_async_method_with_connection("remote_addr", |_, _, conn| async move {
+			Ok(conn.remote_addr.expect("HTTP connections have a remote address; qed"))
+		})
+		.unwrap();
 
 	let handle = server.start(module).unwrap();
 	(addr, handle)
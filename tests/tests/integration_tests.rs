@@ -27,17 +27,24 @@
 #![cfg(test)]
 #![allow(clippy::blacklisted_name)]
 
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use futures::TryStreamExt;
-use helpers::{http_server, http_server_with_access_control, websocket_server, websocket_server_with_subscription};
-use jsonrpsee::core::client::{ClientT, IdKind, Subscription, SubscriptionClientT};
+use helpers::{
+	http_server, http_server_with_access_control, websocket_server, websocket_server_with_subscription,
+	websocket_server_with_subscription_and_builder,
+};
+use jsonrpsee::client_transport::ws::WsHandshakeError;
+use jsonrpsee::core::client::{ClientT, ErrorWithData, IdKind, Subscription, SubscriptionClientT};
 use jsonrpsee::core::error::{SubscriptionClosed, SubscriptionClosedReason};
 use jsonrpsee::core::{Error, JsonValue};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::rpc_params;
 use jsonrpsee::ws_client::WsClientBuilder;
+use jsonrpsee::ws_server::WsServerBuilder;
+use jsonrpsee_test_utils::TimeoutFutureExt;
 use tokio::time::interval;
 use tokio_stream::wrappers::IntervalStream;
 
@@ -60,6 +67,257 @@ async fn ws_subscription_works() {
 	}
 }
 
+#[tokio::test]
+async fn ws_raw_subscription_works() {
+	let (server_addr, _) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut hello_sub = client.subscribe_raw("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	let mut foo_sub = client.subscribe_raw("subscribe_foo", None, "unsubscribe_foo").await.unwrap();
+
+	for _ in 0..3 {
+		let hello = hello_sub.next().await.unwrap().unwrap();
+		let foo = foo_sub.next().await.unwrap().unwrap();
+		assert_eq!(hello.get(), r#""hello from subscription""#);
+		assert_eq!(foo.get(), "1337");
+	}
+}
+
+#[tokio::test]
+async fn ws_client_clones_share_the_connection_until_the_last_one_is_dropped() {
+	use jsonrpsee::ws_server::{ConnectionStates, RpcModule, WsServerBuilder};
+
+	let connections = ConnectionStates::new();
+	let server = WsServerBuilder::default()
+		.set_connection_state(connections.clone(), |_| ())
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let clones = vec![client.clone(), client.clone()];
+	assert_eq!(client.strong_count(), 3);
+	assert_eq!(connections.len(), 1);
+
+	drop(client);
+	let last = clones[1].clone();
+	drop(clones);
+	assert_eq!(last.strong_count(), 1);
+	let response: String = last.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
+	assert!(last.is_connected());
+	assert_eq!(connections.len(), 1);
+
+	drop(last);
+	for _ in 0..50 {
+		if connections.is_empty() {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert!(connections.is_empty(), "the server must see the connection closed");
+}
+
+#[tokio::test]
+async fn ws_client_reports_pending_requests_and_active_subscriptions() {
+	let (server_addr, _) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = Arc::new(WsClientBuilder::default().build(&server_url).await.unwrap());
+	assert_eq!(client.pending_requests(), 0);
+	assert_eq!(client.active_subscriptions(), 0);
+
+	let _hello_sub: Subscription<String> =
+		client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	let _foo_sub: Subscription<u64> = client.subscribe("subscribe_foo", None, "unsubscribe_foo").await.unwrap();
+	assert_eq!(client.active_subscriptions(), 2);
+	assert_eq!(client.pending_requests(), 0);
+
+	let c = client.clone();
+	let slow = tokio::spawn(async move { c.request::<String>("slow_hello", None).await });
+
+	// The request is counted once the background task has sent it.
+	while client.pending_requests() == 0 {
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert_eq!(client.pending_requests(), 1);
+	assert_eq!(client.active_subscriptions(), 2);
+
+	assert_eq!(slow.await.unwrap().unwrap(), "hello");
+	assert_eq!(client.pending_requests(), 0);
+}
+
+#[tokio::test]
+async fn ws_chunked_subscription_results_are_reassembled() {
+	use jsonrpsee::types::SubscriptionChunk;
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_big", "big", "unsubscribe_big", |_, mut sink, _| {
+			// 22 bytes of JSON, the two byte `é` doesn't fit into the first chunk and starts the second one.
+			sink.send_chunked(&("x".repeat(8) + "é" + &"x".repeat(10)), 10).unwrap();
+			Ok(())
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let expected = "x".repeat(8) + "é" + &"x".repeat(10);
+
+	let mut raw: Subscription<SubscriptionChunk<'static>> =
+		client.subscribe("subscribe_big", None, "unsubscribe_big").await.unwrap();
+	let mut chunks = Vec::new();
+	for _ in 0..3 {
+		chunks.push(raw.next().await.unwrap().unwrap());
+	}
+	assert!(chunks.iter().enumerate().all(|(i, c)| c.seq == i as u32 && c.total == 3));
+	assert_eq!(chunks.iter().map(|c| c.data.as_ref()).collect::<String>(), format!("\"{}\"", expected));
+
+	let mut sub: Subscription<String> = client.subscribe("subscribe_big", None, "unsubscribe_big").await.unwrap();
+	let big: String = sub.next_chunked().await.unwrap().unwrap();
+	assert_eq!(big, expected);
+}
+
+#[tokio::test]
+async fn ws_dropped_requests_are_cancelled_on_the_server() {
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+	use std::sync::atomic::{AtomicBool, Ordering};
+
+	// Records whether the method future was dropped before it completed.
+	struct DropGuard(Arc<AtomicBool>);
+	impl Drop for DropGuard {
+		fn drop(&mut self) {
+			self.0.store(true, Ordering::SeqCst);
+		}
+	}
+
+	let server = WsServerBuilder::default().enable_request_cancellation(true).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let dropped = Arc::new(AtomicBool::new(false));
+	let completed = Arc::new(AtomicBool::new(false));
+	let mut module = RpcModule::new((dropped.clone(), completed.clone()));
+	module
+		.register_async_method("expensive", |_, ctx| async move {
+			let _guard = DropGuard(ctx.0.clone());
+			tokio::time::sleep(Duration::from_secs(5)).await;
+			ctx.1.store(true, Ordering::SeqCst);
+			Ok("done")
+		})
+		.unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().cancel_dropped_requests(true).build(&server_url).await.unwrap();
+	let call = client.request::<String>("expensive", None);
+	assert!(tokio::time::timeout(Duration::from_millis(100), call).await.is_err());
+
+	for _ in 0..50 {
+		if dropped.load(Ordering::SeqCst) {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert!(dropped.load(Ordering::SeqCst), "the server must stop processing the cancelled call");
+	assert!(!completed.load(Ordering::SeqCst));
+	assert_eq!(client.pending_requests(), 0);
+
+	// The connection is still usable.
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn ws_closed_subscription_is_forgotten_without_unsubscribing() {
+	use jsonrpsee::core::server::rpc_module::SubscriptionEvent;
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let unsubscribed = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, mut sink, _| {
+			std::thread::spawn(move || {
+				while sink.send(&"hello").is_ok() {
+					std::thread::sleep(Duration::from_millis(10));
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+	let counter = unsubscribed.clone();
+	module.on_subscription_event(move |event| {
+		if let SubscriptionEvent::Unsubscribed { .. } = event {
+			counter.fetch_add(1, Ordering::SeqCst);
+		}
+	});
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+	assert_eq!(client.active_subscriptions(), 1);
+
+	sub.close();
+	for _ in 0..50 {
+		if client.active_subscriptions() == 0 {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert_eq!(client.active_subscriptions(), 0);
+
+	// The server keeps sending, the notifications are ignored by the client.
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert_eq!(unsubscribed.load(Ordering::SeqCst), 0);
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn ws_idle_subscriptions_are_reported_unsubscribed_when_the_connection_closes() {
+	use jsonrpsee::core::server::rpc_module::SubscriptionEvent;
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module
+		.register_subscription("subscribe_idle", "idle", "unsubscribe_idle", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	// The hook looks the subscriptions up again, which must not deadlock on the registry lock.
+	let closer = module.subscription_closer();
+	let remaining: Arc<std::sync::Mutex<Vec<usize>>> = Default::default();
+	let recorded = remaining.clone();
+	module.on_subscription_event(move |event| {
+		if let SubscriptionEvent::Unsubscribed { conn_id, .. } = event {
+			recorded.lock().unwrap().push(closer.subscriptions_for_connection(conn_id).len());
+		}
+	});
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let _sub: Subscription<String> = client.subscribe("subscribe_idle", None, "unsubscribe_idle").await.unwrap();
+	drop(client);
+
+	for _ in 0..50 {
+		if !remaining.lock().unwrap().is_empty() {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert_eq!(*remaining.lock().unwrap(), vec![0]);
+}
+
 #[tokio::test]
 async fn ws_subscription_with_input_works() {
 	let (server_addr, _) = websocket_server_with_subscription().await;
@@ -83,6 +341,24 @@ async fn ws_method_call_works() {
 	assert_eq!(&response, "hello");
 }
 
+#[tokio::test]
+async fn ws_unknown_methods_are_reported_as_method_not_found() {
+	let (server_addr, _) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let err = client.request::<String>("say_goodbye", None).await.unwrap_err();
+	assert!(matches!(err, Error::RemoteMethodNotFound { method, .. } if method == "say_goodbye"));
+	let err = client.subscribe::<String>("subscribe_goodbye", None, "unsubscribe_goodbye").await.unwrap_err();
+	assert!(matches!(err, Error::RemoteMethodNotFound { method, .. } if method == "subscribe_goodbye"));
+	let batch = vec![("say_hello", None), ("say_goodbye", None)];
+	let err = client.batch_request::<String>(batch).await.unwrap_err();
+	assert!(matches!(err, Error::RemoteMethodNotFound { method, .. } if method == "say_goodbye"));
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+}
+
 #[tokio::test]
 async fn ws_method_call_str_id_works() {
 	let server_addr = websocket_server().await;
@@ -101,6 +377,37 @@ async fn http_method_call_works() {
 	assert_eq!(&response, "hello");
 }
 
+#[tokio::test]
+async fn http_unknown_methods_are_reported_as_method_not_found() {
+	let (server_addr, _handle) = http_server().await;
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let err = client.request::<String>("say_goodbye", None).await.unwrap_err();
+	assert!(matches!(err, Error::RemoteMethodNotFound { method, .. } if method == "say_goodbye"));
+	let batch = vec![("say_hello", None), ("say_goodbye", None)];
+	let err = client.batch_request::<String>(batch).await.unwrap_err();
+	assert!(matches!(err, Error::RemoteMethodNotFound { method, .. } if method == "say_goodbye"));
+
+	let err: ErrorWithData<()> = client.request::<String>("say_goodbye", None).await.unwrap_err().into();
+	assert!(matches!(err, ErrorWithData::Call { code: -32601, .. }), "{:?}", err);
+}
+
+#[tokio::test]
+async fn http_client_reuses_idle_connections() {
+	let (server_addr, _handle) = http_server().await;
+	let uri = format!("http://{}", server_addr);
+
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let first: SocketAddr = client.request("remote_addr", None).await.unwrap();
+	let second: SocketAddr = client.request("remote_addr", None).await.unwrap();
+	assert_eq!(first, second);
+
+	let client = HttpClientBuilder::default().max_idle_connections(0).build(&uri).unwrap();
+	let first: SocketAddr = client.request("remote_addr", None).await.unwrap();
+	let second: SocketAddr = client.request("remote_addr", None).await.unwrap();
+	assert_ne!(first, second);
+}
+
 #[tokio::test]
 async fn http_method_call_str_id_works() {
 	let (server_addr, _handle) = http_server().await;
@@ -139,6 +446,41 @@ async fn ws_subscription_several_clients() {
 	}
 }
 
+#[tokio::test]
+async fn ws_subscription_several_clients_over_max_connections() {
+	let (server_addr, _) =
+		websocket_server_with_subscription_and_builder(WsServerBuilder::default().max_connections(10)).await;
+	let server_url = format!("ws://{}", server_addr);
+
+	let mut clients = Vec::with_capacity(10);
+	for _ in 0..10 {
+		let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+		let hello_sub: Subscription<JsonValue> =
+			client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+		clients.push((client, hello_sub))
+	}
+
+	// The eleventh client is rejected at the handshake.
+	let err = match WsClientBuilder::default().build(&server_url).await {
+		Err(Error::Transport(err)) => err,
+		other => panic!("Expected a transport error; got {:?}", other.map(|_| ())),
+	};
+	assert!(matches!(err.downcast_ref::<WsHandshakeError>(), Some(WsHandshakeError::Rejected { status_code: 429 })));
+
+	// The connected clients are not affected.
+	for (client, hello_sub) in &mut clients {
+		assert!(client.is_connected());
+		assert_eq!(hello_sub.next().await.unwrap().unwrap(), "hello from subscription");
+	}
+
+	// Once a client leaves, a new one can connect.
+	drop(clients.pop());
+	// Give the server some time to notice the disconnection.
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+}
+
 #[tokio::test]
 async fn ws_subscription_several_clients_with_drop() {
 	let (server_addr, _) = websocket_server_with_subscription().await;
@@ -268,12 +610,91 @@ async fn wss_works() {
 	assert_eq!(&response, "Kusama");
 }
 
+/// Accepts one connection and returns the first bytes sent on it, which is the TLS ClientHello for TLS clients.
+async fn first_bytes_sent_to_server() -> (SocketAddr, tokio::task::JoinHandle<Vec<u8>>) {
+	use tokio::io::AsyncReadExt;
+
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	let bytes = tokio::spawn(async move {
+		let (mut socket, _) = listener.accept().await.unwrap();
+		let mut buf = vec![0; 4096];
+		let len = socket.read(&mut buf).await.unwrap();
+		buf.truncate(len);
+		buf
+	});
+	(addr, bytes)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+	haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[tokio::test]
+async fn wss_sends_the_overridden_tls_server_name() {
+	let (addr, client_hello) = first_bytes_sent_to_server().await;
+	let url = format!("wss://{}", addr);
+	let client = WsClientBuilder::default()
+		.tls_server_name("rpc.example.com")
+		.connection_timeout(Duration::from_millis(500))
+		.build(&url);
+	assert!(client.await.is_err());
+	assert!(contains(&client_hello.await.unwrap(), b"rpc.example.com"));
+}
+
+#[tokio::test]
+async fn https_sends_the_overridden_tls_server_name() {
+	let (addr, client_hello) = first_bytes_sent_to_server().await;
+	let url = format!("https://{}", addr);
+	let client = HttpClientBuilder::default()
+		.tls_server_name("rpc.example.com")
+		.request_timeout(Duration::from_millis(500))
+		.build(&url)
+		.unwrap();
+	assert!(client.request::<String>("say_hello", None).await.is_err());
+	assert!(contains(&client_hello.await.unwrap(), b"rpc.example.com"));
+}
+
 #[tokio::test]
 async fn ws_with_non_ascii_url_doesnt_hang_or_panic() {
 	let err = WsClientBuilder::default().build("wss://♥♥♥♥♥♥∀∂").await;
 	assert!(matches!(err, Err(Error::Transport(_))));
 }
 
+#[tokio::test]
+async fn ws_client_to_http_server_fails_with_rejected_status() {
+	let (server_addr, _handle) = http_server().await;
+	let server_url = format!("ws://{}", server_addr);
+
+	let err = match WsClientBuilder::default().build(&server_url).await {
+		Err(Error::Transport(err)) => err,
+		other => panic!("Expected a transport error; got {:?}", other.map(|_| ())),
+	};
+	assert!(matches!(err.downcast_ref::<WsHandshakeError>(), Some(WsHandshakeError::Rejected { status_code: 405 })));
+}
+
+#[tokio::test]
+async fn ws_client_to_http2_only_server_fails_with_unsupported_protocol() {
+	use tokio::io::AsyncWriteExt;
+
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let server_addr = listener.local_addr().unwrap();
+	tokio::spawn(async move {
+		let (mut socket, _) = listener.accept().await.unwrap();
+		// HTTP/2 servers start with an empty SETTINGS frame and keep the connection open.
+		socket.write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0]).await.unwrap();
+		tokio::time::sleep(Duration::from_secs(60)).await;
+	});
+
+	let url = format!("ws://{}", server_addr);
+	let build = WsClientBuilder::default().build(&url);
+	let err = match tokio::time::timeout(Duration::from_secs(5), build).await.expect("handshake must not hang") {
+		Err(Error::Transport(err)) => err,
+		other => panic!("Expected a transport error; got {:?}", other.map(|_| ())),
+	};
+	assert!(matches!(err.downcast_ref::<WsHandshakeError>(), Some(WsHandshakeError::UnsupportedProtocol(_))));
+}
+
 #[tokio::test]
 async fn http_with_non_ascii_url_doesnt_hang_or_panic() {
 	let err = HttpClientBuilder::default().build("http://♥♥♥♥♥♥∀∂");
@@ -486,6 +907,26 @@ async fn ws_batch_works() {
 	assert_eq!(responses, vec!["hello".to_string(), "hello".to_string()]);
 }
 
+#[tokio::test]
+async fn ws_method_can_read_remote_addr() {
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let remote_addr: SocketAddr = client.request("remote_addr", None).await.unwrap();
+	assert_eq!(remote_addr.ip(), server_addr.ip());
+	assert_ne!(remote_addr.port(), server_addr.port());
+}
+
+#[tokio::test]
+async fn http_method_can_read_remote_addr() {
+	let (server_addr, _handle) = http_server().await;
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let remote_addr: SocketAddr = client.request("remote_addr", None).await.unwrap();
+	assert_eq!(remote_addr.ip(), server_addr.ip());
+	assert_ne!(remote_addr.port(), server_addr.port());
+}
+
 #[tokio::test]
 async fn http_unsupported_methods_dont_work() {
 	use hyper::{Body, Client, Method, Request};
@@ -622,3 +1063,225 @@ fn comma_separated_header_values(headers: &hyper::HeaderMap, header: &str) -> Ve
 		.map(|header| header.to_ascii_lowercase())
 		.collect()
 }
+
+#[tokio::test]
+async fn client_over_in_memory_transport() {
+	use futures::channel::mpsc;
+	use futures::StreamExt;
+	use jsonrpsee::core::async_trait;
+	use jsonrpsee::core::client::{Client, TransportReceiverT, TransportSenderT};
+	use jsonrpsee::RpcModule;
+
+	struct Sender(mpsc::UnboundedSender<String>);
+
+	#[async_trait]
+	impl TransportSenderT for Sender {
+		type Error = mpsc::TrySendError<String>;
+
+		async fn send(&mut self, msg: String) -> Result<(), Self::Error> {
+			self.0.unbounded_send(msg)
+		}
+	}
+
+	struct Receiver(mpsc::UnboundedReceiver<String>);
+
+	#[async_trait]
+	impl TransportReceiverT for Receiver {
+		type Error = std::io::Error;
+
+		async fn receive(&mut self) -> Result<String, Self::Error> {
+			self.0.next().await.ok_or_else(|| std::io::ErrorKind::BrokenPipe.into())
+		}
+	}
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let (to_server, mut from_client) = mpsc::unbounded::<String>();
+	let (to_client, from_server) = mpsc::unbounded();
+	tokio::spawn(async move {
+		while let Some(request) = from_client.next().await {
+			if let Some(response) = module.dispatch(&request).await {
+				let _ = to_client.unbounded_send(response);
+			}
+		}
+	});
+
+	let client = Client::new(Sender(to_server), Receiver(from_server));
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn error_data_is_deserialized_into_the_registered_type() {
+	use jsonrpsee::core::client::ErrorDataClient;
+	use jsonrpsee::http_server::{HttpServerBuilder, RpcModule};
+	use jsonrpsee::types::error::CallError;
+
+	#[derive(Debug, PartialEq, serde::Deserialize)]
+	struct Reason {
+		retry_after: u64,
+	}
+
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let uri = format!("http://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module
+		.register_method::<(), _>("busy", |_, _| {
+			let data = serde_json::value::to_raw_value(&serde_json::json!({ "retry_after": 5 })).unwrap();
+			Err(CallError::Custom { code: -32010, message: "Busy".into(), data: Some(data) }.into())
+		})
+		.unwrap();
+	module
+		.register_method::<(), _>("fails", |_, _| {
+			Err(CallError::Custom { code: -32011, message: "Failed".into(), data: None }.into())
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = ErrorDataClient::<_, Reason>::new(HttpClientBuilder::default().build(&uri).unwrap());
+	let err = client.request::<()>("busy", None).await.unwrap_err();
+	assert!(matches!(
+		err,
+		ErrorWithData::Call { code: -32010, message, data: Some(Reason { retry_after: 5 }) } if message == "Busy"
+	));
+
+	let err = client.request::<()>("fails", None).await.unwrap_err();
+	assert!(matches!(err, ErrorWithData::Call { code: -32011, data: None, .. }));
+}
+
+#[tokio::test]
+async fn structured_error_data_reaches_the_client_intact() {
+	use jsonrpsee::types::error::{CallError, ErrorResponse};
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+
+	const DATA: &str = r#"[{"field":"name","reason":"empty"},{"field":"age","limits":[0,1.5e2]},null,"x"]"#;
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module
+		.register_method::<(), _>("validate", |_, _| {
+			let data = serde_json::value::RawValue::from_string(DATA.to_owned()).unwrap();
+			Err(CallError::Custom { code: -32602, message: "Invalid fields".into(), data: Some(data) }.into())
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let resp = match client.request::<()>("validate", None).await.unwrap_err() {
+		Error::Request(resp) => resp,
+		err => panic!("Expected a call error, got: {:?}", err),
+	};
+	let err: ErrorResponse = serde_json::from_str(&resp).unwrap();
+	assert_eq!(err.error.message, "Invalid fields");
+	assert_eq!(err.error.data.map(|data| data.get()), Some(DATA));
+}
+
+#[tokio::test]
+async fn waiting_for_a_silent_subscription_times_out() {
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module
+		.register_subscription("subscribe_silent", "silent", "unsubscribe_silent", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	module
+		.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, mut sink, sinks| {
+			sink.send(&"hello")?;
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut silent: Subscription<String> =
+		client.subscribe("subscribe_silent", None, "unsubscribe_silent").await.unwrap();
+	assert!(silent.next_timeout(Duration::from_millis(100)).await.is_err());
+
+	let mut hello: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	let first = hello.next_timeout(Duration::from_secs(5)).await.unwrap();
+	assert_eq!(first.unwrap().unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn stream_subscriptions_send_the_items_of_the_stream() {
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module
+		.register_stream_subscription("subscribe_count", "count", "unsubscribe_count", |params, _| {
+			let to: u32 = params.one()?;
+			Ok(futures::stream::iter(1..=to))
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut sub: Subscription<u32> =
+		client.subscribe("subscribe_count", rpc_params![3], "unsubscribe_count").await.unwrap();
+	for expected in 1..=3 {
+		assert_eq!(sub.next().with_default_timeout().await.unwrap().unwrap().unwrap(), expected);
+	}
+
+	// The subscription is closed once the stream ends.
+	let exp = SubscriptionClosed::new(SubscriptionClosedReason::Server("No close reason provided".to_string()));
+	let closed = sub.next().with_default_timeout().await.unwrap();
+	assert!(matches!(closed, Some(Err(Error::SubscriptionClosed(close_reason))) if close_reason == exp));
+}
+
+/// Makes 20 calls with `request_many` and returns the results along with the most calls that were in flight at
+/// the same time.
+async fn ws_request_many(concurrency: usize) -> (Vec<u64>, usize) {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	#[derive(Default)]
+	struct InFlight {
+		current: AtomicUsize,
+		max: AtomicUsize,
+	}
+
+	let in_flight = Arc::new(InFlight::default());
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = jsonrpsee::RpcModule::new(in_flight.clone());
+	module
+		.register_async_method("double", |params, in_flight| async move {
+			let n: u64 = params.one()?;
+			let current = in_flight.current.fetch_add(1, Ordering::SeqCst) + 1;
+			in_flight.max.fetch_max(current, Ordering::SeqCst);
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			in_flight.current.fetch_sub(1, Ordering::SeqCst);
+			Ok(n * 2)
+		})
+		.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let calls = (0..20u64).map(|n| ("double", rpc_params![n])).collect();
+	let results: Vec<Result<u64, Error>> = client.request_many(calls, concurrency).await;
+
+	(results.into_iter().map(Result::unwrap).collect(), in_flight.max.load(Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn ws_request_many_bounds_the_requests_in_flight() {
+	let (results, max_in_flight) = ws_request_many(4).await;
+	assert_eq!(results, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+	assert!(max_in_flight <= 4);
+}
+
+#[tokio::test]
+async fn ws_request_many_without_concurrency_sends_one_request_at_a_time() {
+	let (results, max_in_flight) = ws_request_many(0).await;
+	assert_eq!(results, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+	assert_eq!(max_in_flight, 1);
+}
@@ -29,10 +29,12 @@ use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use jsonrpsee::core::{client::ClientT, middleware::Middleware, Error};
+use jsonrpsee::core::middleware::{LatencyHistogram, Middleware, UNKNOWN_METHOD};
+use jsonrpsee::core::{client::ClientT, Error};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
 use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::Version;
 use jsonrpsee::ws_client::WsClientBuilder;
 use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
 use jsonrpsee::RpcModule;
@@ -193,3 +195,93 @@ async fn http_server_middleware() {
 	// HTTP server doesn't track connections
 	assert_eq!(inner.connections, (0, 0));
 }
+
+#[derive(Clone, Default)]
+struct Versions(Arc<Mutex<Vec<(String, Version)>>>);
+
+impl Middleware for Versions {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_version(&self, name: &str, version: Version) {
+		self.0.lock().unwrap().push((name.to_owned(), version));
+	}
+}
+
+#[tokio::test]
+async fn middleware_sees_the_version_of_the_request() {
+	let versions = Versions::default();
+	let server = HttpServerBuilder::default().set_middleware(versions.clone()).build("127.0.0.1:0").unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	let server_handle = server.start(test_module()).unwrap();
+
+	let client = HttpClientBuilder::default().v1_compat(true).build(&server_url).unwrap();
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	assert_eq!(
+		*versions.0.lock().unwrap(),
+		vec![("say_hello".to_owned(), Version::V1), ("say_hello".to_owned(), Version::V2)]
+	);
+
+	server_handle.stop().unwrap().await.unwrap();
+}
+
+#[tokio::test]
+async fn latency_histogram_records_method_latencies() {
+	let buckets = vec![Duration::from_millis(10), Duration::from_millis(500), Duration::from_secs(5)];
+	let reported = Arc::new(Mutex::new(Vec::new()));
+	let histogram = {
+		let reported = reported.clone();
+		LatencyHistogram::new(buckets.clone())
+			.on_latency(move |name, latency| reported.lock().unwrap().push((name.to_owned(), latency)))
+	};
+
+	let server = HttpServerBuilder::default().set_middleware(histogram.clone()).build("127.0.0.1:0").unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	let server_handle = server.start(test_module()).unwrap();
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	// `say_hello` sleeps for 50 ms.
+	let say_hello = histogram.method("say_hello").unwrap();
+	assert_eq!(say_hello.buckets, buckets);
+	assert_eq!(say_hello.counts, vec![0, 2, 0, 0]);
+	assert_eq!(say_hello.count(), 2);
+	assert!(say_hello.sum >= Duration::from_millis(100));
+	assert!(histogram.method("unknown_method").is_none());
+
+	{
+		let reported = reported.lock().unwrap();
+		assert_eq!(reported.len(), 2);
+		assert!(reported.iter().all(|(name, latency)| name == "say_hello" && *latency >= Duration::from_millis(50)));
+	}
+
+	server_handle.stop().unwrap().await.unwrap();
+}
+
+#[tokio::test]
+async fn latency_histogram_folds_unknown_methods() {
+	let module = test_module();
+	let histogram = LatencyHistogram::default().with_methods(module.method_names());
+
+	let server = HttpServerBuilder::default().set_middleware(histogram.clone()).build("127.0.0.1:0").unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	let server_handle = server.start(module).unwrap();
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+	assert!(client.request::<String>("unknown_method", None).await.is_err());
+	assert!(client.request::<String>("another_unknown_method", None).await.is_err());
+
+	assert_eq!(histogram.method("say_hello").unwrap().count(), 1);
+	assert_eq!(histogram.method(UNKNOWN_METHOD).unwrap().count(), 2);
+	assert!(histogram.method("unknown_method").is_none());
+	assert_eq!(histogram.snapshot().len(), 2);
+
+	server_handle.stop().unwrap().await.unwrap();
+}
@@ -320,3 +320,26 @@ async fn subscriptions_do_not_work_for_http_servers() {
 	assert!(matches!(htclient.sub().await, Err(Error::HttpNotImplemented)));
 	assert_eq!(htclient.sync_method().await.unwrap(), 10);
 }
+
+#[tokio::test]
+async fn macro_generated_and_hand_written_modules_can_be_mixed() {
+	use jsonrpsee::core::server::rpc_module::{Methods, RpcModule};
+	use jsonrpsee::types::EmptyParams;
+
+	let mut module = RpcModule::new(());
+	module.register_method("hand_written", |_, _| Ok("hello")).unwrap();
+	module.merge(RpcServerImpl.into_rpc()).unwrap();
+
+	let res: String = module.call("hand_written", EmptyParams::new()).await.unwrap();
+	assert_eq!(res, "hello");
+	let res: String = module.call("foo_params", [json!(42_u64), json!("Hello")]).await.unwrap();
+	assert_eq!(res, "Called with: 42, Hello");
+
+	// And the other way around, starting from the macro-generated methods.
+	let mut module: RpcModule<()> = Methods::from(RpcServerImpl.into_rpc()).into();
+	module.register_method("hand_written", |_, _| Ok("hello")).unwrap();
+	let res: u16 = module.call("foo_bar", EmptyParams::new()).await.unwrap();
+	assert_eq!(res, 10);
+	let res: String = module.call("hand_written", EmptyParams::new()).await.unwrap();
+	assert_eq!(res, "hello");
+}
@@ -25,10 +25,14 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use futures::channel::mpsc;
+use futures::StreamExt;
 use jsonrpsee::core::error::{Error, SubscriptionClosed, SubscriptionClosedReason};
+use jsonrpsee::core::server::helpers::{MethodSink, SinkClosed};
 use jsonrpsee::core::server::rpc_module::*;
-use jsonrpsee::types::{EmptyParams, Params};
+use jsonrpsee::types::{Bytes, EmptyParams, Id, Params, Version};
 use serde::{Deserialize, Serialize};
 
 // Helper macro to assert that a binding is of a specific type.
@@ -82,6 +86,115 @@ fn rpc_register_alias() {
 	assert!(module.method("hello_foobar").is_some());
 }
 
+#[test]
+fn rpc_module_to_openrpc() {
+	use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+	#[rpc(server)]
+	pub trait Greeter {
+		/// Greets the caller.
+		#[method(name = "greet")]
+		fn greet(&self) -> RpcResult<String>;
+
+		#[method(name = "undocumented")]
+		fn undocumented(&self) -> RpcResult<()>;
+	}
+
+	impl GreeterServer for () {
+		fn greet(&self) -> RpcResult<String> {
+			Ok("hello".into())
+		}
+
+		fn undocumented(&self) -> RpcResult<()> {
+			Ok(())
+		}
+	}
+
+	let mut module = ().into_rpc();
+	module.register_method("farewell", |_: Params, _| Ok("bye")).unwrap();
+	module.set_method_description("farewell", "Says goodbye.").unwrap();
+	assert!(matches!(module.set_method_description("unknown", "Nope."), Err(Error::MethodNotFound(_))));
+
+	let doc = module.to_openrpc();
+	assert_eq!(doc["openrpc"], "1.2.6");
+	assert_eq!(
+		doc["methods"],
+		serde_json::json!([
+			{ "name": "farewell", "params": [], "description": "Says goodbye." },
+			{ "name": "greet", "params": [], "description": "Greets the caller." },
+			{ "name": "undocumented", "params": [] },
+		])
+	);
+}
+
+#[tokio::test]
+async fn calling_unknown_method_without_server_uses_the_fallback() {
+	let mut module = RpcModule::new(());
+	module.register_method("hello", |_, _| Ok("world")).unwrap();
+	module.set_fallback(|id, method, _, sink, _| async move {
+		tokio::task::yield_now().await;
+		sink.send_response(id, format!("fallback for {}", method))
+	});
+
+	let hello: String = module.call("hello", EmptyParams::new()).await.unwrap();
+	assert_eq!(hello, "world");
+	let answer: String = module.call("upstream_method", EmptyParams::new()).await.unwrap();
+	assert_eq!(answer, "fallback for upstream_method");
+}
+
+#[tokio::test]
+async fn dispatching_raw_requests_without_server() {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.register_async_method("say_hello_async", |_, _| async move { Ok("lo") }).unwrap();
+
+	let resp = module.dispatch(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).await;
+	assert_eq!(resp.as_deref(), Some(r#"{"jsonrpc":"2.0","result":"lo","id":1}"#));
+
+	let resp = module
+		.dispatch(
+			r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"say_hello_async","id":2}]"#,
+		)
+		.await;
+	assert_eq!(
+		resp.as_deref(),
+		Some(r#"[{"jsonrpc":"2.0","result":"lo","id":1},{"jsonrpc":"2.0","result":"lo","id":2}]"#)
+	);
+
+	assert_eq!(module.dispatch(r#"{"jsonrpc":"2.0","method":"say_hello"}"#).await, None);
+
+	let resp = module.dispatch(r#"{"jsonrpc":"2.0","method":"nope","id":3}"#).await;
+	assert_eq!(
+		resp.as_deref(),
+		Some(r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":3}"#)
+	);
+
+	let resp = module.dispatch("[]").await;
+	assert_eq!(
+		resp.as_deref(),
+		Some(r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":null}"#)
+	);
+}
+
+#[tokio::test]
+async fn handlers_see_the_version_of_the_request() {
+	let mut module = RpcModule::new(());
+	module
+		.register_method("version", |params, _| {
+			Ok(match params.version() {
+				Version::V1 => "1.0",
+				Version::V2 => "2.0",
+			})
+		})
+		.unwrap();
+
+	let resp = module.dispatch(r#"{"method":"version","params":[],"id":1}"#).await;
+	assert_eq!(resp.as_deref(), Some(r#"{"jsonrpc":"2.0","result":"1.0","id":1}"#));
+
+	let resp = module.dispatch(r#"{"jsonrpc":"2.0","method":"version","id":2}"#).await;
+	assert_eq!(resp.as_deref(), Some(r#"{"jsonrpc":"2.0","result":"2.0","id":2}"#));
+}
+
 #[tokio::test]
 async fn calling_method_without_server() {
 	// Call sync method with no params
@@ -227,6 +340,318 @@ async fn subscribing_without_server() {
 	assert!(matches!(sub_err, Error::SubscriptionClosed(close_reason) if close_reason == exp));
 }
 
+#[tokio::test]
+async fn progress_is_sent_before_the_response() {
+	let mut module = RpcModule::new(());
+	module
+		.register_method_with_progress("download", "download_progress", |_, progress, _| async move {
+			progress.send(&50_u32)?;
+			progress.send(&100_u32)?;
+			Ok::<_, Error>("done")
+		})
+		.unwrap();
+
+	let (first, mut rest) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"download","id":7}"#).await.unwrap();
+	let second = rest.next().await.unwrap();
+	let response = rest.next().await.unwrap();
+
+	assert_eq!(first, r#"{"jsonrpc":"2.0","method":"download_progress","params":{"id":7,"progress":50}}"#);
+	assert_eq!(second, r#"{"jsonrpc":"2.0","method":"download_progress","params":{"id":7,"progress":100}}"#);
+	assert_eq!(response, r#"{"jsonrpc":"2.0","result":"done","id":7}"#);
+
+	// Only the response is returned when the caller can't tell progress and responses apart.
+	let response: String = module.call("download", EmptyParams::new()).await.unwrap();
+	assert_eq!(response, "done");
+	let response = module.dispatch(r#"[{"jsonrpc":"2.0","method":"download","id":7}]"#).await;
+	assert_eq!(response.as_deref(), Some(r#"[{"jsonrpc":"2.0","result":"done","id":7}]"#));
+}
+
+#[tokio::test]
+async fn forwarding_a_stream_into_the_subscription_sink() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, sink, _| {
+			let stream = futures::stream::iter(vec![1_u32, 2, 3]);
+			tokio::spawn(async move {
+				let _ = stream.map(Ok).forward(sink).await;
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let mut my_sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	for i in 1..=3 {
+		let (val, _) = my_sub.next::<u32>().await.unwrap().unwrap();
+		assert_eq!(val, i);
+	}
+
+	// The sink is dropped once the stream is exhausted.
+	let sub_err = my_sub.next::<u32>().await.unwrap().unwrap_err();
+	assert!(matches!(sub_err, Error::SubscriptionClosed(_)));
+}
+
+#[tokio::test]
+async fn forwarding_raw_values_into_the_subscription_sink() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, mut sink, _| {
+			let upstream = jsonrpsee::core::to_json_raw_value(&serde_json::json!({ "block": 1 })).unwrap();
+			sink.send_raw(&upstream).unwrap();
+			Ok(())
+		})
+		.unwrap();
+
+	let mut my_sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let (val, _) = my_sub.next::<serde_json::Value>().await.unwrap().unwrap();
+	assert_eq!(val, serde_json::json!({ "block": 1 }));
+}
+
+#[tokio::test]
+async fn subscriptions_merged_into_a_context_module_can_be_driven() {
+	let mut subs = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	subs.register_subscription("my_sub", "my_sub", "my_unsub", |_, mut sink, sinks| {
+		sink.send(&"hello").unwrap();
+		// Kept alive, such that the subscription stays open until it's closed from the outside.
+		sinks.lock().unwrap().push(sink);
+		Ok(())
+	})
+	.unwrap();
+
+	let mut module = RpcModule::new(Vec::<u8>::new());
+	let closer = module.subscription_closer();
+	module.merge(subs).unwrap();
+
+	let mut first = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let (val, _) = first.next::<String>().await.unwrap().unwrap();
+	assert_eq!(val, "hello");
+
+	let sub_id = first.subscription_id().clone();
+	let unsubscribed: bool = module.call("my_unsub", [&sub_id]).await.unwrap();
+	assert!(unsubscribed);
+
+	// The merged subscriptions are closed along with those of the module they were merged into.
+	let mut second = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	assert_eq!(second.next::<String>().await.unwrap().unwrap().0, "hello");
+	assert_eq!(closer.close_all("shutting down"), 1);
+	let exp = SubscriptionClosed::new(SubscriptionClosedReason::Server("shutting down".to_string()));
+	assert!(matches!(second.next::<String>().await, Some(Err(Error::SubscriptionClosed(reason))) if reason == exp));
+}
+
+#[tokio::test]
+async fn external_producers_send_to_subscriptions_through_a_registry() {
+	let registry = SubscriptionRegistry::<String>::new();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription_with_registry(
+			"sub_account",
+			"account",
+			"unsub_account",
+			registry.clone(),
+			|params, _| params.one::<String>().map_err(Into::into),
+		)
+		.unwrap();
+
+	let mut alice = module.subscribe("sub_account", ["alice"]).await.unwrap();
+	let mut bob = module.subscribe("sub_account", ["bob"]).await.unwrap();
+	assert_eq!(registry.subscribers(&"alice".to_string()), 1);
+
+	let producer = registry.clone();
+	tokio::spawn(async move {
+		assert_eq!(producer.send(&"alice".to_string(), &1_u64).unwrap(), 1);
+		assert_eq!(producer.send(&"carol".to_string(), &2_u64).unwrap(), 0);
+		assert_eq!(producer.send(&"bob".to_string(), &3_u64).unwrap(), 1);
+	})
+	.await
+	.unwrap();
+
+	assert_eq!(alice.next::<u64>().await.unwrap().unwrap().0, 1);
+	assert_eq!(bob.next::<u64>().await.unwrap().unwrap().0, 3);
+
+	// Closed subscriptions are dropped from the registry on the next send.
+	let sub_id = alice.subscription_id().clone();
+	let _: bool = module.call("unsub_account", [&sub_id]).await.unwrap();
+	assert_eq!(registry.send(&"alice".to_string(), &4_u64).unwrap(), 0);
+	assert_eq!(registry.subscribers(&"alice".to_string()), 0);
+}
+
+#[tokio::test]
+async fn registry_sweeps_closed_subscriptions_on_insert() {
+	let registry = SubscriptionRegistry::<u32>::new();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription_with_registry("sub_block", "block", "unsub_block", registry.clone(), |params, _| {
+			params.one::<u32>().map_err(Into::into)
+		})
+		.unwrap();
+
+	// Keys that are never sent to.
+	for n in 0..40_u32 {
+		let sub = module.subscribe("sub_block", [n]).await.unwrap();
+		let _: bool = module.call("unsub_block", [sub.subscription_id()]).await.unwrap();
+	}
+	assert_eq!(registry.subscribers(&0), 0);
+	assert_eq!(registry.subscribers(&31), 0);
+}
+
+#[tokio::test]
+async fn cancelling_the_context_closes_its_subscriptions() {
+	let shutdown = Arc::new(tokio::sync::Notify::new());
+	let mut module = RpcModule::new(shutdown.clone());
+	module
+		.register_subscription("sub_db", "db", "unsub_db", |_, mut sink, _| {
+			tokio::spawn(async move {
+				// Keeps producing until the subscription is closed from the outside.
+				while sink.send(&"row").is_ok() {
+					tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let closer = module.subscription_closer();
+	let token = shutdown.clone();
+	let closed = tokio::spawn(async move {
+		token.notified().await;
+		closer.close_all("database closed")
+	});
+
+	let mut first = module.subscribe("sub_db", EmptyParams::new()).await.unwrap();
+	let mut second = module.subscribe("sub_db", EmptyParams::new()).await.unwrap();
+	assert_eq!(first.next::<String>().await.unwrap().unwrap().0, "row");
+
+	shutdown.notify_one();
+	assert_eq!(closed.await.unwrap(), 2);
+
+	let exp = SubscriptionClosed::new(SubscriptionClosedReason::Server("database closed".to_string()));
+	for sub in [&mut first, &mut second] {
+		let closed = loop {
+			match sub.next::<String>().await {
+				Some(Ok(_)) => continue,
+				other => break other,
+			}
+		};
+		assert!(matches!(closed, Some(Err(Error::SubscriptionClosed(close_reason))) if close_reason == exp));
+	}
+}
+
+#[tokio::test]
+async fn event_hook_can_list_subscriptions_while_closing_all() {
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	let closer = module.subscription_closer();
+	let hook_closer = closer.clone();
+	let remaining: Arc<std::sync::Mutex<Vec<usize>>> = Default::default();
+	let recorded = remaining.clone();
+	module.on_subscription_event(move |event| {
+		if let SubscriptionEvent::Unsubscribed { conn_id, .. } = event {
+			recorded.lock().unwrap().push(hook_closer.subscriptions_for_connection(conn_id).len());
+		}
+	});
+
+	let _first = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let _second = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+
+	assert_eq!(closer.close_all("shutting down"), 2);
+	assert_eq!(*remaining.lock().unwrap(), vec![0, 0]);
+}
+
+#[tokio::test]
+async fn subscriptions_of_a_connection_can_be_listed_and_closed() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("sub_ticks", "tick", "unsub_ticks", |_, mut sink, _| {
+			tokio::spawn(async move {
+				while sink.send(&"tick").is_ok() {
+					tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+	let closer = module.subscription_closer();
+
+	// Two subscriptions on connection 7 and one on connection 0.
+	let (tx, mut rx) = mpsc::unbounded();
+	let sink = MethodSink::new(tx);
+	let conn = ConnectionInfo::new(7, None);
+	let close = Arc::new(tokio::sync::Notify::new());
+	let mut sub_ids = Vec::new();
+	for id in 1..=2 {
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"sub_ticks","id":{}}}"#, id);
+		assert!(module.execute(serde_json::from_str(&req).unwrap(), &sink, conn.clone(), close.clone()).await);
+		let resp = loop {
+			let msg: serde_json::Value = serde_json::from_str(&rx.next().await.unwrap()).unwrap();
+			if msg["id"] == id {
+				break msg;
+			}
+		};
+		sub_ids.push(resp["result"].to_string());
+	}
+	let other = module.subscribe("sub_ticks", EmptyParams::new()).await.unwrap();
+
+	let listed = closer.subscriptions_for_connection(7);
+	let mut listed_json: Vec<String> = listed.iter().map(|id| serde_json::to_string(id).unwrap()).collect();
+	listed_json.sort();
+	sub_ids.sort();
+	assert_eq!(listed_json, sub_ids);
+	assert_eq!(closer.subscriptions_for_connection(0), vec![other.subscription_id().clone().into_owned()]);
+	assert!(closer.subscriptions_for_connection(8).is_empty());
+
+	let (closed_id, kept_id) = (&listed[0], &listed[1]);
+	assert!(closer.close(7, closed_id, "misbehaving client"));
+	assert!(!closer.close(7, closed_id, "misbehaving client"));
+	assert!(!closer.close(8, kept_id, "misbehaving client"));
+	assert_eq!(&closer.subscriptions_for_connection(7), std::slice::from_ref(kept_id));
+	assert_eq!(closer.subscriptions_for_connection(0).len(), 1);
+
+	// The subscriber is told why the subscription was closed.
+	let closed_id = serde_json::to_value(closed_id).unwrap();
+	let closed = loop {
+		let msg: serde_json::Value = serde_json::from_str(&rx.next().await.unwrap()).unwrap();
+		if msg["params"]["subscription"] == closed_id && msg["params"]["result"] != "tick" {
+			break msg;
+		}
+	};
+	let exp = SubscriptionClosed::new(SubscriptionClosedReason::Server("misbehaving client".to_string()));
+	assert_eq!(closed["params"]["result"], serde_json::to_value(&exp).unwrap());
+}
+
+#[tokio::test]
+async fn methods_can_be_executed_against_an_in_memory_sink() {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.register_async_method("say_hello_async", |_, _| async { Ok("lo") }).unwrap();
+
+	assert!(module.method("say_hello").is_some());
+	assert!(module.method("say_goodbye").is_none());
+
+	let (tx, mut rx) = mpsc::unbounded();
+	let sink = MethodSink::new(tx);
+	let conn = ConnectionInfo::new(7, None);
+	let close = Arc::new(tokio::sync::Notify::new());
+
+	let req = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).unwrap();
+	assert!(module.execute(req, &sink, conn.clone(), close.clone()).await);
+	assert_eq!(rx.next().await.unwrap(), r#"{"jsonrpc":"2.0","result":"lo","id":1}"#);
+
+	let req = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"say_hello_async","id":2}"#).unwrap();
+	assert!(module.execute(req, &sink, conn.clone(), close.clone()).await);
+	assert_eq!(rx.next().await.unwrap(), r#"{"jsonrpc":"2.0","result":"lo","id":2}"#);
+
+	let req = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"say_goodbye","id":3}"#).unwrap();
+	assert!(!module.execute(req, &sink, conn, close).await);
+	assert_eq!(
+		rx.next().await.unwrap(),
+		r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":3}"#
+	);
+}
+
 #[tokio::test]
 async fn close_test_subscribing_without_server() {
 	tracing_subscriber::FmtSubscriber::builder()
@@ -282,3 +707,652 @@ async fn close_test_subscribing_without_server() {
 		matches!(my_sub2.next::<String>().await, Some(Err(Error::SubscriptionClosed(close_reason))) if close_reason == exp)
 	);
 }
+
+#[tokio::test]
+async fn calling_method_with_large_integers_roundtrips() {
+	let mut module = RpcModule::new(());
+	module
+		.register_method("double", |params, _| {
+			let n: u128 = params.one()?;
+			Ok(n * 2)
+		})
+		.unwrap();
+	module.register_method("max", |_: Params, _| Ok(u128::MAX)).unwrap();
+	module.register_method("min", |_: Params, _| Ok(i128::MIN)).unwrap();
+
+	let res: u128 = module.call("max", EmptyParams::new()).await.unwrap();
+	assert_eq!(res, u128::MAX);
+
+	let res: i128 = module.call("min", EmptyParams::new()).await.unwrap();
+	assert_eq!(res, i128::MIN);
+
+	// Larger than `2^53` and `u64::MAX`.
+	let big = u64::MAX as u128 + 1;
+	let res: u128 = module.call("double", [big]).await.unwrap();
+	assert_eq!(res, big * 2);
+}
+
+#[tokio::test]
+async fn calling_method_with_binary_data_roundtrips() {
+	let mut module = RpcModule::new(());
+	module
+		.register_method("reverse", |params, _| {
+			let mut blob: Bytes = params.one()?;
+			blob.reverse();
+			Ok(blob)
+		})
+		.unwrap();
+
+	let blob: Bytes = (0..1024).map(|i| (i % 251) as u8).collect::<Vec<_>>().into();
+	let res: Bytes = module.call("reverse", [blob.clone()]).await.unwrap();
+	assert_eq!(res.len(), 1024);
+	assert!(res.iter().eq(blob.iter().rev()));
+}
+
+#[test]
+fn sync_method_reports_failure_when_the_sink_is_closed() {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let callback = match module.method("say_hello").unwrap().inner() {
+		MethodKind::Sync(callback) => callback.clone(),
+		_ => panic!("say_hello is a sync method"),
+	};
+	let conn = ConnectionInfo::new(0, None);
+
+	let (tx, rx) = mpsc::unbounded();
+	let sink = MethodSink::new(tx);
+	assert!(callback(Id::Number(1), Params::new(None), &sink, &conn));
+
+	drop(rx);
+	assert!(!callback(Id::Number(2), Params::new(None), &sink, &conn));
+	assert!(sink.is_closed());
+}
+
+#[test]
+fn closed_sink_is_reported_apart_from_errors() {
+	let (tx, rx) = mpsc::unbounded();
+	let sink = MethodSink::new(tx);
+	assert_eq!(sink.try_send_response(Id::Number(1), "hello"), Ok(true));
+	assert_eq!(sink.try_send_call_error(Id::Number(2), Error::Custom("nope".into())), Ok(false));
+
+	drop(rx);
+	assert_eq!(sink.try_send_response(Id::Number(3), "hello"), Err(SinkClosed));
+	assert_eq!(sink.try_send_call_error(Id::Number(4), Error::Custom("nope".into())), Err(SinkClosed));
+	assert!(!sink.send_response(Id::Number(5), "hello"));
+}
+
+#[tokio::test]
+async fn subscriptions_are_not_started_on_a_closed_sink() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, _, _| {
+			panic!("the subscription isn't started once its sink is closed")
+		})
+		.unwrap();
+	let closer = module.subscription_closer();
+	let conn = ConnectionInfo::new(0, None);
+	let close = Arc::new(tokio::sync::Notify::new());
+
+	let (tx, rx) = mpsc::unbounded();
+	let sink = MethodSink::new(tx);
+	drop(rx);
+	let req = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"my_sub","id":1}"#).unwrap();
+	assert!(!module.execute(req, &sink, conn, close).await);
+	assert!(closer.subscriptions_for_connection(0).is_empty());
+}
+
+#[tokio::test]
+async fn raw_method_forwards_params_untouched() {
+	let mut module = RpcModule::new(());
+	module
+		.register_raw_method("forward", |id, params, sink, _| {
+			let id = serde_json::to_string(&id).unwrap();
+			let response = format!(r#"{{"jsonrpc":"2.0","result":{},"id":{}}}"#, params.get(), id);
+			sink.send_raw(response).is_ok()
+		})
+		.unwrap();
+
+	let (resp, _) = module
+		.raw_json_request(r#"{"jsonrpc":"2.0","method":"forward","params":[1, {"a": "b"}],"id":7}"#)
+		.await
+		.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":[1, {"a": "b"}],"id":7}"#);
+
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"forward","id":"x"}"#).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":null,"id":"x"}"#);
+}
+
+#[tokio::test]
+async fn subscription_initial_value_is_sent_right_after_the_subscribe_response() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, mut sink, _| {
+			sink.send(&"snapshot")?;
+			tokio::spawn(sink.pipe_from_stream(futures::stream::iter(vec!["delta"])));
+			Ok(())
+		})
+		.unwrap();
+
+	let (resp, mut stream) =
+		module.raw_json_request(r#"{"jsonrpc":"2.0","method":"my_sub","params":[],"id":0}"#).await.unwrap();
+	let resp: serde_json::Value = serde_json::from_str(&resp).unwrap();
+	let sub_id = resp["result"].clone();
+	assert!(!sub_id.is_null());
+
+	for expected in ["snapshot", "delta"] {
+		let notif: serde_json::Value = serde_json::from_str(&stream.next().await.unwrap()).unwrap();
+		assert_eq!(notif["params"]["subscription"], sub_id);
+		assert_eq!(notif["params"]["result"], expected);
+	}
+}
+
+#[tokio::test(start_paused = true)]
+async fn rate_limited_subscription_sends_only_the_latest_values() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, sink, _| {
+			// Produce 1000 items per second for one second.
+			let stream = futures::stream::unfold(0_u32, |i| async move {
+				if i == 1000 {
+					return None;
+				}
+				tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+				Some((i, i + 1))
+			});
+			tokio::spawn(sink.with_rate_limit(10).pipe_from_stream(Box::pin(stream)));
+			Ok(())
+		})
+		.unwrap();
+
+	let mut my_sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let mut received = Vec::new();
+	while let Some(Ok((val, _))) = my_sub.next::<u32>().await {
+		received.push(val);
+	}
+
+	// One item per 100ms window, plus the first one which is sent right away.
+	assert!((10..=11).contains(&received.len()), "received {} items", received.len());
+	assert!(received.windows(2).all(|w| w[0] < w[1]));
+	// The most recent value is always delivered.
+	assert_eq!(received.last(), Some(&999));
+}
+
+#[tokio::test(start_paused = true)]
+async fn rate_limit_applies_to_notifications_sent_on_the_sink() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, sink, _| {
+			let mut sink = sink.with_rate_limit(10);
+			tokio::spawn(async move {
+				for i in 0..1000_u32 {
+					sink.send(&i).unwrap();
+					tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+				}
+				// The last item is held back until the sink is dropped.
+				tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let mut my_sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let mut received = Vec::new();
+	while let Some(Ok((val, _))) = my_sub.next::<u32>().await {
+		received.push(val);
+	}
+
+	assert!((10..=11).contains(&received.len()), "received {} items", received.len());
+	assert_eq!(received.first(), Some(&0));
+	assert_eq!(received.last(), Some(&999));
+}
+
+#[tokio::test]
+async fn rate_limited_sink_can_be_fed_from_a_thread() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, sink, _| {
+			let mut sink = sink.with_rate_limit(1);
+			std::thread::spawn(move || {
+				for i in 0..5_u32 {
+					sink.send(&i).unwrap();
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let mut my_sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let mut received = Vec::new();
+	while let Some(Ok((val, _))) = my_sub.next::<u32>().await {
+		received.push(val);
+	}
+
+	// The first item is sent right away and the last one when the sink is dropped.
+	assert_eq!(received, vec![0, 4]);
+}
+
+#[derive(Clone, Default)]
+struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.0.lock().unwrap().extend_from_slice(buf);
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+#[tokio::test]
+async fn failed_calls_are_logged_with_method_and_request_id() {
+	use jsonrpsee::types::error::CallError;
+
+	let logs = CapturedLogs::default();
+	let writer = logs.clone();
+	let subscriber = tracing_subscriber::fmt()
+		.with_writer(move || writer.clone())
+		.with_ansi(false)
+		.with_max_level(tracing::Level::DEBUG)
+		.finish();
+	let _guard = tracing::subscriber::set_default(subscriber);
+
+	let mut module = RpcModule::new(());
+	module.register_method::<(), _>("fails", |_, _| Err(Error::Custom("boom".into()))).unwrap();
+	module.register_method("needs_params", |params, _| params.one::<u64>().map_err(Into::into)).unwrap();
+	module
+		.register_method::<(), _>("broken", |_, _| {
+			Err(CallError::from_std_error(std::io::Error::other("db is down")).into())
+		})
+		.unwrap();
+	module
+		.register_method::<(), _>("refuses", |_, _| {
+			Err(CallError::Custom { code: -32001, message: "not allowed".into(), data: None }.into())
+		})
+		.unwrap();
+	for (method, id) in [("fails", 42), ("needs_params", 43), ("broken", 44), ("refuses", 45)] {
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"{}","id":{}}}"#, method, id);
+		module.raw_json_request(&req).await.unwrap();
+	}
+
+	let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+	let line = |needle: &str| logs.lines().find(|line| line.contains(needle)).unwrap_or_else(|| panic!("{}", logs));
+	// Unexpected errors are worth a warning, errors caused by the client are not.
+	assert!(line("Call failed with [method=fails id=42]: Custom error: boom").contains("WARN"));
+	assert!(line("Call failed with [method=needs_params id=43]").contains("DEBUG"));
+	assert!(line("Call failed with [method=broken id=44]").contains("WARN"));
+	assert!(line("Call failed with [method=refuses id=45]").contains("DEBUG"));
+}
+
+#[tokio::test]
+async fn stream_subscriptions_are_forwarded_on_the_stream_executor() {
+	use jsonrpsee::core::server::executor::{Executor, TokioExecutor};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	#[derive(Debug, Clone, Default)]
+	struct CountingExecutor(Arc<AtomicUsize>);
+
+	impl Executor for CountingExecutor {
+		fn spawn(&self, task: futures::future::BoxFuture<'static, ()>) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+			TokioExecutor.spawn(task);
+		}
+	}
+
+	let executor = CountingExecutor::default();
+	let mut module = RpcModule::new(());
+	module
+		.register_stream_subscription("sub_count", "count", "unsub_count", |_, _| Ok(futures::stream::iter(1..=2)))
+		.unwrap();
+	module.set_stream_executor(executor.clone());
+
+	let mut sub = module.subscribe("sub_count", EmptyParams::new()).await.unwrap();
+	assert_eq!(sub.next::<u32>().await.unwrap().unwrap().0, 1);
+	assert_eq!(sub.next::<u32>().await.unwrap().unwrap().0, 2);
+	assert_eq!(executor.0.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn take_methods_leaves_the_module_reusable() {
+	let sinks: Arc<std::sync::Mutex<Vec<SubscriptionSink>>> = Default::default();
+	let mut module = RpcModule::new(sinks.clone());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+
+	let methods = module.take_methods();
+	assert!(module.method("my_sub").is_none());
+	assert!(methods.method("my_sub").is_some());
+
+	// The module keeps its context and can be used to register new methods.
+	module.register_method("subscribers", |_, sinks| Ok(sinks.lock().unwrap().len())).unwrap();
+
+	let mut my_sub = methods.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let result: usize = module.call("subscribers", EmptyParams::new()).await.unwrap();
+	assert_eq!(result, 1);
+
+	// The module kept the subscribers of the taken methods.
+	assert_eq!(module.broadcast("my_sub", &"broadcast").unwrap(), 1);
+	let (val, _) = my_sub.next::<String>().await.unwrap().unwrap();
+	assert_eq!(val, "broadcast");
+	assert_eq!(module.subscription_closer().subscriptions_for_connection(0), vec![my_sub.subscription_id().clone()]);
+}
+
+#[test]
+fn case_insensitive_methods_reject_names_differing_only_in_case() {
+	let mut module = RpcModule::new(());
+	module.register_method("foo", |_, _| Ok(())).unwrap();
+	module.register_method("FOO", |_, _| Ok(())).unwrap();
+	assert!(matches!(Methods::from(module).into_case_insensitive(), Err(Error::MethodAlreadyRegistered(_))));
+
+	let mut module = RpcModule::new(());
+	module.register_method("system_chain", |_, _| Ok(())).unwrap();
+	let mut methods = Methods::from(module).into_case_insensitive().unwrap();
+	assert_eq!(methods.method_with_name("System_Chain").map(|(name, _)| name), Some("system_chain"));
+
+	let mut other = RpcModule::new(());
+	other.register_method("SYSTEM_CHAIN", |_, _| Ok(())).unwrap();
+	assert!(matches!(methods.merge(other), Err(Error::MethodAlreadyRegistered(_))));
+
+	// Methods added afterwards are looked up ignoring case as well.
+	let mut other = RpcModule::new(());
+	other.register_method("system_health", |_, _| Ok(())).unwrap();
+	methods.merge(other).unwrap();
+	assert_eq!(methods.method_with_name("SYSTEM_HEALTH").map(|(name, _)| name), Some("system_health"));
+	assert!(methods.method_with_name("system_version").is_none());
+}
+
+#[test]
+fn try_merge_reports_all_conflicts_and_merges_nothing() {
+	let mut module = RpcModule::new(());
+	module.register_method("foo", |_, _| Ok(())).unwrap();
+	module.register_method("bar", |_, _| Ok(())).unwrap();
+
+	let mut other = RpcModule::new(());
+	other.register_method("bar", |_, _| Ok(())).unwrap();
+	other.register_method("baz", |_, _| Ok(())).unwrap();
+	other.register_method("foo", |_, _| Ok(())).unwrap();
+
+	assert_eq!(module.try_merge(other.clone()), Err(vec!["bar".to_string(), "foo".to_string()]));
+	assert!(module.method("baz").is_none());
+
+	let mut no_conflicts = RpcModule::new(());
+	no_conflicts.register_method("baz", |_, _| Ok(())).unwrap();
+	assert_eq!(module.try_merge(no_conflicts), Ok(()));
+	assert!(module.method("baz").is_some());
+}
+
+#[tokio::test]
+async fn subscription_events_are_reported_on_subscribe_and_unsubscribe() {
+	let events: Arc<std::sync::Mutex<Vec<(&'static str, String)>>> = Default::default();
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	let recorded = events.clone();
+	module.on_subscription_event(move |event| {
+		let event = match event {
+			SubscriptionEvent::Subscribed { method, sub_id, .. } => ("opened", format!("{}:{:?}", method, sub_id)),
+			SubscriptionEvent::Unsubscribed { method, sub_id, .. } => ("closed", format!("{}:{:?}", method, sub_id)),
+		};
+		recorded.lock().unwrap().push(event);
+	});
+
+	for _ in 0..2 {
+		let sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+		let sub_id = sub.subscription_id().clone();
+		let unsubscribed: bool = module.call("my_unsub", [&sub_id]).await.unwrap();
+		assert!(unsubscribed);
+
+		let expected = format!("my_sub:{:?}", sub_id);
+		let events = events.lock().unwrap();
+		assert_eq!(&events[events.len() - 2..], [("opened", expected.clone()), ("closed", expected)]);
+	}
+
+	let events = events.lock().unwrap();
+	assert_eq!(events.iter().filter(|(kind, _)| *kind == "opened").count(), 2);
+	assert_eq!(events.iter().filter(|(kind, _)| *kind == "closed").count(), 2);
+}
+
+#[tokio::test]
+async fn subscription_stats_report_age_and_last_activity() {
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module.register_subscription_stats("rpc_subscriptionStats").unwrap();
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, mut sink, sinks| {
+			sink.send(&"hello").unwrap();
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let _ = sub.next::<String>().await.unwrap().unwrap();
+	tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+	let stats: serde_json::Value = module.call("rpc_subscriptionStats", EmptyParams::new()).await.unwrap();
+	let stats = stats.as_array().unwrap();
+	assert_eq!(stats.len(), 1);
+	assert_eq!(stats[0]["method"], "my_sub");
+	assert_eq!(stats[0]["subscription"], serde_json::to_value(sub.subscription_id()).unwrap());
+	let (age, idle) = (stats[0]["ageMs"].as_u64().unwrap(), stats[0]["idleMs"].as_u64().unwrap());
+	assert!(age >= 50 && idle >= 50 && idle <= age && idle < 5_000);
+
+	let sub_id = sub.subscription_id().clone();
+	let _: bool = module.call("my_unsub", [&sub_id]).await.unwrap();
+	let stats: serde_json::Value = module.call("rpc_subscriptionStats", EmptyParams::new()).await.unwrap();
+	assert_eq!(stats, serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn silent_subscriptions_are_reported_when_enabled() {
+	let logs = CapturedLogs::default();
+	let writer = logs.clone();
+	let subscriber = tracing_subscriber::fmt().with_writer(move || writer.clone()).with_ansi(false).finish();
+	let _guard = tracing::subscriber::set_default(subscriber);
+
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module.warn_on_silent_subscriptions(true);
+	module.register_subscription("silent_sub", "silent_sub", "silent_unsub", |_, _, _| Ok(())).unwrap();
+	module
+		.register_subscription("kept_sub", "kept_sub", "kept_unsub", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	module
+		.register_subscription("sending_sub", "sending_sub", "sending_unsub", |_, mut sink, _| sink.send(&"hello"))
+		.unwrap();
+
+	let _kept = module.subscribe("kept_sub", EmptyParams::new()).await.unwrap();
+	let _sending = module.subscribe("sending_sub", EmptyParams::new()).await.unwrap();
+	assert!(!String::from_utf8(logs.0.lock().unwrap().clone()).unwrap().contains("without sending anything"));
+
+	let _silent = module.subscribe("silent_sub", EmptyParams::new()).await.unwrap();
+	let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+	assert!(
+		logs.contains("Subscription callback of 'silent_sub' dropped its sink without sending anything"),
+		"{}",
+		logs
+	);
+}
+
+#[tokio::test]
+async fn subscription_ids_can_be_made_deterministic() {
+	use jsonrpsee::core::id_providers::SequentialIdProvider;
+	use jsonrpsee::types::SubscriptionId;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, mut sink, _| {
+			sink.send(&"lo").unwrap();
+			Ok(())
+		})
+		.unwrap();
+	module.set_id_provider(SequentialIdProvider::new(42));
+
+	let first = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let second = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	assert_eq!(first.subscription_id(), &SubscriptionId::Num(42));
+	assert_eq!(second.subscription_id(), &SubscriptionId::Num(43));
+}
+
+#[test]
+fn full_range_subscription_ids_exceed_the_js_safe_range() {
+	use jsonrpsee::core::id_providers::{FullRangeIntegerIdProvider, RandomIntegerIdProvider};
+	use jsonrpsee::core::traits::IdProvider;
+	use jsonrpsee::types::SubscriptionId;
+
+	const JS_SAFE_MAX: u64 = (1 << 53) - 1;
+	let num = |id: SubscriptionId| match id {
+		SubscriptionId::Num(n) => n,
+		SubscriptionId::Str(s) => panic!("expected a numeric ID, got {:?}", s),
+	};
+
+	assert!((0..256).all(|_| num(RandomIntegerIdProvider.next_id()) <= JS_SAFE_MAX));
+	// Each random ID fits into 53 bits with a probability of 2^-11, so this fails with a probability of 2^-352.
+	assert!((0..32).any(|_| num(FullRangeIntegerIdProvider.next_id()) > JS_SAFE_MAX));
+}
+
+#[tokio::test]
+async fn identical_subscriptions_on_a_connection_can_be_deduplicated() {
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	// Subscriptions opened before are not indexed, so they aren't reused.
+	let before = module.subscribe("my_sub", ["a"]).await.unwrap();
+	module.deduplicate_subscriptions(true);
+
+	let first = module.subscribe("my_sub", ["a"]).await.unwrap();
+	assert_ne!(before.subscription_id(), first.subscription_id());
+	let second = module.subscribe("my_sub", ["a"]).await.unwrap();
+	let other = module.subscribe("my_sub", ["b"]).await.unwrap();
+	assert_eq!(first.subscription_id(), second.subscription_id());
+	assert_ne!(first.subscription_id(), other.subscription_id());
+
+	// Once unsubscribed, an identical call opens a new subscription.
+	let sub_id = first.subscription_id().clone();
+	let unsubscribed: bool = module.call("my_unsub", [&sub_id]).await.unwrap();
+	assert!(unsubscribed);
+	let third = module.subscribe("my_sub", ["a"]).await.unwrap();
+	assert_ne!(third.subscription_id(), &sub_id);
+}
+
+#[tokio::test]
+async fn cached_responses_are_served_without_running_the_method() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::time::Duration;
+
+	let runs = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(runs.clone());
+	module
+		.register_method("chain_getHeader", |params, runs| {
+			runs.fetch_add(1, Ordering::SeqCst);
+			params.one::<u64>().map(|n| n * 2).map_err(Into::into)
+		})
+		.unwrap();
+	module
+		.register_async_method("chain_getBlock", |params, runs| async move {
+			runs.fetch_add(1, Ordering::SeqCst);
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			params.one::<u64>().map_err(Into::into)
+		})
+		.unwrap();
+	let headers = module.cache_responses("chain_getHeader", Duration::from_secs(60)).unwrap();
+	module.cache_responses("chain_getBlock", Duration::from_millis(200)).unwrap();
+	let runs = || runs.load(Ordering::SeqCst);
+
+	assert_eq!(module.call::<_, u64>("chain_getHeader", [21_u64]).await.unwrap(), 42);
+	assert_eq!(module.call::<_, u64>("chain_getHeader", [21_u64]).await.unwrap(), 42);
+	assert_eq!(runs(), 1);
+	assert_eq!(module.call::<_, u64>("chain_getHeader", [1_u64]).await.unwrap(), 2);
+	assert_eq!(runs(), 2);
+
+	// Errors aren't cached.
+	assert!(module.call::<_, u64>("chain_getHeader", ["a"]).await.is_err());
+	assert!(module.call::<_, u64>("chain_getHeader", ["a"]).await.is_err());
+	assert_eq!(runs(), 4);
+
+	headers.clear();
+	assert_eq!(module.call::<_, u64>("chain_getHeader", [21_u64]).await.unwrap(), 42);
+	assert_eq!(runs(), 5);
+
+	// Concurrent calls with the same params wait for the running one.
+	let (a, b) = tokio::join!(
+		module.call::<_, u64>("chain_getBlock", [7_u64]),
+		module.call::<_, u64>("chain_getBlock", [7_u64])
+	);
+	assert_eq!((a.unwrap(), b.unwrap()), (7, 7));
+	assert_eq!(runs(), 6);
+
+	tokio::time::sleep(Duration::from_millis(250)).await;
+	assert_eq!(module.call::<_, u64>("chain_getBlock", [7_u64]).await.unwrap(), 7);
+	assert_eq!(runs(), 7);
+}
+
+#[tokio::test]
+async fn cached_responses_are_bounded() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::time::Duration;
+
+	let runs = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(runs.clone());
+	module
+		.register_method("chain_getHeader", |params, runs| {
+			runs.fetch_add(1, Ordering::SeqCst);
+			params.one::<u64>().map_err(Into::into)
+		})
+		.unwrap();
+	let headers = module.cache_responses("chain_getHeader", Duration::from_secs(60)).unwrap();
+	headers.set_max_entries(2);
+	let runs = || runs.load(Ordering::SeqCst);
+
+	for n in 0..3_u64 {
+		assert_eq!(module.call::<_, u64>("chain_getHeader", [n]).await.unwrap(), n);
+	}
+	assert_eq!(runs(), 3);
+
+	// The oldest result was evicted to make room for the last one.
+	assert_eq!(module.call::<_, u64>("chain_getHeader", [2_u64]).await.unwrap(), 2);
+	assert_eq!(module.call::<_, u64>("chain_getHeader", [1_u64]).await.unwrap(), 1);
+	assert_eq!(runs(), 3);
+	assert_eq!(module.call::<_, u64>("chain_getHeader", [0_u64]).await.unwrap(), 0);
+	assert_eq!(runs(), 4);
+}
+
+#[tokio::test]
+async fn calls_with_the_wrong_number_of_params_are_rejected() {
+	let mut module = RpcModule::new(());
+	module
+		.register_method("add", |params, _| {
+			let (a, b): (u64, u64) = params.parse()?;
+			Ok(a + b)
+		})
+		.unwrap();
+	module.expect_params("add", 2).unwrap();
+	assert!(matches!(module.expect_params("unknown", 2), Err(Error::MethodNotFound(_))));
+
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"add","params":[1],"id":1}"#).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Expected 2 params, got 1"},"id":1}"#);
+
+	let (resp, _) =
+		module.raw_json_request(r#"{"jsonrpc":"2.0","method":"add","params":[1, 2, 3],"id":2}"#).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Expected 2 params, got 3"},"id":2}"#);
+
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"add","id":3}"#).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Expected 2 params, got 0"},"id":3}"#);
+
+	let sum: u64 = module.call("add", [1, 2]).await.unwrap();
+	assert_eq!(sum, 3);
+}
@@ -0,0 +1,155 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! A newtype to send binary data in params and results without the overhead of hex or JSON arrays.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Binary data that is serialized as a base64 string using the standard alphabet with padding
+/// ([RFC 4648 section 4](https://datatracker.ietf.org/doc/html/rfc4648#section-4)).
+///
+/// This makes the payload about 33% larger than the raw data, compared to 100% for hex encoding.
+///
+/// ```
+/// use jsonrpsee_types::Bytes;
+///
+/// let bytes = Bytes::from(vec![0xde, 0xad, 0xbe, 0xef]);
+/// assert_eq!(serde_json::to_string(&bytes).unwrap(), r#""3q2+7w==""#);
+/// assert_eq!(serde_json::from_str::<Bytes>(r#""3q2+7w==""#).unwrap(), bytes);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Bytes {
+	/// Create a new [`Bytes`].
+	pub fn new(data: Vec<u8>) -> Self {
+		Self(data)
+	}
+
+	/// Consume the wrapper and return the underlying bytes.
+	pub fn into_inner(self) -> Vec<u8> {
+		self.0
+	}
+}
+
+impl From<Vec<u8>> for Bytes {
+	fn from(data: Vec<u8>) -> Self {
+		Self(data)
+	}
+}
+
+impl From<&[u8]> for Bytes {
+	fn from(data: &[u8]) -> Self {
+		Self(data.to_vec())
+	}
+}
+
+impl From<Bytes> for Vec<u8> {
+	fn from(bytes: Bytes) -> Self {
+		bytes.0
+	}
+}
+
+impl AsRef<[u8]> for Bytes {
+	fn as_ref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
+impl Deref for Bytes {
+	type Target = Vec<u8>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl DerefMut for Bytes {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.0
+	}
+}
+
+impl Serialize for Bytes {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&base64::encode(&self.0))
+	}
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_str(BytesVisitor)
+	}
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+	type Value = Bytes;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a base64 encoded string")
+	}
+
+	fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+	where
+		E: de::Error,
+	{
+		base64::decode(v).map(Bytes).map_err(|e| E::custom(format_args!("invalid base64: {}", e)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Bytes;
+
+	#[test]
+	fn serialize_and_deserialize_roundtrip() {
+		let bytes: Bytes = (0..=255u8).collect::<Vec<_>>().into();
+		let ser = serde_json::to_string(&bytes).unwrap();
+		// 86 groups of 4 characters, plus the quotes.
+		assert_eq!(ser.len(), 86 * 4 + 2);
+		assert_eq!(serde_json::from_str::<Bytes>(&ser).unwrap(), bytes);
+
+		assert_eq!(serde_json::to_string(&Bytes::default()).unwrap(), r#""""#);
+	}
+
+	#[test]
+	fn deserialize_invalid_base64_fails() {
+		assert!(serde_json::from_str::<Bytes>(r#""not base64!""#).is_err());
+		assert!(serde_json::from_str::<Bytes>("[1,2,3]").is_err());
+	}
+}
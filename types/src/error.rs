@@ -291,6 +291,19 @@ mod tests {
 		assert_eq!(exp, err);
 	}
 
+	#[test]
+	fn structured_data_roundtrips() {
+		for data in [r#"[1,"two",{"three":3.0}]"#, r#"{"errors":[{"field":"name"}],"count":1}"#, "42"] {
+			let ser = format!(
+				r#"{{"jsonrpc":"2.0","error":{{"code":-32602,"message":"Invalid params","data":{}}},"id":1}}"#,
+				data
+			);
+			let err: ErrorResponse = serde_json::from_str(&ser).unwrap();
+			assert_eq!(err.error.data.map(|d| d.get()), Some(data));
+			assert_eq!(serde_json::to_string(&err).unwrap(), ser);
+		}
+	}
+
 	#[test]
 	fn deserialized_error_with_quoted_str() {
 		let raw = r#"{
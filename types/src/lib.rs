@@ -43,10 +43,19 @@ pub mod response;
 /// JSON-RPC response error object related types.
 pub mod error;
 
+/// Binary data carried as base64 strings.
+pub mod bytes;
+
+/// JSON-RPC 1.0 compatibility types.
+pub mod v1;
+
+pub use bytes::Bytes;
 pub use error::ErrorResponse;
-pub use params::{Id, Params, ParamsSequence, ParamsSer, SubscriptionId, TwoPointZero};
-pub use request::{InvalidRequest, Notification, NotificationSer, Request, RequestSer};
-pub use response::{Response, SubscriptionPayload, SubscriptionResponse};
+pub use params::{Id, Params, ParamsSequence, ParamsSer, SubscriptionId, TwoPointZero, Version};
+pub use request::{InvalidRequest, Notification, NotificationSer, Request, RequestSer, CANCEL_REQUEST_METHOD};
+pub use response::{
+	ProgressNotification, ProgressPayload, Response, SubscriptionChunk, SubscriptionPayload, SubscriptionResponse,
+};
 
 /// Empty `RpcParams` type;
 pub type EmptyParams = Vec<()>;
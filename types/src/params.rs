@@ -33,9 +33,10 @@ use crate::error::CallError;
 use alloc::collections::BTreeMap;
 use anyhow::anyhow;
 use beef::Cow;
-use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::de::{self, Deserializer, IgnoredAny, Unexpected, Visitor};
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
 use serde_json::Value as JsonValue;
 
 /// JSON-RPC v2 marker type.
@@ -80,23 +81,69 @@ impl Serialize for TwoPointZero {
 	}
 }
 
+/// JSON-RPC version of an incoming request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Version {
+	/// [JSON-RPC 1.0](https://www.jsonrpc.org/specification_v1), requests without a `jsonrpc` member.
+	V1,
+	/// JSON-RPC 2.0, requests with `"jsonrpc":"2.0"`.
+	V2,
+}
+
+impl<'de> Deserialize<'de> for Version {
+	/// Only `"2.0"` is valid, JSON-RPC 1.0 requests have no `jsonrpc` member at all.
+	fn deserialize<D>(deserializer: D) -> Result<Version, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		TwoPointZero::deserialize(deserializer).map(|_| Version::V2)
+	}
+}
+
 /// Parameters sent with an incoming JSON-RPC request.
 ///
 /// The data containing the params is a `Cow<&str>` and can either be a borrowed `&str` of JSON from an incoming
 /// [`super::request::Request`] (which in turn borrows it from the input buffer that is shared between requests);
 /// or, it can be an owned [`String`].
 #[derive(Clone, Debug)]
-pub struct Params<'a>(Option<Cow<'a, str>>);
+pub struct Params<'a> {
+	json: Option<Cow<'a, str>>,
+	version: Version,
+}
 
 impl<'a> Params<'a> {
-	/// Create params
+	/// Create params of a JSON-RPC 2.0 request.
 	pub fn new(raw: Option<&'a str>) -> Self {
-		Self(raw.map(|r| r.trim().into()))
+		Self { json: raw.map(|r| r.trim().into()), version: Version::V2 }
+	}
+
+	/// Set the JSON-RPC version of the request the params were sent with.
+	pub fn with_version(mut self, version: Version) -> Self {
+		self.version = version;
+		self
+	}
+
+	/// Returns the JSON-RPC version of the request the params were sent with.
+	pub fn version(&self) -> Version {
+		self.version
+	}
+
+	/// Returns the raw JSON of the params, if any.
+	pub fn as_str(&self) -> Option<&str> {
+		self.json.as_deref()
+	}
+
+	/// Returns the raw JSON of the params, if any and if it is valid JSON.
+	///
+	/// This is an escape hatch for params that [`Params::parse`] and [`Params::one`] can't handle,
+	/// for example to run a custom deserializer on them.
+	pub fn as_raw(&self) -> Option<&RawValue> {
+		self.json.as_deref().and_then(|json| serde_json::from_str(json).ok())
 	}
 
 	/// Returns true if the contained JSON is an object
 	pub fn is_object(&self) -> bool {
-		let json: &str = match self.0 {
+		let json: &str = match self.json {
 			Some(ref cow) => cow,
 			None => return false,
 		};
@@ -108,7 +155,7 @@ impl<'a> Params<'a> {
 	/// This allows sequential parsing of the incoming params, using an `Iterator`-style API and is useful when the RPC
 	/// request has optional parameters at the tail that may or may not be present.
 	pub fn sequence(&self) -> ParamsSequence {
-		let json = match self.0.as_ref() {
+		let json = match self.json.as_ref() {
 			// It's assumed that params is `[a,b,c]`, if empty regard as no params.
 			Some(json) if json == "[]" => "",
 			Some(json) => json,
@@ -117,17 +164,89 @@ impl<'a> Params<'a> {
 		ParamsSequence(json)
 	}
 
+	/// Returns an iterator that parses each positional parameter to type `T` as it goes, for methods that take a
+	/// variable number of parameters of the same type.
+	///
+	/// The iteration stops after the first parameter that fails to parse. Params by name (a JSON object) yield a
+	/// single error and missing params yield nothing.
+	///
+	/// ```
+	/// # use jsonrpsee_types::params::Params;
+	/// let params = Params::new(Some(r#"["0x01", "0x02", "0x03"]"#));
+	/// let hashes: Vec<&str> = params.sequence_of().collect::<Result<_, _>>().unwrap();
+	///
+	/// assert_eq!(hashes, ["0x01", "0x02", "0x03"]);
+	/// ```
+	pub fn sequence_of<'b, T>(&'b self) -> impl Iterator<Item = Result<T, CallError>> + 'b
+	where
+		T: Deserialize<'b> + 'b,
+	{
+		let mut seq = self.sequence();
+		let mut failed = false;
+		std::iter::from_fn(move || {
+			if failed {
+				return None;
+			}
+			let next = seq.next_inner::<T>();
+			failed = matches!(next, Some(Err(_)));
+			next
+		})
+	}
+
+	/// Returns the number of positional parameters.
+	///
+	/// Params by name (a JSON object) and missing params are regarded as zero positional parameters.
+	pub fn len(&self) -> usize {
+		self.iter().count()
+	}
+
+	/// Returns true if there are no positional parameters, see [`Params::len`].
+	pub fn is_empty(&self) -> bool {
+		self.iter().next().is_none()
+	}
+
+	/// Returns an iterator over the raw JSON of each positional parameter.
+	///
+	/// This is useful for methods that accept a variable number of parameters, each parameter
+	/// can be parsed individually with [`serde_json::from_str`] once its shape is known.
+	///
+	/// ```
+	/// # use jsonrpsee_types::params::Params;
+	/// let params = Params::new(Some(r#"[1, "two", {"three": 3}]"#));
+	/// let raw: Vec<&str> = params.iter().map(|p| p.get()).collect();
+	///
+	/// assert_eq!(raw, ["1", r#""two""#, r#"{"three": 3}"#]);
+	/// ```
+	pub fn iter(&self) -> impl Iterator<Item = &RawValue> {
+		let mut seq = if self.is_object() { ParamsSequence("") } else { self.sequence() };
+		std::iter::from_fn(move || seq.next_inner::<&RawValue>().and_then(Result::ok))
+	}
+
 	/// Attempt to parse all parameters as an array or map into type `T`.
 	pub fn parse<T>(&'a self) -> Result<T, CallError>
 	where
 		T: Deserialize<'a>,
 	{
 		// NOTE(niklasad1): Option::None is serialized as `null` so we provide that here.
-		let params = self.0.as_ref().map(AsRef::as_ref).unwrap_or("null");
+		let params = self.json.as_ref().map(AsRef::as_ref).unwrap_or("null");
 		serde_json::from_str(params).map_err(|e| CallError::InvalidParams(e.into()))
 	}
 
 	/// Attempt to parse parameters as an array of a single value of type `T`, and returns that value.
+	///
+	/// `T` may borrow from the params, parsing a `&str` doesn't allocate. Borrowing fails for strings containing
+	/// escape sequences, which must be parsed as a `String` instead.
+	///
+	/// ```
+	/// # use jsonrpsee_types::params::Params;
+	/// let params = Params::new(Some(r#"["0xdeadbeef"]"#));
+	/// let hash: &str = params.one().unwrap();
+	/// assert_eq!(hash, "0xdeadbeef");
+	///
+	/// let params = Params::new(Some(r#"["quoted \"hash\""]"#));
+	/// assert!(params.one::<&str>().is_err());
+	/// assert_eq!(params.one::<String>().unwrap(), r#"quoted "hash""#);
+	/// ```
 	pub fn one<T>(&'a self) -> Result<T, CallError>
 	where
 		T: Deserialize<'a>,
@@ -135,11 +254,34 @@ impl<'a> Params<'a> {
 		self.parse::<[T; 1]>().map(|[res]| res)
 	}
 
+	/// Attempt to parse parameters as an array of at most one value of type `T`, and returns that value or
+	/// `T::default()` when the parameter is missing or `null`.
+	///
+	/// ```
+	/// # use jsonrpsee_types::params::Params;
+	/// let limit: u32 = Params::new(Some("[10]")).one_or_default().unwrap();
+	/// assert_eq!(limit, 10);
+	///
+	/// let limit: u32 = Params::new(None).one_or_default().unwrap();
+	/// assert_eq!(limit, 0);
+	/// ```
+	pub fn one_or_default<T>(&'a self) -> Result<T, CallError>
+	where
+		T: Deserialize<'a> + Default,
+	{
+		let mut seq = self.sequence();
+		let value = seq.optional_next::<T>()?.unwrap_or_default();
+		match seq.next_inner::<IgnoredAny>() {
+			None => Ok(value),
+			Some(_) => Err(CallError::InvalidParams(anyhow!("Expected at most one param"))),
+		}
+	}
+
 	/// Convert `Params<'a>` to `Params<'static>` so that it can be moved across threads.
 	///
 	/// This will cause an allocation if the params internally are using a borrowed JSON slice.
 	pub fn into_owned(self) -> Params<'static> {
-		Params(self.0.map(|s| Cow::owned(s.into_owned())))
+		Params { json: self.json.map(|s| Cow::owned(s.into_owned())), version: self.version }
 	}
 }
 
@@ -250,6 +392,37 @@ impl<'a> ParamsSequence<'a> {
 			None => Ok(None),
 		}
 	}
+
+	/// Parse the trailing object of options that follows the positional parameters to type `T`.
+	///
+	/// The result will be `T::default()` for `null` and for missing options, and an error if more parameters follow.
+	///
+	/// ```
+	/// # use jsonrpsee_types::params::Params;
+	/// #[derive(Default, serde::Deserialize)]
+	/// struct Options {
+	///     full: bool,
+	/// }
+	///
+	/// let params = Params::new(Some(r#"["0x01", {"full": true}]"#));
+	/// let mut seq = params.sequence();
+	///
+	/// let hash: &str = seq.next().unwrap();
+	/// let options: Options = seq.options().unwrap();
+	///
+	/// assert_eq!(hash, "0x01");
+	/// assert!(options.full);
+	/// ```
+	pub fn options<T>(&mut self) -> Result<T, CallError>
+	where
+		T: Deserialize<'a> + Default,
+	{
+		let options = self.optional_next::<T>()?.unwrap_or_default();
+		match self.next_inner::<IgnoredAny>() {
+			None => Ok(options),
+			Some(_) => Err(CallError::InvalidParams(anyhow!("Expected the options to be the last param"))),
+		}
+	}
 }
 
 /// [Serializable JSON-RPC parameters](https://www.jsonrpc.org/specification#parameter_structures)
@@ -405,6 +578,69 @@ mod test {
 	use super::{Cow, Id, JsonValue, Params, ParamsSer, SubscriptionId, TwoPointZero};
 	use crate::response::SubscriptionPayload;
 
+	#[test]
+	fn params_sequence_of_parses_each_param() {
+		#[derive(Debug, PartialEq, serde::Deserialize)]
+		struct Hash(String);
+
+		let params = Params::new(Some(r#"["0xaa", "0xbb", "0xcc"]"#));
+		let hashes: Vec<Hash> = params.sequence_of().collect::<Result<_, _>>().unwrap();
+		assert_eq!(hashes, [Hash("0xaa".into()), Hash("0xbb".into()), Hash("0xcc".into())]);
+
+		let params = Params::new(Some(r#"["0xaa", 1, "0xcc"]"#));
+		let results: Vec<Result<Hash, _>> = params.sequence_of().collect();
+		assert_eq!(results.len(), 2);
+		assert!(results[0].is_ok());
+		assert!(results[1].is_err());
+
+		assert_eq!(Params::new(None).sequence_of::<Hash>().count(), 0);
+		assert_eq!(Params::new(Some("[]")).sequence_of::<Hash>().count(), 0);
+		assert!(Params::new(Some(r#"{"a": "0xaa"}"#)).sequence_of::<Hash>().all(|r| r.is_err()));
+		assert_eq!(Params::new(Some(r#"{"a": "0xaa"}"#)).sequence_of::<Hash>().count(), 1);
+	}
+
+	#[test]
+	fn params_one_or_default() {
+		assert_eq!(Params::new(Some("[7]")).one_or_default::<u32>().unwrap(), 7);
+		assert_eq!(Params::new(Some("[null]")).one_or_default::<u32>().unwrap(), 0);
+		assert_eq!(Params::new(Some("[]")).one_or_default::<u32>().unwrap(), 0);
+		assert_eq!(Params::new(None).one_or_default::<u32>().unwrap(), 0);
+
+		assert!(Params::new(Some(r#"["seven"]"#)).one_or_default::<u32>().is_err());
+		assert!(Params::new(Some("[7, 8]")).one_or_default::<u32>().is_err());
+		assert!(Params::new(Some(r#"{"a": 7}"#)).one_or_default::<u32>().is_err());
+	}
+
+	#[test]
+	fn params_sequence_trailing_options() {
+		#[derive(Debug, Default, PartialEq, serde::Deserialize)]
+		#[serde(default)]
+		struct Opts {
+			full: bool,
+			limit: Option<u32>,
+		}
+
+		let params = Params::new(Some(r#"["0xaa", {"full": true}]"#));
+		let mut seq = params.sequence();
+		let parsed: (&str, Opts) = (seq.next().unwrap(), seq.options().unwrap());
+		assert_eq!(parsed, ("0xaa", Opts { full: true, limit: None }));
+
+		let params = Params::new(Some(r#"["0xaa"]"#));
+		let mut seq = params.sequence();
+		let parsed: (&str, Opts) = (seq.next().unwrap(), seq.options().unwrap());
+		assert_eq!(parsed, ("0xaa", Opts::default()));
+
+		let params = Params::new(Some(r#"["0xaa", {"full": true}, 1]"#));
+		let mut seq = params.sequence();
+		assert_eq!(seq.next::<&str>().unwrap(), "0xaa");
+		assert!(seq.options::<Opts>().is_err());
+
+		let params = Params::new(Some(r#"["0xaa", {"limit": "ten"}]"#));
+		let mut seq = params.sequence();
+		assert_eq!(seq.next::<&str>().unwrap(), "0xaa");
+		assert!(seq.options::<Opts>().is_err());
+	}
+
 	#[test]
 	fn id_deserialization() {
 		let s = r#""2""#;
@@ -495,6 +731,47 @@ mod test {
 		assert!(obj.is_ok());
 	}
 
+	#[test]
+	fn params_as_raw() {
+		assert!(Params::new(None).as_raw().is_none());
+		assert!(Params::new(Some("[1")).as_raw().is_none());
+
+		// A tolerant decoder accepting hex numbers with or without the `0x` prefix.
+		fn parse_hex(params: &Params) -> Vec<u64> {
+			let raw = params.as_raw().unwrap();
+			let items: Vec<&str> = serde_json::from_str(raw.get()).unwrap();
+			items.iter().map(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).unwrap()).collect()
+		}
+
+		let params = Params::new(Some(r#" ["0x1f", "ff", "0x0"] "#));
+		assert_eq!(params.as_raw().unwrap().get(), r#"["0x1f", "ff", "0x0"]"#);
+		assert_eq!(parse_hex(&params), [31, 255, 0]);
+	}
+
+	#[test]
+	fn params_len_and_iter() {
+		let none = Params::new(None);
+		assert_eq!(none.len(), 0);
+		assert!(none.is_empty());
+
+		let empty = Params::new(Some("[]"));
+		assert_eq!(empty.len(), 0);
+		assert!(empty.is_empty());
+
+		let one = Params::new(Some(r#"["foo"]"#));
+		assert_eq!(one.len(), 1);
+		assert!(!one.is_empty());
+
+		let three = Params::new(Some(r#"[1, [2, 3], {"a": null}]"#));
+		assert_eq!(three.len(), 3);
+		let raw: Vec<&str> = three.iter().map(|p| p.get()).collect();
+		assert_eq!(raw, ["1", "[2, 3]", r#"{"a": null}"#]);
+
+		let object = Params::new(Some(r#"{"beef":99,"dinner":0}"#));
+		assert_eq!(object.len(), 0);
+		assert!(object.is_empty());
+	}
+
 	#[test]
 	fn params_sequence_borrows() {
 		let params = Params::new(Some(r#"["foo", "bar"]"#));
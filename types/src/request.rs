@@ -27,17 +27,26 @@
 //! Types to handle JSON-RPC requests according to the [spec](https://www.jsonrpc.org/specification#request-object).
 //! Some types come with a "*Ser" variant that implements [`serde::Serialize`]; these are used in the client.
 
-use crate::params::{Id, ParamsSer, TwoPointZero};
+use crate::params::{Id, ParamsSer, TwoPointZero, Version};
 use beef::Cow;
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
 
+/// Method of the notification a client sends to tell the server that it's no longer interested in the response to a
+/// call, with the ID of that call as the only parameter.
+///
+/// This is not part of the JSON-RPC spec, both the client and the server have to opt into it.
+pub const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+
 /// JSON-RPC request object as defined in the [spec](https://www.jsonrpc.org/specification#request-object).
+///
+/// Members other than the ones defined by the spec are ignored. Requests without a `jsonrpc` member are
+/// [JSON-RPC 1.0](https://www.jsonrpc.org/specification_v1) requests.
 #[derive(Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
 pub struct Request<'a> {
 	/// JSON-RPC version.
-	pub jsonrpc: TwoPointZero,
+	#[serde(default = "version_one")]
+	pub jsonrpc: Version,
 	/// Request ID
 	#[serde(borrow)]
 	pub id: Id<'a>,
@@ -50,12 +59,16 @@ pub struct Request<'a> {
 }
 
 impl<'a> Request<'a> {
-	/// Create a new [`Request`].
+	/// Create a new JSON-RPC 2.0 [`Request`].
 	pub fn new(method: Cow<'a, str>, params: Option<&'a RawValue>, id: Id<'a>) -> Self {
-		Self { jsonrpc: TwoPointZero, id, method, params }
+		Self { jsonrpc: Version::V2, id, method, params }
 	}
 }
 
+fn version_one() -> Version {
+	Version::V1
+}
+
 /// JSON-RPC Invalid request as defined in the [spec](https://www.jsonrpc.org/specification#request-object).
 #[derive(Deserialize, Debug, PartialEq)]
 pub struct InvalidRequest<'a> {
@@ -66,8 +79,9 @@ pub struct InvalidRequest<'a> {
 
 /// JSON-RPC notification (a request object without a request ID) as defined in the
 /// [spec](https://www.jsonrpc.org/specification#request-object).
+///
+/// Members other than the ones defined by the spec are ignored.
 #[derive(Serialize, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
 pub struct Notification<'a, T> {
 	/// JSON-RPC version.
 	pub jsonrpc: TwoPointZero,
@@ -131,11 +145,13 @@ impl<'a> NotificationSer<'a> {
 
 #[cfg(test)]
 mod test {
-	use super::{Id, InvalidRequest, Notification, NotificationSer, ParamsSer, Request, RequestSer, TwoPointZero};
+	use super::{
+		Id, InvalidRequest, Notification, NotificationSer, ParamsSer, Request, RequestSer, TwoPointZero, Version,
+	};
 	use serde_json::{value::RawValue, Value};
 
 	fn assert_request<'a>(request: Request<'a>, id: Id<'a>, method: &str, params: Option<&str>) {
-		assert_eq!(request.jsonrpc, TwoPointZero);
+		assert_eq!(request.jsonrpc, Version::V2);
 		assert_eq!(request.id, id);
 		assert_eq!(request.method, method);
 		assert_eq!(request.params.map(RawValue::get), params);
@@ -190,6 +206,17 @@ mod test {
 		assert_eq!(dsr.jsonrpc, TwoPointZero);
 	}
 
+	#[test]
+	fn deserialize_call_without_version() {
+		let ser = r#"{"method":"getblockhash","params":[1],"id":1}"#;
+		let req: Request = serde_json::from_str(ser).unwrap();
+		assert_eq!(req.jsonrpc, Version::V1);
+		assert_eq!(req.method, "getblockhash");
+
+		let ser = r#"{"jsonrpc":"1.0","method":"getblockhash","params":[1],"id":1}"#;
+		assert!(serde_json::from_str::<Request>(ser).is_err());
+	}
+
 	#[test]
 	fn deserialize_call_bad_id_should_fail() {
 		let ser = r#"{"jsonrpc":"2.0","method":"say_hello","params":[],"id":{}}"#;
@@ -29,6 +29,7 @@
 use crate::params::{Id, SubscriptionId, TwoPointZero};
 use crate::request::Notification;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 /// JSON-RPC successful response object as defined in the [spec](https://www.jsonrpc.org/specification#response_object).
 #[derive(Serialize, Deserialize, Debug)]
@@ -60,9 +61,37 @@ pub struct SubscriptionPayload<'a, T> {
 	pub result: T,
 }
 
+/// A piece of a subscription result that was too large to be sent in one message.
+///
+/// The result is serialized to JSON and `data` holds the `seq`th of `total` consecutive slices of it,
+/// the receiver concatenates them in order and parses the JSON once the last chunk arrives.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SubscriptionChunk<'a> {
+	/// Position of the chunk, starting at zero.
+	pub seq: u32,
+	/// Total number of chunks the result was split into.
+	pub total: u32,
+	/// Slice of the serialized result.
+	pub data: Cow<'a, str>,
+}
+
 /// Subscription response object, embedding a [`SubscriptionPayload`] in the `params` member.
 pub type SubscriptionResponse<'a, T> = Notification<'a, SubscriptionPayload<'a, T>>;
 
+/// Intermediate progress of a call, sent before its response.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProgressPayload<'a, T> {
+	/// ID of the call the progress is about.
+	#[serde(borrow)]
+	pub id: Id<'a>,
+	/// Progress.
+	pub progress: T,
+}
+
+/// Progress notification object, embedding a [`ProgressPayload`] in the `params` member.
+pub type ProgressNotification<'a, T> = Notification<'a, ProgressPayload<'a, T>>;
+
 #[cfg(test)]
 mod tests {
 	use super::{Id, Response, TwoPointZero};
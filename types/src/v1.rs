@@ -0,0 +1,119 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Types to talk to legacy servers that speak [JSON-RPC 1.0](https://www.jsonrpc.org/specification_v1).
+//!
+//! JSON-RPC 1.0 objects carry no `jsonrpc` version field, params are always positional and a
+//! response contains both `result` and `error`, one of which is `null`.
+
+use crate::params::{Id, ParamsSer};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+/// Serializable [JSON-RPC 1.0 request object](https://www.jsonrpc.org/specification_v1#a1.1Requestmethodinvocation).
+///
+/// Notifications are requests with a `null` ID.
+#[derive(Serialize, Debug)]
+pub struct RequestSer<'a> {
+	/// Name of the method to be invoked.
+	pub method: &'a str,
+	/// Parameter values of the request, serialized as an empty array if there are none.
+	#[serde(serialize_with = "serialize_params")]
+	pub params: Option<ParamsSer<'a>>,
+	/// Request ID
+	pub id: &'a Id<'a>,
+}
+
+impl<'a> RequestSer<'a> {
+	/// Create a new serializable JSON-RPC 1.0 request.
+	pub fn new(id: &'a Id<'a>, method: &'a str, params: Option<ParamsSer<'a>>) -> Self {
+		Self { method, params, id }
+	}
+}
+
+fn serialize_params<S: serde::Serializer>(params: &Option<ParamsSer>, serializer: S) -> Result<S::Ok, S::Error> {
+	match params {
+		Some(params) => params.serialize(serializer),
+		None => ParamsSer::ArrayRef(&[]).serialize(serializer),
+	}
+}
+
+/// [JSON-RPC 1.0 response object](https://www.jsonrpc.org/specification_v1#a1.2Response).
+///
+/// The call succeeded if `error` is `None`; a `None` result then stands for a `null` result.
+#[derive(Deserialize, Debug)]
+pub struct Response<'a, T> {
+	/// Result.
+	pub result: Option<T>,
+	/// Error, its shape is not defined by the specification.
+	#[serde(borrow)]
+	pub error: Option<&'a RawValue>,
+	/// Request ID
+	#[serde(borrow)]
+	pub id: Id<'a>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Id, ParamsSer, RequestSer, Response};
+	use crate::error::{ErrorCode, ErrorObject};
+
+	#[test]
+	fn serialize_request_without_version() {
+		let id = Id::Number(1);
+		let params = ParamsSer::Array(vec![1.into(), "a".into()]);
+
+		let ser = serde_json::to_string(&RequestSer::new(&id, "getblockhash", Some(params))).unwrap();
+		assert_eq!(ser, r#"{"method":"getblockhash","params":[1,"a"],"id":1}"#);
+
+		let ser = serde_json::to_string(&RequestSer::new(&id, "getblockcount", None)).unwrap();
+		assert_eq!(ser, r#"{"method":"getblockcount","params":[],"id":1}"#);
+	}
+
+	#[test]
+	fn deserialize_success_response() {
+		let dsr: Response<u64> = serde_json::from_str(r#"{"result":99,"error":null,"id":11}"#).unwrap();
+		assert_eq!(dsr.result, Some(99));
+		assert!(dsr.error.is_none());
+		assert_eq!(dsr.id, Id::Number(11));
+
+		let dsr: Response<u64> = serde_json::from_str(r#"{"result":null,"error":null,"id":"x"}"#).unwrap();
+		assert_eq!(dsr.result, None);
+		assert!(dsr.error.is_none());
+		assert_eq!(dsr.id, Id::Str("x".into()));
+	}
+
+	#[test]
+	fn deserialize_error_response() {
+		let ser = r#"{"result":null,"error":{"code":-32601,"message":"Method not found"},"id":1}"#;
+		let dsr: Response<u64> = serde_json::from_str(ser).unwrap();
+		assert_eq!(dsr.result, None);
+		assert_eq!(dsr.id, Id::Number(1));
+
+		let err: ErrorObject = serde_json::from_str(dsr.error.unwrap().get()).unwrap();
+		assert_eq!(err, ErrorCode::MethodNotFound.into());
+	}
+}
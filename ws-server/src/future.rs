@@ -32,11 +32,14 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Weak};
 use std::task::{Context, Poll};
 
-use futures_util::future::FutureExt;
+use futures_util::future::{self, FutureExt};
 use futures_util::task::AtomicWaker;
+use jsonrpsee_core::server::rpc_module::Methods;
 use jsonrpsee_core::Error;
 use tokio::time::{self, Duration, Interval};
 
+use crate::server::LiveMethods;
+
 /// Polling for server stop monitor interval in milliseconds.
 const STOP_MONITOR_POLLING_INTERVAL: u64 = 1000;
 
@@ -83,6 +86,21 @@ where
 		DriverSelect { selector, driver: self }.await
 	}
 
+	/// Drive the futures until fewer than `max` of them remain or the server is stopped.
+	pub(crate) async fn drive_below(&mut self, max: usize, stop_monitor: &StopMonitor) {
+		future::poll_fn(|cx| {
+			self.drive(cx);
+			self.poll_stop_monitor_heartbeat(cx);
+
+			if self.futures.len() < max || stop_monitor.shutdown_requested() {
+				Poll::Ready(())
+			} else {
+				Poll::Pending
+			}
+		})
+		.await
+	}
+
 	fn drive(&mut self, cx: &mut Context) {
 		let mut i = 0;
 
@@ -181,36 +199,56 @@ impl StopMonitor {
 		self.0.shutdown_requested.load(Ordering::Relaxed)
 	}
 
-	pub(crate) fn handle(&self) -> ServerHandle {
-		ServerHandle(Arc::downgrade(&self.0))
+	pub(crate) fn handle(&self, methods: &Arc<LiveMethods>) -> ServerHandle {
+		ServerHandle { monitor: Arc::downgrade(&self.0), methods: Arc::downgrade(methods) }
 	}
 }
 
 /// Handle that is able to stop the running server or wait for it to finish
 /// its execution.
 #[derive(Debug, Clone)]
-pub struct ServerHandle(Weak<MonitorInner>);
+pub struct ServerHandle {
+	monitor: Weak<MonitorInner>,
+	methods: Weak<LiveMethods>,
+}
 
 impl ServerHandle {
 	/// Requests server to stop. Returns an error if server was already stopped.
 	///
 	/// Returns a future that can be awaited for when the server shuts down.
 	pub fn stop(self) -> Result<ShutdownWaiter, Error> {
-		if let Some(arc) = Weak::upgrade(&self.0) {
+		if let Some(arc) = Weak::upgrade(&self.monitor) {
 			// We proceed only if the previous value of the flag was `false`
 			if !arc.shutdown_requested.swap(true, Ordering::Relaxed) {
-				return Ok(ShutdownWaiter(self.0));
+				return Ok(ShutdownWaiter(self.monitor));
 			}
 		}
 		Err(Error::AlreadyStopped)
 	}
+
+	/// Replace the methods of the running server by `methods`, for instance to serve a new version of the API
+	/// without dropping the connections.
+	///
+	/// Requests received afterwards are dispatched to the new methods, the ones received before complete with the old
+	/// methods and the subscriptions made until then stay active. Those subscriptions are closed by the unsubscribe
+	/// method of the new methods that goes with a subscribe method of the same name. The methods are prepared like the
+	/// ones given to [`WsServer::start`](crate::WsServer::start), which fails the same way. Returns an error if the
+	/// server was stopped.
+	pub fn swap_methods(&self, methods: impl Into<Methods>) -> Result<(), Error> {
+		match (Weak::upgrade(&self.monitor), Weak::upgrade(&self.methods)) {
+			(Some(monitor), Some(live)) if !monitor.shutdown_requested.load(Ordering::Relaxed) => {
+				live.swap(methods.into())
+			}
+			_ => Err(Error::AlreadyStopped),
+		}
+	}
 }
 
 impl Future for ServerHandle {
 	type Output = ();
 
 	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-		let mut shutdown_waiter = ShutdownWaiter(self.0.clone());
+		let mut shutdown_waiter = ShutdownWaiter(self.monitor.clone());
 
 		shutdown_waiter.poll_unpin(cx)
 	}
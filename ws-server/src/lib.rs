@@ -39,8 +39,10 @@ mod server;
 mod tests;
 
 pub use future::{ServerHandle as WsServerHandle, ShutdownWaiter as WsShutdownWaiter};
+pub use jsonrpsee_core::server::connection_state::ConnectionStates;
+pub use jsonrpsee_core::server::executor::{Executor, TokioExecutor};
 pub use jsonrpsee_core::server::rpc_module::{RpcModule, SubscriptionSink};
 pub use jsonrpsee_core::{id_providers::*, traits::IdProvider};
 pub use jsonrpsee_types as types;
-pub use server::{Builder as WsServerBuilder, Server as WsServer};
+pub use server::{Builder as WsServerBuilder, QueueOverflowPolicy, Server as WsServer};
 pub use tracing;
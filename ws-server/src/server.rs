@@ -24,43 +24,110 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::net::SocketAddr;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use crate::future::{FutureDriver, ServerHandle, StopMonitor};
 use crate::types::error::ErrorCode;
-use crate::types::{Id, Request};
-use futures_channel::mpsc;
-use futures_util::future::{join_all, FutureExt};
-use futures_util::io::{BufReader, BufWriter};
+use crate::types::{Id, CANCEL_REQUEST_METHOD};
+use arc_swap::ArcSwap;
+use futures_channel::{mpsc, oneshot};
+use futures_util::future::{join_all, AbortHandle, Abortable, Aborted, FutureExt};
+use futures_util::io::{AsyncRead, AsyncWrite, BufReader, BufWriter};
 use futures_util::stream::StreamExt;
-use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
+use jsonrpsee_core::id_providers::{FullRangeIntegerIdProvider, RandomIntegerIdProvider};
 use jsonrpsee_core::middleware::Middleware;
-use jsonrpsee_core::server::helpers::{collect_batch_response, prepare_error, MethodSink};
+use jsonrpsee_core::server::connection_state::ConnectionStates;
+use jsonrpsee_core::server::executor::{Executor, TokioExecutor};
+use jsonrpsee_core::server::helpers::{
+	batch_too_large, collect_batch_response, duplicate_keys_error, has_unknown_fields, health_module, parse_request,
+	prepare_error, ActiveSubscriptions, BufferedMessages, MethodSink, ParsedRequest, RawNotification,
+};
+use jsonrpsee_core::server::ip_filter::IpFilter;
 use jsonrpsee_core::server::resource_limiting::Resources;
-use jsonrpsee_core::server::rpc_module::{ConnState, ConnectionId, MethodKind, Methods};
+use jsonrpsee_core::server::rpc_module::{ConnState, ConnectionId, ConnectionInfo, MethodKind, Methods};
 use jsonrpsee_core::traits::IdProvider;
-use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
+use jsonrpsee_core::{json_depth_exceeds, Error, MAX_JSON_DEPTH, TEN_MB_SIZE_BYTES};
 use jsonrpsee_types::Params;
 use soketto::connection::Error as SokettoError;
+use soketto::data::ByteSlice125;
 use soketto::handshake::{server::Response, Server as SokettoServer};
 use soketto::Sender;
 use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
 use tokio::sync::Notify;
-use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+use tokio_util::compat::TokioAsyncReadCompatExt;
 
 /// Default maximum connections allowed.
 const MAX_CONNECTIONS: u64 = 100;
+/// Default number of messages waiting to be written to a connection above which subscriptions aren't ready.
+const MAX_BUFFERED_MESSAGES: usize = 1024;
+/// Default maximum number of async calls and batches of a connection that are processed at the same time.
+const MAX_PENDING_REQUESTS: usize = 1024;
+
+/// What to do with a request of a connection that already has as many pending requests as allowed, see
+/// [`Builder::max_pending_requests`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueOverflowPolicy {
+	/// Stop reading from the connection until a pending request completes, which slows the client down.
+	Backpressure,
+	/// Answer async calls and batches with a server is busy error without processing them.
+	Shed,
+}
+
+/// Methods that a running server dispatches calls to, which [`ServerHandle::swap_methods`] replaces.
+#[derive(Debug)]
+pub(crate) struct LiveMethods {
+	methods: ArcSwap<Methods>,
+	/// Name of the built-in health method, if enabled.
+	health_endpoint: Option<&'static str>,
+	/// Whether method names are looked up ignoring ASCII case when there is no exact match.
+	case_insensitive_method_names: bool,
+	resources: Resources,
+}
+
+impl LiveMethods {
+	fn new(cfg: &Settings, resources: &Resources) -> Self {
+		Self {
+			methods: ArcSwap::from_pointee(Methods::new()),
+			health_endpoint: cfg.health_endpoint,
+			case_insensitive_method_names: cfg.case_insensitive_method_names,
+			resources: resources.clone(),
+		}
+	}
+
+	/// Replace the methods, after adding the built-in ones and initializing their resources, such that the
+	/// subscriptions opened with the previous methods can be closed with the new ones.
+	pub(crate) fn swap(&self, mut methods: Methods) -> Result<(), Error> {
+		if let Some(name) = self.health_endpoint {
+			methods.merge(health_module(name))?;
+		}
+		if self.case_insensitive_method_names {
+			methods = methods.into_case_insensitive()?;
+		}
+		let methods = methods.initialize_resources(&self.resources)?;
+		methods.carry_over_subscriptions(&self.load());
+		self.methods.store(Arc::new(methods));
+		Ok(())
+	}
+
+	fn load(&self) -> Arc<Methods> {
+		self.methods.load_full()
+	}
+}
 
 /// A WebSocket JSON RPC server.
 pub struct Server<M> {
-	listener: TcpListener,
+	listeners: Vec<TcpListener>,
 	cfg: Settings,
 	stop_monitor: StopMonitor,
 	resources: Resources,
+	methods: Arc<LiveMethods>,
 	middleware: M,
 	id_provider: Arc<dyn IdProvider>,
 }
@@ -68,7 +135,7 @@ pub struct Server<M> {
 impl<M> std::fmt::Debug for Server<M> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.debug_struct("Server")
-			.field("listener", &self.listener)
+			.field("listeners", &self.listeners)
 			.field("cfg", &self.cfg)
 			.field("stop_monitor", &self.stop_monitor)
 			.field("id_provider", &self.id_provider)
@@ -78,41 +145,51 @@ impl<M> std::fmt::Debug for Server<M> {
 }
 
 impl<M: Middleware> Server<M> {
-	/// Returns socket address to which the server is bound.
+	/// Returns socket address to which the server is bound, the first one if it's bound to several.
 	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
-		self.listener.local_addr().map_err(Into::into)
+		self.listeners[0].local_addr().map_err(Into::into)
+	}
+
+	/// Returns all the socket addresses to which the server is bound, see [`Builder::build_all`].
+	pub fn local_addrs(&self) -> Result<Vec<SocketAddr>, Error> {
+		self.listeners.iter().map(|listener| listener.local_addr().map_err(Into::into)).collect()
 	}
 
 	/// Returns the handle to stop the running server.
 	pub fn server_handle(&self) -> ServerHandle {
-		self.stop_monitor.handle()
+		self.stop_monitor.handle(&self.methods)
 	}
 
-	/// Start responding to connections requests. This will run on the tokio runtime until the server is stopped.
-	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
-		let methods = methods.into().initialize_resources(&self.resources)?;
+	/// Start responding to connections requests. This will run on the executor of the server, the tokio runtime by
+	/// default, until the server is stopped.
+	pub fn start(self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
+		self.methods.swap(methods.into())?;
 		let handle = self.server_handle();
 
-		match self.cfg.tokio_runtime.take() {
-			Some(rt) => rt.spawn(self.start_inner(methods)),
-			None => tokio::spawn(self.start_inner(methods)),
-		};
+		let executor = self.cfg.executor.clone();
+		executor.spawn(self.start_inner().boxed());
 
 		Ok(handle)
 	}
 
-	async fn start_inner(self, methods: Methods) {
+	async fn start_inner(self) {
+		let methods = self.methods;
 		let stop_monitor = self.stop_monitor;
 		let resources = self.resources;
 		let middleware = self.middleware;
 
 		let mut id = 0;
 		let mut connections = FutureDriver::default();
-		let mut incoming = Monitored::new(Incoming(self.listener), &stop_monitor);
+		let mut incoming = Monitored::new(Incoming { listeners: self.listeners, next: 0 }, &stop_monitor);
 
 		loop {
 			match connections.select_with(&mut incoming).await {
-				Ok((socket, _addr)) => {
+				Ok((socket, remote_addr)) => {
+					if !self.cfg.ip_filter.is_allowed(remote_addr.ip()) {
+						tracing::warn!("Dropping connection from denied address {}", remote_addr);
+						continue;
+					}
+
 					if let Err(e) = socket.set_nodelay(true) {
 						tracing::error!("Could not set NODELAY on socket: {:?}", e);
 						continue;
@@ -132,6 +209,7 @@ impl<M: Middleware> Server<M> {
 						socket,
 						HandshakeResponse::Accept {
 							conn_id: id,
+							remote_addr,
 							methods,
 							resources: &resources,
 							cfg,
@@ -173,7 +251,11 @@ enum MonitoredError<E> {
 	Selector(E),
 }
 
-struct Incoming(TcpListener);
+struct Incoming {
+	listeners: Vec<TcpListener>,
+	/// Index of the listener polled first, rotated so that a busy listener can't starve the others.
+	next: usize,
+}
 
 impl<'a> Future for Monitored<'a, Incoming> {
 	type Output = Result<(TcpStream, SocketAddr), MonitoredError<std::io::Error>>;
@@ -185,7 +267,17 @@ impl<'a> Future for Monitored<'a, Incoming> {
 			return Poll::Ready(Err(MonitoredError::Shutdown));
 		}
 
-		this.future.0.poll_accept(cx).map_err(MonitoredError::Selector)
+		let incoming = &mut this.future;
+		let len = incoming.listeners.len();
+
+		for offset in 0..len {
+			let idx = (incoming.next + offset) % len;
+			if let Poll::Ready(res) = incoming.listeners[idx].poll_accept(cx) {
+				incoming.next = (idx + 1) % len;
+				return Poll::Ready(res.map_err(MonitoredError::Selector));
+			}
+		}
+		Poll::Pending
 	}
 }
 
@@ -212,7 +304,8 @@ enum HandshakeResponse<'a, M> {
 	},
 	Accept {
 		conn_id: ConnectionId,
-		methods: &'a Methods,
+		remote_addr: SocketAddr,
+		methods: &'a Arc<LiveMethods>,
 		resources: &'a Resources,
 		cfg: &'a Settings,
 		stop_monitor: &'a StopMonitor,
@@ -225,11 +318,9 @@ async fn handshake<M>(socket: tokio::net::TcpStream, mode: HandshakeResponse<'_,
 where
 	M: Middleware,
 {
-	// For each incoming background_task we perform a handshake.
-	let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket.compat())));
-
 	match mode {
 		HandshakeResponse::Reject { status_code } => {
+			let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket.compat())));
 			// Forced rejection, don't need to read anything from the socket
 			let reject = Response::Reject { status_code };
 			server.send_response(&reject).await?;
@@ -241,20 +332,61 @@ where
 
 			Ok(())
 		}
-		HandshakeResponse::Accept { conn_id, methods, resources, cfg, stop_monitor, middleware, id_provider } => {
+		HandshakeResponse::Accept {
+			conn_id,
+			remote_addr,
+			methods,
+			resources,
+			cfg,
+			stop_monitor,
+			middleware,
+			id_provider,
+		} => {
 			tracing::debug!("Accepting new connection: {}", conn_id);
-			let key = {
+
+			#[cfg(feature = "http")]
+			if cfg.accept_http {
+				return serve_http_or_ws(
+					socket,
+					conn_id,
+					remote_addr,
+					methods,
+					resources,
+					cfg,
+					stop_monitor,
+					middleware,
+					id_provider,
+				)
+				.await;
+			}
+
+			// For each incoming background_task we perform a handshake.
+			let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket.compat())));
+			let handshake = {
 				let req = server.receive_request().await?;
 				let host_check = cfg.allowed_hosts.verify("Host", Some(req.headers().host));
 				let origin_check = cfg.allowed_origins.verify("Origin", req.headers().origin);
 
 				host_check.and(origin_check).map(|()| req.key())
 			};
+			// soketto compares whole header values with the supported protocols, which misses the protocols of
+			// comma-separated lists, so they are read from the raw request instead.
+			let request = server.take_buffer();
+			let protocol = select_protocol(&cfg.protocols, protocol_headers(&request));
+			server.set_buffer(request);
+			let handshake = handshake.map(|key| (key, protocol));
+
+			let protocol = match handshake {
+				Ok((_, None)) if !cfg.protocols.is_empty() => {
+					let reject = Response::Reject { status_code: 400 };
+					server.send_response(&reject).await?;
 
-			match key {
-				Ok(key) => {
-					let accept = Response::Accept { key, protocol: None };
+					return Err(no_supported_protocol());
+				}
+				Ok((key, protocol)) => {
+					let accept = Response::Accept { key, protocol: protocol.as_deref() };
 					server.send_response(&accept).await?;
+					protocol
 				}
 				Err(error) => {
 					let reject = Response::Reject { status_code: 403 };
@@ -262,40 +394,264 @@ where
 
 					return Err(error);
 				}
-			}
+			};
 
-			let join_result = tokio::spawn(background_task(
-				server,
+			run_connection(
+				server.into_builder(),
 				conn_id,
-				methods.clone(),
-				resources.clone(),
-				cfg.max_request_body_size,
-				stop_monitor.clone(),
+				remote_addr,
+				protocol,
+				methods,
+				resources,
+				cfg,
+				stop_monitor,
 				middleware,
 				id_provider,
-			))
-			.await;
+			)
+			.await
+		}
+	}
+}
 
-			match join_result {
-				Err(_) => Err(Error::Custom("Background task was aborted".into())),
-				Ok(result) => result,
-			}
+/// The first protocol requested in the `Sec-WebSocket-Protocol` header `values` that the server supports.
+fn select_protocol<'a>(supported: &[String], values: impl Iterator<Item = &'a str>) -> Option<Arc<str>> {
+	values
+		.flat_map(|value| value.split(','))
+		.map(str::trim)
+		.find(|requested| supported.iter().any(|p| p == requested))
+		.map(Into::into)
+}
+
+/// The values of the `Sec-WebSocket-Protocol` headers of a raw handshake request.
+fn protocol_headers(request: &[u8]) -> impl Iterator<Item = &str> {
+	request
+		.split(|&b| b == b'\n')
+		.take_while(|line| !line.is_empty() && *line != b"\r")
+		.filter_map(|line| std::str::from_utf8(line).ok())
+		.filter_map(|line| line.split_once(':'))
+		.filter(|(name, _)| name.trim().eq_ignore_ascii_case("Sec-WebSocket-Protocol"))
+		.map(|(_, value)| value)
+}
+
+/// Error of a handshake that requests none of the subprotocols supported by the server.
+fn no_supported_protocol() -> Error {
+	let error = "Sec-WebSocket-Protocol denied: none of the requested subprotocols is supported".to_owned();
+	tracing::warn!("{}", error);
+	Error::Request(error)
+}
+
+/// Serve a connection on which the WebSocket handshake has been performed until it is closed.
+#[allow(clippy::too_many_arguments)]
+async fn run_connection<S, M>(
+	builder: soketto::connection::Builder<S>,
+	conn_id: ConnectionId,
+	remote_addr: SocketAddr,
+	protocol: Option<Arc<str>>,
+	methods: &Arc<LiveMethods>,
+	resources: &Resources,
+	cfg: &Settings,
+	stop_monitor: &StopMonitor,
+	middleware: M,
+	id_provider: Arc<dyn IdProvider>,
+) -> Result<(), Error>
+where
+	S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+	M: Middleware,
+{
+	let conn = ConnectionInfo::new(conn_id, Some(remote_addr)).with_protocol(protocol);
+	let connection_state = cfg.connection_state.as_ref().map(|hooks| {
+		(hooks.on_connect)(&conn);
+		ConnectionStateGuard { hooks, conn_id }
+	});
+
+	let task = background_task(
+		builder,
+		conn,
+		methods.clone(),
+		resources.clone(),
+		cfg.max_request_body_size,
+		cfg.reject_duplicate_keys,
+		cfg.reject_unknown_fields,
+		cfg.max_json_depth,
+		cfg.max_batch_size,
+		cfg.request_cancellation,
+		cfg.max_buffered_messages,
+		cfg.max_pending_requests,
+		cfg.queue_overflow_policy,
+		cfg.idle_connection_timeout,
+		cfg.subscription_ping_interval,
+		stop_monitor.clone(),
+		middleware,
+		id_provider,
+		cfg.executor.clone(),
+	);
+	// The result is dropped along with the task if it panics.
+	let (result_tx, result_rx) = oneshot::channel();
+	cfg.executor.spawn(
+		async move {
+			let _ = result_tx.send(task.await);
 		}
+		.boxed(),
+	);
+	let join_result = result_rx.await;
+	drop(connection_state);
+
+	match join_result {
+		Err(_) => Err(Error::Custom("Background task was aborted".into())),
+		Ok(result) => result,
 	}
 }
 
-async fn background_task(
-	server: SokettoServer<'_, BufReader<BufWriter<Compat<tokio::net::TcpStream>>>>,
+/// Serve a connection with HTTP, answering JSON-RPC requests and switching to WebSocket on an upgrade request.
+#[cfg(feature = "http")]
+#[allow(clippy::too_many_arguments)]
+async fn serve_http_or_ws<M>(
+	socket: TcpStream,
 	conn_id: ConnectionId,
-	methods: Methods,
+	remote_addr: SocketAddr,
+	methods: &Arc<LiveMethods>,
+	resources: &Resources,
+	cfg: &Settings,
+	stop_monitor: &StopMonitor,
+	middleware: M,
+	id_provider: Arc<dyn IdProvider>,
+) -> Result<(), Error>
+where
+	M: Middleware,
+{
+	use futures_util::future::{self, BoxFuture};
+	use hyper::service::{service_fn, Service};
+	use jsonrpsee_http_server::RpcService;
+	use soketto::handshake::http::{is_upgrade_request, Server as UpgradeServer};
+
+	type PendingUpgrade = (UpgradeServer, hyper::upgrade::OnUpgrade, Option<Arc<str>>);
+
+	let upgrade: Arc<std::sync::Mutex<Option<PendingUpgrade>>> = Default::default();
+
+	let service = {
+		let upgrade = upgrade.clone();
+		let methods = methods.clone();
+		let resources = resources.clone();
+		let middleware = middleware.clone();
+		let cfg = cfg.clone();
+
+		service_fn(move |mut request: hyper::Request<hyper::Body>| -> BoxFuture<'static, Result<_, hyper::Error>> {
+			let header = |name: &str| request.headers().get(name).map(|value| value.as_bytes());
+			let host_check = cfg.allowed_hosts.verify("Host", header("host"));
+			let origin_check = cfg.allowed_origins.verify("Origin", header("origin"));
+			if host_check.and(origin_check).is_err() {
+				let mut response = hyper::Response::new(hyper::Body::empty());
+				*response.status_mut() = hyper::StatusCode::FORBIDDEN;
+				return future::ready(Ok(response)).boxed();
+			}
+
+			if is_upgrade_request(&request) {
+				let protocol = select_protocol(
+					&cfg.protocols,
+					request
+						.headers()
+						.get_all(hyper::header::SEC_WEBSOCKET_PROTOCOL)
+						.iter()
+						.filter_map(|value| value.to_str().ok()),
+				);
+				if protocol.is_none() && !cfg.protocols.is_empty() {
+					let error = no_supported_protocol();
+					let mut response = hyper::Response::new(hyper::Body::from(error.to_string()));
+					*response.status_mut() = hyper::StatusCode::BAD_REQUEST;
+					return future::ready(Ok(response)).boxed();
+				}
+
+				let mut server = UpgradeServer::new();
+				let response = match server.receive_request(&request) {
+					Ok(response) => {
+						let mut response = response.map(|()| hyper::Body::empty());
+						if let Some(protocol) = &protocol {
+							let value = hyper::header::HeaderValue::from_str(protocol)
+								.expect("the protocol was parsed from a header value; qed");
+							response.headers_mut().insert(hyper::header::SEC_WEBSOCKET_PROTOCOL, value);
+						}
+						let on_upgrade = hyper::upgrade::on(&mut request);
+						*upgrade.lock().expect("lock is not poisoned; qed") = Some((server, on_upgrade, protocol));
+						response
+					}
+					Err(e) => {
+						tracing::warn!("Could not upgrade connection: {}", e);
+						let mut response = hyper::Response::new(hyper::Body::from(format!("Could not upgrade: {}", e)));
+						*response.status_mut() = hyper::StatusCode::BAD_REQUEST;
+						response
+					}
+				};
+				return future::ready(Ok(response)).boxed();
+			}
+
+			let mut service = RpcService::new(
+				(*methods.load()).clone(),
+				resources.clone(),
+				middleware.clone(),
+				cfg.max_request_body_size,
+				cfg.max_json_depth,
+				remote_addr,
+			)
+			.reject_duplicate_keys(cfg.reject_duplicate_keys)
+			.reject_unknown_fields(cfg.reject_unknown_fields)
+			.max_batch_size(cfg.max_batch_size);
+			service.call(request)
+		})
+	};
+
+	hyper::server::conn::Http::new().http1_only(true).serve_connection(socket, service).with_upgrades().await?;
+
+	let pending = upgrade.lock().expect("lock is not poisoned; qed").take();
+	match pending {
+		Some((server, on_upgrade, protocol)) => {
+			let upgraded = on_upgrade.await?;
+			let builder = server.into_builder(BufReader::new(BufWriter::new(upgraded.compat())));
+			run_connection(
+				builder,
+				conn_id,
+				remote_addr,
+				protocol,
+				methods,
+				resources,
+				cfg,
+				stop_monitor,
+				middleware,
+				id_provider,
+			)
+			.await
+		}
+		None => Ok(()),
+	}
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn background_task<S>(
+	mut builder: soketto::connection::Builder<S>,
+	conn: ConnectionInfo,
+	live_methods: Arc<LiveMethods>,
 	resources: Resources,
 	max_request_body_size: u32,
+	reject_duplicate_keys: bool,
+	reject_unknown_fields: bool,
+	max_json_depth: u32,
+	max_batch_size: Option<u32>,
+	request_cancellation: bool,
+	max_buffered_messages: usize,
+	max_pending_requests: usize,
+	queue_overflow_policy: QueueOverflowPolicy,
+	idle_connection_timeout: Option<Duration>,
+	subscription_ping_interval: Option<Duration>,
 	stop_server: StopMonitor,
 	middleware: impl Middleware,
 	id_provider: Arc<dyn IdProvider>,
-) -> Result<(), Error> {
+	executor: Arc<dyn Executor>,
+) -> Result<(), Error>
+where
+	S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+	let conn_id = conn.id;
+
 	// And we can finally transition to a websocket background_task.
-	let mut builder = server.into_builder();
 	builder.set_max_message_size(max_request_body_size as usize);
 	let (mut sender, mut receiver) = builder.finish();
 	let (tx, mut rx) = mpsc::unbounded::<String>();
@@ -303,16 +659,39 @@ async fn background_task(
 	let close_notify_server_stop = close_notify.clone();
 
 	let stop_server2 = stop_server.clone();
-	let sink = MethodSink::new_with_limit(tx, max_request_body_size);
+	let buffered = BufferedMessages::new(max_buffered_messages);
+	let subscriptions = ActiveSubscriptions::new();
+	let sink = MethodSink::new_with_limit(tx, max_request_body_size)
+		.with_buffered_messages(buffered.clone())
+		.with_active_subscriptions(subscriptions.clone());
+	let active_subscriptions = subscriptions.clone();
 
 	middleware.on_connect();
 
 	// Send results back to the client.
-	tokio::spawn(async move {
+	executor.spawn(Box::pin(async move {
 		while !stop_server2.shutdown_requested() {
-			if let Some(response) = rx.next().await {
+			let next = match subscription_ping_interval {
+				Some(interval) => match tokio::time::timeout(interval, rx.next()).await {
+					Ok(next) => next,
+					// Nothing was sent for `interval`, keep the connection open if it has subscriptions.
+					Err(_) => {
+						if subscriptions.count() > 0 {
+							if let Err(err) = send_ws_ping(&mut sender).await {
+								tracing::error!("WS transport error: {:?}; terminate connection", err);
+								break;
+							}
+						}
+						continue;
+					}
+				},
+				None => rx.next().await,
+			};
+			if let Some(response) = next {
 				// If websocket message send fail then terminate the connection.
-				if let Err(err) = send_ws_message(&mut sender, response).await {
+				let res = send_ws_message(&mut sender, response).await;
+				buffered.written();
+				if let Err(err) = res {
 					tracing::error!("WS transport error: {:?}; terminate connection", err);
 					break;
 				}
@@ -321,30 +700,57 @@ async fn background_task(
 			}
 		}
 
+		buffered.close();
+
 		// Terminate connection and send close message.
 		let _ = sender.close().await;
 
 		// Force `conn_tx` to this async block and close it down
 		// when the connection closes to be on safe side.
 		close_notify_server_stop.notify_one();
-	});
+	}));
 
 	// Buffer for incoming data.
 	let mut data = Vec::with_capacity(100);
+	// Async method calls that the client may still cancel.
+	let cancelable_calls = CancelableCalls::default();
+	let cancelable_calls = &cancelable_calls;
 	let mut method_executors = FutureDriver::default();
 	let middleware = &middleware;
 
 	let result = loop {
 		data.clear();
 
+		if queue_overflow_policy == QueueOverflowPolicy::Backpressure {
+			method_executors.drive_below(max_pending_requests, &stop_server).await;
+		}
+
 		{
 			// Need the extra scope to drop this pinned future and reclaim access to `data`
-			let receive = receiver.receive_data(&mut data);
+			let receive = async {
+				match idle_connection_timeout {
+					// `None` once the connection has been idle for too long.
+					Some(timeout) => {
+						tokio::time::timeout(timeout, receiver.receive_data(&mut data)).await.ok().transpose()
+					}
+					None => receiver.receive_data(&mut data).await.map(Some),
+				}
+			};
 
 			tokio::pin!(receive);
 
-			if let Err(err) = method_executors.select_with(Monitored::new(receive, &stop_server)).await {
-				match err {
+			match method_executors.select_with(Monitored::new(receive, &stop_server)).await {
+				Ok(Some(_)) => (),
+				Ok(None) => {
+					tracing::debug!(
+						"Closing connection {} that has been idle for {:?}",
+						conn_id,
+						idle_connection_timeout
+					);
+					sink.close();
+					break Ok(());
+				}
+				Err(err) => match err {
 					MonitoredError::Selector(SokettoError::Closed) => {
 						tracing::debug!("WS transport error: remote peer terminated the connection: {}", conn_id);
 						sink.close();
@@ -366,34 +772,74 @@ async fn background_task(
 						break Err(err.into());
 					}
 					MonitoredError::Shutdown => break Ok(()),
-				};
-			};
+				},
+			}
 		};
 
 		tracing::debug!("recv {} bytes", data.len());
 
+		// The task sending the responses is gone, nothing we do for this request would reach the client.
+		if sink.is_closed() {
+			tracing::debug!("WS transport error: can't send responses on connection {}; terminate connection", conn_id);
+			break Ok(());
+		}
+
 		let request_start = middleware.on_request();
+		middleware.on_queue_depth(method_executors.count());
+		let shed_requests =
+			queue_overflow_policy == QueueOverflowPolicy::Shed && method_executors.count() >= max_pending_requests;
+
+		if json_depth_exceeds(&data, max_json_depth) {
+			tracing::debug!("recv request nested deeper than {}; rejecting", max_json_depth);
+			sink.send_error(Id::Null, ErrorCode::ParseError.into());
+			middleware.on_response(request_start);
+			continue;
+		}
+
+		if let Some(code) = reject_duplicate_keys.then(|| duplicate_keys_error(&data)).flatten() {
+			tracing::debug!("recv request with duplicate keys or which can't be checked for them; rejecting");
+			sink.send_error(Id::Null, code.into());
+			middleware.on_response(request_start);
+			continue;
+		}
+
+		if reject_unknown_fields && has_unknown_fields(&data) {
+			tracing::debug!("recv request with unknown fields; rejecting");
+			sink.send_error(prepare_error(&data).0, ErrorCode::InvalidRequest.into());
+			middleware.on_response(request_start);
+			continue;
+		}
+
+		// Calls are dispatched to the methods at the time they are received, even if they are swapped meanwhile.
+		let methods = live_methods.load();
 
 		match data.get(0) {
-			Some(b'{') => {
-				if let Ok(req) = serde_json::from_slice::<Request>(&data) {
+			Some(b'{') => match parse_request(&data) {
+				Ok(ParsedRequest::Call(req)) => {
 					tracing::debug!("recv method call={}", req.method);
 					tracing::trace!("recv: req={:?}", req);
 
 					let id = req.id.clone();
-					let params = Params::new(req.params.map(|params| params.get()));
+					let params = Params::new(req.params.map(|params| params.get())).with_version(req.jsonrpc);
 
 					middleware.on_call(&req.method);
+					middleware.on_version(&req.method, req.jsonrpc);
 
 					match methods.method_with_name(&req.method) {
 						None => {
-							sink.send_error(req.id, ErrorCode::MethodNotFound.into());
-							middleware.on_response(request_start);
+							let fallback = methods.call_fallback(req.id, &req.method, req.params, &sink, conn_id);
+							method_executors.add(
+								async move {
+									fallback.await;
+									middleware.on_response(request_start);
+								}
+								.boxed(),
+							);
 						}
 						Some((name, method)) => match &method.inner() {
 							MethodKind::Sync(callback) => match method.claim(name, &resources) {
 								Ok(guard) => {
-									let result = (callback)(id, params, &sink);
+									let result = (callback)(id, params, &sink, &conn);
 
 									middleware.on_result(name, result, request_start);
 									middleware.on_response(request_start);
@@ -409,19 +855,50 @@ async fn background_task(
 									middleware.on_response(request_start);
 								}
 							},
+							MethodKind::Async(_) if shed_requests => {
+								tracing::debug!("Too many pending requests; shedding call to {}", name);
+								sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+								middleware.on_result(name, false, request_start);
+								middleware.on_response(request_start);
+							}
 							MethodKind::Async(callback) => match method.claim(name, &resources) {
 								Ok(guard) => {
+									let callback = callback.clone();
 									let sink = sink.clone();
 									let id = id.into_owned();
 									let params = params.into_owned();
+									let conn = conn.clone();
 
 									let fut = async move {
-										let result = (callback)(id, params, sink, conn_id, Some(guard)).await;
+										let result = (callback)(id, params, sink, conn, Some(guard)).await;
 										middleware.on_result(name, result, request_start);
 										middleware.on_response(request_start);
 									};
 
-									method_executors.add(fut.boxed());
+									if request_cancellation {
+										let id = req.id.into_owned();
+										let (handle, registration) = AbortHandle::new_pair();
+										let token = cancelable_calls.insert(id.clone(), handle);
+
+										let fut = async move {
+											match Abortable::new(fut, registration).await {
+												Ok(()) => cancelable_calls.remove(&id, token),
+												// The client removed the call when cancelling it.
+												Err(Aborted) => {
+													tracing::debug!(
+														"Call to {} with id {:?} cancelled by the client",
+														name,
+														id
+													);
+													middleware.on_result(name, false, request_start);
+													middleware.on_response(request_start);
+												}
+											}
+										};
+										method_executors.add(fut.boxed());
+									} else {
+										method_executors.add(fut.boxed());
+									}
 								}
 								Err(err) => {
 									tracing::error!(
@@ -456,45 +933,72 @@ async fn background_task(
 							},
 						},
 					}
-				} else {
-					let (id, code) = prepare_error(&data);
-					sink.send_error(id, code.into());
+				}
+				Ok(ParsedRequest::Notification(notif)) => {
+					if let Some(id) = request_cancellation.then(|| parse_cancel_request(&notif)).flatten() {
+						tracing::debug!("recv cancel request for id={:?}", id);
+						cancelable_calls.cancel(&id);
+					}
+					middleware.on_response(request_start);
+				}
+				Ok(ParsedRequest::Batch(_)) | Ok(ParsedRequest::NotificationBatch(_)) => {
+					unreachable!("A message starting with `{{` is not a batch; qed")
+				}
+				Err(err) => {
+					sink.send_error(err.id(), err.code().into());
 					middleware.on_response(request_start);
 				}
+			},
+			Some(b'[') if shed_requests => {
+				tracing::debug!("Too many pending requests; shedding batch");
+				sink.send_error(Id::Null, ErrorCode::ServerIsBusy.into());
+				middleware.on_response(request_start);
 			}
 			Some(b'[') => {
 				// Make sure the following variables are not moved into async closure below.
 				let d = std::mem::take(&mut data);
 				let resources = &resources;
-				let methods = &methods;
 				let sink = sink.clone();
 				let id_provider = id_provider.clone();
 				let close_notify2 = close_notify.clone();
+				let conn = &conn;
 
 				let fut = async move {
+					let methods = &methods;
 					// Batch responses must be sent back as a single message so we read the results from each
 					// request in the batch and read the results off of a new channel, `rx_batch`, and then send the
 					// complete batch response back to the client over `tx`.
 					let (tx_batch, mut rx_batch) = mpsc::unbounded();
-					let sink_batch = MethodSink::new_with_limit(tx_batch, max_request_body_size);
-					if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(&d) {
-						tracing::debug!("recv batch len={}", batch.len());
-						tracing::trace!("recv: batch={:?}", batch);
-						if !batch.is_empty() {
+					let sink_batch =
+						MethodSink::new_with_limit(tx_batch, max_request_body_size).without_notifications();
+					match parse_request(&d) {
+						Ok(ParsedRequest::Batch(batch))
+							if max_batch_size.is_some_and(|max| batch.len() > max as usize) =>
+						{
+							tracing::debug!("recv batch len={}; rejecting", batch.len());
+							sink.send_error(Id::Null, batch_too_large(max_batch_size.expect("checked above; qed")));
+							middleware.on_response(request_start);
+						}
+						Ok(ParsedRequest::Batch(batch)) => {
+							tracing::debug!("recv batch len={}", batch.len());
+							tracing::trace!("recv: batch={:?}", batch);
 							join_all(batch.into_iter().filter_map(move |req| {
 								let id = req.id.clone();
-								let params = Params::new(req.params.map(|params| params.get()));
+								let params =
+									Params::new(req.params.map(|params| params.get())).with_version(req.jsonrpc);
 								let name = &req.method;
 
 								match methods.method_with_name(name) {
-									None => {
-										sink_batch.send_error(req.id, ErrorCode::MethodNotFound.into());
-										None
-									}
+									None => Some(
+										methods
+											.call_fallback(req.id, name, req.params, &sink_batch, conn.id)
+											.map(|_| ())
+											.boxed(),
+									),
 									Some((name, method_callback)) => match &method_callback.inner() {
 										MethodKind::Sync(callback) => match method_callback.claim(name, resources) {
 											Ok(guard) => {
-												let result = (callback)(id, params, &sink_batch);
+												let result = (callback)(id, params, &sink_batch, conn);
 												middleware.on_result(name, result, request_start);
 												drop(guard);
 												None
@@ -509,30 +1013,35 @@ async fn background_task(
 												None
 											}
 										},
-										MethodKind::Async(callback) => match method_callback
-											.claim(&req.method, resources)
-										{
-											Ok(guard) => {
-												let sink_batch = sink_batch.clone();
-												let id = id.into_owned();
-												let params = params.into_owned();
-
-												Some(async move {
-													let result =
-														(callback)(id, params, sink_batch, conn_id, Some(guard)).await;
-													middleware.on_result(&req.method, result, request_start);
-												})
-											}
-											Err(err) => {
-												tracing::error!(
+										MethodKind::Async(callback) => {
+											match method_callback.claim(&req.method, resources) {
+												Ok(guard) => {
+													let sink_batch = sink_batch.clone();
+													let id = id.into_owned();
+													let params = params.into_owned();
+													let conn = conn.clone();
+
+													Some(
+														async move {
+															let result =
+																(callback)(id, params, sink_batch, conn, Some(guard))
+																	.await;
+															middleware.on_result(&req.method, result, request_start);
+														}
+														.boxed(),
+													)
+												}
+												Err(err) => {
+													tracing::error!(
 													"[Methods::execute_with_resources] failed to lock resources: {:?}",
 													err
 												);
-												sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
-												middleware.on_result(&req.method, false, request_start);
-												None
+													sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
+													middleware.on_result(&req.method, false, request_start);
+													None
+												}
 											}
-										},
+										}
 										MethodKind::Subscription(callback) => {
 											match method_callback.claim(&req.method, resources) {
 												Ok(guard) => {
@@ -570,14 +1079,16 @@ async fn background_task(
 							} else {
 								middleware.on_response(request_start);
 							}
-						} else {
-							sink.send_error(Id::Null, ErrorCode::InvalidRequest.into());
+						}
+						// Notifications are not answered.
+						Ok(ParsedRequest::NotificationBatch(_)) => middleware.on_response(request_start),
+						Ok(ParsedRequest::Call(_)) | Ok(ParsedRequest::Notification(_)) => {
+							unreachable!("A message starting with `[` is a batch; qed")
+						}
+						Err(err) => {
+							sink.send_error(err.id(), err.code().into());
 							middleware.on_response(request_start);
 						}
-					} else {
-						let (id, code) = prepare_error(&d);
-						sink.send_error(id, code.into());
-						middleware.on_response(request_start);
 					}
 				};
 
@@ -596,9 +1107,59 @@ async fn background_task(
 	// proper drop behaviour.
 	method_executors.await;
 
+	// Close the subscriptions whose producers didn't notice the connection is gone.
+	active_subscriptions.reset();
+
 	result
 }
 
+/// Async method calls of a connection that the client may still cancel, by ID.
+///
+/// The client may reuse the ID of a call that is still in flight, so the calls are also told apart by a token such
+/// that a call that completes only forgets its own [`AbortHandle`].
+#[derive(Default)]
+struct CancelableCalls {
+	calls: Mutex<HashMap<Id<'static>, Vec<(u64, AbortHandle)>>>,
+	next_token: AtomicU64,
+}
+
+impl CancelableCalls {
+	/// Add a call and return its token.
+	fn insert(&self, id: Id<'static>, handle: AbortHandle) -> u64 {
+		let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+		self.calls.lock().expect("not poisoned; qed").entry(id).or_default().push((token, handle));
+		token
+	}
+
+	/// Forget the call with `id` and `token` once it completed.
+	fn remove(&self, id: &Id<'static>, token: u64) {
+		let mut calls = self.calls.lock().expect("not poisoned; qed");
+		if let Some(handles) = calls.get_mut(id) {
+			handles.retain(|(call_token, _)| *call_token != token);
+			if handles.is_empty() {
+				calls.remove(id);
+			}
+		}
+	}
+
+	/// Abort all the calls with `id`.
+	fn cancel(&self, id: &Id<'static>) {
+		let handles = self.calls.lock().expect("not poisoned; qed").remove(id);
+		for (_, handle) in handles.into_iter().flatten() {
+			handle.abort();
+		}
+	}
+}
+
+/// Returns the ID of the call to cancel if `notif` is a [`CANCEL_REQUEST_METHOD`] notification.
+fn parse_cancel_request(notif: &RawNotification) -> Option<Id<'static>> {
+	if notif.method != CANCEL_REQUEST_METHOD {
+		return None;
+	}
+	let [id] = serde_json::from_str::<[Id; 1]>(notif.params?.get()).ok()?;
+	Some(id.into_owned())
+}
+
 #[derive(Debug, Clone)]
 enum AllowedValue {
 	Any,
@@ -630,8 +1191,66 @@ struct Settings {
 	allowed_origins: AllowedValue,
 	/// Policy by which to accept or deny incoming requests based on the `Host` header.
 	allowed_hosts: AllowedValue,
-	/// Custom tokio runtime to run the server on.
-	tokio_runtime: Option<tokio::runtime::Handle>,
+	/// Policy by which to accept or drop incoming connections based on the IP address of the peer.
+	ip_filter: IpFilter,
+	/// Subprotocols supported by the server, any (and no negotiation) if empty.
+	protocols: Arc<[String]>,
+	/// Executor to spawn the tasks of the server on.
+	executor: Arc<dyn Executor>,
+	/// Whether to reject requests that contain JSON objects with duplicate keys.
+	reject_duplicate_keys: bool,
+	/// Whether to reject requests with members other than `jsonrpc`, `method`, `params` and `id`.
+	reject_unknown_fields: bool,
+	/// Max nesting depth of JSON arrays and objects in requests.
+	max_json_depth: u32,
+	/// Max number of calls in a batch request.
+	max_batch_size: Option<u32>,
+	/// Name of the built-in health method, if enabled.
+	health_endpoint: Option<&'static str>,
+	/// Whether method names are looked up ignoring ASCII case when there is no exact match.
+	case_insensitive_method_names: bool,
+	/// Whether clients may cancel pending async method calls.
+	request_cancellation: bool,
+	/// Number of messages waiting to be written to a connection above which subscriptions aren't ready.
+	max_buffered_messages: usize,
+	/// Maximum number of async calls and batches of a connection that are processed at the same time.
+	max_pending_requests: usize,
+	/// What to do with requests above `max_pending_requests`.
+	queue_overflow_policy: QueueOverflowPolicy,
+	/// Duration without receiving any message after which a connection is closed, if configured.
+	idle_connection_timeout: Option<Duration>,
+	/// Duration without sending any message after which connections with subscriptions are pinged, if configured.
+	subscription_ping_interval: Option<Duration>,
+	/// Creates and removes the per-connection state, if configured.
+	connection_state: Option<ConnectionStateHooks>,
+	/// Whether to answer JSON-RPC requests over HTTP on connections that aren't upgraded to WebSocket.
+	#[cfg(feature = "http")]
+	accept_http: bool,
+}
+
+/// Type-erased lifecycle of the [`ConnectionStates`] given to [`Builder::set_connection_state`].
+#[derive(Clone)]
+struct ConnectionStateHooks {
+	on_connect: Arc<dyn Fn(&ConnectionInfo) + Send + Sync>,
+	on_disconnect: Arc<dyn Fn(ConnectionId) + Send + Sync>,
+}
+
+/// Removes the per-connection state when dropped, such that it's removed even if serving the connection is cancelled.
+struct ConnectionStateGuard<'a> {
+	hooks: &'a ConnectionStateHooks,
+	conn_id: ConnectionId,
+}
+
+impl Drop for ConnectionStateGuard<'_> {
+	fn drop(&mut self) {
+		(self.hooks.on_disconnect)(self.conn_id);
+	}
+}
+
+impl std::fmt::Debug for ConnectionStateHooks {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("ConnectionStateHooks").finish_non_exhaustive()
+	}
 }
 
 impl Default for Settings {
@@ -641,7 +1260,24 @@ impl Default for Settings {
 			max_connections: MAX_CONNECTIONS,
 			allowed_origins: AllowedValue::Any,
 			allowed_hosts: AllowedValue::Any,
-			tokio_runtime: None,
+			ip_filter: IpFilter::default(),
+			protocols: Arc::from(Vec::<String>::new()),
+			executor: Arc::new(TokioExecutor),
+			reject_duplicate_keys: false,
+			reject_unknown_fields: false,
+			max_json_depth: MAX_JSON_DEPTH,
+			max_batch_size: None,
+			health_endpoint: None,
+			case_insensitive_method_names: false,
+			request_cancellation: false,
+			max_buffered_messages: MAX_BUFFERED_MESSAGES,
+			max_pending_requests: MAX_PENDING_REQUESTS,
+			queue_overflow_policy: QueueOverflowPolicy::Backpressure,
+			idle_connection_timeout: None,
+			subscription_ping_interval: None,
+			connection_state: None,
+			#[cfg(feature = "http")]
+			accept_http: false,
 		}
 	}
 }
@@ -661,7 +1297,7 @@ impl Default for Builder {
 			settings: Settings::default(),
 			resources: Resources::default(),
 			middleware: (),
-			id_provider: Arc::new(RandomIntegerIdProvider),
+			id_provider: Arc::new(FullRangeIntegerIdProvider),
 		}
 	}
 }
@@ -680,6 +1316,147 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Reject requests that contain JSON objects with duplicate keys, such as `{"a":1,"a":2}`,
+	/// with an invalid request error. Default is false.
+	///
+	/// See [`has_duplicate_keys`](jsonrpsee_core::server::helpers::has_duplicate_keys) for why and how they're
+	/// detected, requests that can't be checked are answered with a parse error.
+	pub fn reject_duplicate_keys(mut self, reject: bool) -> Self {
+		self.settings.reject_duplicate_keys = reject;
+		self
+	}
+
+	/// Reject requests with members other than `jsonrpc`, `method`, `params` and `id`, such as
+	/// `{"jsonrpc":"2.0","method":"foo","id":1,"extra":1}`, with an invalid request error, as strict
+	/// JSON-RPC 2.0 validation requires.
+	///
+	/// Default is false, unknown members are ignored.
+	pub fn reject_unknown_fields(mut self, reject: bool) -> Self {
+		self.settings.reject_unknown_fields = reject;
+		self
+	}
+
+	/// Sets the maximum nesting depth of JSON arrays and objects in a request (default is 128).
+	///
+	/// Deeper requests are answered with a parse error without being deserialized.
+	pub fn max_json_depth(mut self, depth: u32) -> Self {
+		self.settings.max_json_depth = depth;
+		self
+	}
+
+	/// Sets the maximum number of calls in a batch request (default is no limit).
+	///
+	/// Larger batches are answered with an invalid request error without processing any of their calls.
+	pub fn max_batch_size(mut self, size: u32) -> Self {
+		self.settings.max_batch_size = Some(size);
+		self
+	}
+
+	/// Answer calls to the method `name` with `{"ok":true,"uptime":<seconds since start>}`, for liveness probes.
+	/// Disabled by default.
+	///
+	/// [`Server::start`] fails with [`Error::MethodAlreadyRegistered`] if the given methods already contain `name`.
+	pub fn with_health_endpoint(mut self, name: &'static str) -> Self {
+		self.settings.health_endpoint = Some(name);
+		self
+	}
+
+	/// Answer calls to methods whose name only differs in ASCII case from a registered method, for instance
+	/// `System_Chain` for `system_chain`, when no method matches exactly. Default is false.
+	///
+	/// [`Server::start`] fails with [`Error::MethodAlreadyRegistered`] if two of the given methods only differ in case.
+	pub fn case_insensitive_method_names(mut self, enabled: bool) -> Self {
+		self.settings.case_insensitive_method_names = enabled;
+		self
+	}
+
+	/// Stop processing an async method call when the client sends a [`CANCEL_REQUEST_METHOD`] notification with
+	/// the ID of the call, because it's no longer interested in the response. Default is false.
+	///
+	/// The future of the call is dropped at its next `.await` point and no response is sent. All the calls in flight
+	/// with that ID are cancelled if the client reused it. Synchronous methods, subscriptions and the calls of a batch
+	/// run to completion.
+	pub fn enable_request_cancellation(mut self, enabled: bool) -> Self {
+		self.settings.request_cancellation = enabled;
+		self
+	}
+
+	/// Close connections on which no message was received for `timeout`, to reclaim the resources of abandoned
+	/// clients. Default is to keep idle connections open.
+	///
+	/// Pings and the notifications of active subscriptions don't count as activity, only messages sent by the client.
+	pub fn idle_connection_timeout(mut self, timeout: Duration) -> Self {
+		self.settings.idle_connection_timeout = Some(timeout);
+		self
+	}
+
+	/// Send a WebSocket ping on connections with active subscriptions that haven't sent anything for `interval`,
+	/// so that proxies don't close them while the subscriptions are idle. Default is to not send pings.
+	pub fn subscription_ping_interval(mut self, interval: Duration) -> Self {
+		self.settings.subscription_ping_interval = Some(interval);
+		self
+	}
+
+	/// Set the number of messages waiting to be written to a connection above which
+	/// [`SubscriptionSink::ready`](jsonrpsee_core::server::rpc_module::SubscriptionSink::ready) waits for the client
+	/// to catch up. Default is 1024.
+	///
+	/// The messages are still queued if a subscription sends without waiting.
+	pub fn max_buffered_messages(mut self, max: usize) -> Self {
+		self.settings.max_buffered_messages = max;
+		self
+	}
+
+	/// Set the maximum number of async method calls and batches of a connection that are processed at the same
+	/// time. Default is 1024.
+	///
+	/// What happens to the requests above the limit is set with [`Builder::queue_overflow_policy`]: by default the
+	/// server stops reading from the connection until one of them completes. Synchronous methods and subscriptions
+	/// complete before the next request is read, so they don't count.
+	pub fn max_pending_requests(mut self, max: usize) -> Self {
+		self.settings.max_pending_requests = max;
+		self
+	}
+
+	/// Set what to do with the requests of a connection that has [`Builder::max_pending_requests`] requests
+	/// pending. Default is [`QueueOverflowPolicy::Backpressure`].
+	pub fn queue_overflow_policy(mut self, policy: QueueOverflowPolicy) -> Self {
+		self.settings.queue_overflow_policy = policy;
+		self
+	}
+
+	/// Keep a state per connection in `states`, created with `init` once a connection is accepted and
+	/// removed after it is closed and all its calls completed. Default is no per-connection state.
+	///
+	/// Method handlers look the state up with the ID of the connection they were called on, see
+	/// [`RpcModule::register_method_with_connection`](jsonrpsee_core::server::rpc_module::RpcModule::register_method_with_connection).
+	///
+	/// ```rust
+	/// use jsonrpsee_ws_server::{ConnectionStates, RpcModule, WsServerBuilder};
+	///
+	/// let sessions = ConnectionStates::new();
+	/// let builder = WsServerBuilder::default().set_connection_state(sessions.clone(), |_conn| "anonymous".to_string());
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method_with_connection("whoami", move |_, _, conn| Ok(sessions.get(conn.id))).unwrap();
+	/// ```
+	pub fn set_connection_state<T, F>(mut self, states: ConnectionStates<T>, init: F) -> Self
+	where
+		T: Send + 'static,
+		F: Fn(&ConnectionInfo) -> T + Send + Sync + 'static,
+	{
+		let connect_states = states.clone();
+		self.settings.connection_state = Some(ConnectionStateHooks {
+			on_connect: Arc::new(move |conn| {
+				connect_states.insert(conn.id, init(conn));
+			}),
+			on_disconnect: Arc::new(move |conn_id| {
+				states.remove(conn_id);
+			}),
+		});
+		self
+	}
+
 	/// Set the maximum number of connections allowed. Default is 100.
 	pub fn max_connections(mut self, max: u64) -> Self {
 		self.settings.max_connections = max;
@@ -714,7 +1491,7 @@ impl<M> Builder<M> {
 		List: IntoIterator<Item = Origin>,
 		Origin: Into<String>,
 	{
-		let list: Box<_> = list.into_iter().map(Into::into).collect();
+		let list: Box<[String]> = list.into_iter().map(Into::into).collect();
 
 		if list.len() == 0 {
 			return Err(Error::EmptyAllowList("Origin"));
@@ -754,6 +1531,21 @@ impl<M> Builder<M> {
 		Builder { settings: self.settings, resources: self.resources, middleware, id_provider: self.id_provider }
 	}
 
+	/// Set the subprotocols supported by the server, in the `Sec-WebSocket-Protocol` header of the handshake.
+	///
+	/// The first subprotocol requested by the client that is in the list is selected and exposed to the handlers
+	/// in [`ConnectionInfo::protocol`]. Connections requesting none of them are rejected with `400 Bad Request`.
+	///
+	/// By default no subprotocol is negotiated and any connection is accepted. An empty list restores the default.
+	pub fn set_protocols<Protocol, List>(mut self, list: List) -> Self
+	where
+		List: IntoIterator<Item = Protocol>,
+		Protocol: Into<String>,
+	{
+		self.settings.protocols = list.into_iter().map(Into::into).collect();
+		self
+	}
+
 	/// Restores the default behavior of allowing connections with `Origin` header
 	/// containing any value. This will undo any list set by [`set_allowed_origins`](Builder::set_allowed_origins).
 	pub fn allow_all_origins(mut self) -> Self {
@@ -779,7 +1571,7 @@ impl<M> Builder<M> {
 		List: IntoIterator<Item = Host>,
 		Host: Into<String>,
 	{
-		let list: Box<_> = list.into_iter().map(Into::into).collect();
+		let list: Box<[String]> = list.into_iter().map(Into::into).collect();
 
 		if list.len() == 0 {
 			return Err(Error::EmptyAllowList("Host"));
@@ -797,11 +1589,56 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Only accept connections from IP addresses in `ranges`, given in CIDR notation such as `192.168.0.0/16`.
+	/// Connections from other addresses are dropped before the handshake.
+	///
+	/// By default accepts connections from any address.
+	pub fn set_allowed_ips<Range, List>(mut self, ranges: List) -> Result<Self, Error>
+	where
+		List: IntoIterator<Item = Range>,
+		Range: AsRef<str>,
+	{
+		self.settings.ip_filter.set_allowed(ranges)?;
+		Ok(self)
+	}
+
+	/// Drop connections from IP addresses in `ranges`, given in CIDR notation such as `10.0.0.0/8`, before the
+	/// handshake. Takes precedence over [`set_allowed_ips`](Builder::set_allowed_ips).
+	///
+	/// By default no address is denied.
+	pub fn set_denied_ips<Range, List>(mut self, ranges: List) -> Result<Self, Error>
+	where
+		List: IntoIterator<Item = Range>,
+		Range: AsRef<str>,
+	{
+		self.settings.ip_filter.set_denied(ranges)?;
+		Ok(self)
+	}
+
+	/// Answer JSON-RPC requests sent with HTTP `POST` on the port of the server too, like the HTTP server does, while
+	/// upgrade requests switch the connection to WebSocket (default is false).
+	///
+	/// Both transports share the methods, the resources and the `Host` and `Origin` policies of the server.
+	#[cfg(feature = "http")]
+	pub fn accept_http(mut self, enabled: bool) -> Self {
+		self.settings.accept_http = enabled;
+		self
+	}
+
 	/// Configure a custom [`tokio::runtime::Handle`] to run the server on.
 	///
 	/// Default: [`tokio::spawn`]
 	pub fn custom_tokio_runtime(mut self, rt: tokio::runtime::Handle) -> Self {
-		self.settings.tokio_runtime = Some(rt);
+		self.settings.executor = Arc::new(rt);
+		self
+	}
+
+	/// Configure a custom [`Executor`] to spawn the tasks of the server on, replacing any runtime configured with
+	/// [`Builder::custom_tokio_runtime`]. The executor must poll the tasks within the context of a tokio runtime.
+	///
+	/// Default: [`tokio::spawn`]
+	pub fn custom_executor<E: Executor + 'static>(mut self, executor: E) -> Self {
+		self.settings.executor = Arc::new(executor);
 		self
 	}
 
@@ -811,7 +1648,8 @@ impl<M> Builder<M> {
 	/// You may choose static dispatch or dynamic dispatch because
 	/// `IdProvider` is implemented for `Box<T>`.
 	///
-	/// Default: [`RandomIntegerIdProvider`].
+	/// Default: [`FullRangeIntegerIdProvider`]. Use a [`RandomIntegerIdProvider`] to keep the IDs within the range
+	/// that JavaScript clients can represent exactly, or see [`Builder::full_width_subscription_ids`].
 	///
 	/// # Examples
 	///
@@ -830,6 +1668,22 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Generate random subscription IDs across the full `u64` range instead of masking them to 53 bits
+	/// (default is `true`).
+	///
+	/// The mask keeps the IDs within the range a JavaScript `Number` can represent exactly, at the cost of a
+	/// higher chance of collisions. Disable this if some of the clients are JavaScript based, they would
+	/// silently round the larger IDs and then fail to unsubscribe.
+	///
+	/// This replaces the ID provider with a [`FullRangeIntegerIdProvider`] or a [`RandomIntegerIdProvider`],
+	/// overriding [`Builder::set_id_provider`].
+	pub fn full_width_subscription_ids(mut self, enabled: bool) -> Self {
+		let provider: Arc<dyn IdProvider> =
+			if enabled { Arc::new(FullRangeIntegerIdProvider) } else { Arc::new(RandomIntegerIdProvider) };
+		self.id_provider = provider;
+		self
+	}
+
 	/// Finalize the configuration of the server. Consumes the [`Builder`].
 	///
 	/// ```rust
@@ -848,23 +1702,54 @@ impl<M> Builder<M> {
 	///
 	pub async fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<M>, Error> {
 		let listener = TcpListener::bind(addrs).await?;
+		Ok(self.build_with_listeners(vec![listener]))
+	}
+
+	/// Finalize the configuration of the server and bind it to every address of `addrs`, for instance to both
+	/// `0.0.0.0:9944` and `[::]:9944`. The server accepts connections from all of them and is stopped by one handle.
+	///
+	/// Fails if `addrs` is empty or if binding to any of the addresses fails.
+	pub async fn build_all(self, addrs: Vec<SocketAddr>) -> Result<Server<M>, Error> {
+		if addrs.is_empty() {
+			return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "No address to bind to").into());
+		}
+		let mut listeners = Vec::with_capacity(addrs.len());
+		for addr in addrs {
+			listeners.push(TcpListener::bind(addr).await?);
+		}
+		Ok(self.build_with_listeners(listeners))
+	}
+
+	fn build_with_listeners(self, listeners: Vec<TcpListener>) -> Server<M> {
 		let stop_monitor = StopMonitor::new();
 		let resources = self.resources;
-		Ok(Server {
-			listener,
+		let methods = Arc::new(LiveMethods::new(&self.settings, &resources));
+		Server {
+			listeners,
 			cfg: self.settings,
 			stop_monitor,
 			resources,
+			methods,
 			middleware: self.middleware,
 			id_provider: self.id_provider,
-		})
+		}
 	}
 }
 
-async fn send_ws_message(
-	sender: &mut Sender<BufReader<BufWriter<Compat<TcpStream>>>>,
-	response: String,
-) -> Result<(), Error> {
+async fn send_ws_ping<S>(sender: &mut Sender<S>) -> Result<(), Error>
+where
+	S: AsyncRead + AsyncWrite + Unpin,
+{
+	tracing::debug!("send keep-alive ping");
+	let payload = ByteSlice125::try_from(&[][..]).expect("an empty payload is shorter than 125 bytes; qed");
+	sender.send_ping(payload).await?;
+	sender.flush().await.map_err(Into::into)
+}
+
+async fn send_ws_message<S>(sender: &mut Sender<S>, response: String) -> Result<(), Error>
+where
+	S: AsyncRead + AsyncWrite + Unpin,
+{
 	tracing::debug!("send {} bytes", response.len());
 	tracing::trace!("send: {}", response);
 	sender.send_text_owned(response).await?;
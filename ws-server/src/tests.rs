@@ -27,16 +27,21 @@
 #![cfg(test)]
 use std::fmt;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 use crate::types::error::CallError;
 use crate::types::{Response, SubscriptionId};
-use crate::{future::ServerHandle, RpcModule, WsServerBuilder};
+use crate::{future::ServerHandle, ConnectionStates, QueueOverflowPolicy, RpcModule, WsServerBuilder};
 use anyhow::anyhow;
 use futures_util::future::join;
+use jsonrpsee_core::server::helpers::Health;
 use jsonrpsee_core::{traits::IdProvider, DeserializeOwned, Error};
 use jsonrpsee_test_utils::helpers::*;
-use jsonrpsee_test_utils::mocks::{Id, TestContext, WebSocketTestClient, WebSocketTestError};
+use jsonrpsee_test_utils::mocks::{
+	Id, RawWebSocketTestClient, TestContext, WebSocketTestClient, WebSocketTestError, PING_OPCODE, TEXT_OPCODE,
+};
 use jsonrpsee_test_utils::TimeoutFutureExt;
 use serde_json::Value as JsonValue;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
@@ -448,6 +453,30 @@ async fn should_return_method_not_found() {
 	assert_eq!(response, method_not_found(Id::Str("foo".into())));
 }
 
+#[tokio::test]
+async fn unknown_methods_are_routed_to_the_fallback() {
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.set_fallback(|id, method, params, sink, _| async move {
+		// Stands in for forwarding the call to another server.
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		let params = params.map(|params| params.get().to_owned());
+		sink.send_response(id, (method, params))
+	});
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"upstream_call","params":[1,2],"id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(serde_json::json!(["upstream_call", "[1,2]"]), Id::Num(1)));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"upstream_call","id":2}]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, format!("[{}]", ok_response(serde_json::json!(["upstream_call", null]), Id::Num(2))));
+}
+
 #[tokio::test]
 async fn invalid_json_id_missing_value() {
 	let addr = server().await;
@@ -465,9 +494,213 @@ async fn invalid_request_object() {
 	let addr = server().await;
 	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
 
-	let req = r#"{"jsonrpc":"2.0","method":"bar","id":1,"is_not_request_object":1}"#;
+	let req = r#"{"jsonrpc":"2.0","method":1,"id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, invalid_request(Id::Num(1)));
+}
+
+#[tokio::test]
+async fn empty_method_is_an_invalid_request() {
+	let addr = server().await;
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"","id":1}"#;
 	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
 	assert_eq!(response, invalid_request(Id::Num(1)));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"","id":1},{"jsonrpc":"2.0","method":"say_hello","id":2}]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		r#"[{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":1},{"jsonrpc":"2.0","result":"hello","id":2}]"#
+	);
+}
+
+#[tokio::test]
+async fn duplicate_keys_rejected_in_strict_mode() {
+	init_logger();
+
+	let server = WsServerBuilder::default().reject_duplicate_keys(true).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","method":"say_goodbye","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, invalid_request(Id::Null));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"say_hello","params":{"a":1,"a":2},"id":1}]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, invalid_request(Id::Null));
+
+	// The connection is still usable.
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn deeply_nested_requests_are_rejected() {
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let nested = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+	let req = format!(r#"{{"jsonrpc":"2.0","method":"say_hello","params":{},"id":1}}"#, nested);
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, parse_error(Id::Null));
+
+	let response = client.send_request_text(nested).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, parse_error(Id::Null));
+
+	// The connection is still usable.
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn batches_over_the_limit_are_rejected() {
+	init_logger();
+
+	let server = WsServerBuilder::default().max_batch_size(2).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let batch = r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"say_hello","id":2}]"#;
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"[{"jsonrpc":"2.0","result":"hello","id":1},{"jsonrpc":"2.0","result":"hello","id":2}]"#);
+
+	let batch = r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"say_hello","id":2},{"jsonrpc":"2.0","method":"say_hello","id":3}]"#;
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Batch too large, at most 2 calls are accepted"},"id":null}"#
+	);
+
+	handle.stop().unwrap();
+}
+
+/// Server with an async method that waits until it's notified, and a sync method.
+async fn server_with_pending_calls(
+	builder: WsServerBuilder<QueueDepth>,
+) -> (SocketAddr, ServerHandle, Arc<PendingCalls>) {
+	let server = builder.max_pending_requests(1).build("127.0.0.1:0").await.unwrap();
+	let calls = Arc::new(PendingCalls::default());
+	let mut module = RpcModule::new(calls.clone());
+	module
+		.register_async_method("wait", |_, calls| async move {
+			calls.started.notify_one();
+			calls.release.notified().await;
+			Ok("released")
+		})
+		.unwrap();
+	module
+		.register_method("say_hello", |_, calls| {
+			calls.hellos.fetch_add(1, Ordering::SeqCst);
+			Ok("hello")
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	(addr, server.start(module).unwrap(), calls)
+}
+
+/// Signals of the calls of [`server_with_pending_calls`].
+#[derive(Default)]
+struct PendingCalls {
+	/// Notified when a `wait` call starts.
+	started: tokio::sync::Notify,
+	/// Completes the pending `wait` call.
+	release: tokio::sync::Notify,
+	/// Number of `say_hello` calls answered.
+	hellos: AtomicUsize,
+}
+
+/// Middleware that keeps the greatest queue depth it was told about.
+#[derive(Clone, Default)]
+struct QueueDepth(Arc<AtomicUsize>);
+
+impl jsonrpsee_core::middleware::Middleware for QueueDepth {
+	type Instant = ();
+
+	fn on_request(&self) {}
+
+	fn on_queue_depth(&self, depth: usize) {
+		self.0.fetch_max(depth, Ordering::SeqCst);
+	}
+}
+
+#[tokio::test]
+async fn pending_requests_above_the_limit_wait_with_backpressure() {
+	init_logger();
+
+	let depth = QueueDepth::default();
+	let builder = WsServerBuilder::default().set_middleware(depth.clone());
+	let (addr, handle, calls) = server_with_pending_calls(builder).await;
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	client.send(call("wait", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+	client.send(call("say_hello", Vec::<()>::new(), Id::Num(2))).await.unwrap();
+	calls.started.notified().with_default_timeout().await.unwrap();
+
+	// The second call isn't read while the first one is pending.
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert_eq!(calls.hellos.load(Ordering::SeqCst), 0);
+	assert_eq!(depth.0.load(Ordering::SeqCst), 0);
+
+	calls.release.notify_one();
+	let response = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("released".to_owned()), Id::Num(1)));
+	let response = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(2)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn pending_requests_above_the_limit_are_shed() {
+	init_logger();
+
+	let depth = QueueDepth::default();
+	let builder =
+		WsServerBuilder::default().set_middleware(depth.clone()).queue_overflow_policy(QueueOverflowPolicy::Shed);
+	let (addr, handle, calls) = server_with_pending_calls(builder).await;
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	client.send(call("wait", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+
+	// Async calls and batches are answered right away, sync calls are still processed.
+	let response = client.send_request_text(call("wait", Vec::<()>::new(), Id::Num(2))).await.unwrap();
+	assert_eq!(response, server_is_busy(Id::Num(2)));
+	let req = format!("[{}]", call("wait", Vec::<()>::new(), Id::Num(3)));
+	let response = client.send_request_text(req).await.unwrap();
+	assert_eq!(response, server_is_busy(Id::Null));
+	let response = client.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(4))).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(4)));
+	assert_eq!(depth.0.load(Ordering::SeqCst), 1);
+
+	calls.release.notify_one();
+	let response = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("released".to_owned()), Id::Num(1)));
+
+	handle.stop().unwrap();
 }
 
 #[tokio::test]
@@ -509,12 +742,44 @@ async fn parse_error_request_should_not_close_connection() {
 	assert_eq!(response2, ok_response(JsonValue::String("hello".to_owned()), Id::Num(33)));
 }
 
+#[tokio::test]
+async fn unknown_fields_rejected_in_strict_mode() {
+	init_logger();
+
+	let server = WsServerBuilder::default().reject_unknown_fields(true).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1,"foo":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, invalid_request(Id::Num(1)));
+
+	// The connection is still usable.
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":2}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(2)));
+}
+
+#[tokio::test]
+async fn unknown_fields_ignored_by_default() {
+	let addr = server().await;
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1,"foo":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+}
+
 #[tokio::test]
 async fn invalid_request_should_not_close_connection() {
 	let addr = server().await;
 	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
 
-	let req = r#"{"jsonrpc":"2.0","method":"bar","id":1,"is_not_request_object":1}"#;
+	let req = r#"{"jsonrpc":"2.0","method":1,"id":1}"#;
 	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
 	assert_eq!(response, invalid_request(Id::Num(1)));
 	let request = r#"{"jsonrpc":"2.0","method":"say_hello","id":33}"#;
@@ -625,6 +890,51 @@ async fn unsubscribe_wrong_sub_id_type() {
 	assert!(!unsub);
 }
 
+#[tokio::test]
+async fn subscriptions_can_be_ended_through_every_unsubscribe_alias() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.set_id_provider(crate::SequentialIdProvider::new(1))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, sink, _| {
+			std::thread::spawn(move || loop {
+				let _ = &sink;
+				std::thread::sleep(std::time::Duration::from_secs(30));
+			});
+			Ok(())
+		})
+		.unwrap();
+	module.register_alias("hello_unsubscribe", "unsubscribe_hello").unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let first: u64 =
+		deser_call(client.send_request_text(call("subscribe_hello", Vec::<()>::new(), Id::Num(0))).await.unwrap());
+	let second: u64 =
+		deser_call(client.send_request_text(call("subscribe_hello", Vec::<()>::new(), Id::Num(1))).await.unwrap());
+	assert_eq!((first, second), (1, 2));
+
+	for (unsubscribe, sub_id) in [("unsubscribe_hello", first), ("hello_unsubscribe", second)] {
+		let unsub: bool =
+			deser_call(client.send_request_text(call(unsubscribe, vec![sub_id], Id::Num(2))).await.unwrap());
+		assert!(unsub, "{} must end subscription {}", unsubscribe, sub_id);
+	}
+
+	// Both subscriptions are gone, whichever name is used.
+	for (unsubscribe, sub_id) in [("hello_unsubscribe", first), ("unsubscribe_hello", second)] {
+		let unsub: bool =
+			deser_call(client.send_request_text(call(unsubscribe, vec![sub_id], Id::Num(3))).await.unwrap());
+		assert!(!unsub);
+	}
+}
+
 #[tokio::test]
 async fn custom_subscription_id_works() {
 	#[derive(Debug, Clone)]
@@ -664,3 +974,690 @@ async fn custom_subscription_id_works() {
 	let unsub = client.send_request_text(call("unsubscribe_hello", vec!["0xdeadbeef"], Id::Num(1))).await.unwrap();
 	assert_eq!(&unsub, r#"{"jsonrpc":"2.0","result":true,"id":1}"#);
 }
+
+async fn subscription_ids_with(full_width: bool) -> Vec<u64> {
+	let server = WsServerBuilder::default()
+		.full_width_subscription_ids(full_width)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, sink, _| {
+			std::thread::spawn(move || loop {
+				let _ = &sink;
+				std::thread::sleep(std::time::Duration::from_secs(30));
+			});
+			Ok(())
+		})
+		.unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let mut ids = Vec::new();
+	for i in 0..32 {
+		let sub = client.send_request_text(call("subscribe_hello", Vec::<()>::new(), Id::Num(i))).await.unwrap();
+		ids.push(deser_call::<u64>(sub));
+	}
+	ids
+}
+
+#[tokio::test]
+async fn full_width_subscription_ids_exceed_the_js_safe_range() {
+	init_logger();
+	// Each random ID fits into 53 bits with a probability of 2^-11, so this fails with a probability of 2^-352.
+	let ids = subscription_ids_with(true).await;
+	assert!(ids.iter().any(|&id| id > 1 << 53), "{:?}", ids);
+}
+
+#[tokio::test]
+async fn masked_subscription_ids_stay_within_the_js_safe_range() {
+	init_logger();
+	let ids = subscription_ids_with(false).await;
+	assert!(ids.iter().all(|&id| id < 1 << 53), "{:?}", ids);
+}
+
+#[tokio::test]
+async fn health_endpoint_works() {
+	init_logger();
+
+	let server = WsServerBuilder::default().with_health_endpoint("system_health").build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(RpcModule::new(())).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"system_health","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	let response: Response<Health> = serde_json::from_str(&response).unwrap();
+	assert!(response.result.ok);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn connection_state_is_set_on_connect_and_removed_on_disconnect() {
+	init_logger();
+
+	let sessions = ConnectionStates::new();
+	let server = WsServerBuilder::default()
+		.set_connection_state(sessions.clone(), |conn| format!("session-{}", conn.id))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+
+	let mut module = RpcModule::new(());
+	let handler_sessions = sessions.clone();
+	module.register_method_with_connection("session", move |_, _, conn| Ok(handler_sessions.get(conn.id))).unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"session","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(deser_call::<Option<String>>(response), Some("session-0".to_string()));
+	assert_eq!(sessions.len(), 1);
+
+	client.close().with_default_timeout().await.unwrap().unwrap();
+	for _ in 0..50 {
+		if sessions.is_empty() {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert!(sessions.is_empty());
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn connection_state_is_removed_when_serving_the_connection_is_cancelled() {
+	init_logger();
+
+	let rt = tokio::runtime::Builder::new_multi_thread().worker_threads(1).enable_all().build().unwrap();
+	let sessions = ConnectionStates::new();
+	let server = WsServerBuilder::default()
+		.custom_tokio_runtime(rt.handle().clone())
+		.set_connection_state(sessions.clone(), |conn| format!("session-{}", conn.id))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = call("unknown", Vec::<()>::new(), Id::Num(1));
+	client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(sessions.len(), 1);
+
+	// Shutting the runtime down drops the tasks of the server without letting them finish.
+	tokio::task::spawn_blocking(move || drop(rt)).await.unwrap();
+	assert!(sessions.is_empty());
+}
+
+#[tokio::test]
+async fn case_insensitive_method_names_works() {
+	init_logger();
+
+	let server = WsServerBuilder::default().case_insensitive_method_names(true).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("foo", |_, _| Ok("lo")).unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"FOO","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn method_names_are_case_sensitive_by_default() {
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("foo", |_, _| Ok("lo")).unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"FOO","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, method_not_found(Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn subscriptions_wait_for_slow_clients_when_ready_is_awaited() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_buffered_messages(4)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let sent = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(sent.clone());
+	module
+		.register_subscription("subscribe_big", "big", "unsubscribe_big", |_, mut sink, sent| {
+			tokio::spawn(async move {
+				let big = "x".repeat(512 * 1024);
+				for _ in 0..200 {
+					sink.ready().await;
+					if sink.send(&big).is_err() {
+						break;
+					}
+					sent.fetch_add(1, Ordering::SeqCst);
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send_request_text(call("subscribe_big", Vec::<()>::new(), Id::Num(0))).await.unwrap();
+
+	// The client doesn't read, once the socket buffers are full the producer has to wait instead of queueing
+	// all of the 100 MiB.
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	let sent_while_stalled = sent.load(Ordering::SeqCst);
+	assert!(sent_while_stalled < 100, "sent {} messages to a stalled client", sent_while_stalled);
+
+	// It resumes as the client reads.
+	for _ in 0..sent_while_stalled {
+		client.receive().with_default_timeout().await.unwrap().unwrap();
+	}
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert!(sent.load(Ordering::SeqCst) > sent_while_stalled);
+}
+
+#[tokio::test]
+async fn methods_can_be_swapped_without_dropping_connections() {
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut old = RpcModule::new(());
+	old.register_method("version", |_, _| Ok("old")).unwrap();
+	let handle = server.start(old).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = call("version", Vec::<()>::new(), Id::Num(1));
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("old".to_owned()), Id::Num(1)));
+
+	let mut new = RpcModule::new(());
+	new.register_method("version", |_, _| Ok("new")).unwrap();
+	handle.swap_methods(new).unwrap();
+
+	let req = call("version", Vec::<()>::new(), Id::Num(2));
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("new".to_owned()), Id::Num(2)));
+
+	handle.clone().stop().unwrap().with_default_timeout().await.unwrap();
+	assert!(matches!(handle.swap_methods(RpcModule::new(())), Err(Error::AlreadyStopped)));
+}
+
+#[tokio::test]
+async fn subscriptions_can_be_closed_after_swapping_the_methods() {
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let sinks = Arc::new(std::sync::Mutex::new(Vec::new()));
+	let mut old = RpcModule::new(sinks.clone());
+	old.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, sink, sinks| {
+		sinks.lock().unwrap().push(sink);
+		Ok(())
+	})
+	.unwrap();
+	let handle = server.start(old).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let sub_call = call("subscribe_hello", Vec::<()>::new(), Id::Num(0));
+	let sub_id: u64 = deser_call(client.send_request_text(sub_call).with_default_timeout().await.unwrap().unwrap());
+
+	let mut new = RpcModule::new(());
+	new.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, _, _| Ok(())).unwrap();
+	handle.swap_methods(new).unwrap();
+
+	let unsub_call = call("unsubscribe_hello", vec![sub_id], Id::Num(1));
+	let unsubscribed: bool =
+		deser_call(client.send_request_text(unsub_call).with_default_timeout().await.unwrap().unwrap());
+	assert!(unsubscribed);
+	let mut sink = sinks.lock().unwrap().pop().unwrap();
+	assert!(matches!(sink.send(&"hello"), Err(Error::SubscriptionClosed(_))));
+
+	let unsub_call = call("unsubscribe_hello", vec![sub_id], Id::Num(2));
+	let unsubscribed: bool =
+		deser_call(client.send_request_text(unsub_call).with_default_timeout().await.unwrap().unwrap());
+	assert!(!unsubscribed);
+}
+
+#[tokio::test]
+async fn subscriptions_carried_over_by_swapping_the_methods_are_closed_by_the_closer() {
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let sinks = Arc::new(std::sync::Mutex::new(Vec::new()));
+	let mut old = RpcModule::new(sinks.clone());
+	old.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, sink, sinks| {
+		sinks.lock().unwrap().push(sink);
+		Ok(())
+	})
+	.unwrap();
+	let handle = server.start(old).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let sub_call = call("subscribe_hello", Vec::<()>::new(), Id::Num(0));
+	let sub_id: u64 = deser_call(client.send_request_text(sub_call).with_default_timeout().await.unwrap().unwrap());
+
+	let mut new = RpcModule::new(());
+	new.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, _, _| Ok(())).unwrap();
+	let closer = new.subscription_closer();
+	handle.swap_methods(new).unwrap();
+
+	assert_eq!(closer.close_all("shutting down"), 1);
+	let closed = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert!(closed.contains(r#""method":"hello""#) && closed.contains("shutting down"), "{}", closed);
+	let mut sink = sinks.lock().unwrap().pop().unwrap();
+	assert!(matches!(sink.send(&"hello"), Err(Error::SubscriptionClosed(_))));
+	assert_eq!(closer.close_all("shutting down"), 0);
+
+	let unsub_call = call("unsubscribe_hello", vec![sub_id], Id::Num(1));
+	let unsubscribed: bool =
+		deser_call(client.send_request_text(unsub_call).with_default_timeout().await.unwrap().unwrap());
+	assert!(!unsubscribed);
+}
+
+#[tokio::test]
+async fn calls_sharing_an_id_can_be_cancelled() {
+	init_logger();
+
+	// Counts the calls that were dropped before completing.
+	struct Cancelled(Arc<AtomicUsize>, bool);
+	impl Drop for Cancelled {
+		fn drop(&mut self) {
+			if !self.1 {
+				self.0.fetch_add(1, Ordering::SeqCst);
+			}
+		}
+	}
+
+	let server = WsServerBuilder::default()
+		.enable_request_cancellation(true)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let cancelled = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(cancelled.clone());
+	module
+		.register_async_method("sleep", |params, cancelled| async move {
+			let millis: u64 = params.one()?;
+			let mut guard = Cancelled((*cancelled).clone(), false);
+			tokio::time::sleep(Duration::from_millis(millis)).await;
+			guard.1 = true;
+			Ok(millis)
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(call("sleep", vec![60_000], Id::Num(1))).with_default_timeout().await.unwrap().unwrap();
+	// Completing the second call with the same ID must not forget the first one.
+	let response = client.send_request_text(call("sleep", vec![10], Id::Num(1))).with_default_timeout().await.unwrap();
+	assert_eq!(deser_call::<u64>(response.unwrap()), 10);
+
+	let cancel = r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":[1]}"#;
+	client.send(cancel).with_default_timeout().await.unwrap().unwrap();
+	for _ in 0..100 {
+		if cancelled.load(Ordering::SeqCst) == 1 {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert_eq!(cancelled.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn connections_are_filtered_by_ip() {
+	init_logger();
+
+	let server = WsServerBuilder::default()
+		.set_allowed_ips(["127.0.0.0/8"])
+		.unwrap()
+		.set_denied_ips(["10.0.0.0/8"])
+		.unwrap()
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = call("say_hello", Vec::<()>::new(), Id::Num(1));
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+
+	let server = WsServerBuilder::default()
+		.set_denied_ips(["127.0.0.1"])
+		.unwrap()
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	assert!(WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_err());
+}
+
+#[cfg(feature = "http")]
+#[tokio::test]
+async fn http_and_ws_are_served_on_the_same_port() {
+	init_logger();
+
+	let server = WsServerBuilder::default()
+		.accept_http(true)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, jsonrpsee_test_utils::mocks::StatusCode::OK);
+	assert_eq!(response.body, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn subscription_send_times_out_when_the_client_does_not_read() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_buffered_messages(4)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let (tx, mut rx) = futures_channel::mpsc::unbounded();
+	let mut module = RpcModule::new(tx);
+	module
+		.register_subscription("subscribe_big", "big", "unsubscribe_big", |_, mut sink, tx| {
+			let tx = (*tx).clone();
+			tokio::spawn(async move {
+				let big = "x".repeat(512 * 1024);
+				loop {
+					if let Err(err) = sink.send_timeout(&big, Duration::from_millis(100)).await {
+						let _ = tx.unbounded_send(err);
+						break;
+					}
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send_request_text(call("subscribe_big", Vec::<()>::new(), Id::Num(0))).await.unwrap();
+
+	// The client doesn't read, so the producer gives up instead of waiting forever.
+	let err = futures_util::StreamExt::next(&mut rx).with_default_timeout().await.unwrap().unwrap();
+	assert!(matches!(err, Error::RequestTimeout));
+}
+
+#[tokio::test]
+async fn idle_connections_are_closed() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.idle_connection_timeout(Duration::from_millis(300))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	// Each request resets the timeout.
+	for i in 0..4 {
+		tokio::time::sleep(Duration::from_millis(150)).await;
+		let req = call("say_hello", Vec::<()>::new(), Id::Num(i));
+		let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(i)));
+	}
+
+	// The server closes the connection once the client stops sending.
+	assert!(client.receive().with_default_timeout().await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn server_runs_on_a_custom_executor() {
+	use futures_util::future::BoxFuture;
+	use futures_util::StreamExt;
+
+	/// Runs the tasks on the tokio runtime of a dedicated thread, executors must poll them within a tokio context.
+	#[derive(Debug)]
+	struct SingleThreadExecutor(futures_channel::mpsc::UnboundedSender<BoxFuture<'static, ()>>);
+
+	impl crate::Executor for SingleThreadExecutor {
+		fn spawn(&self, task: BoxFuture<'static, ()>) {
+			self.0.unbounded_send(task).unwrap();
+		}
+	}
+
+	init_logger();
+	let (tx, mut rx) = futures_channel::mpsc::unbounded::<BoxFuture<'static, ()>>();
+	std::thread::Builder::new()
+		.name("custom-executor".into())
+		.spawn(move || {
+			let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+			rt.block_on(async move {
+				while let Some(task) = rx.next().await {
+					tokio::spawn(task);
+				}
+			});
+		})
+		.unwrap();
+
+	let server = WsServerBuilder::default()
+		.custom_executor(SingleThreadExecutor(tx))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("thread_name", |_, _| Ok(std::thread::current().name().map(ToOwned::to_owned))).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = call("thread_name", Vec::<()>::new(), Id::Num(1));
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("custom-executor".into(), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn pings_are_answered_with_pongs() {
+	init_logger();
+	let addr = server().with_default_timeout().await.unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let pong = client.ping(b"are you there?").with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(pong, b"are you there?");
+	let pong = client.ping(&[]).with_default_timeout().await.unwrap().unwrap();
+	assert!(pong.is_empty());
+
+	// The pings didn't reach the methods, the next response answers the next request.
+	let req = call("say_hello", Vec::<()>::new(), Id::Num(1));
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn server_can_be_bound_to_several_addresses() {
+	init_logger();
+	let mut addrs = vec!["127.0.0.1:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+	// Hosts without IPv6 only exercise the IPv4 loopback.
+	let ipv6 = std::net::TcpListener::bind("[::1]:0").is_ok();
+	if ipv6 {
+		addrs[1] = "[::1]:0".parse().unwrap();
+	}
+	let server = WsServerBuilder::default().build_all(addrs).with_default_timeout().await.unwrap().unwrap();
+	let local_addrs = server.local_addrs().unwrap();
+	assert_eq!(local_addrs.len(), 2);
+	assert!(local_addrs[0].is_ipv4());
+	assert_eq!(local_addrs[1].is_ipv6(), ipv6);
+	assert_eq!(server.local_addr().unwrap(), local_addrs[0]);
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let handle = server.start(module).unwrap();
+
+	for (i, addr) in local_addrs.into_iter().enumerate() {
+		let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+		let req = call("say_hello", Vec::<()>::new(), Id::Num(i as u64));
+		let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(i as u64)));
+	}
+
+	handle.stop().unwrap().with_default_timeout().await.unwrap();
+}
+
+#[tokio::test]
+async fn binding_to_no_address_fails() {
+	assert!(WsServerBuilder::default().build_all(Vec::new()).await.is_err());
+}
+
+#[tokio::test]
+async fn null_id_is_answered_but_missing_id_is_not() {
+	let addr = server().await;
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	client.send(r#"{"jsonrpc":"2.0","method":"say_hello"}"#).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"say_hello","id":null}"#).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Null));
+
+	// Nothing is sent back for the notification.
+	assert!(client.receive().with_timeout(Duration::from_millis(200)).await.is_err());
+}
+
+#[tokio::test]
+async fn subprotocol_is_negotiated_and_exposed_to_handlers() {
+	let server = WsServerBuilder::default()
+		.set_protocols(["jsonrpc-2.0", "vendor.v1"])
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_method_with_connection("protocol", |_, _, conn| Ok(conn.protocol.as_deref().map(ToOwned::to_owned)))
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let (mut client, protocol) = WebSocketTestClient::with_protocols(addr, &["unknown", "vendor.v1"])
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(protocol.as_deref(), Some("vendor.v1"));
+	let response = client.send_request_text(call("protocol", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+	assert_eq!(response, ok_response("vendor.v1".into(), Id::Num(1)));
+
+	// Connections requesting none of the supported subprotocols are rejected.
+	let rejected = WebSocketTestClient::with_protocols(addr, &["unknown"]).with_default_timeout().await.unwrap();
+	assert!(matches!(rejected, Err(WebSocketTestError::RejectedWithStatusCode(400))));
+	let rejected = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap();
+	assert!(matches!(rejected, Err(WebSocketTestError::RejectedWithStatusCode(400))));
+}
+
+#[tokio::test]
+async fn no_subprotocol_is_negotiated_by_default() {
+	let (addr, _handle) = server_with_handles().await;
+	let (mut client, protocol) =
+		WebSocketTestClient::with_protocols(addr, &["jsonrpc-2.0"]).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(protocol, None);
+	let response = client.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn idle_subscriptions_are_kept_alive_with_pings() {
+	let server = WsServerBuilder::default()
+		.subscription_ping_interval(Duration::from_millis(100))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	// Keeps the sinks alive without ever sending on them.
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module
+		.register_subscription("subscribe_idle", "idle", "unsubscribe_idle", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	// A connection with an idle subscription is pinged.
+	let mut client = RawWebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send_text(&call("subscribe_idle", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+	let (opcode, _) = client.receive_frame().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(opcode, TEXT_OPCODE);
+	for _ in 0..3 {
+		let (opcode, payload) = client.receive_frame().with_timeout(Duration::from_millis(500)).await.unwrap().unwrap();
+		assert_eq!(opcode, PING_OPCODE);
+		assert!(payload.is_empty());
+	}
+
+	// A connection without subscriptions isn't.
+	let mut client = RawWebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send_text(&call("say_hello", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+	let (opcode, payload) = client.receive_frame().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(opcode, TEXT_OPCODE);
+	assert_eq!(String::from_utf8(payload).unwrap(), ok_response("hello".into(), Id::Num(1)));
+	assert!(client.receive_frame().with_timeout(Duration::from_millis(300)).await.is_err());
+}